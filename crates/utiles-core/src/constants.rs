@@ -6,6 +6,9 @@ pub const MAX_ZOOM: u8 = 30;
 /// MAX ZOOM JS NUMBER (28)
 pub const MAX_ZOOM_JS: u8 = 28;
 
+/// Default (web-mercator) tile size in pixels
+pub const TILE_SIZE: u32 = 256;
+
 /// Earth radius in meters
 pub const EARTH_RADIUS: f64 = 6_378_137.0;
 
@@ -114,7 +114,7 @@ impl TryFrom<(u64, u64, u64)> for Tile {
                 tuple.0, tuple.1, tuple.2
             ))
         })?;
-        Ok(Self::new(x, y, z))
+        Self::try_new(x, y, z)
     }
 }
 
@@ -233,6 +233,6 @@ impl TryFrom<&Map<String, Value>> for Tile {
                 serde_json::to_string(&map).unwrap_or_default(),
             )
         })?;
-        Ok(Self::new(x, y, z))
+        Self::try_new(x, y, z)
     }
 }
@@ -1,4 +1,6 @@
 //! GDAL related functions/utils
+use crate::constants::{EARTH_CIRCUMFERENCE, TILE_SIZE};
+use crate::resolution::zoom_for_resolution;
 
 /// A geotransform is an affine transformation from the image coordinate space
 /// (row, column), also known as (pixel, line) to the georeferenced coordinate
@@ -49,14 +51,20 @@ impl GeoTransform {
     }
 
     /// Compute the optimal zoom level for a geo-transform.
+    #[must_use]
+    pub fn optzoom(&self) -> u8 {
+        self.optzoom_tile_size(TILE_SIZE)
+    }
+
+    /// Compute the optimal zoom level for a geo-transform and tile size
+    /// (e.g. 512 for 512 px/retina tiles).
     #[expect(clippy::cast_sign_loss)]
     #[expect(clippy::cast_possible_truncation)]
     #[must_use]
-    pub fn optzoom(&self) -> u8 {
-        let equator = 2.0 * std::f64::consts::PI * 6_378_137.0; // 2 * pi * radius of earth in meters
-        let resolution = self.gt1 * (equator / 360.0);
-        let zoom_level = (equator / 256.0) / resolution; // Assuming pixel_size is 256 as in the previous example
-        (zoom_level.log2().min(20.0).floor() + 0.5) as u8
+    pub fn optzoom_tile_size(&self, tile_size: u32) -> u8 {
+        let resolution = self.gt1 * (EARTH_CIRCUMFERENCE / 360.0);
+        let zoom_level = zoom_for_resolution(resolution, tile_size);
+        (zoom_level.min(20.0).floor().max(0.0) + 0.5) as u8
     }
 }
 
@@ -73,6 +81,15 @@ pub fn geotransform2optzoom(geotransform: (f64, f64, f64, f64, f64, f64)) -> u8
     gt.optzoom()
 }
 
+/// Compute the optimal zoom level for a geo-transform and tile size.
+#[must_use]
+pub fn geotransform2optzoom_tile_size(
+    geotransform: (f64, f64, f64, f64, f64, f64),
+    tile_size: u32,
+) -> u8 {
+    GeoTransform::from(geotransform).optzoom_tile_size(tile_size)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,4 +106,11 @@ mod tests {
         ));
         assert_eq!(optz, 12);
     }
+
+    #[test]
+    fn test_geotransform2optzoom_tile_size() {
+        let gt = (-77.000_138, 0.000_278, 0.0, 26.000_138_9, 0.0, -0.000_278);
+        assert_eq!(geotransform2optzoom_tile_size(gt, 256), 12);
+        assert_eq!(geotransform2optzoom_tile_size(gt, 512), 11);
+    }
 }
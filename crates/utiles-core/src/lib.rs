@@ -16,16 +16,21 @@ pub use bbox::{BBox, geobbox_merge};
 #[doc(inline)]
 pub use errors::{UtilesCoreError, UtilesCoreResult};
 #[doc(inline)]
-pub use gdal::geotransform2optzoom;
+pub use gdal::{geotransform2optzoom, geotransform2optzoom_tile_size};
 pub use lnglat::{LngLat, wrap_lon};
 pub use point::{Point2d, Point3d};
 pub use textiles::*;
 #[doc(inline)]
 pub use tile::Tile;
+#[cfg(feature = "pmtiles")]
+pub use tile_id::Hilbert;
+pub use tile_id::{Morton, TileId};
 #[doc(inline)]
 pub use tile_like::TileLike;
 pub use tile_strfmt::{TileStringFormat, TileStringFormatter};
 pub use tile_zbox::TileZBox;
+pub use tileset::TileSet;
+pub use tms::TileMatrixSet;
 #[doc(inline)]
 pub use traits::{Coord2dLike, IsOk, LngLatLike, TileChildren1, TileParent};
 pub use web_geo_bounds::web_geo_bounds_union;
@@ -50,6 +55,7 @@ pub mod gdal;
 pub mod geostats;
 pub mod lnglat;
 pub mod parsing;
+pub mod pixel;
 
 mod asserts;
 mod edges;
@@ -62,18 +68,24 @@ pub mod pmtiles;
 pub mod point;
 pub mod projection;
 mod quadkey;
+pub mod resolution;
 pub mod sibling_relationship;
+pub mod terrain;
 mod tests;
 mod textiles;
 pub mod tile;
 pub mod tile_data_row;
 mod tile_feature;
+pub mod tile_id;
+pub mod tile_input;
 mod tile_like;
 mod tile_strfmt;
 mod tile_tuple;
 pub mod tile_type;
 pub mod tile_zbox;
 mod tilecrz;
+pub mod tileset;
+pub mod tms;
 mod traits;
 mod web_geo_bounds;
 pub mod zoom;
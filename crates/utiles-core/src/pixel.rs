@@ -0,0 +1,199 @@
+//! Pixel transforms ~ lnglat, web-mercator, global pixel and tile-local coords
+//!
+//! Global pixels are measured from the upper-left corner of the world at a
+//! zoom (`tile_size * 2^z` pixels wide); tile-local coordinates are measured
+//! from the upper-left corner of a tile in tile pixels or in an extent (e.g.
+//! 4096 for MVT).
+use crate::constants::EARTH_CIRCUMFERENCE;
+use crate::errors::UtilesCoreResult;
+use crate::resolution::{lnglat2tile_pixel, tile_pixel2lnglat};
+use crate::{LngLat, Tile, UtilesCoreError, lnglat2tile_frac};
+
+/// Default extent of (mapbox) vector tile coordinates
+pub const MVT_EXTENT: u32 = 4096;
+
+/// Size of the world in pixels at a zoom
+fn world_size(zoom: u8, tile_size: u32) -> f64 {
+    f64::from(tile_size) * f64::from(zoom).exp2()
+}
+
+/// Convert lng/lat to global pixel coordinates at a zoom
+///
+/// # Errors
+///
+/// Returns error if the latitude is out of the web-mercator range
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::pixel::lnglat2pixel;
+/// let (px, py) = lnglat2pixel(0.0, 0.0, 1, 256).unwrap();
+/// assert!((px - 256.0).abs() < 1e-9 && (py - 256.0).abs() < 1e-9);
+/// ```
+pub fn lnglat2pixel(
+    lng: f64,
+    lat: f64,
+    zoom: u8,
+    tile_size: u32,
+) -> UtilesCoreResult<(f64, f64)> {
+    let (xf, yf, _) = lnglat2tile_frac(lng, lat, zoom);
+    if !yf.is_finite() {
+        return Err(UtilesCoreError::InvalidLngLat(format!(
+            "latitude out of web-mercator range: {lat}"
+        )));
+    }
+    let size = f64::from(tile_size);
+    Ok((xf * size, yf * size))
+}
+
+/// Convert global pixel coordinates at a zoom to lng/lat
+#[must_use]
+pub fn pixel2lnglat(px: f64, py: f64, zoom: u8, tile_size: u32) -> LngLat {
+    let (tile, x, y) = pixel2tile(px, py, zoom, tile_size);
+    tile_pixel2lnglat(&tile, x, y, tile_size)
+}
+
+/// Convert web-mercator (EPSG:3857) meters to global pixel coordinates
+#[must_use]
+pub fn webmercator2pixel(x: f64, y: f64, zoom: u8, tile_size: u32) -> (f64, f64) {
+    let size = world_size(zoom, tile_size);
+    (
+        (x / EARTH_CIRCUMFERENCE + 0.5) * size,
+        (0.5 - y / EARTH_CIRCUMFERENCE) * size,
+    )
+}
+
+/// Convert global pixel coordinates to web-mercator (EPSG:3857) meters
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::pixel::pixel2webmercator;
+/// let (x, y) = pixel2webmercator(0.0, 0.0, 0, 512);
+/// assert!((x + 20_037_508.342_789_244).abs() < 1e-6);
+/// assert!((y - 20_037_508.342_789_244).abs() < 1e-6);
+/// ```
+#[must_use]
+pub fn pixel2webmercator(px: f64, py: f64, zoom: u8, tile_size: u32) -> (f64, f64) {
+    let size = world_size(zoom, tile_size);
+    (
+        (px / size - 0.5) * EARTH_CIRCUMFERENCE,
+        (0.5 - py / size) * EARTH_CIRCUMFERENCE,
+    )
+}
+
+/// Return the tile containing a global pixel and the tile-local pixel
+///
+/// Pixels on the right/bottom edge of the world belong to the last tile.
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::Tile;
+/// use utiles_core::pixel::pixel2tile;
+/// let (tile, x, y) = pixel2tile(300.0, 10.5, 1, 256);
+/// assert_eq!(tile, Tile::new(1, 0, 1));
+/// assert_eq!((x, y), (44.0, 10.5));
+/// ```
+#[must_use]
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
+pub fn pixel2tile(px: f64, py: f64, zoom: u8, tile_size: u32) -> (Tile, f64, f64) {
+    let size = f64::from(tile_size);
+    let max = f64::from(2_u32.pow(u32::from(zoom)) - 1);
+    let tx = (px / size).floor().clamp(0.0, max);
+    let ty = (py / size).floor().clamp(0.0, max);
+    (
+        Tile::new(tx as u32, ty as u32, zoom),
+        tx.mul_add(-size, px),
+        ty.mul_add(-size, py),
+    )
+}
+
+/// Convert tile-local pixel coordinates to global pixel coordinates
+#[must_use]
+pub fn tile_pixel2pixel(tile: &Tile, x: f64, y: f64, tile_size: u32) -> (f64, f64) {
+    let size = f64::from(tile_size);
+    (
+        f64::from(tile.x).mul_add(size, x),
+        f64::from(tile.y).mul_add(size, y),
+    )
+}
+
+/// Return the tile containing a lng/lat and the tile-local coordinates in
+/// an extent (e.g. [`MVT_EXTENT`])
+///
+/// # Errors
+///
+/// Returns error if the latitude is out of the web-mercator range
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::Tile;
+/// use utiles_core::pixel::{MVT_EXTENT, lnglat2tile_extent};
+/// let (tile, x, y) = lnglat2tile_extent(-90.0, 0.0, 1, MVT_EXTENT).unwrap();
+/// assert_eq!(tile, Tile::new(0, 1, 1));
+/// assert!((x - 2048.0).abs() < 1e-9 && y.abs() < 1e-9);
+/// ```
+pub fn lnglat2tile_extent(
+    lng: f64,
+    lat: f64,
+    zoom: u8,
+    extent: u32,
+) -> UtilesCoreResult<(Tile, f64, f64)> {
+    lnglat2tile_pixel(lng, lat, zoom, extent)
+}
+
+/// Convert tile-local coordinates in an extent to lng/lat
+#[must_use]
+pub fn tile_extent2lnglat(tile: &Tile, x: f64, y: f64, extent: u32) -> LngLat {
+    tile_pixel2lnglat(tile, x, y, extent)
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::lnglat2webmercator;
+
+    #[test]
+    fn pixel_roundtrips() {
+        let (lng, lat) = (-122.419_4, 37.774_9);
+        for (zoom, tile_size) in [(0, 256), (5, 512), (14, 256), (20, 1024)] {
+            let (px, py) = lnglat2pixel(lng, lat, zoom, tile_size).unwrap();
+            let ll = pixel2lnglat(px, py, zoom, tile_size);
+            assert!((ll.lng() - lng).abs() < 1e-9 && (ll.lat() - lat).abs() < 1e-9);
+
+            let (mx, my) = lnglat2webmercator(lng, lat);
+            let (mpx, mpy) = webmercator2pixel(mx, my, zoom, tile_size);
+            assert!((mpx - px).abs() < 1e-6 && (mpy - py).abs() < 1e-6);
+            let (mx2, my2) = pixel2webmercator(px, py, zoom, tile_size);
+            assert!((mx2 - mx).abs() < 1e-6 && (my2 - my).abs() < 1e-6);
+
+            let (tile, x, y) = pixel2tile(px, py, zoom, tile_size);
+            assert_eq!(tile, crate::tile(lng, lat, zoom, None).unwrap());
+            assert_eq!(tile_pixel2pixel(&tile, x, y, tile_size), (px, py));
+        }
+    }
+
+    #[test]
+    fn extent_coords() {
+        let (lng, lat) = (2.294_5, 48.858_2);
+        let (tile, x, y) = lnglat2tile_extent(lng, lat, 15, MVT_EXTENT).unwrap();
+        let (ptile, px, py) = lnglat2tile_pixel(lng, lat, 15, 256).unwrap();
+        assert_eq!(tile, ptile);
+        assert!((x / 16.0 - px).abs() < 1e-6 && (y / 16.0 - py).abs() < 1e-6);
+        let ll = tile_extent2lnglat(&tile, x, y, MVT_EXTENT);
+        assert!((ll.lng() - lng).abs() < 1e-9 && (ll.lat() - lat).abs() < 1e-9);
+        assert!(lnglat2pixel(0.0, 90.0, 1, 256).is_err());
+    }
+
+    #[test]
+    fn pixel2tile_edges() {
+        let (tile, x, y) = pixel2tile(512.0, 512.0, 1, 256);
+        assert_eq!(tile, Tile::new(1, 1, 1));
+        assert_eq!((x, y), (256.0, 256.0));
+    }
+}
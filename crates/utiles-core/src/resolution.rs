@@ -0,0 +1,219 @@
+//! Tile-size aware resolution/scale math
+//!
+//! Web-mercator tile math for 256 px tiles, 512 px (vector style) tiles and
+//! retina (`@2x`) tiles. Zoom levels are `f64` so fractional (style) zooms
+//! work; integer zooms are exact.
+use std::f64::consts::PI;
+
+use crate::constants::{EARTH_CIRCUMFERENCE, TILE_SIZE};
+use crate::errors::UtilesCoreResult;
+use crate::{LngLat, Tile, UtilesCoreError, lnglat2tile_frac};
+
+/// Standardized rendering pixel size in meters (0.28 mm; OGC WMTS/SLD)
+pub const OGC_PIXEL_SIZE: f64 = 0.000_28;
+
+/// Parse a tile size ~ pixels (`256`, `512`) and/or a retina scale (`@2x`,
+/// `256@2x`, `2x`); scales w/o a size are relative to 256 px tiles
+///
+/// # Errors
+///
+/// Returns error if the string is not a (positive) tile size/scale
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::resolution::parse_tile_size;
+/// assert_eq!(parse_tile_size("512").unwrap(), 512);
+/// assert_eq!(parse_tile_size("@2x").unwrap(), 512);
+/// assert_eq!(parse_tile_size("512@2x").unwrap(), 1024);
+/// assert!(parse_tile_size("0").is_err());
+/// ```
+pub fn parse_tile_size(s: &str) -> UtilesCoreResult<u32> {
+    let invalid = || UtilesCoreError::ParseError(format!("invalid tile size: {s}"));
+    let s = s.trim().to_lowercase();
+    let (size, scale) = match s.split_once('@') {
+        Some((size, scale)) => (size, Some(scale)),
+        None if s.ends_with('x') => ("", Some(s.as_str())),
+        None => (s.as_str(), None),
+    };
+    let size = if size.is_empty() {
+        TILE_SIZE
+    } else {
+        size.parse::<u32>().map_err(|_| invalid())?
+    };
+    let scale = match scale {
+        Some(scale) => scale
+            .strip_suffix('x')
+            .unwrap_or(scale)
+            .parse::<u32>()
+            .map_err(|_| invalid())?,
+        None => 1,
+    };
+    size.checked_mul(scale)
+        .filter(|size| *size > 0)
+        .ok_or_else(invalid)
+}
+
+/// Return the resolution (meters/pixel at the equator) at a zoom
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::resolution::resolution;
+/// assert!((resolution(0.0, 256) - 156_543.033_928_041).abs() < 1e-6);
+/// assert!((resolution(1.0, 512) - 39_135.758_482_010).abs() < 1e-6);
+/// ```
+#[must_use]
+pub fn resolution(zoom: f64, tile_size: u32) -> f64 {
+    EARTH_CIRCUMFERENCE / (f64::from(tile_size) * zoom.exp2())
+}
+
+/// Return the ground resolution (meters/pixel) at a latitude and zoom
+#[must_use]
+pub fn meters_per_pixel(lat: f64, zoom: f64, tile_size: u32) -> f64 {
+    resolution(zoom, tile_size) * lat.to_radians().cos()
+}
+
+/// Return the (OGC) scale denominator at a zoom (e.g. `1:559082264` at z0)
+#[must_use]
+pub fn scale_denominator(zoom: f64, tile_size: u32) -> f64 {
+    resolution(zoom, tile_size) / OGC_PIXEL_SIZE
+}
+
+/// Return the (fractional) zoom for a resolution in meters/pixel
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::resolution::{resolution, zoom_for_resolution};
+/// let z = zoom_for_resolution(resolution(12.0, 512), 512);
+/// assert!((z - 12.0).abs() < 1e-9);
+/// // same resolution w/ 256 px tiles is one zoom level deeper
+/// let z = zoom_for_resolution(resolution(12.0, 512), 256);
+/// assert!((z - 13.0).abs() < 1e-9);
+/// ```
+#[must_use]
+pub fn zoom_for_resolution(resolution: f64, tile_size: u32) -> f64 {
+    (EARTH_CIRCUMFERENCE / (f64::from(tile_size) * resolution)).log2()
+}
+
+/// Return the (fractional) zoom for an (OGC) scale denominator
+#[must_use]
+pub fn zoom_for_scale_denominator(scale_denominator: f64, tile_size: u32) -> f64 {
+    zoom_for_resolution(scale_denominator * OGC_PIXEL_SIZE, tile_size)
+}
+
+/// Return the tile containing a lng/lat and the pixel position within it
+///
+/// Pixels are measured from the upper-left corner of the tile (x right,
+/// y down).
+///
+/// # Errors
+///
+/// Returns error if the latitude is out of the web-mercator range
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::Tile;
+/// use utiles_core::resolution::lnglat2tile_pixel;
+/// let (tile, px, py) = lnglat2tile_pixel(0.0, 0.0, 1, 512).unwrap();
+/// assert_eq!(tile, Tile::new(1, 1, 1));
+/// assert!(px.abs() < 1e-9 && py.abs() < 1e-9);
+/// ```
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
+pub fn lnglat2tile_pixel(
+    lng: f64,
+    lat: f64,
+    zoom: u8,
+    tile_size: u32,
+) -> UtilesCoreResult<(Tile, f64, f64)> {
+    let (xf, yf, z) = lnglat2tile_frac(lng, lat, zoom);
+    if !yf.is_finite() {
+        return Err(UtilesCoreError::InvalidLngLat(format!(
+            "latitude out of web-mercator range: {lat}"
+        )));
+    }
+    let max = f64::from(2_u32.pow(u32::from(z)) - 1);
+    let (x, y) = (xf.floor().clamp(0.0, max), yf.floor().clamp(0.0, max));
+    let size = f64::from(tile_size);
+    Ok((
+        Tile::new(x as u32, y as u32, z),
+        (xf - x) * size,
+        (yf - y) * size,
+    ))
+}
+
+/// Return the lng/lat of a pixel position within a tile
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::{Tile, ul};
+/// use utiles_core::resolution::tile_pixel2lnglat;
+/// let ll = tile_pixel2lnglat(&Tile::new(1, 1, 2), 0.0, 0.0, 512);
+/// let ul = ul(1, 1, 2);
+/// assert!((ll.lng() - ul.lng()).abs() < 1e-9 && (ll.lat() - ul.lat()).abs() < 1e-9);
+/// let center = tile_pixel2lnglat(&Tile::new(0, 0, 0), 256.0, 256.0, 512);
+/// assert!(center.lng().abs() < 1e-9 && center.lat().abs() < 1e-9);
+/// ```
+#[must_use]
+pub fn tile_pixel2lnglat(tile: &Tile, px: f64, py: f64, tile_size: u32) -> LngLat {
+    let size = f64::from(tile_size);
+    let z2 = f64::from(tile.z).exp2();
+    let xf = (f64::from(tile.x) + px / size) / z2;
+    let yf = (f64::from(tile.y) + py / size) / z2;
+    let lng = xf.mul_add(360.0, -180.0);
+    let lat = (PI * 2.0f64.mul_add(-yf, 1.0)).sinh().atan().to_degrees();
+    LngLat::new(lng, lat)
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn resolution_tile_sizes() {
+        // 512 px tiles at z are 256 px tiles at z + 1; @2x tiles are 512 px
+        for z in 0..=22 {
+            let zoom = f64::from(z);
+            let r512 = resolution(zoom, 512);
+            assert!((r512 - resolution(zoom + 1.0, 256)).abs() < 1e-9);
+            assert!(
+                (r512 - resolution(zoom, parse_tile_size("@2x").unwrap())).abs()
+                    < 1e-12
+            );
+            assert!((zoom_for_resolution(r512, 512) - zoom).abs() < 1e-9);
+        }
+        assert!(
+            (meters_per_pixel(60.0, 0.0, 256) - resolution(0.0, 256) / 2.0).abs()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn scale_denominators() {
+        let sd = scale_denominator(0.0, 256);
+        assert!((sd - 559_082_264.028_717).abs() < 1e-3, "sd: {sd}");
+        assert!((zoom_for_scale_denominator(sd / 4.0, 256) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tile_pixel_roundtrip() {
+        let (lng, lat) = (-105.0349, 39.9571);
+        for tile_size in [256, 512, 1024] {
+            let (tile, px, py) = lnglat2tile_pixel(lng, lat, 12, tile_size).unwrap();
+            assert_eq!(tile, crate::tile(lng, lat, 12, None).unwrap());
+            assert!((0.0..f64::from(tile_size)).contains(&px));
+            assert!((0.0..f64::from(tile_size)).contains(&py));
+            let ll = tile_pixel2lnglat(&tile, px, py, tile_size);
+            assert!((ll.lng() - lng).abs() < 1e-9);
+            assert!((ll.lat() - lat).abs() < 1e-9);
+        }
+        assert!(parse_tile_size("abc").is_err());
+        assert!(parse_tile_size("@0x").is_err());
+    }
+}
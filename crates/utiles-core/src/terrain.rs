@@ -0,0 +1,153 @@
+//! Terrain-RGB (mapbox) and Terrarium (mapzen) elevation encodings
+//!
+//! Both encodings pack an elevation (meters) into the rgb channels of a
+//! raster tile pixel:
+//!
+//! - Terrain-RGB: `-10000 + (r * 256 * 256 + g * 256 + b) * 0.1`
+//! - Terrarium: `(r * 256 + g + b / 256) - 32768`
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::errors::{UtilesCoreError, UtilesCoreResult};
+
+/// Elevation encoding of raster (dem) tiles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TerrainEncoding {
+    /// Mapbox Terrain-RGB (0.1m precision)
+    #[default]
+    TerrainRgb,
+    /// Mapzen/AWS Terrarium (1/256m precision)
+    Terrarium,
+}
+
+impl TerrainEncoding {
+    /// Decode an elevation (meters) from a pixel's rgb values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use utiles_core::terrain::TerrainEncoding;
+    /// assert_eq!(TerrainEncoding::TerrainRgb.decode([1, 134, 160]), 0.0);
+    /// assert_eq!(TerrainEncoding::Terrarium.decode([128, 0, 0]), 0.0);
+    /// ```
+    #[must_use]
+    pub fn decode(self, rgb: [u8; 3]) -> f64 {
+        let [r, g, b] = rgb.map(f64::from);
+        match self {
+            Self::TerrainRgb => {
+                // 0.1 * v w/o the float error of multiplying by 0.1
+                (r.mul_add(65536.0, g.mul_add(256.0, b)) - 100_000.0) / 10.0
+            }
+            Self::Terrarium => r.mul_add(256.0, g) + b / 256.0 - 32768.0,
+        }
+    }
+
+    /// Encode an elevation (meters) as rgb values (clamped to the range of
+    /// the encoding)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use utiles_core::terrain::TerrainEncoding;
+    /// assert_eq!(TerrainEncoding::TerrainRgb.encode(0.0), [1, 134, 160]);
+    /// assert_eq!(TerrainEncoding::Terrarium.encode(-0.5), [127, 255, 128]);
+    /// ```
+    #[must_use]
+    #[expect(clippy::cast_possible_truncation)]
+    #[expect(clippy::cast_sign_loss)]
+    pub fn encode(self, elevation: f64) -> [u8; 3] {
+        match self {
+            Self::TerrainRgb => {
+                let v = (elevation.mul_add(10.0, 100_000.0))
+                    .round()
+                    .clamp(0.0, 16_777_215.0) as u32;
+                [(v >> 16) as u8, (v >> 8) as u8, v as u8]
+            }
+            Self::Terrarium => {
+                let v = ((elevation + 32768.0) * 256.0)
+                    .round()
+                    .clamp(0.0, 16_777_215.0) as u32;
+                [(v >> 16) as u8, (v >> 8) as u8, v as u8]
+            }
+        }
+    }
+
+    /// Value of the (maplibre/mapbox style) `encoding` raster-dem property
+    #[must_use]
+    pub const fn style_encoding(self) -> &'static str {
+        match self {
+            Self::TerrainRgb => "mapbox",
+            Self::Terrarium => "terrarium",
+        }
+    }
+}
+
+impl Display for TerrainEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::TerrainRgb => "terrain-rgb",
+            Self::Terrarium => "terrarium",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl FromStr for TerrainEncoding {
+    type Err = UtilesCoreError;
+
+    fn from_str(s: &str) -> UtilesCoreResult<Self> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "terrain-rgb" | "terrainrgb" | "mapbox" => Ok(Self::TerrainRgb),
+            "terrarium" | "mapzen" => Ok(Self::Terrarium),
+            _ => Err(UtilesCoreError::ParseError(format!(
+                "invalid terrain encoding: {s} (terrain-rgb/mapbox, terrarium)"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn roundtrip_terrain_rgb() {
+        for elevation in [-10_000.0, -432.1, 0.0, 0.1, 8848.8, 1_667_721.5] {
+            let rgb = TerrainEncoding::TerrainRgb.encode(elevation);
+            let decoded = TerrainEncoding::TerrainRgb.decode(rgb);
+            assert!((decoded - elevation).abs() < 0.05, "{elevation} {decoded}");
+        }
+        // clamped
+        assert_eq!(TerrainEncoding::TerrainRgb.encode(-20_000.0), [0, 0, 0]);
+    }
+
+    #[test]
+    fn roundtrip_terrarium() {
+        for elevation in [-32_768.0, -432.125, 0.0, 8848.86, 32_767.5] {
+            let rgb = TerrainEncoding::Terrarium.encode(elevation);
+            let decoded = TerrainEncoding::Terrarium.decode(rgb);
+            assert!((decoded - elevation).abs() < 1.0 / 256.0, "{elevation}");
+        }
+        assert_eq!(TerrainEncoding::Terrarium.encode(40_000.0), [255, 255, 255]);
+    }
+
+    #[test]
+    fn parse_encoding() {
+        assert_eq!(
+            "mapbox".parse::<TerrainEncoding>().unwrap(),
+            TerrainEncoding::TerrainRgb
+        );
+        assert_eq!(
+            "Terrain_RGB".parse::<TerrainEncoding>().unwrap(),
+            TerrainEncoding::TerrainRgb
+        );
+        assert_eq!(
+            "terrarium".parse::<TerrainEncoding>().unwrap(),
+            TerrainEncoding::Terrarium
+        );
+        assert!("nope".parse::<TerrainEncoding>().is_err());
+        assert_eq!(TerrainEncoding::Terrarium.to_string(), "terrarium");
+    }
+}
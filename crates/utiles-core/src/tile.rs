@@ -187,8 +187,9 @@ impl Tile {
     ///
     /// Returns an error if the tile is invalid (e.g. x or y >= 2^z)
     pub fn try_new(x: u32, y: u32, z: u8) -> UtilesCoreResult<Self> {
-        let max_xy = 2_u32.pow(u32::from(z));
-        if x >= max_xy || y >= max_xy {
+        // 2^z overflows u32 for z >= 32 (any x/y is below 2^z)
+        let max_xy = 2_u32.checked_pow(u32::from(z));
+        if max_xy.is_some_and(|max_xy| x >= max_xy || y >= max_xy) {
             Err(UtilesCoreError::InvalidTile(format!(
                 "(x={x},y={y},z={z}) x < 2^z and y < 2^z",
             )))
@@ -285,7 +286,8 @@ impl Tile {
     ///
     /// # Errors
     ///
-    /// Returns error if serde parsing fails
+    /// Returns error if serde parsing fails or the tile is invalid
+    /// (x or y >= 2^z)
     ///
     /// # Examples
     /// ```
@@ -296,7 +298,7 @@ impl Tile {
     pub fn from_json_obj(json: &str) -> UtilesCoreResult<Self> {
         let res = serde_json::from_str::<Self>(json);
         match res {
-            Ok(tile) => Ok(tile),
+            Ok(tile) => Self::try_new(tile.x, tile.y, tile.z),
             Err(_e) => Err(UtilesCoreError::TileParseError(json.to_string())),
         }
     }
@@ -305,18 +307,20 @@ impl Tile {
     ///
     /// # Errors
     ///
-    /// Returns error if serde parsing fails
+    /// Returns error if serde parsing fails or the tile is invalid
+    /// (x or y >= 2^z)
     ///
     /// # Examples
     /// ```
     /// use utiles_core::Tile;
     /// let tile = Tile::from_json_arr("[1, 2, 3]").unwrap();
     /// assert_eq!(tile, Tile::new(1, 2, 3));
+    /// assert!(Tile::from_json_arr("[1, 0, 0]").is_err());
     /// ```
     pub fn from_json_arr(json: &str) -> UtilesCoreResult<Self> {
         let res = serde_json::from_str::<(u32, u32, u8)>(json);
         match res {
-            Ok((x, y, z)) => Ok(Self::new(x, y, z)),
+            Ok((x, y, z)) => Self::try_new(x, y, z),
             Err(_e) => Err(UtilesCoreError::TileParseError(json.to_string())),
         }
     }
@@ -530,67 +534,73 @@ impl Tile {
     ///
     /// Returns an error if the feature creation fails (which may be impossible [2024-08-14])
     pub fn feature(&self, opts: &FeatureOptions) -> UtilesCoreResult<TileFeature> {
-        let buffer = opts.buffer.unwrap_or(0.0);
-        let precision = opts.precision.unwrap_or(-1);
         // Compute the bounds
         let (west, south, east, north) = self.bbox();
         // Handle projected coordinates
-        let (mut west, mut south, mut east, mut north) = match opts.projection {
-            // Projection::Geographic=> (west, south, east, north),
+        let bounds = match opts.projection {
             Projection::Mercator => {
-                // let (east_merc, north_merc) = utiles_core::xy(east, north, Some(false));
                 let (west_merc, south_merc) = xy(west, south, None);
                 let (east_merc, north_merc) = xy(east, north, None);
                 (west_merc, south_merc, east_merc, north_merc)
             }
             Projection::Geographic => (west, south, east, north),
         };
+        Ok(bounds_feature(self.tuple_string(), bounds, opts))
+    }
+}
 
-        // Apply buffer
-        west -= buffer;
-        south -= buffer;
-        east += buffer;
-        north += buffer;
-
-        // Apply precision
-        if precision >= 0 {
-            let precision_factor = 10_f64.powi(precision);
-            west = (west * precision_factor).round() / precision_factor;
-            south = (south * precision_factor).round() / precision_factor;
-            east = (east * precision_factor).round() / precision_factor;
-            north = (north * precision_factor).round() / precision_factor;
-        }
-
-        // Compute bbox and geometry
-        let bbox = (
-            west.min(east),
-            south.min(north),
-            west.max(east),
-            south.max(north),
-        );
-        let xyz = self.tuple_string();
-        let geometry_coordinates = vec![vec![
-            vec![west, south],
-            vec![east, south],
-            vec![east, north],
-            vec![west, north],
-            vec![west, south],
-        ]];
-        let mut properties: Map<String, Value> = Map::new();
-        properties.insert("title".to_string(), Value::from(format!("XYZ tile {xyz}")));
-        properties.extend(opts.props.clone().unwrap_or_default());
-        let id = opts.fid.clone().unwrap_or(xyz);
-        let tile_feature = TileFeature {
-            id,
-            type_: "Feature".to_string(),
-            geometry: TileFeatureGeometry {
-                type_: "Polygon".to_string(),
-                coordinates: geometry_coordinates,
-            },
-            bbox,
-            properties,
-        };
-        Ok(tile_feature)
+/// Return a polygon feature for (already projected) tile bounds
+pub(crate) fn bounds_feature(
+    xyz: String,
+    bounds: (f64, f64, f64, f64),
+    opts: &FeatureOptions,
+) -> TileFeature {
+    let buffer = opts.buffer.unwrap_or(0.0);
+    let precision = opts.precision.unwrap_or(-1);
+    let (mut west, mut south, mut east, mut north) = bounds;
+
+    // Apply buffer
+    west -= buffer;
+    south -= buffer;
+    east += buffer;
+    north += buffer;
+
+    // Apply precision
+    if precision >= 0 {
+        let precision_factor = 10_f64.powi(precision);
+        west = (west * precision_factor).round() / precision_factor;
+        south = (south * precision_factor).round() / precision_factor;
+        east = (east * precision_factor).round() / precision_factor;
+        north = (north * precision_factor).round() / precision_factor;
+    }
+
+    // Compute bbox and geometry
+    let bbox = (
+        west.min(east),
+        south.min(north),
+        west.max(east),
+        south.max(north),
+    );
+    let geometry_coordinates = vec![vec![
+        vec![west, south],
+        vec![east, south],
+        vec![east, north],
+        vec![west, north],
+        vec![west, south],
+    ]];
+    let mut properties: Map<String, Value> = Map::new();
+    properties.insert("title".to_string(), Value::from(format!("XYZ tile {xyz}")));
+    properties.extend(opts.props.clone().unwrap_or_default());
+    let id = opts.fid.clone().unwrap_or(xyz);
+    TileFeature {
+        id,
+        type_: "Feature".to_string(),
+        geometry: TileFeatureGeometry {
+            type_: "Polygon".to_string(),
+            coordinates: geometry_coordinates,
+        },
+        bbox,
+        properties,
     }
 }
 
@@ -0,0 +1,182 @@
+//! Tile id families ~ hilbert and morton (z-order) curve ids
+//!
+//! Per-zoom ids index a tile within its zoom level (`0..4^z`); global ids
+//! add the number of tiles in all lower zoom levels so ids are unique across
+//! zooms and sort by zoom first (hilbert global ids are pmtiles tile-ids,
+//! so hilbert ids need the `pmtiles` feature).
+//!
+//! Both curves keep nearby tiles close together, which makes them handy
+//! for cache-friendly write orders and for partitioning tiles into ranges.
+use crate::fns::int_2_offset_zoom;
+
+/// Tile id family mapping tiles to integer ids (and back)
+pub trait TileId {
+    /// Name of the id family
+    const NAME: &'static str;
+
+    /// Id of the tile within its zoom level (`0..4^z`)
+    fn zoom_id(x: u32, y: u32, z: u8) -> u64;
+
+    /// Tile x/y of a per-zoom id
+    fn from_zoom_id(id: u64, z: u8) -> (u32, u32);
+
+    /// Id of the tile unique across zoom levels
+    #[must_use]
+    fn global_id(x: u32, y: u32, z: u8) -> u64 {
+        zoom_id_offset(z) + Self::zoom_id(x, y, z)
+    }
+
+    /// Tile x/y/z of a global id
+    #[must_use]
+    fn from_global_id(id: u64) -> (u32, u32, u8) {
+        let (zoom_id, z) = int_2_offset_zoom(id);
+        let (x, y) = Self::from_zoom_id(zoom_id, z);
+        (x, y, z)
+    }
+}
+
+/// Number of tiles in all zoom levels below `z` (first global id at `z`)
+#[must_use]
+pub const fn zoom_id_offset(z: u8) -> u64 {
+    ((1u64 << (2 * z as u32)) - 1) / 3
+}
+
+/// Hilbert curve ids ~ pmtiles tile-ids
+#[cfg(feature = "pmtiles")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hilbert;
+
+/// Morton (z-order) ids ~ interleaved x/y bits (same order as quadkeys)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Morton;
+
+/// Spread the bits of `v` to the even bits of a `u64`
+const fn part1by1(v: u32) -> u64 {
+    let mut v = v as u64;
+    v = (v | (v << 16)) & 0x0000_ffff_0000_ffff;
+    v = (v | (v << 8)) & 0x00ff_00ff_00ff_00ff;
+    v = (v | (v << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+    v
+}
+
+/// Compact the even bits of a `u64` (inverse of `part1by1`)
+#[expect(clippy::cast_possible_truncation)]
+const fn compact1by1(v: u64) -> u32 {
+    let mut v = v & 0x5555_5555_5555_5555;
+    v = (v | (v >> 1)) & 0x3333_3333_3333_3333;
+    v = (v | (v >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | (v >> 4)) & 0x00ff_00ff_00ff_00ff;
+    v = (v | (v >> 8)) & 0x0000_ffff_0000_ffff;
+    v = (v | (v >> 16)) & 0x0000_0000_ffff_ffff;
+    v as u32
+}
+
+/// Morton index of x/y
+#[must_use]
+pub const fn morton_encode(x: u32, y: u32) -> u64 {
+    part1by1(x) | (part1by1(y) << 1)
+}
+
+/// X/y of a morton index
+#[must_use]
+pub const fn morton_decode(m: u64) -> (u32, u32) {
+    (compact1by1(m), compact1by1(m >> 1))
+}
+
+impl TileId for Morton {
+    const NAME: &'static str = "morton";
+
+    fn zoom_id(x: u32, y: u32, _z: u8) -> u64 {
+        morton_encode(x, y)
+    }
+
+    fn from_zoom_id(id: u64, _z: u8) -> (u32, u32) {
+        morton_decode(id)
+    }
+}
+
+#[cfg(feature = "pmtiles")]
+impl TileId for Hilbert {
+    const NAME: &'static str = "hilbert";
+
+    fn zoom_id(x: u32, y: u32, z: u8) -> u64 {
+        if z == 0 {
+            return 0;
+        }
+        fast_hilbert::xy2h(x, y, z)
+    }
+
+    fn from_zoom_id(id: u64, z: u8) -> (u32, u32) {
+        if z == 0 {
+            return (0, 0);
+        }
+        fast_hilbert::h2xy(id, z)
+    }
+
+    fn global_id(x: u32, y: u32, z: u8) -> u64 {
+        crate::pmtiles::xyz2pmid(x, y, z)
+    }
+
+    fn from_global_id(id: u64) -> (u32, u32, u8) {
+        crate::pmtiles::pmid2xyz(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: TileId>(z: u8) {
+        let n = 1u32 << z;
+        let mut ids = Vec::new();
+        for x in 0..n {
+            for y in 0..n {
+                let id = T::zoom_id(x, y, z);
+                assert_eq!(T::from_zoom_id(id, z), (x, y), "{} {x} {y} {z}", T::NAME);
+                assert_eq!(T::from_global_id(T::global_id(x, y, z)), (x, y, z));
+                ids.push(id);
+            }
+        }
+        ids.sort_unstable();
+        let expected = (0..u64::from(n) * u64::from(n)).collect::<Vec<_>>();
+        assert_eq!(ids, expected, "{} ids are a permutation", T::NAME);
+    }
+
+    #[test]
+    fn roundtrips() {
+        for z in 0..6 {
+            #[cfg(feature = "pmtiles")]
+            roundtrip::<Hilbert>(z);
+            roundtrip::<Morton>(z);
+        }
+        let (x, y) = (1_234_567, 7_654_321);
+        #[cfg(feature = "pmtiles")]
+        assert_eq!(
+            Hilbert::from_zoom_id(Hilbert::zoom_id(x, y, 28), 28),
+            (x, y)
+        );
+        assert_eq!(Morton::from_zoom_id(Morton::zoom_id(x, y, 28), 28), (x, y));
+    }
+
+    #[test]
+    fn morton_quadkey_order() {
+        assert_eq!(Morton::zoom_id(1, 0, 1), 1);
+        assert_eq!(Morton::zoom_id(0, 1, 1), 2);
+        assert_eq!(Morton::zoom_id(1, 1, 1), 3);
+        assert_eq!(Morton::global_id(0, 0, 2), 5);
+    }
+
+    #[cfg(feature = "pmtiles")]
+    #[test]
+    fn hilbert_adjacent() {
+        // consecutive hilbert ids are always neighboring tiles
+        let z = 5;
+        for id in 1..(1u64 << (2 * z)) {
+            let (x0, y0) = Hilbert::from_zoom_id(id - 1, z);
+            let (x1, y1) = Hilbert::from_zoom_id(id, z);
+            assert_eq!(x0.abs_diff(x1) + y0.abs_diff(y1), 1);
+        }
+    }
+}
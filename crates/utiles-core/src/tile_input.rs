@@ -0,0 +1,270 @@
+//! Tile input parsing ~ csv/tsv rows, `z/x/y` paths and geojson features
+//!
+//! Complements the json tile parsing (`Tile::from_json`) for tiles coming from
+//! spreadsheets, database dumps, server logs and geojson (e.g. `utiles shapes`
+//! output).
+use serde_json::Value;
+
+use crate::errors::UtilesCoreResult;
+use crate::{Tile, UtilesCoreError, flipy};
+
+/// Parse a tile from the last `z/x/y` segments of a url or file path
+///
+/// The `y` segment may have a file extension and/or a `@2x` style suffix;
+/// query strings and fragments are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use utiles_core::Tile;
+/// use utiles_core::tile_input::parse_tile_path;
+/// let tile = parse_tile_path("https://tiles.example.com/osm/3/2/5.png?key=abc");
+/// assert_eq!(tile, Some(Tile::new(2, 5, 3)));
+/// let tile = parse_tile_path("tiles/3/2/5@2x.webp");
+/// assert_eq!(tile, Some(Tile::new(2, 5, 3)));
+/// assert_eq!(parse_tile_path("3/8/5.png"), None);
+/// ```
+#[must_use]
+pub fn parse_tile_path(path: &str) -> Option<Tile> {
+    let path = path.split(['?', '#']).next().unwrap_or_default().trim();
+    let segments = path.split(['/', '\\']).collect::<Vec<_>>();
+    segments.windows(3).rev().find_map(|w| {
+        let z = w[0].parse::<u8>().ok().filter(|z| *z <= 30)?;
+        let x = w[1].parse::<u32>().ok()?;
+        let ndigits = w[2].bytes().take_while(u8::is_ascii_digit).count();
+        let (y, suffix) = w[2].split_at(ndigits);
+        if !(suffix.is_empty() || suffix.starts_with(['.', '@'])) {
+            return None;
+        }
+        let y = y.parse::<u32>().ok()?;
+        Tile::try_new(x, y, z).ok()
+    })
+}
+
+/// Split a delimited row into fields (double-quoted fields may contain the
+/// delimiter; `""` is an escaped quote)
+fn split_row(row: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// Tile column positions of a csv/tsv header
+///
+/// Recognized column names (case-insensitive):
+///
+/// - `z`, `zoom`, `zoom_level`
+/// - `x`, `col`, `column`, `tile_column`
+/// - `y`, `row`, `tile_row`
+///
+/// Rows of mbtiles-style headers (`tile_row`) are TMS (flipped y).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileColumns {
+    /// Field delimiter
+    pub delimiter: char,
+    /// Index of the zoom column
+    pub z: usize,
+    /// Index of the x column
+    pub x: usize,
+    /// Index of the y column
+    pub y: usize,
+    /// Rows use TMS y (flipped)
+    pub tms: bool,
+}
+
+impl TileColumns {
+    /// Return the tile columns of a header row if it has z, x and y columns
+    #[must_use]
+    pub fn from_header(header: &str, delimiter: char) -> Option<Self> {
+        let names = split_row(header, delimiter)
+            .into_iter()
+            .map(|name| name.to_lowercase())
+            .collect::<Vec<_>>();
+        let position = |candidates: &[&str]| {
+            names.iter().position(|n| candidates.contains(&n.as_str()))
+        };
+        let z = position(&["z", "zoom", "zoom_level"])?;
+        let x = position(&["x", "col", "column", "tile_column"])?;
+        let y = position(&["y", "row", "tile_row"])?;
+        Some(Self {
+            delimiter,
+            z,
+            x,
+            y,
+            tms: names[y] == "tile_row",
+        })
+    }
+
+    /// Return the tile columns of a tab or comma delimited header row
+    #[must_use]
+    pub fn sniff(header: &str) -> Option<Self> {
+        if header.contains('\t') {
+            Self::from_header(header, '\t')
+        } else {
+            Self::from_header(header, ',')
+        }
+    }
+
+    /// Parse a (data) row into a tile
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the row is missing a tile column or is not a valid tile
+    pub fn parse_row(&self, row: &str) -> UtilesCoreResult<Tile> {
+        let fields = split_row(row, self.delimiter);
+        let field = |i: usize| {
+            fields
+                .get(i)
+                .ok_or_else(|| UtilesCoreError::TileParseError(row.to_string()))
+        };
+        let invalid = |_| UtilesCoreError::TileParseError(row.to_string());
+        let z = field(self.z)?.parse::<u8>().map_err(invalid)?;
+        let x = field(self.x)?.parse::<u32>().map_err(invalid)?;
+        let y = field(self.y)?.parse::<u32>().map_err(invalid)?;
+        if z > 30 || y >= 1 << z {
+            return Err(UtilesCoreError::InvalidTile(row.to_string()));
+        }
+        let y = if self.tms { flipy(y, z) } else { y };
+        Tile::try_new(x, y, z)
+    }
+}
+
+/// Parse the tile of a geojson feature
+///
+/// The tile is taken from the feature properties (`x`/`y`/`z` or `tile`) or
+/// the feature id (`(x, y, z)` as written by `utiles shapes` or `z/x/y`).
+///
+/// # Errors
+///
+/// Returns error if the feature has no tile properties/id
+pub fn tile_from_feature(feature: &Value) -> UtilesCoreResult<Tile> {
+    if let Some(props) = feature["properties"].as_object()
+        && let Ok(tile) = Tile::try_from(&Value::Object(props.clone()))
+    {
+        return Ok(tile);
+    }
+    match &feature["id"] {
+        Value::String(id) => {
+            let id = id.trim();
+            if let Some(xyz) = id.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+                Tile::from_json_arr(&format!("[{xyz}]"))
+            } else {
+                parse_tile_path(id)
+                    .ok_or_else(|| UtilesCoreError::TileParseError(id.to_string()))
+            }
+        }
+        id @ Value::Array(_) => Tile::try_from(id),
+        _ => Err(UtilesCoreError::TileParseError(format!(
+            "feature w/o tile properties or id: {feature}"
+        ))),
+    }
+}
+
+/// Parse the tiles of a geojson feature or feature-collection
+///
+/// # Errors
+///
+/// Returns error if any feature has no tile properties/id
+pub fn tiles_from_geojson(geojson: &Value) -> UtilesCoreResult<Vec<Tile>> {
+    match geojson["type"].as_str() {
+        Some("FeatureCollection") => geojson["features"].as_array().map_or_else(
+            || Ok(Vec::new()),
+            |features| features.iter().map(tile_from_feature).collect(),
+        ),
+        Some("Feature") => Ok(vec![tile_from_feature(geojson)?]),
+        _ => Err(UtilesCoreError::TileParseError(format!(
+            "not a geojson feature(-collection): {geojson}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn tile_paths() {
+        let cases = [
+            ("3/2/5", Some(Tile::new(2, 5, 3))),
+            ("/data/tiles/3/2/5.pbf.gz", Some(Tile::new(2, 5, 3))),
+            ("C:\\tiles\\1\\1\\0.png", Some(Tile::new(1, 0, 1))),
+            (
+                "http://x.com/v1/12/654/1583@2x.png#frag",
+                Some(Tile::new(654, 1583, 12)),
+            ),
+            // last valid z/x/y wins
+            ("10/3/2/1", Some(Tile::new(2, 1, 3))),
+            ("3/2/5x.png", None),
+            ("1/2/0", None),
+            ("a/b/c", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(parse_tile_path(path), expected, "path: {path}");
+        }
+    }
+
+    #[test]
+    fn csv_columns() {
+        let cols = TileColumns::sniff("name,x,y,z").unwrap();
+        assert_eq!((cols.delimiter, cols.x, cols.y, cols.z), (',', 1, 2, 3));
+        assert!(!cols.tms);
+        assert_eq!(
+            cols.parse_row("\"a, b\",1,2,3").unwrap(),
+            Tile::new(1, 2, 3)
+        );
+        assert!(cols.parse_row("a,1,2").is_err());
+        assert!(cols.parse_row("a,1,8,3").is_err());
+        assert!(TileColumns::sniff("name,x,y").is_none());
+    }
+
+    #[test]
+    fn tsv_mbtiles_columns() {
+        let cols =
+            TileColumns::sniff("zoom_level\ttile_column\ttile_row\ttile_data").unwrap();
+        assert!(cols.tms);
+        assert_eq!(cols.parse_row("3\t2\t2\tabc").unwrap(), Tile::new(2, 5, 3));
+    }
+
+    #[test]
+    fn geojson_features() {
+        let fc = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "id": "(1, 2, 3)", "properties": {}, "geometry": null},
+                {"type": "Feature", "properties": {"x": 1, "y": 0, "z": 1}, "geometry": null},
+                {"type": "Feature", "properties": {"tile": [0, 0, 0]}, "geometry": null},
+                {"type": "Feature", "id": "4/3/2", "geometry": null},
+            ]
+        });
+        assert_eq!(
+            tiles_from_geojson(&fc).unwrap(),
+            vec![
+                Tile::new(1, 2, 3),
+                Tile::new(1, 0, 1),
+                Tile::new(0, 0, 0),
+                Tile::new(3, 2, 4)
+            ]
+        );
+        let no_tile = serde_json::json!({"type": "Feature", "properties": {"a": 1}});
+        assert!(tiles_from_geojson(&no_tile).is_err());
+    }
+}
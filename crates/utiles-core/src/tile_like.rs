@@ -1,6 +1,9 @@
 use crate::bbox::WebBBox;
 #[cfg(feature = "pmtiles")]
 use crate::pmtiles;
+#[cfg(feature = "pmtiles")]
+use crate::tile_id::Hilbert;
+use crate::tile_id::{Morton, TileId};
 use crate::{
     BBox, LngLat, Tile, TileZBox, flipy, neighbors, neighbors_wrap_x, xyz2rmid,
 };
@@ -114,6 +117,19 @@ pub trait TileLike {
         self.pmtileid()
     }
 
+    /// Return the hilbert curve id of the tile within its zoom level
+    #[cfg(feature = "pmtiles")]
+    #[must_use]
+    fn hilbert_id(&self) -> u64 {
+        Hilbert::zoom_id(self.x(), self.y(), self.z())
+    }
+
+    /// Return the morton (z-order) id of the tile within its zoom level
+    #[must_use]
+    fn morton_id(&self) -> u64 {
+        Morton::zoom_id(self.x(), self.y(), self.z())
+    }
+
     /// Return the row major id for the tile
     #[must_use]
     fn row_major_id(&self) -> u64 {
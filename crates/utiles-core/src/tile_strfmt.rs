@@ -3,7 +3,11 @@ use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
 
 use crate::bbox::WebBBox;
-use crate::{BBox, TileLike};
+#[cfg(feature = "pmtiles")]
+use crate::tile_id::Hilbert;
+use crate::tile_id::{Morton, TileId};
+use crate::tile_type::tiletype_str;
+use crate::{BBox, Tile, TileLike, xy};
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub enum FormatTokens {
@@ -15,12 +19,24 @@ pub enum FormatTokens {
     Quadkey,
     #[cfg(feature = "pmtiles")]
     PmtileId,
+    #[cfg(feature = "pmtiles")]
+    Hilbert,
+    #[cfg(feature = "pmtiles")]
+    HilbertGlobal,
+    Morton,
+    MortonGlobal,
     JsonObj,
     JsonArr,
     GeoBBox,
     Projwin,
     BBoxWeb,
     ProjwinWeb,
+    Lng,
+    Lat,
+    Ul,
+    UlWeb,
+    Wkt,
+    Ext,
 }
 
 impl Display for FormatTokens {
@@ -34,62 +50,324 @@ impl Display for FormatTokens {
             Self::Quadkey => "{quadkey}",
             #[cfg(feature = "pmtiles")]
             Self::PmtileId => "{pmtileid}",
+            #[cfg(feature = "pmtiles")]
+            Self::Hilbert => "{hilbert}",
+            #[cfg(feature = "pmtiles")]
+            Self::HilbertGlobal => "{hilbert_global}",
+            Self::Morton => "{morton}",
+            Self::MortonGlobal => "{morton_global}",
             Self::JsonObj => "{json_obj}",
             Self::JsonArr => "{json_arr}",
             Self::GeoBBox => "{bbox}",
             Self::Projwin => "{projwin}",
             Self::BBoxWeb => "{bbox_web}",
             Self::ProjwinWeb => "{projwin_web}",
+            Self::Lng => "{lng}",
+            Self::Lat => "{lat}",
+            Self::Ul => "{ul}",
+            Self::UlWeb => "{ul_web}",
+            Self::Wkt => "{wkt}",
+            Self::Ext => "{ext}",
         })
     }
 }
 
+impl FormatTokens {
+    /// Token name as used in format strings (w/o braces)
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::X => "x",
+            Self::Y => "y",
+            Self::Z => "z",
+            Self::Yup => "-y",
+            Self::ZxyFslash => "zxy",
+            Self::Quadkey => "quadkey",
+            #[cfg(feature = "pmtiles")]
+            Self::PmtileId => "pmtileid",
+            #[cfg(feature = "pmtiles")]
+            Self::Hilbert => "hilbert",
+            #[cfg(feature = "pmtiles")]
+            Self::HilbertGlobal => "hilbert_global",
+            Self::Morton => "morton",
+            Self::MortonGlobal => "morton_global",
+            Self::JsonObj => "json_obj",
+            Self::JsonArr => "json_arr",
+            Self::GeoBBox => "bbox",
+            Self::Projwin => "projwin",
+            Self::BBoxWeb => "bbox_web",
+            Self::ProjwinWeb => "projwin_web",
+            Self::Lng => "lng",
+            Self::Lat => "lat",
+            Self::Ul => "ul",
+            Self::UlWeb => "ul_web",
+            Self::Wkt => "wkt",
+            Self::Ext => "ext",
+        }
+    }
+
+    /// Token from a (lowercase) token name or alias
+    #[must_use]
+    pub fn from_name(s: &str) -> Option<Self> {
+        let t = match s {
+            "x" => Self::X,
+            "y" => Self::Y,
+            "z" => Self::Z,
+            "yup" | "-y" => Self::Yup,
+            "zxy" => Self::ZxyFslash,
+            "quadkey" | "qk" => Self::Quadkey,
+            #[cfg(feature = "pmtiles")]
+            "pmtileid" | "pmid" => Self::PmtileId,
+            #[cfg(feature = "pmtiles")]
+            "hilbert" => Self::Hilbert,
+            #[cfg(feature = "pmtiles")]
+            "hilbert_global" => Self::HilbertGlobal,
+            "morton" | "zorder" => Self::Morton,
+            "morton_global" | "zorder_global" => Self::MortonGlobal,
+            "json" | "json_arr" => Self::JsonArr,
+            "json_obj" | "obj" => Self::JsonObj,
+            "bbox" => Self::GeoBBox,
+            "projwin" => Self::Projwin,
+            "bbox_web" => Self::BBoxWeb,
+            "projwin_web" => Self::ProjwinWeb,
+            "lng" | "lon" => Self::Lng,
+            "lat" => Self::Lat,
+            "ul" => Self::Ul,
+            "ul_web" => Self::UlWeb,
+            "wkt" => Self::Wkt,
+            "ext" => Self::Ext,
+            _ => return None,
+        };
+        Some(t)
+    }
+
+    /// Return true if the token is an integer (supports `+n`/`-n` offsets)
+    #[must_use]
+    pub const fn is_integer(&self) -> bool {
+        matches!(self, Self::X | Self::Y | Self::Z | Self::Yup)
+    }
+}
+
+/// Parent tile selector of a format expression
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash)]
+pub enum ParentLevel {
+    /// Parent (ancestor) at zoom level ~ `parent(10)`
+    Zoom(u8),
+    /// Parent n levels up ~ `parent(z-2)` (`parent` is `parent(z-1)`)
+    Up(u8),
+}
+
+impl ParentLevel {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        match s.strip_prefix('z') {
+            Some(up) => up
+                .trim()
+                .strip_prefix('-')?
+                .trim()
+                .parse()
+                .ok()
+                .map(Self::Up),
+            None => s.parse().ok().map(Self::Zoom),
+        }
+    }
+
+    /// Zoom level of the parent of a tile at zoom `z` (clamped to `0..=z`)
+    #[must_use]
+    pub fn zoom(self, z: u8) -> u8 {
+        match self {
+            Self::Zoom(pz) => pz.min(z),
+            Self::Up(n) => z.saturating_sub(n),
+        }
+    }
+}
+
+impl Display for ParentLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Zoom(z) => write!(f, "parent({z})"),
+            Self::Up(n) => write!(f, "parent(z-{n})"),
+        }
+    }
+}
+
+/// Width/zero-padding/precision of a format expression ~ `:06`, `:8`, `:.5`
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash, Default)]
+pub struct FormatSpec {
+    pub zero_pad: bool,
+    pub width: usize,
+    pub precision: Option<usize>,
+}
+
+impl FormatSpec {
+    fn parse(s: &str) -> Option<Self> {
+        let (width, precision) = match s.split_once('.') {
+            Some((w, p)) => (w, Some(p.parse().ok()?)),
+            None => (s, None),
+        };
+        Some(Self {
+            zero_pad: width.len() > 1 && width.starts_with('0'),
+            width: if width.is_empty() {
+                0
+            } else {
+                width.parse().ok()?
+            },
+            precision,
+        })
+    }
+
+    /// Pad a formatted value to the spec width (zero padding keeps the sign first)
+    fn pad(&self, s: String) -> String {
+        if s.len() >= self.width {
+            s
+        } else if self.zero_pad {
+            let (sign, digits) = s
+                .strip_prefix('-')
+                .map_or(("", s.as_str()), |digits| ("-", digits));
+            format!("{sign}{digits:0>w$}", w = self.width - sign.len())
+        } else {
+            format!("{s:>w$}", w = self.width)
+        }
+    }
+}
+
+impl Display for FormatSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.zero_pad {
+            f.write_str("0")?;
+        }
+        if self.width > 0 {
+            write!(f, "{}", self.width)?;
+        }
+        if let Some(p) = self.precision {
+            write!(f, ".{p}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Format token w/ parent selector, integer offset and spec
+///
+/// `{parent(10).zxy}`, `{z+1}`, `{x:06}`, `{parent(z-2).y-1:04}`, `{lng:.5}`
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
-pub enum FormatParts {
-    Str(String),
-    Token(FormatTokens),
+pub struct FormatExpr {
+    pub parent: Option<ParentLevel>,
+    pub token: FormatTokens,
+    pub offset: i64,
+    pub spec: FormatSpec,
 }
 
-impl From<FormatTokens> for &'static str {
-    fn from(t: FormatTokens) -> Self {
-        match t {
-            FormatTokens::X => "{x}",
-            FormatTokens::Y => "{y}",
-            FormatTokens::Z => "{z}",
-            FormatTokens::Yup => "{-y}",
-            FormatTokens::ZxyFslash => "{z}/{x}/{y}",
-            FormatTokens::Quadkey => "{quadkey}",
-            #[cfg(feature = "pmtiles")]
-            FormatTokens::PmtileId => "{pmtileid}",
-            FormatTokens::JsonObj => "{json_obj}",
-            FormatTokens::JsonArr => "{json_arr}",
-            FormatTokens::GeoBBox => "{bbox}",
-            FormatTokens::Projwin => "{projwin}",
-            FormatTokens::BBoxWeb => "{bbox_web}",
-            FormatTokens::ProjwinWeb => "{projwin_web}",
+impl FormatExpr {
+    /// Parse a format expression (contents of `{...}`)
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        let (expr, spec) = match s.rsplit_once(':') {
+            Some((expr, spec)) => (expr, FormatSpec::parse(spec)?),
+            None => (s, FormatSpec::default()),
+        };
+        let (parent, expr) = match expr.strip_prefix("parent") {
+            Some(rest) => {
+                let (level, rest) = match rest.strip_prefix('(') {
+                    Some(rest) => {
+                        let (arg, rest) = rest.split_once(')')?;
+                        (ParentLevel::parse(arg)?, rest)
+                    }
+                    None => (ParentLevel::Up(1), rest),
+                };
+                (Some(level), rest.strip_prefix('.')?)
+            }
+            None => (None, expr),
+        };
+        // offset sign after the first char (`-y` is a token name)
+        let (name, offset) = match expr
+            .char_indices()
+            .skip(1)
+            .filter(|(_, c)| *c == '+' || *c == '-')
+            .last()
+        {
+            Some((i, _)) => (&expr[..i], expr[i..].trim().parse::<i64>().ok()?),
+            None => (expr, 0),
+        };
+        let token = FormatTokens::from_name(name.trim())?;
+        if offset != 0 && !token.is_integer() {
+            return None;
+        }
+        Some(Self {
+            parent,
+            token,
+            offset,
+            spec,
+        })
+    }
+
+    /// Format a tile (and tile data extension) w/ the expression
+    fn fmt_tile(&self, tile: &dyn TileLike, ext: &str) -> String {
+        let t = match self.parent {
+            Some(level) => {
+                let shift = tile.z() - level.zoom(tile.z());
+                Tile {
+                    x: tile.x() >> shift,
+                    y: tile.y() >> shift,
+                    z: tile.z() - shift,
+                }
+            }
+            None => Tile {
+                x: tile.x(),
+                y: tile.y(),
+                z: tile.z(),
+            },
+        };
+        let precision = self.spec.precision;
+        let s = match self.token {
+            FormatTokens::X => (i64::from(t.x) + self.offset).to_string(),
+            FormatTokens::Y => (i64::from(t.y) + self.offset).to_string(),
+            FormatTokens::Z => (i64::from(t.z) + self.offset).to_string(),
+            FormatTokens::Yup => (i64::from(t.yup()) + self.offset).to_string(),
+            FormatTokens::Lng => fmt_f64(t.center().lng(), precision),
+            FormatTokens::Lat => fmt_f64(t.center().lat(), precision),
+            FormatTokens::Ul => ul_str(&t, precision),
+            FormatTokens::UlWeb => ul_web_str(&t, precision),
+            FormatTokens::Wkt => wkt_str(&t, precision),
+            FormatTokens::Ext => ext.to_string(),
+            ref token => token_fn(token)(&t),
+        };
+        self.spec.pad(s)
+    }
+}
+
+impl Display for FormatExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{")?;
+        if let Some(parent) = self.parent {
+            write!(f, "{parent}.")?;
+        }
+        f.write_str(self.token.name())?;
+        if self.offset != 0 {
+            write!(f, "{:+}", self.offset)?;
         }
+        if self.spec != FormatSpec::default() {
+            write!(f, ":{}", self.spec)?;
+        }
+        f.write_str("}")
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub enum FormatParts {
+    Str(String),
+    Token(FormatTokens),
+    Expr(FormatExpr),
+}
+
 impl From<&str> for FormatParts {
     fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "x" => Self::Token(FormatTokens::X),
-            "y" => Self::Token(FormatTokens::Y),
-            "z" => Self::Token(FormatTokens::Z),
-            "yup" | "-y" => Self::Token(FormatTokens::Yup),
-            "zxy" => Self::Token(FormatTokens::ZxyFslash),
-            "quadkey" | "qk" => Self::Token(FormatTokens::Quadkey),
-            #[cfg(feature = "pmtiles")]
-            "pmtileid" | "pmid" => Self::Token(FormatTokens::PmtileId),
-            "json" | "json_arr" => Self::Token(FormatTokens::JsonArr),
-            "json_obj" | "obj" => Self::Token(FormatTokens::JsonObj),
-            "bbox" => Self::Token(FormatTokens::GeoBBox),
-            "projwin" => Self::Token(FormatTokens::Projwin),
-            "bbox_web" => Self::Token(FormatTokens::BBoxWeb),
-            "projwin_web" => Self::Token(FormatTokens::ProjwinWeb),
-
-            _ => Self::Str(s.to_string()),
+        let lower = s.to_lowercase();
+        if let Some(token) = FormatTokens::from_name(&lower) {
+            Self::Token(token)
+        } else if let Some(expr) = FormatExpr::parse(&lower) {
+            Self::Expr(expr)
+        } else {
+            Self::Str(s.to_string())
         }
     }
 }
@@ -105,12 +383,24 @@ impl From<&FormatTokens> for String {
             FormatTokens::Quadkey => "{quadkey}".to_string(),
             #[cfg(feature = "pmtiles")]
             FormatTokens::PmtileId => "{pmtileid}".to_string(),
+            #[cfg(feature = "pmtiles")]
+            FormatTokens::Hilbert => "{hilbert}".to_string(),
+            #[cfg(feature = "pmtiles")]
+            FormatTokens::HilbertGlobal => "{hilbert_global}".to_string(),
+            FormatTokens::Morton => "{morton}".to_string(),
+            FormatTokens::MortonGlobal => "{morton_global}".to_string(),
             FormatTokens::JsonObj => "{json_obj}".to_string(),
             FormatTokens::JsonArr => "{json_arr}".to_string(),
             FormatTokens::GeoBBox => "{bbox}".to_string(),
             FormatTokens::Projwin => "{projwin}".to_string(),
             FormatTokens::BBoxWeb => "{bbox_web}".to_string(),
             FormatTokens::ProjwinWeb => "{projwin_web}".to_string(),
+            FormatTokens::Lng => "{lng}".to_string(),
+            FormatTokens::Lat => "{lat}".to_string(),
+            FormatTokens::Ul => "{ul}".to_string(),
+            FormatTokens::UlWeb => "{ul_web}".to_string(),
+            FormatTokens::Wkt => "{wkt}".to_string(),
+            FormatTokens::Ext => "{ext}".to_string(),
         }
     }
 }
@@ -120,6 +410,7 @@ impl From<&FormatParts> for String {
         match p {
             FormatParts::Str(s) => s.clone(), // yolo clone here
             FormatParts::Token(t) => Self::from(t),
+            FormatParts::Expr(e) => e.to_string(),
         }
     }
 }
@@ -228,6 +519,88 @@ impl PartialEq<Self> for TileStringFormatter {
 enum FmtPart {
     Static(&'static str),
     Dynamic(fn(&dyn TileLike) -> String),
+    Ext,
+    Expr(FormatExpr),
+}
+
+fn fmt_f64(v: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(p) => format!("{v:.p$}"),
+        None => v.to_string(),
+    }
+}
+
+/// Upper-left corner lng/lat ~ `"lng lat"`
+fn ul_str(tile: &dyn TileLike, precision: Option<usize>) -> String {
+    let ul = tile.ul();
+    format!(
+        "{} {}",
+        fmt_f64(ul.lng(), precision),
+        fmt_f64(ul.lat(), precision)
+    )
+}
+
+/// Upper-left corner web-mercator x/y ~ `"x y"`
+fn ul_web_str(tile: &dyn TileLike, precision: Option<usize>) -> String {
+    let ul = tile.ul();
+    let (x, y) = xy(ul.lng(), ul.lat(), None);
+    format!("{} {}", fmt_f64(x, precision), fmt_f64(y, precision))
+}
+
+/// Tile polygon (lng/lat) as WKT
+fn wkt_str(tile: &dyn TileLike, precision: Option<usize>) -> String {
+    let (w, s, e, n) = tile.bbox();
+    let [w, s, e, n] = [w, s, e, n].map(|v| fmt_f64(v, precision));
+    format!("POLYGON (({w} {s}, {e} {s}, {e} {n}, {w} {n}, {w} {s}))")
+}
+
+/// Format function for a (tile-data independent) token
+fn token_fn(token: &FormatTokens) -> fn(&dyn TileLike) -> String {
+    match token {
+        FormatTokens::X => |tile| tile.x().to_string(),
+        FormatTokens::Y => |tile| tile.y().to_string(),
+        FormatTokens::Yup => |tile| tile.yup().to_string(),
+        FormatTokens::Z => |tile| tile.z().to_string(),
+        FormatTokens::ZxyFslash => |tile| tile.zxy_str_fslash(),
+        FormatTokens::Quadkey => |tile| tile.quadkey(),
+        #[cfg(feature = "pmtiles")]
+        FormatTokens::PmtileId => |tile| tile.pmtileid().to_string(),
+        #[cfg(feature = "pmtiles")]
+        FormatTokens::Hilbert => |tile| tile.hilbert_id().to_string(),
+        #[cfg(feature = "pmtiles")]
+        FormatTokens::HilbertGlobal => {
+            |tile| Hilbert::global_id(tile.x(), tile.y(), tile.z()).to_string()
+        }
+        FormatTokens::Morton => |tile| tile.morton_id().to_string(),
+        FormatTokens::MortonGlobal => {
+            |tile| Morton::global_id(tile.x(), tile.y(), tile.z()).to_string()
+        }
+        FormatTokens::JsonArr => |tile| tile.json_arr(),
+        FormatTokens::JsonObj => |tile| tile.json_obj(),
+        FormatTokens::GeoBBox => |tile| {
+            let b: BBox = tile.bbox().into();
+            b.json_arr()
+        },
+        FormatTokens::Projwin => |tile| {
+            let b: BBox = tile.bbox().into();
+            b.projwin_str()
+        },
+        FormatTokens::ProjwinWeb => |tile| {
+            let b: WebBBox = tile.webbbox();
+            b.projwin_str()
+        },
+        FormatTokens::BBoxWeb => |tile| {
+            let b: WebBBox = tile.webbbox();
+            b.json_arr()
+        },
+        FormatTokens::Lng => |tile| tile.center().lng().to_string(),
+        FormatTokens::Lat => |tile| tile.center().lat().to_string(),
+        FormatTokens::Ul => |tile| ul_str(tile, None),
+        FormatTokens::UlWeb => |tile| ul_web_str(tile, None),
+        FormatTokens::Wkt => |tile| wkt_str(tile, None),
+        // resolved from tile data (see `FmtPart::Ext`)
+        FormatTokens::Ext => |_| String::new(),
+    }
 }
 
 impl TileStringFormatter {
@@ -245,62 +618,9 @@ impl TileStringFormatter {
                 FormatParts::Str(s) => {
                     parts.push(FmtPart::Static(Box::leak(s.clone().into_boxed_str())));
                 }
-                FormatParts::Token(t) => match t {
-                    FormatTokens::X => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.x().to_string()));
-                    }
-                    FormatTokens::Y => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.y().to_string()));
-                    }
-                    FormatTokens::Yup => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.yup().to_string()));
-                    }
-                    FormatTokens::Z => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.z().to_string()));
-                    }
-                    FormatTokens::ZxyFslash => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.zxy_str_fslash()));
-                    }
-                    FormatTokens::Quadkey => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.quadkey()));
-                    }
-                    #[cfg(feature = "pmtiles")]
-                    FormatTokens::PmtileId => {
-                        parts
-                            .push(FmtPart::Dynamic(|tile| tile.pmtileid().to_string()));
-                    }
-                    FormatTokens::JsonArr => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.json_arr()));
-                    }
-                    FormatTokens::JsonObj => {
-                        parts.push(FmtPart::Dynamic(|tile| tile.json_obj()));
-                    }
-                    FormatTokens::GeoBBox => {
-                        parts.push(FmtPart::Dynamic(|tile| {
-                            let b: BBox = tile.bbox().into();
-                            b.json_arr()
-                        }));
-                    }
-                    FormatTokens::Projwin => {
-                        parts.push(FmtPart::Dynamic(|tile| {
-                            let b: BBox = tile.bbox().into();
-                            b.projwin_str()
-                        }));
-                    }
-
-                    FormatTokens::ProjwinWeb => {
-                        parts.push(FmtPart::Dynamic(|tile| {
-                            let b: WebBBox = tile.webbbox();
-                            b.projwin_str()
-                        }));
-                    }
-                    FormatTokens::BBoxWeb => {
-                        parts.push(FmtPart::Dynamic(|tile| {
-                            let b: WebBBox = tile.webbbox();
-                            b.json_arr()
-                        }));
-                    }
-                },
+                FormatParts::Token(FormatTokens::Ext) => parts.push(FmtPart::Ext),
+                FormatParts::Token(t) => parts.push(FmtPart::Dynamic(token_fn(t))),
+                FormatParts::Expr(e) => parts.push(FmtPart::Expr(e.clone())),
             }
         }
         parts
@@ -317,16 +637,42 @@ impl TileStringFormatter {
     }
 
     pub fn fmt_tile_custom<T: TileLike>(&self, tile: &T) -> String {
+        self.fmt_tile_ext(tile, "")
+    }
+
+    /// Format a tile w/ `{ext}` resolved to the given extension
+    pub fn fmt_tile_ext<T: TileLike>(&self, tile: &T, ext: &str) -> String {
         let mut out = String::with_capacity(self.tile_fmt.fmtstr.len() * 2); // Assuming average length doubling due to replacements
         for part in &self.parts {
             match part {
                 FmtPart::Static(s) => out.push_str(s),
                 FmtPart::Dynamic(f) => out.push_str(&f(tile)),
+                FmtPart::Ext => out.push_str(ext),
+                FmtPart::Expr(e) => out.push_str(&e.fmt_tile(tile, ext)),
             }
         }
         out
     }
 
+    /// Format a tile w/ `{ext}` resolved from the tile data (`png`, `pbf.gz`...)
+    pub fn fmt_tile_data<T: TileLike>(&self, tile: &T, data: &[u8]) -> String {
+        if self.uses_ext() {
+            self.fmt_tile_ext(tile, &tiletype_str(data))
+        } else {
+            self.fmt_tile(tile)
+        }
+    }
+
+    /// Return true if the format uses the tile data extension (`{ext}`)
+    #[must_use]
+    pub fn uses_ext(&self) -> bool {
+        self.tile_fmt.tokens.iter().any(|t| match t {
+            FormatParts::Token(token) => *token == FormatTokens::Ext,
+            FormatParts::Expr(e) => e.token == FormatTokens::Ext,
+            FormatParts::Str(_) => false,
+        })
+    }
+
     pub fn fmt_tile<T: TileLike>(&self, tile: &T) -> String {
         match self.tile_fmt.fmtstr.as_str() {
             "{json_arr}" => tile.json_arr(),
@@ -360,7 +706,6 @@ impl Default for TileStringFormatter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Tile;
 
     #[test]
     fn test_formatter_zxy() {
@@ -422,4 +767,68 @@ mod tests {
         assert_eq!(f.n_tokens(), 1);
         assert_eq!(f.fmt_tile(&tile), "tiles/3/1/2");
     }
+
+    #[cfg(feature = "pmtiles")]
+    #[test]
+    fn test_formatter_curve_ids() {
+        let f = TileStringFormatter::new("{hilbert} {morton} {morton_global}");
+        assert_eq!(f.n_tokens(), 3);
+        let tile = Tile::new(1, 0, 1);
+        assert_eq!(f.fmt_tile(&tile), "3 1 2");
+    }
+
+    #[test]
+    fn test_formatter_exprs() {
+        let tile = Tile::new(486, 332, 10);
+        let f = TileStringFormatter::new("{z:02}/{x:06}/{y:6}");
+        assert_eq!(f.fmt_tile(&tile), "10/000486/   332");
+        let f =
+            TileStringFormatter::new("{parent(8).zxy} {parent.zxy} {parent(z-2).x}");
+        assert_eq!(f.fmt_tile(&tile), "8/121/83 9/243/166 121");
+        let f = TileStringFormatter::new("{z+1}/{x-1:04}/{-y+1}");
+        assert_eq!(f.fmt_tile(&tile), "11/0485/692");
+        // parent at/below zoom ~ tile itself
+        let f = TileStringFormatter::new("{parent(12).zxy}");
+        assert_eq!(f.fmt_tile(&tile), "10/486/332");
+        // unknown/invalid expressions are kept as strings
+        let f = TileStringFormatter::new("{quadkey+1}{nope}");
+        assert_eq!(f.n_tokens(), 0);
+    }
+
+    #[test]
+    fn test_formatter_expr_fmtstr() {
+        let fmt = "{parent(z-2).y-1:04}{parent(3).quadkey}{lng:.3}";
+        let f = TileStringFormatter::new(fmt);
+        assert_eq!(f.n_tokens(), 3);
+        assert_eq!(f.fmtstr(), fmt);
+        assert_eq!(TileStringFormatter::new(f.fmtstr()), f);
+    }
+
+    #[test]
+    fn test_formatter_geo_tokens() {
+        let tile = Tile::new(0, 0, 1);
+        let f = TileStringFormatter::new("{lng} {lat:.4}|{ul}|{ul_web:.1}");
+        assert_eq!(
+            f.fmt_tile(&tile),
+            "-90 42.5256|-180 85.0511287798066|-20037508.3 20037508.3"
+        );
+        let f = TileStringFormatter::new("{wkt:.0}");
+        assert_eq!(
+            f.fmt_tile(&Tile::new(0, 0, 0)),
+            "POLYGON ((-180 -85, 180 -85, 180 85, -180 85, -180 -85))"
+        );
+    }
+
+    #[test]
+    fn test_formatter_ext() {
+        let tile = Tile::new(1, 2, 3);
+        let f = TileStringFormatter::new("tiles/{zxy}.{ext}");
+        assert!(f.uses_ext());
+        assert_eq!(f.fmt_tile_ext(&tile, "png"), "tiles/3/1/2.png");
+        assert_eq!(
+            f.fmt_tile_data(&tile, b"\x89PNG\r\n\x1a\n\0\0\0\0"),
+            "tiles/3/1/2.png"
+        );
+        assert!(!TileStringFormatter::new("{zxy}").uses_ext());
+    }
 }
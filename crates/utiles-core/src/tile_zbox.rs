@@ -1,11 +1,12 @@
 //! `TileZBox` - zoom-x-y bounding box
 
 use fns::flipy;
+use serde::{Deserialize, Serialize};
 
 use crate::{Point2d, Tile, TileLike, UtilesCoreError, UtilesCoreResult, fns};
 
 /// A struct representing a bbox of tiles at a specific zoom level
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TileZBox {
     pub zoom: u8,
     pub min: Point2d<u32>,
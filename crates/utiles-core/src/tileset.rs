@@ -0,0 +1,558 @@
+//! `TileSet` - compact tile sets as per-zoom z-order (morton) ranges
+//!
+//! Tiles are keyed by their morton/z-order index (`x` and `y` bits
+//! interleaved; same order as quadkeys) and stored as sorted, disjoint,
+//! inclusive ranges per zoom. Every quadtree-aligned block of tiles is a
+//! single contiguous range, so the coverage of a polygon at high zooms is a
+//! few ranges per boundary tile rather than one entry per tile, and set
+//! algebra is a linear merge of the ranges.
+//!
+//! Zoom levels are independent; a tile at z10 and its children at z11 are
+//! different members (use `simplify` to merge coverages across zooms).
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fns::flipy;
+use crate::tile_id::{morton_decode, morton_encode};
+use crate::{Tile, TileLike, TileZBox, UtilesCoreError, UtilesCoreResult};
+
+/// Inclusive range of morton indices
+type Range = (u64, u64);
+
+/// Number of tiles at a zoom level
+const fn zoom_ntiles(zoom: u8) -> u64 {
+    1u64 << (2 * zoom as u32)
+}
+
+/// Push a range onto sorted ranges merging overlapping/adjacent ranges
+fn push_range(ranges: &mut Vec<Range>, (start, end): Range) {
+    match ranges.last_mut() {
+        Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+        _ => ranges.push((start, end)),
+    }
+}
+
+/// Quadtree-aligned `TileZBox` blocks of the ranges of a zoom (z-order)
+fn zoom_zboxes(zoom: u8, ranges: &[Range]) -> impl Iterator<Item = TileZBox> + '_ {
+    ranges.iter().flat_map(move |(start, end)| {
+        let (mut m, end) = (*start, *end);
+        std::iter::from_fn(move || {
+            if m > end {
+                return None;
+            }
+            // largest aligned block starting at m that fits the range
+            let mut level = 0u8;
+            while level < zoom
+                && m % zoom_ntiles(level + 1) == 0
+                && m + zoom_ntiles(level + 1) - 1 <= end
+            {
+                level += 1;
+            }
+            let (x, y) = morton_decode(m);
+            let size = 1u32 << level;
+            m += zoom_ntiles(level);
+            Some(TileZBox::new(x, x + size - 1, y, y + size - 1, zoom))
+        })
+    })
+}
+
+/// `OR` clauses as a balanced tree (depth `log2(n)` rather than `n`)
+fn balanced_or(clauses: &[String]) -> String {
+    if let [clause] = clauses {
+        return clause.clone();
+    }
+    let (left, right) = clauses.split_at(clauses.len() / 2);
+    format!("({} OR {})", balanced_or(left), balanced_or(right))
+}
+
+fn ranges_union(a: &[Range], b: &[Range]) -> Vec<Range> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let next = if j >= b.len() || (i < a.len() && a[i].0 <= b[j].0) {
+            i += 1;
+            a[i - 1]
+        } else {
+            j += 1;
+            b[j - 1]
+        };
+        push_range(&mut merged, next);
+    }
+    merged
+}
+
+fn ranges_intersection(a: &[Range], b: &[Range]) -> Vec<Range> {
+    let mut intersection = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = a[i].0.max(b[j].0);
+        let end = a[i].1.min(b[j].1);
+        if start <= end {
+            intersection.push((start, end));
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    intersection
+}
+
+fn ranges_difference(a: &[Range], b: &[Range]) -> Vec<Range> {
+    let mut difference = Vec::new();
+    let mut j = 0;
+    for (start, end) in a {
+        let mut cur = *start;
+        while j < b.len() && b[j].1 < cur {
+            j += 1;
+        }
+        let mut k = j;
+        while k < b.len() && b[k].0 <= *end {
+            if b[k].0 > cur {
+                difference.push((cur, b[k].0 - 1));
+            }
+            cur = cur.max(b[k].1.saturating_add(1));
+            k += 1;
+        }
+        if cur <= *end {
+            difference.push((cur, *end));
+        }
+    }
+    difference
+}
+
+/// Compact set of tiles stored as per-zoom morton (z-order) ranges
+///
+/// Serializes to json as `{"<zoom>": [[start, end], ...]}` w/ inclusive
+/// morton index ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    try_from = "BTreeMap<u8, Vec<(u64, u64)>>",
+    into = "BTreeMap<u8, Vec<(u64, u64)>>"
+)]
+pub struct TileSet {
+    zooms: BTreeMap<u8, Vec<Range>>,
+}
+
+impl TileSet {
+    /// Create a new empty `TileSet`
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return true if the set contains no tiles
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.zooms.is_empty()
+    }
+
+    /// Return the number of tiles in the set
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.zooms
+            .values()
+            .flatten()
+            .map(|(start, end)| end - start + 1)
+            .sum()
+    }
+
+    /// Return the number of ranges used to store the set
+    #[must_use]
+    pub fn nranges(&self) -> usize {
+        self.zooms.values().map(Vec::len).sum()
+    }
+
+    /// Return the zoom levels w/ tiles in the set
+    #[must_use]
+    pub fn zooms(&self) -> Vec<u8> {
+        self.zooms.keys().copied().collect()
+    }
+
+    /// Return the morton index ranges for a zoom level
+    #[must_use]
+    pub fn ranges(&self, zoom: u8) -> &[(u64, u64)] {
+        self.zooms.get(&zoom).map_or(&[], Vec::as_slice)
+    }
+
+    /// Return true if the set contains the tile
+    #[must_use]
+    pub fn contains<T: TileLike>(&self, tile: &T) -> bool {
+        let m = morton_encode(tile.x(), tile.y());
+        let ranges = self.ranges(tile.z());
+        let idx = ranges.partition_point(|(_, end)| *end < m);
+        ranges.get(idx).is_some_and(|(start, _)| *start <= m)
+    }
+
+    fn union_ranges(&mut self, zoom: u8, ranges: &[Range]) {
+        if ranges.is_empty() {
+            return;
+        }
+        let merged = ranges_union(self.ranges(zoom), ranges);
+        self.zooms.insert(zoom, merged);
+    }
+
+    /// Insert a tile into the set
+    pub fn insert<T: TileLike>(&mut self, tile: &T) {
+        let m = morton_encode(tile.x(), tile.y());
+        self.union_ranges(tile.z(), &[(m, m)]);
+    }
+
+    /// Insert all tiles of a `TileZBox` into the set
+    ///
+    /// The box is decomposed into quadtree-aligned blocks (one range each).
+    pub fn insert_zbox(&mut self, zbox: &TileZBox) {
+        let mut ranges = Vec::new();
+        // (block x, block y, block level) in z-order
+        let mut stack = vec![(0u32, 0u32, zbox.zoom)];
+        while let Some((bx, by, level)) = stack.pop() {
+            let size = 1u32 << level;
+            let (minx, miny) = (bx * size, by * size);
+            let (maxx, maxy) = (minx + (size - 1), miny + (size - 1));
+            if maxx < zbox.minx()
+                || minx > zbox.maxx()
+                || maxy < zbox.miny()
+                || miny > zbox.maxy()
+            {
+                continue;
+            }
+            if minx >= zbox.minx()
+                && maxx <= zbox.maxx()
+                && miny >= zbox.miny()
+                && maxy <= zbox.maxy()
+            {
+                let start = morton_encode(minx, miny);
+                push_range(&mut ranges, (start, start + zoom_ntiles(level) - 1));
+                continue;
+            }
+            // children pushed in reverse so they pop in z-order
+            let (cx, cy) = (bx * 2, by * 2);
+            stack.extend([
+                (cx + 1, cy + 1, level - 1),
+                (cx, cy + 1, level - 1),
+                (cx + 1, cy, level - 1),
+                (cx, cy, level - 1),
+            ]);
+        }
+        self.union_ranges(zbox.zoom, &ranges);
+    }
+
+    /// Return the union of two sets
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        for (zoom, ranges) in &other.zooms {
+            union.union_ranges(*zoom, ranges);
+        }
+        union
+    }
+
+    /// Return the intersection of two sets
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        let zooms = self
+            .zooms
+            .iter()
+            .filter_map(|(zoom, ranges)| {
+                let other_ranges = other.zooms.get(zoom)?;
+                let intersection = ranges_intersection(ranges, other_ranges);
+                (!intersection.is_empty()).then_some((*zoom, intersection))
+            })
+            .collect();
+        Self { zooms }
+    }
+
+    /// Return the tiles in `self` that are not in `other`
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        let zooms = self
+            .zooms
+            .iter()
+            .filter_map(|(zoom, ranges)| {
+                let difference = match other.zooms.get(zoom) {
+                    Some(other_ranges) => ranges_difference(ranges, other_ranges),
+                    None => ranges.clone(),
+                };
+                (!difference.is_empty()).then_some((*zoom, difference))
+            })
+            .collect();
+        Self { zooms }
+    }
+
+    /// Iterate over the tiles in z-order (zoom, then morton index)
+    pub fn iter(&self) -> impl Iterator<Item = Tile> + '_ {
+        self.zooms.iter().flat_map(|(zoom, ranges)| {
+            ranges.iter().flat_map(move |(start, end)| {
+                (*start..=*end).map(move |m| {
+                    let (x, y) = morton_decode(m);
+                    Tile::new(x, y, *zoom)
+                })
+            })
+        })
+    }
+
+    /// Iterate over the set as quadtree-aligned `TileZBox` blocks (z-order)
+    pub fn zboxes(&self) -> impl Iterator<Item = TileZBox> + '_ {
+        self.zooms
+            .iter()
+            .flat_map(|(zoom, ranges)| zoom_zboxes(*zoom, ranges))
+    }
+
+    /// Return the SQL `WHERE` clause for tms mbtiles like db with optional
+    /// prefix for column names
+    ///
+    /// Adjacent blocks spanning the same rows are merged to keep the clause
+    /// short and the terms are `OR`-ed as a balanced tree so the expression
+    /// depth stays far below sqlite's limit (1000) for any number of blocks;
+    /// an empty set returns a clause matching nothing.
+    #[must_use]
+    pub fn mbtiles_sql_where_prefix(&self, prefix: Option<&str>) -> String {
+        let mut clauses: Vec<String> = Vec::new();
+        for (zoom, ranges) in &self.zooms {
+            if ranges.first() == Some(&(0, zoom_ntiles(*zoom) - 1)) {
+                clauses.push(format!("(zoom_level = {zoom})"));
+                continue;
+            }
+            let mut blocks = zoom_zboxes(*zoom, ranges).collect::<Vec<_>>();
+            blocks.sort_unstable_by_key(|zbox| (zbox.miny(), zbox.maxy(), zbox.minx()));
+            let mut zboxes: Vec<TileZBox> = Vec::new();
+            for zbox in blocks {
+                match zboxes.last_mut() {
+                    Some(last)
+                        if last.miny() == zbox.miny()
+                            && last.maxy() == zbox.maxy()
+                            && last.maxx() + 1 == zbox.minx() =>
+                    {
+                        last.max.x = zbox.maxx();
+                    }
+                    _ => zboxes.push(zbox),
+                }
+            }
+            clauses.extend(zboxes.iter().map(|z| z.mbtiles_sql_where_prefix(prefix)));
+        }
+        if clauses.is_empty() {
+            "(0 = 1)".to_string()
+        } else {
+            balanced_or(&clauses)
+        }
+    }
+
+    /// Return the SQL `WHERE` clause for an mbtiles database
+    #[must_use]
+    pub fn mbtiles_sql_where(&self) -> String {
+        self.mbtiles_sql_where_prefix(None)
+    }
+
+    /// Return true if the tms (`tile_row`) flipped tile is in the set
+    #[must_use]
+    pub fn contains_tms(&self, x: u32, tile_row: u32, zoom: u8) -> bool {
+        self.contains(&Tile::new(x, flipy(tile_row, zoom), zoom))
+    }
+
+    /// Parse a `TileSet` from json
+    ///
+    /// # Errors
+    ///
+    /// Error if the json is invalid or a range is out of bounds for its zoom
+    pub fn from_json(json: &str) -> UtilesCoreResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serialize the `TileSet` to json
+    ///
+    /// # Errors
+    ///
+    /// Error if serialization fails (should not happen)
+    pub fn to_json(&self) -> UtilesCoreResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+impl TryFrom<BTreeMap<u8, Vec<(u64, u64)>>> for TileSet {
+    type Error = UtilesCoreError;
+
+    fn try_from(zooms: BTreeMap<u8, Vec<(u64, u64)>>) -> Result<Self, Self::Error> {
+        let mut tileset = Self::new();
+        for (zoom, mut ranges) in zooms {
+            if zoom > 30 {
+                return Err(UtilesCoreError::InvalidZoom(zoom.to_string()));
+            }
+            if let Some((start, end)) = ranges
+                .iter()
+                .find(|(start, end)| start > end || *end >= zoom_ntiles(zoom))
+            {
+                return Err(UtilesCoreError::AdHoc(format!(
+                    "invalid tileset range for zoom {zoom}: [{start}, {end}]"
+                )));
+            }
+            ranges.sort_unstable();
+            let mut merged = Vec::with_capacity(ranges.len());
+            for range in ranges {
+                push_range(&mut merged, range);
+            }
+            tileset.union_ranges(zoom, &merged);
+        }
+        Ok(tileset)
+    }
+}
+
+impl From<TileSet> for BTreeMap<u8, Vec<(u64, u64)>> {
+    fn from(tileset: TileSet) -> Self {
+        tileset.zooms
+    }
+}
+
+impl From<&TileZBox> for TileSet {
+    fn from(zbox: &TileZBox) -> Self {
+        let mut tileset = Self::new();
+        tileset.insert_zbox(zbox);
+        tileset
+    }
+}
+
+impl<T: TileLike> FromIterator<T> for TileSet {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tileset = Self::new();
+        tileset.extend(iter);
+        tileset
+    }
+}
+
+impl<T: TileLike> Extend<T> for TileSet {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut mortons: BTreeMap<u8, Vec<u64>> = BTreeMap::new();
+        for tile in iter {
+            mortons
+                .entry(tile.z())
+                .or_default()
+                .push(morton_encode(tile.x(), tile.y()));
+        }
+        for (zoom, mut ms) in mortons {
+            ms.sort_unstable();
+            let mut ranges = Vec::new();
+            for m in ms {
+                push_range(&mut ranges, (m, m));
+            }
+            self.union_ranges(zoom, &ranges);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiles(tileset: &TileSet) -> Vec<(u32, u32, u8)> {
+        tileset.iter().map(|t| (t.x, t.y, t.z)).collect()
+    }
+
+    #[test]
+    fn zbox_blocks() {
+        // full z10 children of a z2 tile ~ one range
+        let zbox = TileZBox::from_tile(&Tile::new(1, 2, 2)).zoom_depth(8);
+        let tileset = TileSet::from(&zbox);
+        assert_eq!(tileset.nranges(), 1);
+        assert_eq!(tileset.len(), zbox.length());
+        assert_eq!(tileset.zboxes().collect::<Vec<_>>(), vec![zbox]);
+
+        // unaligned box
+        let zbox = TileZBox::new(3, 9, 5, 6, 5);
+        let tileset = TileSet::from(&zbox);
+        assert_eq!(tileset.len(), zbox.length());
+        for tile in zbox {
+            assert!(tileset.contains(&Tile::from(tile)));
+        }
+        assert!(!tileset.contains(&Tile::new(2, 5, 5)));
+        assert!(!tileset.contains(&Tile::new(3, 5, 6)));
+        let covered = tileset.zboxes().map(|zbox| zbox.length()).sum::<u64>();
+        assert_eq!(covered, zbox.length());
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = TileSet::from(&TileZBox::new(0, 3, 0, 3, 3));
+        let b = TileSet::from(&TileZBox::new(2, 5, 2, 5, 3));
+        assert_eq!(a.union(&b).len(), 16 + 16 - 4);
+        assert_eq!(
+            tiles(&a.intersection(&b)),
+            vec![(2, 2, 3), (3, 2, 3), (2, 3, 3), (3, 3, 3)]
+        );
+        let diff = a.difference(&b);
+        assert_eq!(diff.len(), 12);
+        assert!(!diff.contains(&Tile::new(3, 3, 3)));
+        assert!(diff.contains(&Tile::new(1, 3, 3)));
+        assert!(a.difference(&a).is_empty());
+        // zooms are independent
+        let z4 = TileSet::from_iter([Tile::new(0, 0, 4)]);
+        assert_eq!(a.union(&z4).zooms(), vec![3, 4]);
+        assert!(a.intersection(&z4).is_empty());
+    }
+
+    #[test]
+    fn iter_zorder() {
+        let tileset: TileSet = [
+            Tile::new(1, 1, 1),
+            Tile::new(0, 0, 0),
+            Tile::new(0, 1, 1),
+            Tile::new(1, 0, 1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            tiles(&tileset),
+            vec![(0, 0, 0), (1, 0, 1), (0, 1, 1), (1, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn json_and_sql() {
+        let tileset = TileSet::from(&TileZBox::new(0, 3, 0, 1, 2));
+        let json = tileset.to_json().expect("json");
+        assert_eq!(json, r#"{"2":[[0,7]]}"#);
+        assert_eq!(TileSet::from_json(&json).ok(), Some(tileset.clone()));
+        assert!(TileSet::from_json(r#"{"1":[[0,4]]}"#).is_err());
+        assert_eq!(
+            tileset.mbtiles_sql_where(),
+            "(zoom_level = 2 AND tile_column >= 0 AND tile_column <= 3 AND tile_row >= 2 AND tile_row <= 3)"
+        );
+        let world = TileSet::from(&TileZBox::new(0, 1, 0, 1, 1));
+        assert_eq!(world.mbtiles_sql_where(), "(zoom_level = 1)");
+        assert_eq!(TileSet::new().mbtiles_sql_where(), "(0 = 1)");
+        assert!(tileset.contains_tms(0, 3, 2));
+    }
+
+    #[test]
+    fn sql_where_many_blocks() {
+        // checkerboard ~ 2048 blocks that can't be merged
+        let mut tileset = TileSet::new();
+        for x in 0..64 {
+            for y in 0..64 {
+                if (x + y) % 2 == 0 {
+                    tileset.insert(&Tile::new(x, y, 6));
+                }
+            }
+        }
+        let conn = rusqlite::Connection::open_in_memory().expect("sqlite");
+        conn.execute_batch(
+            "CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER);",
+        )
+        .expect("create");
+        let mut insert = conn
+            .prepare("INSERT INTO tiles VALUES (?1, ?2, ?3)")
+            .expect("prepare");
+        for x in 0..64u32 {
+            for y in 0..64u32 {
+                insert.execute((6, x, flipy(y, 6))).expect("insert");
+            }
+        }
+        let query = format!(
+            "SELECT count(*) FROM tiles WHERE {}",
+            tileset.mbtiles_sql_where()
+        );
+        let n: i64 = conn.query_row(&query, [], |row| row.get(0)).expect("query");
+        assert_eq!(n, 2048);
+        assert_eq!(tileset.len(), 2048);
+    }
+}
@@ -0,0 +1,860 @@
+//! OGC `TileMatrixSet` ~ tile grids other than `WebMercatorQuad`
+//!
+//! Tile matrix sets are loaded from the OGC Two Dimensional Tile Matrix Set
+//! (2.0) JSON encoding or from one of the built-in standard sets:
+//!
+//! - `WebMercatorQuad` (EPSG:3857; same grid as the functions in `fns`)
+//! - `WorldCRS84Quad` (OGC:CRS84 lon/lat; 2x1 tiles at zoom 0)
+//! - `WGS1984Quad` (EPSG:4326 lat/lon axis order; 2x1 tiles at zoom 0)
+//!
+//! The zoom of a tile is the index of its tile matrix in `tileMatrices`
+//! (ordered from coarsest to finest). Conversions between lng/lat and the
+//! tile matrix set's CRS are supported for web-mercator and geographic
+//! CRSs; tile math in CRS units (`tile_xy`, `xy_bounds`) works for any CRS.
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bbox::BBox;
+use crate::constants::{EARTH_CIRCUMFERENCE, LL_EPSILON};
+use crate::errors::{UtilesCoreError, UtilesCoreResult};
+use crate::fns::{as_zooms, lnglat2webmercator, webmercator2lnglat};
+use crate::projection::Projection;
+use crate::tile::{FeatureOptions, bounds_feature};
+use crate::tile_feature::TileFeature;
+use crate::tile_zbox::{TileZBox, TileZBoxes};
+use crate::zoom::ZoomOrZooms;
+use crate::{Tile, TileLike};
+
+/// Half the web-mercator world width in meters
+const WEB_MERCATOR_EXTENT: f64 = EARTH_CIRCUMFERENCE / 2.0;
+
+/// Standardized rendering pixel size (0.28mm) used for scale denominators
+const STANDARD_PIXEL_SIZE: f64 = 0.000_28;
+
+/// Meters per degree at the equator
+const METERS_PER_DEGREE: f64 = EARTH_CIRCUMFERENCE / 360.0;
+
+/// Finest zoom level of the built-in tile matrix sets
+const BUILTIN_MAXZOOM: u8 = 24;
+
+/// Ids of the built-in tile matrix sets
+pub const BUILTIN_TMS_IDS: [&str; 3] =
+    ["WebMercatorQuad", "WorldCRS84Quad", "WGS1984Quad"];
+
+/// Corner of a tile matrix that tile row/column 0 start from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CornerOfOrigin {
+    #[default]
+    TopLeft,
+    BottomLeft,
+}
+
+/// CRS of a tile matrix set ~ either a uri string or a `{"uri": ...}` object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TmsCrs {
+    Uri(String),
+    Ref { uri: String },
+}
+
+impl TmsCrs {
+    /// Return the CRS uri
+    #[must_use]
+    pub fn uri(&self) -> &str {
+        match self {
+            Self::Uri(uri) | Self::Ref { uri } => uri,
+        }
+    }
+}
+
+/// Supported CRS kinds for lng/lat conversions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CrsKind {
+    WebMercator,
+    Geographic,
+}
+
+/// A single tile matrix (zoom level) of a `TileMatrixSet`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TileMatrix {
+    pub id: String,
+
+    #[serde(default)]
+    pub scale_denominator: f64,
+
+    /// Size of a pixel in CRS units
+    pub cell_size: f64,
+
+    #[serde(default)]
+    pub corner_of_origin: CornerOfOrigin,
+
+    /// Origin in the CRS axis order (see `orderedAxes`)
+    pub point_of_origin: [f64; 2],
+
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub matrix_width: u32,
+    pub matrix_height: u32,
+}
+
+impl TileMatrix {
+    /// Return the width and height of a tile in CRS units
+    #[must_use]
+    pub fn tile_span(&self) -> (f64, f64) {
+        (
+            self.cell_size * f64::from(self.tile_width),
+            self.cell_size * f64::from(self.tile_height),
+        )
+    }
+}
+
+/// OGC Two Dimensional Tile Matrix Set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TileMatrixSet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+
+    pub crs: TmsCrs,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ordered_axes: Option<Vec<String>>,
+
+    pub tile_matrices: Vec<TileMatrix>,
+}
+
+/// Tile constructor w/o the web-mercator `x, y < 2^z` (debug) assertion
+const fn matrix_tile(x: u32, y: u32, z: u8) -> Tile {
+    Tile { x, y, z }
+}
+
+/// Clamp a fractional tile column/row to `[0, n - 1]`
+#[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn clamp_index(frac: f64, n: u32) -> u32 {
+    let max = f64::from(n.saturating_sub(1));
+    if frac.is_nan() {
+        0
+    } else {
+        frac.floor().clamp(0.0, max) as u32
+    }
+}
+
+impl TileMatrixSet {
+    /// Build a quadtree tile matrix set w/ 256x256 tiles
+    #[expect(clippy::too_many_arguments)]
+    fn quad(
+        id: &str,
+        title: &str,
+        crs: &str,
+        axes: [&str; 2],
+        point_of_origin: [f64; 2],
+        cell_size: f64,
+        matrix_width: u32,
+        meters_per_unit: f64,
+    ) -> Self {
+        let tile_matrices = (0..=BUILTIN_MAXZOOM)
+            .map(|z| {
+                let cell_size = cell_size / f64::from(1u32 << z);
+                TileMatrix {
+                    id: z.to_string(),
+                    scale_denominator: cell_size * meters_per_unit
+                        / STANDARD_PIXEL_SIZE,
+                    cell_size,
+                    corner_of_origin: CornerOfOrigin::TopLeft,
+                    point_of_origin,
+                    tile_width: 256,
+                    tile_height: 256,
+                    matrix_width: matrix_width << z,
+                    matrix_height: 1 << z,
+                }
+            })
+            .collect();
+        Self {
+            id: Some(id.to_string()),
+            title: Some(title.to_string()),
+            uri: Some(format!(
+                "http://www.opengis.net/def/tilematrixset/OGC/1.0/{id}"
+            )),
+            crs: TmsCrs::Uri(crs.to_string()),
+            ordered_axes: Some(axes.iter().map(ToString::to_string).collect()),
+            tile_matrices,
+        }
+    }
+
+    /// `WebMercatorQuad` ~ the google/osm/xyz web-mercator grid
+    #[must_use]
+    pub fn web_mercator_quad() -> Self {
+        Self::quad(
+            "WebMercatorQuad",
+            "Google Maps Compatible for the World",
+            "http://www.opengis.net/def/crs/EPSG/0/3857",
+            ["X", "Y"],
+            [-WEB_MERCATOR_EXTENT, WEB_MERCATOR_EXTENT],
+            2.0 * WEB_MERCATOR_EXTENT / 256.0,
+            1,
+            1.0,
+        )
+    }
+
+    /// `WorldCRS84Quad` ~ lon/lat grid w/ 2x1 tiles at zoom 0
+    #[must_use]
+    pub fn world_crs84_quad() -> Self {
+        Self::quad(
+            "WorldCRS84Quad",
+            "CRS84 for the World",
+            "http://www.opengis.net/def/crs/OGC/1.3/CRS84",
+            ["Lon", "Lat"],
+            [-180.0, 90.0],
+            180.0 / 256.0,
+            2,
+            METERS_PER_DEGREE,
+        )
+    }
+
+    /// `WGS1984Quad` ~ EPSG:4326 (lat/lon axis order) grid w/ 2x1 tiles at zoom 0
+    #[must_use]
+    pub fn wgs1984_quad() -> Self {
+        Self::quad(
+            "WGS1984Quad",
+            "EPSG:4326 for the World",
+            "http://www.opengis.net/def/crs/EPSG/0/4326",
+            ["Lat", "Lon"],
+            [90.0, -180.0],
+            180.0 / 256.0,
+            2,
+            METERS_PER_DEGREE,
+        )
+    }
+
+    /// Return a built-in tile matrix set by id (case-insensitive)
+    #[must_use]
+    pub fn builtin(id: &str) -> Option<Self> {
+        match id.to_lowercase().as_str() {
+            "webmercatorquad" => Some(Self::web_mercator_quad()),
+            "worldcrs84quad" => Some(Self::world_crs84_quad()),
+            "wgs1984quad" => Some(Self::wgs1984_quad()),
+            _ => None,
+        }
+    }
+
+    /// Load a tile matrix set from the OGC (2.0) JSON encoding
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the json is invalid or has no tile matrices
+    pub fn from_json(json: &str) -> UtilesCoreResult<Self> {
+        let tms: Self = serde_json::from_str(json)?;
+        if tms.tile_matrices.is_empty() {
+            return Err(UtilesCoreError::InvalidJson(
+                "tile matrix set has no tileMatrices".to_string(),
+            ));
+        }
+        if tms.tile_matrices.len() > usize::from(u8::MAX) + 1 {
+            return Err(UtilesCoreError::InvalidJson(
+                "tile matrix set has more than 256 tileMatrices".to_string(),
+            ));
+        }
+        Ok(tms)
+    }
+
+    /// Return the tile matrix set as json
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn to_json(&self) -> UtilesCoreResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Minimum zoom (always 0)
+    #[must_use]
+    pub const fn minzoom(&self) -> u8 {
+        0
+    }
+
+    /// Maximum zoom ~ index of the finest tile matrix
+    #[must_use]
+    pub fn maxzoom(&self) -> u8 {
+        u8::try_from(self.tile_matrices.len().saturating_sub(1)).unwrap_or(u8::MAX)
+    }
+
+    /// Return the tile matrix for a zoom level
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the zoom is beyond the tile matrix set's maxzoom
+    pub fn matrix(&self, zoom: u8) -> UtilesCoreResult<&TileMatrix> {
+        self.tile_matrices.get(usize::from(zoom)).ok_or_else(|| {
+            UtilesCoreError::InvalidZoom(format!(
+                "{zoom} (tile matrix set maxzoom: {})",
+                self.maxzoom()
+            ))
+        })
+    }
+
+    /// Return true if every tile matrix halves the previous (quadtree)
+    #[must_use]
+    #[expect(clippy::float_cmp)]
+    pub fn is_quadtree(&self) -> bool {
+        self.tile_matrices.windows(2).all(|w| {
+            let (a, b) = (&w[0], &w[1]);
+            b.matrix_width == a.matrix_width * 2
+                && b.matrix_height == a.matrix_height * 2
+                && b.tile_width == a.tile_width
+                && b.tile_height == a.tile_height
+                && b.corner_of_origin == a.corner_of_origin
+                && b.point_of_origin == a.point_of_origin
+        })
+    }
+
+    /// Return true if the CRS axis order is northing/latitude first
+    fn swapped_axes(&self) -> bool {
+        match &self.ordered_axes {
+            Some(axes) => axes.first().is_some_and(|axis| {
+                matches!(
+                    axis.to_lowercase().as_str(),
+                    "lat" | "latitude" | "y" | "n" | "northing"
+                )
+            }),
+            None => self.crs.uri().ends_with("4326"),
+        }
+    }
+
+    fn crs_kind(&self) -> UtilesCoreResult<CrsKind> {
+        let uri = self.crs.uri();
+        if uri.ends_with("3857") || uri.ends_with("900913") {
+            Ok(CrsKind::WebMercator)
+        } else if uri.ends_with("CRS84") || uri.ends_with("4326") {
+            Ok(CrsKind::Geographic)
+        } else {
+            Err(UtilesCoreError::Unimplemented(format!(
+                "lnglat conversion for tile matrix set crs: {uri}"
+            )))
+        }
+    }
+
+    /// Return the point of origin as (x/easting, y/northing)
+    fn origin(&self, matrix: &TileMatrix) -> (f64, f64) {
+        let [a, b] = matrix.point_of_origin;
+        if self.swapped_axes() { (b, a) } else { (a, b) }
+    }
+
+    /// Convert lng/lat to CRS (x, y) coordinates
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the CRS is not web-mercator or geographic
+    pub fn xy(&self, lng: f64, lat: f64) -> UtilesCoreResult<(f64, f64)> {
+        match self.crs_kind()? {
+            CrsKind::WebMercator => Ok(lnglat2webmercator(lng, lat)),
+            CrsKind::Geographic => Ok((lng, lat)),
+        }
+    }
+
+    /// Convert CRS (x, y) coordinates to lng/lat
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the CRS is not web-mercator or geographic
+    pub fn lnglat(&self, x: f64, y: f64) -> UtilesCoreResult<(f64, f64)> {
+        match self.crs_kind()? {
+            CrsKind::WebMercator => Ok(webmercator2lnglat(x, y)),
+            CrsKind::Geographic => Ok((x, y)),
+        }
+    }
+
+    /// Return the tile containing the CRS coordinates at a zoom level
+    ///
+    /// Coordinates outside the tile matrix are clamped to the edge tiles.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the zoom is beyond the tile matrix set's maxzoom
+    pub fn tile_xy(&self, x: f64, y: f64, zoom: u8) -> UtilesCoreResult<Tile> {
+        let matrix = self.matrix(zoom)?;
+        let (ox, oy) = self.origin(matrix);
+        let (span_x, span_y) = matrix.tile_span();
+        let col = (x - ox) / span_x;
+        let row = match matrix.corner_of_origin {
+            CornerOfOrigin::TopLeft => (oy - y) / span_y,
+            CornerOfOrigin::BottomLeft => (y - oy) / span_y,
+        };
+        Ok(matrix_tile(
+            clamp_index(col, matrix.matrix_width),
+            clamp_index(row, matrix.matrix_height),
+            zoom,
+        ))
+    }
+
+    /// Return the tile containing the lng/lat at a zoom level
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the zoom is invalid or the CRS is unsupported
+    pub fn tile(&self, lng: f64, lat: f64, zoom: u8) -> UtilesCoreResult<Tile> {
+        let (x, y) = self.xy(lng, lat)?;
+        self.tile_xy(x, y, zoom)
+    }
+
+    /// Return the bounds of a tile in CRS units
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tile is not in the tile matrix set
+    pub fn xy_bounds<T: TileLike>(&self, tile: &T) -> UtilesCoreResult<BBox> {
+        let matrix = self.matrix(tile.z())?;
+        if tile.x() >= matrix.matrix_width || tile.y() >= matrix.matrix_height {
+            return Err(UtilesCoreError::InvalidTile(format!(
+                "{} not in tile matrix {} ({}x{})",
+                tile.json_arr(),
+                matrix.id,
+                matrix.matrix_width,
+                matrix.matrix_height
+            )));
+        }
+        let (ox, oy) = self.origin(matrix);
+        let (span_x, span_y) = matrix.tile_span();
+        let west = f64::from(tile.x()).mul_add(span_x, ox);
+        let (south, north) = match matrix.corner_of_origin {
+            CornerOfOrigin::TopLeft => {
+                let north = f64::from(tile.y()).mul_add(-span_y, oy);
+                (north - span_y, north)
+            }
+            CornerOfOrigin::BottomLeft => {
+                let south = f64::from(tile.y()).mul_add(span_y, oy);
+                (south, south + span_y)
+            }
+        };
+        Ok(BBox::new(west, south, west + span_x, north))
+    }
+
+    /// Return the lng/lat bounds of a tile
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tile is not in the tile matrix set or the CRS
+    /// is unsupported
+    pub fn bounds<T: TileLike>(&self, tile: &T) -> UtilesCoreResult<BBox> {
+        self.xy2lnglat_bbox(&self.xy_bounds(tile)?)
+    }
+
+    fn xy2lnglat_bbox(&self, xy: &BBox) -> UtilesCoreResult<BBox> {
+        let (west, south) = self.lnglat(xy.west, xy.south)?;
+        let (east, north) = self.lnglat(xy.east, xy.north)?;
+        Ok(BBox::new(west, south, east, north))
+    }
+
+    /// Return the extent of the tile matrix set (zoom 0) in CRS units
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tile matrix set has no tile matrices
+    pub fn xy_bbox(&self) -> UtilesCoreResult<BBox> {
+        let matrix = self.matrix(0)?;
+        let last = matrix_tile(
+            matrix.matrix_width.saturating_sub(1),
+            matrix.matrix_height.saturating_sub(1),
+            0,
+        );
+        let first = self.xy_bounds(&matrix_tile(0, 0, 0))?;
+        let last = self.xy_bounds(&last)?;
+        Ok(BBox::new(
+            first.west.min(last.west),
+            first.south.min(last.south),
+            first.east.max(last.east),
+            first.north.max(last.north),
+        ))
+    }
+
+    /// Return the lng/lat extent of the tile matrix set
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the CRS is unsupported
+    pub fn bbox(&self) -> UtilesCoreResult<BBox> {
+        self.xy2lnglat_bbox(&self.xy_bbox()?)
+    }
+
+    /// Return the range of tiles intersecting a bbox in CRS units
+    fn xy_range(&self, xy: &BBox, zoom: u8) -> UtilesCoreResult<TileZBox> {
+        let (span_x, span_y) = self.matrix(zoom)?.tile_span();
+        // nudge the edges inward so tiles only touching the bbox are excluded
+        let (eps_x, eps_y) = (span_x * 1e-9, span_y * 1e-9);
+        let (dx, dy) = (
+            eps_x.min((xy.east - xy.west) / 2.0),
+            eps_y.min((xy.north - xy.south) / 2.0),
+        );
+        let a = self.tile_xy(xy.west + dx, xy.north - dy, zoom)?;
+        let b = self.tile_xy(xy.east - dx, xy.south + dy, zoom)?;
+        Ok(TileZBox::new(
+            a.x.min(b.x),
+            a.x.max(b.x),
+            a.y.min(b.y),
+            a.y.max(b.y),
+            zoom,
+        ))
+    }
+
+    /// Return the `TileZBoxes` intersecting a lng/lat bbox at zoom(s)
+    ///
+    /// Bboxes crossing the antimeridian are split and bboxes are clipped to
+    /// the tile matrix set's extent.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a zoom is invalid or the CRS is unsupported
+    pub fn tile_ranges(
+        &self,
+        bbox: BBox,
+        zooms: ZoomOrZooms,
+    ) -> UtilesCoreResult<TileZBoxes> {
+        let zooms = as_zooms(zooms);
+        let extent = self.bbox()?;
+        let mut ranges = vec![];
+        for bbox in bbox.bboxes() {
+            let clipped = BBox::new(
+                bbox.west.max(extent.west),
+                bbox.south.max(extent.south),
+                bbox.east.min(extent.east),
+                bbox.north.min(extent.north),
+            );
+            if clipped.west > clipped.east || clipped.south > clipped.north {
+                continue;
+            }
+            let (west, south) = self.xy(clipped.west, clipped.south)?;
+            let (east, north) = self.xy(clipped.east, clipped.north)?;
+            let xy = BBox::new(west, south, east, north);
+            for zoom in &zooms {
+                ranges.push(self.xy_range(&xy, *zoom)?);
+            }
+        }
+        Ok(TileZBoxes::from(ranges))
+    }
+
+    /// Return the tiles intersecting a lng/lat bbox at zoom(s)
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a zoom is invalid or the CRS is unsupported
+    pub fn tiles(
+        &self,
+        bbox: BBox,
+        zooms: ZoomOrZooms,
+    ) -> UtilesCoreResult<impl Iterator<Item = Tile> + use<>> {
+        let ranges = self.tile_ranges(bbox, zooms)?;
+        Ok(ranges
+            .ranges
+            .into_iter()
+            .flat_map(|range| range.into_iter().map(Tile::from)))
+    }
+
+    /// Return the parent tile `depth` zoom levels up
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the depth exceeds the tile's zoom or the tile is
+    /// not in the tile matrix set
+    pub fn parent<T: TileLike>(&self, tile: &T, depth: u8) -> UtilesCoreResult<Tile> {
+        let zoom = tile.z().checked_sub(depth).ok_or_else(|| {
+            UtilesCoreError::InvalidZoom(format!(
+                "depth {depth} exceeds tile zoom {}",
+                tile.z()
+            ))
+        })?;
+        let xy = self.xy_bounds(tile)?;
+        self.tile_xy(
+            f64::midpoint(xy.west, xy.east),
+            f64::midpoint(xy.south, xy.north),
+            zoom,
+        )
+    }
+
+    /// Return the range of child tiles at `zoom` (default: zoom + 1)
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the zoom is invalid or the tile is not in the tile
+    /// matrix set
+    pub fn children<T: TileLike>(
+        &self,
+        tile: &T,
+        zoom: Option<u8>,
+    ) -> UtilesCoreResult<TileZBox> {
+        let zoom = zoom.unwrap_or_else(|| tile.z().saturating_add(1));
+        if zoom < tile.z() {
+            return Err(UtilesCoreError::InvalidZoom(format!(
+                "children zoom {zoom} < tile zoom {}",
+                tile.z()
+            )));
+        }
+        self.xy_range(&self.xy_bounds(tile)?, zoom)
+    }
+
+    /// Return the (non-wrapping) neighbor tiles of a tile
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tile is not in the tile matrix set
+    pub fn neighbors<T: TileLike>(&self, tile: &T) -> UtilesCoreResult<Vec<Tile>> {
+        self.xy_bounds(tile)?;
+        let matrix = self.matrix(tile.z())?;
+        let (x, y) = (i64::from(tile.x()), i64::from(tile.y()));
+        let neighbors = (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+            .filter(|&xy| xy != (x, y))
+            .filter_map(|(nx, ny)| {
+                let nx = u32::try_from(nx)
+                    .ok()
+                    .filter(|nx| *nx < matrix.matrix_width)?;
+                let ny = u32::try_from(ny)
+                    .ok()
+                    .filter(|ny| *ny < matrix.matrix_height)?;
+                Some(matrix_tile(nx, ny, tile.z()))
+            })
+            .collect();
+        Ok(neighbors)
+    }
+
+    /// Return the smallest tile containing a lng/lat bbox
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the CRS is unsupported or no single tile contains the
+    /// bbox (e.g. a bbox crossing the 2 zoom-0 tiles of `WorldCRS84Quad`)
+    pub fn bounding_tile(&self, bbox: BBox) -> UtilesCoreResult<Tile> {
+        for zoom in (0..=self.maxzoom()).rev() {
+            let tmin = self.tile(bbox.west, bbox.north, zoom)?;
+            let tmax =
+                self.tile(bbox.east - LL_EPSILON, bbox.south + LL_EPSILON, zoom)?;
+            if tmin == tmax {
+                return Ok(tmin);
+            }
+        }
+        Err(UtilesCoreError::InvalidBbox(format!(
+            "{bbox:?} not contained by a single tile of {}",
+            self.id.as_deref().unwrap_or("the tile matrix set")
+        )))
+    }
+
+    /// Return a `GeoJSON` feature for a tile
+    ///
+    /// `Projection::Mercator` outputs the tile bounds in the tile matrix
+    /// set's CRS units and `Projection::Geographic` in lng/lat.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tile is not in the tile matrix set or the CRS
+    /// is unsupported
+    pub fn feature<T: TileLike>(
+        &self,
+        tile: &T,
+        opts: &FeatureOptions,
+    ) -> UtilesCoreResult<TileFeature> {
+        let bbox = match opts.projection {
+            Projection::Mercator => self.xy_bounds(tile)?,
+            Projection::Geographic => self.bounds(tile)?,
+        };
+        let xyz = format!("({}, {}, {})", tile.x(), tile.y(), tile.z());
+        Ok(bounds_feature(xyz, bbox.tuple(), opts))
+    }
+}
+
+impl FromStr for TileMatrixSet {
+    type Err = UtilesCoreError;
+
+    /// Parse a built-in tile matrix set id or OGC tile matrix set json
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::builtin(s).map_or_else(
+            || {
+                if s.trim_start().starts_with('{') {
+                    Self::from_json(s)
+                } else {
+                    Err(UtilesCoreError::ParseError(format!(
+                        "unknown tile matrix set: {s} (builtin: {})",
+                        BUILTIN_TMS_IDS.join(", ")
+                    )))
+                }
+            },
+            Ok,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{a} != {b}");
+    }
+
+    #[test]
+    fn web_mercator_quad_matches_fns() {
+        let tms = TileMatrixSet::web_mercator_quad();
+        assert!(tms.is_quadtree());
+        assert_eq!(tms.maxzoom(), 24);
+        assert_eq!(
+            tms.tile(-105.0, 40.0, 12).expect("tile"),
+            crate::tile(-105.0, 40.0, 12, None).expect("tile")
+        );
+        let bbox = tms.bounds(&matrix_tile(486, 332, 10)).expect("bounds");
+        let (west, south, east, north) = crate::bounds(486, 332, 10);
+        assert_close(bbox.west, west);
+        assert_close(bbox.south, south);
+        assert_close(bbox.east, east);
+        assert_close(bbox.north, north);
+        assert_close(
+            tms.matrix(0).expect("z0").scale_denominator,
+            559_082_264.028_717_8,
+        );
+    }
+
+    #[test]
+    fn web_mercator_quad_tiles_match_fns() {
+        let tms = TileMatrixSet::web_mercator_quad();
+        let bounds = (-105.05, 39.95, -105.0, 40.0);
+        let expected = crate::tiles(bounds, ZoomOrZooms::Zoom(12)).collect::<Vec<_>>();
+        let mut tiles = tms
+            .tiles(BBox::from(bounds), ZoomOrZooms::Zoom(12))
+            .expect("tiles")
+            .collect::<Vec<_>>();
+        tiles.sort();
+        assert_eq!(tiles, expected);
+    }
+
+    #[test]
+    fn world_crs84_quad() {
+        let tms = TileMatrixSet::world_crs84_quad();
+        assert!(tms.is_quadtree());
+        let z0 = tms.matrix(0).expect("z0");
+        assert_eq!((z0.matrix_width, z0.matrix_height), (2, 1));
+        assert_close(z0.scale_denominator, 279_541_132.014_358_9);
+        assert_eq!(
+            tms.tile(-90.0, 45.0, 0).expect("tile"),
+            matrix_tile(0, 0, 0)
+        );
+        assert_eq!(
+            tms.tile(90.0, -45.0, 1).expect("tile"),
+            matrix_tile(3, 1, 1)
+        );
+        let b = tms.bounds(&matrix_tile(3, 1, 1)).expect("bounds");
+        assert_eq!(b, BBox::new(90.0, -90.0, 180.0, 0.0));
+        let world = tms
+            .tile_ranges(BBox::new(-180.0, -90.0, 180.0, 90.0), ZoomOrZooms::Zoom(2))
+            .expect("ranges");
+        assert_eq!(world.length(), 32);
+    }
+
+    #[test]
+    fn wgs1984_quad_axis_order() {
+        let tms = TileMatrixSet::wgs1984_quad();
+        let crs84 = TileMatrixSet::world_crs84_quad();
+        let t = matrix_tile(5, 2, 3);
+        assert_eq!(
+            tms.bounds(&t).expect("bounds"),
+            crs84.bounds(&t).expect("bounds")
+        );
+        assert_eq!(
+            tms.tile(12.5, 41.9, 8).expect("tile"),
+            crs84.tile(12.5, 41.9, 8).expect("tile")
+        );
+    }
+
+    #[test]
+    fn parent_children() {
+        let tms = TileMatrixSet::world_crs84_quad();
+        let t = matrix_tile(5, 2, 3);
+        assert_eq!(tms.parent(&t, 1).expect("parent"), matrix_tile(2, 1, 2));
+        assert_eq!(tms.parent(&t, 3).expect("parent"), matrix_tile(0, 0, 0));
+        assert!(tms.parent(&t, 4).is_err());
+        let children = tms.children(&t, None).expect("children");
+        assert_eq!(children, TileZBox::new(10, 11, 4, 5, 4));
+        let grandchildren = tms.children(&t, Some(5)).expect("children");
+        assert_eq!(grandchildren.length(), 16);
+    }
+
+    #[test]
+    fn neighbors_and_bounding_tile() {
+        let tms = TileMatrixSet::world_crs84_quad();
+        let z0 = tms.neighbors(&matrix_tile(1, 0, 0)).expect("neighbors");
+        assert_eq!(z0, vec![matrix_tile(0, 0, 0)]);
+        let corner = tms.neighbors(&matrix_tile(3, 0, 1)).expect("neighbors");
+        assert_eq!(
+            corner,
+            vec![
+                matrix_tile(2, 0, 1),
+                matrix_tile(2, 1, 1),
+                matrix_tile(3, 1, 1)
+            ]
+        );
+        assert!(tms.neighbors(&matrix_tile(4, 0, 1)).is_err());
+        assert_eq!(
+            tms.bounding_tile(BBox::new(90.0, -90.0, 180.0, 0.0))
+                .expect("bounding tile"),
+            matrix_tile(3, 1, 1)
+        );
+        assert!(tms.bounding_tile(BBox::new(-1.0, -1.0, 1.0, 1.0)).is_err());
+        let web = TileMatrixSet::web_mercator_quad();
+        let bbox = BBox::new(-105.05, 39.95, -105.0, 40.0);
+        assert_eq!(
+            web.bounding_tile(bbox).expect("bounding tile"),
+            crate::bounding_tile(bbox, None).expect("bounding tile")
+        );
+    }
+
+    #[test]
+    fn antimeridian_split() {
+        let tms = TileMatrixSet::world_crs84_quad();
+        let ranges = tms
+            .tile_ranges(BBox::new(170.0, -10.0, -170.0, 10.0), ZoomOrZooms::Zoom(1))
+            .expect("ranges");
+        let tiles = ranges.into_iter().collect::<Vec<_>>();
+        assert_eq!(tiles, vec![(3, 0, 1), (3, 1, 1), (0, 0, 1), (0, 1, 1)]);
+    }
+
+    #[test]
+    fn json_roundtrip_and_custom_grid() {
+        let tms = TileMatrixSet::world_crs84_quad();
+        let json = tms.to_json().expect("json");
+        let roundtrip = TileMatrixSet::from_json(&json).expect("from json");
+        assert_eq!(roundtrip.id, tms.id);
+        assert_eq!(roundtrip.tile_matrices.len(), 25);
+        assert!(roundtrip.is_quadtree());
+
+        let custom = r#"{
+            "id": "BottomLeftCRS84",
+            "crs": {"uri": "http://www.opengis.net/def/crs/OGC/1.3/CRS84"},
+            "tileMatrices": [{
+                "id": "a",
+                "cellSize": 0.3515625,
+                "cornerOfOrigin": "bottomLeft",
+                "pointOfOrigin": [-180.0, -90.0],
+                "tileWidth": 256,
+                "tileHeight": 256,
+                "matrixWidth": 4,
+                "matrixHeight": 2
+            }]
+        }"#;
+        let tms: TileMatrixSet = custom.parse().expect("parse");
+        assert_eq!(
+            tms.tile(-179.0, 89.0, 0).expect("tile"),
+            matrix_tile(0, 1, 0)
+        );
+        assert_eq!(
+            tms.bounds(&matrix_tile(0, 1, 0)).expect("bounds"),
+            BBox::new(-180.0, 0.0, -90.0, 90.0)
+        );
+        assert!(tms.matrix(1).is_err());
+        assert!("NotATms".parse::<TileMatrixSet>().is_err());
+        assert!("worldcrs84quad".parse::<TileMatrixSet>().is_ok());
+    }
+}
@@ -0,0 +1,896 @@
+//! Exact tile coverage for lines/polygons w/ coverage modes and buffers
+//!
+//! Coverage is computed row by row (scanline) and streamed as `TileZBox`
+//! ranges so huge geometries at high zooms never hold every tile in memory.
+//!
+//! Geometries are projected to fractional tile coordinates at the target
+//! zoom; polygon coverage is the union of the tiles the rings pass through
+//! (boundary tiles) and the tiles whose centers are inside the polygon
+//! (even-odd rule, so holes are excluded).
+//!
+//! Rings crossing the antimeridian are unwrapped (longitudes made
+//! continuous) before projecting and the tile x values are wrapped back
+//! afterwards. Rings that wrap around the globe (unwrapped end is 360
+//! degrees from the start) are closed over the nearest pole.
+#![expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+use std::collections::{BTreeMap, VecDeque};
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use utiles_core::{BBox, EARTH_CIRCUMFERENCE, Tile, TileZBox, tile, wrap_lon};
+
+use crate::UtilesCoverError;
+
+/// Max latitude of the web-mercator tile pyramid
+const MAX_LAT: f64 = 85.051_128_779_806_59;
+
+/// How polygon(al) geometries select tiles
+///
+/// Points and lines always use `Intersects`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoverMode {
+    /// Tiles intersecting the polygon interior (default)
+    #[default]
+    Intersects,
+
+    /// Tiles entirely inside the polygon
+    Within,
+
+    /// Tiles whose center is inside the polygon
+    Centroid,
+}
+
+impl FromStr for CoverMode {
+    type Err = UtilesCoverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "intersects" => Ok(Self::Intersects),
+            "within" => Ok(Self::Within),
+            "centroid" | "center" => Ok(Self::Centroid),
+            _ => Err(UtilesCoverError::new(&format!(
+                "invalid cover mode: {s} (intersects, within, centroid)"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for CoverMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Intersects => write!(f, "intersects"),
+            Self::Within => write!(f, "within"),
+            Self::Centroid => write!(f, "centroid"),
+        }
+    }
+}
+
+/// Coverage buffer ~ expands the coverage by a distance
+#[derive(Debug, Clone, Copy)]
+pub enum CoverBuffer {
+    /// Include tiles within n meters (ground distance at the tile's latitude)
+    Meters(f64),
+
+    /// Include the n rings of neighboring tiles
+    Tiles(u32),
+}
+
+// meters compare bitwise so buffers (and cover options) are `Eq`
+impl PartialEq for CoverBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Meters(a), Self::Meters(b)) => a.to_bits() == b.to_bits(),
+            (Self::Tiles(a), Self::Tiles(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for CoverBuffer {}
+
+impl FromStr for CoverBuffer {
+    type Err = UtilesCoverError;
+
+    /// Parse `<n>m`, `<n>km` or `<n>t`/`<n>tiles` (bare numbers are meters)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let err = || UtilesCoverError::new(&format!("invalid cover buffer: {s}"));
+        let meters = |n: &str, factor: f64| {
+            n.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite() && *n >= 0.0)
+                .map(|n| Self::Meters(n * factor))
+                .ok_or_else(err)
+        };
+        if let Some(n) = s.strip_suffix("tiles").or_else(|| s.strip_suffix('t')) {
+            n.trim().parse::<u32>().map(Self::Tiles).map_err(|_| err())
+        } else if let Some(n) = s.strip_suffix("km") {
+            meters(n, 1000.0)
+        } else if let Some(n) = s.strip_suffix('m') {
+            meters(n, 1.0)
+        } else {
+            meters(&s, 1.0)
+        }
+    }
+}
+
+/// Project lng/lat to (unwrapped) fractional tile coordinates
+fn tile_frac(lng: f64, lat: f64, z2: f64) -> (f64, f64) {
+    let lat = lat.clamp(-MAX_LAT, MAX_LAT).to_radians();
+    let x = z2 * (lng / 360.0 + 0.5);
+    let y = z2 * (0.5 - (PI / 4.0 + lat / 2.0).tan().ln() / (2.0 * PI));
+    (x, y.clamp(0.0, z2))
+}
+
+/// Make ring/line longitudes continuous across the antimeridian
+///
+/// `reference` is a longitude the first point is shifted (by multiples of
+/// 360) to be within 180 degrees of; used to keep holes next to their
+/// exterior ring.
+fn unwrap_lngs(coords: &[(f64, f64)], reference: Option<f64>) -> Vec<(f64, f64)> {
+    let mut unwrapped: Vec<(f64, f64)> = Vec::with_capacity(coords.len());
+    let mut offset = 0.0;
+    if let (Some(reference), Some((lng, _))) = (reference, coords.first()) {
+        offset = ((reference - lng) / 360.0).round() * 360.0;
+    }
+    for (i, (lng, lat)) in coords.iter().enumerate() {
+        if i > 0 {
+            let dlng = lng - coords[i - 1].0;
+            if dlng > 180.0 {
+                offset -= 360.0;
+            } else if dlng < -180.0 {
+                offset += 360.0;
+            }
+        }
+        unwrapped.push((lng + offset, *lat));
+    }
+    unwrapped
+}
+
+/// Lng/lat bbox of unwrapped coords w/ the longitudes wrapped back to
+/// `[-180, 180]`; crosses the antimeridian if the coords extend past ±180
+fn unwrapped_bbox(coords: &[(f64, f64)]) -> BBox {
+    let (mut west, mut south, mut east, mut north) = (
+        f64::INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NEG_INFINITY,
+    );
+    for (lng, lat) in coords {
+        west = west.min(*lng);
+        east = east.max(*lng);
+        south = south.min(*lat);
+        north = north.max(*lat);
+    }
+    if east - west >= 360.0 {
+        BBox::new(-180.0, south, 180.0, north)
+    } else {
+        let wrap = |lng: f64| {
+            if (lng - 180.0).abs() < f64::EPSILON {
+                lng
+            } else {
+                wrap_lon(lng)
+            }
+        };
+        BBox::new(wrap(west), south, wrap(east), north)
+    }
+}
+
+/// Unwrap a polygon's rings and close rings encircling a pole
+fn prepare_rings(rings: &[Vec<(f64, f64)>]) -> Vec<Vec<(f64, f64)>> {
+    let mut prepared: Vec<Vec<(f64, f64)>> = Vec::with_capacity(rings.len());
+    let mut reference = None;
+    for ring in rings {
+        let mut ring = unwrap_lngs(ring, reference);
+        if reference.is_none() {
+            // exterior ring ~ shift so it starts within [-180, 180) when it
+            // crosses the antimeridian (e.g. given as 170..190)
+            if unwrapped_bbox(&ring).crosses_antimeridian()
+                && let Some((lng, _)) = ring.first()
+            {
+                let shift = lng - wrap_lon(*lng);
+                for (lng, _) in &mut ring {
+                    *lng -= shift;
+                }
+            }
+            reference = ring.first().map(|(lng, _)| *lng);
+        }
+        if let (Some((first_lng, _)), Some((last_lng, _))) =
+            (ring.first().copied(), ring.last().copied())
+            && (last_lng - first_lng).abs() >= 359.999
+        {
+            // ring wraps around the globe ~ close it over the nearest pole
+            let mean_lat =
+                ring.iter().map(|(_, lat)| lat).sum::<f64>() / ring.len() as f64;
+            let pole = if mean_lat >= 0.0 { 90.0 } else { -90.0 };
+            ring.push((last_lng, pole));
+            ring.push((first_lng, pole));
+            ring.push((first_lng, ring[0].1));
+        }
+        prepared.push(ring);
+    }
+    prepared
+}
+
+/// Inclusive integer range of cells whose open interior overlaps `[lo, hi]`
+///
+/// Returns an empty range for a degenerate interval on a cell edge unless
+/// `touching`, in which case the cell starting at `lo` is used.
+fn cell_range(lo: f64, hi: f64, touching: bool) -> (i64, i64) {
+    let start = lo.floor() as i64;
+    let end = (hi.ceil() as i64 - 1).max(if lo < hi { start } else { start - 1 });
+    if end < start && touching {
+        (start, start)
+    } else {
+        (start, end)
+    }
+}
+
+/// Geometry part to cover (lng/lat coords)
+#[derive(Debug, Clone)]
+pub(crate) enum Shape {
+    Point((f64, f64)),
+    Line(Vec<(f64, f64)>),
+    /// Rings ~ exterior first, then holes
+    Polygon(Vec<Vec<(f64, f64)>>),
+}
+
+/// Segment in (unwrapped) fractional tile coordinates
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    a: (f64, f64),
+    b: (f64, f64),
+    /// Polygon index for ring edges; `None` for line segments and points
+    polygon: Option<usize>,
+    first_row: i64,
+    last_row: i64,
+}
+
+impl Edge {
+    fn new(
+        a: (f64, f64),
+        b: (f64, f64),
+        polygon: Option<usize>,
+        n: i64,
+    ) -> Option<Self> {
+        let (ymin, ymax) = (a.1.min(b.1), a.1.max(b.1));
+        let (first_row, last_row) = cell_range(ymin, ymax, polygon.is_none());
+        if last_row < first_row {
+            return None;
+        }
+        Some(Self {
+            a,
+            b,
+            polygon,
+            first_row: first_row.clamp(0, n - 1),
+            last_row: last_row.clamp(0, n - 1),
+        })
+    }
+
+    /// Columns of the cells in `row` whose interior the edge passes through
+    fn row_cols(&self, row: i64) -> Option<(i64, i64)> {
+        let (a, b) = (self.a, self.b);
+        let (ymin, ymax) = (a.1.min(b.1), a.1.max(b.1));
+        // clip the edge to the row band
+        let (y0, y1) = (ymin.max(row as f64), ymax.min((row + 1) as f64));
+        let x_at = |y: f64| {
+            if (b.1 - a.1).abs() < f64::EPSILON {
+                None
+            } else {
+                Some(a.0 + (b.0 - a.0) * (y - a.1) / (b.1 - a.1))
+            }
+        };
+        let (xa, xb) = match (x_at(y0), x_at(y1)) {
+            (Some(xa), Some(xb)) => (xa.min(xb), xa.max(xb)),
+            _ => (a.0.min(b.0), a.0.max(b.0)),
+        };
+        let (start, end) = cell_range(xa, xb, self.polygon.is_none());
+        (start <= end).then_some((start, end))
+    }
+
+    /// X of the crossing w/ the centerline of `row` (y in `[ymin, ymax)`)
+    fn crossing(&self, row: i64) -> Option<f64> {
+        let (a, b) = (self.a, self.b);
+        let yc = row as f64 + 0.5;
+        if (a.1 - b.1).abs() < f64::EPSILON || yc < a.1.min(b.1) || yc >= a.1.max(b.1) {
+            None
+        } else {
+            Some(a.0 + (b.0 - a.0) * (yc - a.1) / (b.1 - a.1))
+        }
+    }
+}
+
+/// Sort and merge overlapping/adjacent column spans
+fn merge_spans(mut spans: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    spans.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Wrap (unwrapped) column spans into `[0, n)` and merge them
+fn wrap_spans(spans: Vec<(i64, i64)>, n: i64) -> Vec<(i64, i64)> {
+    let mut wrapped: Vec<(i64, i64)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        if end - start + 1 >= n {
+            wrapped.push((0, n - 1));
+            continue;
+        }
+        let start_wrapped = start.rem_euclid(n);
+        let end_wrapped = start_wrapped + (end - start);
+        if end_wrapped < n {
+            wrapped.push((start_wrapped, end_wrapped));
+        } else {
+            wrapped.push((start_wrapped, n - 1));
+            wrapped.push((0, end_wrapped - n));
+        }
+    }
+    merge_spans(wrapped)
+}
+
+/// Remove the (sorted, merged) `holes` spans from a span
+fn subtract_spans(span: (i64, i64), holes: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut remaining = Vec::new();
+    let mut cur = span.0;
+    for (start, end) in holes {
+        if *end < cur {
+            continue;
+        }
+        if *start > span.1 {
+            break;
+        }
+        if *start > cur {
+            remaining.push((cur, start - 1));
+        }
+        cur = end + 1;
+    }
+    if cur <= span.1 {
+        remaining.push((cur, span.1));
+    }
+    remaining
+}
+
+/// Inclusive column spans of a row
+type Spans = Vec<(i64, i64)>;
+
+/// Covered column spans of a row (sorted, merged, within `[0, 2^z)`)
+type RowSpans = (i64, Spans);
+
+/// Scanline rasterizer ~ yields the covered spans of each row top to bottom
+///
+/// Only the edges touching the current row are held active, so memory is
+/// bounded by the geometry and the width of a row rather than the number
+/// of tiles covered.
+#[derive(Debug)]
+struct Scanline {
+    n: i64,
+    mode: CoverMode,
+    /// Edges not yet reached, sorted by descending first row
+    pending: Vec<Edge>,
+    active: Vec<Edge>,
+    row: i64,
+}
+
+impl Scanline {
+    fn new(shapes: &[Shape], zoom: u8, mode: CoverMode) -> Self {
+        let n = 1i64 << zoom;
+        let z2 = f64::from(1u32 << zoom);
+        let project = |coords: Vec<(f64, f64)>| {
+            coords
+                .into_iter()
+                .map(|(lng, lat)| tile_frac(lng, lat, z2))
+                .collect::<Vec<_>>()
+        };
+        let mut edges: Vec<Edge> = Vec::new();
+        let mut npolygons = 0;
+        for shape in shapes {
+            match shape {
+                Shape::Point((lng, lat)) => {
+                    // same tile as `utiles_core::tile` for points on tile edges
+                    let pt = tile(*lng, *lat, zoom, None).map_or_else(
+                        |_| tile_frac(*lng, *lat, z2),
+                        |t| (f64::from(t.x) + 0.5, f64::from(t.y) + 0.5),
+                    );
+                    edges.extend(Edge::new(pt, pt, None, n));
+                }
+                Shape::Line(coords) => {
+                    let pts = project(unwrap_lngs(coords, None));
+                    if let [pt] = pts.as_slice() {
+                        edges.extend(Edge::new(*pt, *pt, None, n));
+                    }
+                    edges.extend(
+                        pts.windows(2)
+                            .filter_map(|seg| Edge::new(seg[0], seg[1], None, n)),
+                    );
+                }
+                Shape::Polygon(rings) => {
+                    let polygon = Some(npolygons);
+                    npolygons += 1;
+                    for ring in prepare_rings(rings).into_iter().map(project) {
+                        if ring.len() < 2 {
+                            continue;
+                        }
+                        let closing = (ring[ring.len() - 1], ring[0]);
+                        edges.extend(
+                            ring.windows(2)
+                                .map(|w| (w[0], w[1]))
+                                .chain([closing])
+                                .filter_map(|(a, b)| Edge::new(a, b, polygon, n)),
+                        );
+                    }
+                }
+            }
+        }
+        edges.sort_unstable_by_key(|edge| std::cmp::Reverse(edge.first_row));
+        Self {
+            n,
+            mode,
+            pending: edges,
+            active: Vec::new(),
+            row: 0,
+        }
+    }
+
+    /// First and last rows the geometry may cover
+    fn row_extent(&self) -> Option<(i64, i64)> {
+        let first = self.pending.last()?.first_row;
+        let last = self.pending.iter().map(|edge| edge.last_row).max()?;
+        Some((first, last))
+    }
+
+    fn row_spans(&self, row: i64) -> Vec<(i64, i64)> {
+        let mut spans: Vec<(i64, i64)> = Vec::new();
+        let mut boundaries: BTreeMap<usize, Vec<(i64, i64)>> = BTreeMap::new();
+        let mut crossings: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+        for edge in &self.active {
+            if let Some(cols) = edge.row_cols(row) {
+                match (edge.polygon, self.mode) {
+                    (None, _) | (Some(_), CoverMode::Intersects) => spans.push(cols),
+                    (Some(polygon), CoverMode::Within) => {
+                        boundaries.entry(polygon).or_default().push(cols);
+                    }
+                    (Some(_), CoverMode::Centroid) => {}
+                }
+            }
+            if let Some(polygon) = edge.polygon
+                && let Some(x) = edge.crossing(row)
+            {
+                crossings.entry(polygon).or_default().push(x);
+            }
+        }
+        for (polygon, mut xs) in crossings {
+            xs.sort_by(f64::total_cmp);
+            let boundary = merge_spans(boundaries.remove(&polygon).unwrap_or_default());
+            for pair in xs.chunks_exact(2) {
+                // columns whose center (col + 0.5) is in [a, b)
+                let start = (pair[0] - 0.5).ceil() as i64;
+                let end = (pair[1] - 0.5).ceil() as i64 - 1;
+                if start > end {
+                    continue;
+                }
+                if self.mode == CoverMode::Within {
+                    spans.extend(subtract_spans((start, end), &boundary));
+                } else {
+                    spans.push((start, end));
+                }
+            }
+        }
+        wrap_spans(spans, self.n)
+    }
+}
+
+impl Iterator for Scanline {
+    type Item = RowSpans;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.active.is_empty() {
+                // skip the empty rows up to the next edge
+                self.row = self.row.max(self.pending.last()?.first_row);
+            }
+            if self.row >= self.n {
+                return None;
+            }
+            while let Some(edge) =
+                self.pending.pop_if(|edge| edge.first_row <= self.row)
+            {
+                self.active.push(edge);
+            }
+            let row = self.row;
+            self.active.retain(|edge| edge.last_row >= row);
+            self.row += 1;
+            let spans = self.row_spans(row);
+            if !spans.is_empty() {
+                return Some((row, spans));
+            }
+        }
+    }
+}
+
+/// Ground size (meters) of a tile at row `y` (at the row's center latitude)
+fn tile_ground_size(y: u32, zoom: u8) -> f64 {
+    let z2 = f64::from(1u32 << zoom);
+    let yc = (f64::from(y) + 0.5) / z2;
+    let lat = (PI * (1.0 - 2.0 * yc)).sinh().atan();
+    EARTH_CIRCUMFERENCE * lat.cos() / z2
+}
+
+/// Greatest tile offset `k` w/ an edge gap (`max(k - 1, 0)`) below `radius`
+fn radius_reach(radius: f64) -> i64 {
+    ((radius + 1.0).ceil() as i64 - 1).max(0)
+}
+
+/// Buffer radius (in tiles) for the tiles of a row
+fn row_radius(buffer: CoverBuffer, row: i64, zoom: u8) -> f64 {
+    let n = 1i64 << zoom;
+    match buffer {
+        CoverBuffer::Tiles(r) => f64::from(r),
+        CoverBuffer::Meters(m) => m / tile_ground_size(row as u32, zoom),
+    }
+    .min(n as f64)
+}
+
+/// Dilates row spans by a buffer using a window of the rows within reach
+struct Buffered<I: Iterator<Item = RowSpans>> {
+    rows: std::iter::Peekable<I>,
+    /// Source rows within reach w/ their buffer radius (in tiles)
+    window: VecDeque<(i64, f64, Spans)>,
+    buffer: CoverBuffer,
+    /// Max number of rows a source row is expanded by
+    reach: i64,
+    zoom: u8,
+    n: i64,
+    row: i64,
+}
+
+impl<I: Iterator<Item = RowSpans>> Buffered<I> {
+    fn new(rows: I, buffer: CoverBuffer, zoom: u8, extent: Option<(i64, i64)>) -> Self {
+        // meters buffers grow (in tiles) towards the poles, so the widest
+        // radius is at one of the extreme rows
+        let reach = extent.map_or(0, |(first, last)| {
+            radius_reach(
+                row_radius(buffer, first, zoom).max(row_radius(buffer, last, zoom)),
+            )
+        });
+        Self {
+            rows: rows.peekable(),
+            window: VecDeque::new(),
+            buffer,
+            reach,
+            zoom,
+            n: 1i64 << zoom,
+            row: 0,
+        }
+    }
+
+    /// Column offset the spans of a source row are expanded by at `dy` rows
+    fn dilation(&self, dy: i64, radius: f64) -> Option<i64> {
+        match self.buffer {
+            CoverBuffer::Tiles(_) => (dy <= radius as i64).then_some(radius as i64),
+            CoverBuffer::Meters(_) => {
+                // distance between the closest edges of the tiles
+                let gy = (dy - 1).max(0) as f64;
+                if dy == 0 {
+                    Some(radius_reach(radius))
+                } else if gy < radius {
+                    Some(radius_reach(radius.mul_add(radius, -gy * gy).sqrt()))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl<I: Iterator<Item = RowSpans>> Iterator for Buffered<I> {
+    type Item = RowSpans;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.window.is_empty() {
+                let (next_row, _) = self.rows.peek()?;
+                self.row = self.row.max(next_row - self.reach).max(0);
+            }
+            if self.row >= self.n {
+                return None;
+            }
+            while let Some((row, spans)) =
+                self.rows.next_if(|(row, _)| *row <= self.row + self.reach)
+            {
+                let radius = row_radius(self.buffer, row, self.zoom);
+                self.window.push_back((row, radius, spans));
+            }
+            while self
+                .window
+                .front()
+                .is_some_and(|(row, ..)| *row < self.row - self.reach)
+            {
+                self.window.pop_front();
+            }
+            let row = self.row;
+            self.row += 1;
+            let mut spans: Vec<(i64, i64)> = Vec::new();
+            for (src_row, radius, src_spans) in &self.window {
+                if let Some(dx) = self.dilation((row - src_row).abs(), *radius) {
+                    let dx = dx.min(self.n);
+                    spans.extend(src_spans.iter().map(|(s, e)| (s - dx, e + dx)));
+                }
+            }
+            if !spans.is_empty() {
+                return Some((row, wrap_spans(spans, self.n)));
+            }
+        }
+    }
+}
+
+/// Streaming tile coverage as `TileZBox` ranges
+///
+/// Rows are rasterized one at a time (scanline) and consecutive rows
+/// covering the same columns are merged into a single range; ranges do not
+/// overlap and are yielded in row order. Use [`CoverRanges::tiles`] for the
+/// individual tiles.
+pub struct CoverRanges {
+    rows: Box<dyn Iterator<Item = RowSpans> + Send>,
+    zoom: u8,
+    /// Current run of rows w/ identical spans (first row, last row, spans)
+    run: Option<(i64, i64, Spans)>,
+    ranges: VecDeque<TileZBox>,
+}
+
+impl CoverRanges {
+    pub(crate) fn new(
+        shapes: &[Shape],
+        zoom: u8,
+        mode: CoverMode,
+        buffer: Option<CoverBuffer>,
+    ) -> Self {
+        let scanline = Scanline::new(shapes, zoom, mode);
+        let rows: Box<dyn Iterator<Item = RowSpans> + Send> = match buffer {
+            Some(buffer) => {
+                let extent = scanline.row_extent();
+                Box::new(Buffered::new(scanline, buffer, zoom, extent))
+            }
+            None => Box::new(scanline),
+        };
+        Self {
+            rows,
+            zoom,
+            run: None,
+            ranges: VecDeque::new(),
+        }
+    }
+
+    /// Return the zoom level of the coverage
+    #[must_use]
+    pub const fn zoom(&self) -> u8 {
+        self.zoom
+    }
+
+    /// Iterate over the individual tiles of the coverage
+    pub fn tiles(self) -> impl Iterator<Item = Tile> + Send {
+        self.flat_map(|zbox| zbox.into_iter().map(Tile::from))
+    }
+
+    fn flush(&mut self, (first, last, spans): (i64, i64, Spans)) {
+        self.ranges.extend(spans.into_iter().map(|(start, end)| {
+            TileZBox::new(
+                start as u32,
+                end as u32,
+                first as u32,
+                last as u32,
+                self.zoom,
+            )
+        }));
+    }
+}
+
+impl Iterator for CoverRanges {
+    type Item = TileZBox;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(zbox) = self.ranges.pop_front() {
+                return Some(zbox);
+            }
+            let Some((row, spans)) = self.rows.next() else {
+                let run = self.run.take()?;
+                self.flush(run);
+                continue;
+            };
+            match &mut self.run {
+                Some((_, last, run_spans))
+                    if *last + 1 == row && *run_spans == spans =>
+                {
+                    *last = row;
+                }
+                run => {
+                    if let Some(run) = run.replace((row, row, spans)) {
+                        self.flush(run);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use utiles_core::TileLike;
+
+    use super::*;
+
+    fn square(w: f64, s: f64, e: f64, n: f64) -> Vec<(f64, f64)> {
+        vec![(w, s), (e, s), (e, n), (w, n), (w, s)]
+    }
+
+    fn cover(rings: &[Vec<(f64, f64)>], zoom: u8, mode: CoverMode) -> HashSet<Tile> {
+        CoverRanges::new(&[Shape::Polygon(rings.to_vec())], zoom, mode, None)
+            .tiles()
+            .collect()
+    }
+
+    fn buffered(tile: Tile, buffer: CoverBuffer) -> HashSet<Tile> {
+        let center = tile.center();
+        let shapes = [Shape::Point((center.lng(), center.lat()))];
+        CoverRanges::new(&shapes, tile.z, CoverMode::Intersects, Some(buffer))
+            .tiles()
+            .collect()
+    }
+
+    #[test]
+    fn parse_mode_and_buffer() {
+        assert_eq!("WITHIN".parse::<CoverMode>().ok(), Some(CoverMode::Within));
+        assert!("nope".parse::<CoverMode>().is_err());
+        assert_eq!(
+            "2t".parse::<CoverBuffer>().ok(),
+            Some(CoverBuffer::Tiles(2))
+        );
+        assert_eq!(
+            "1.5km".parse::<CoverBuffer>().ok(),
+            Some(CoverBuffer::Meters(1500.0))
+        );
+        assert_eq!(
+            "250".parse::<CoverBuffer>().ok(),
+            Some(CoverBuffer::Meters(250.0))
+        );
+        assert!("-1m".parse::<CoverBuffer>().is_err());
+    }
+
+    #[test]
+    fn tile_aligned_square() {
+        // exactly the 4 tiles of the north-east quadrant at zoom 2
+        let ring = square(0.0, 0.0, 180.0, MAX_LAT);
+        let expected: HashSet<Tile> = [(2, 0), (3, 0), (2, 1), (3, 1)]
+            .map(|(x, y)| Tile::new(x, y, 2))
+            .into();
+        assert_eq!(
+            cover(std::slice::from_ref(&ring), 2, CoverMode::Intersects),
+            expected
+        );
+        assert_eq!(
+            cover(std::slice::from_ref(&ring), 2, CoverMode::Within),
+            expected
+        );
+        assert_eq!(cover(&[ring], 2, CoverMode::Centroid), expected);
+    }
+
+    #[test]
+    fn modes() {
+        // zoom 3 tiles are 45 degrees wide; row 3 is 0..~41 degrees north
+        let ring = square(10.0, 10.0, 100.0, 30.0);
+        let intersects = cover(std::slice::from_ref(&ring), 3, CoverMode::Intersects);
+        let within = cover(std::slice::from_ref(&ring), 3, CoverMode::Within);
+        let centroid = cover(&[ring], 3, CoverMode::Centroid);
+        assert_eq!(intersects.len(), 3);
+        assert!(within.is_empty());
+        // tile centers at 22.5 and 67.5 degrees
+        assert_eq!(
+            centroid,
+            HashSet::from([Tile::new(4, 3, 3), Tile::new(5, 3, 3)])
+        );
+    }
+
+    #[test]
+    fn holes() {
+        let exterior = square(-40.0, -40.0, 40.0, 40.0);
+        let hole = square(-10.0, -10.0, 10.0, 10.0);
+        let zoom = 6;
+        let solid = cover(std::slice::from_ref(&exterior), zoom, CoverMode::Within);
+        let donut = cover(&[exterior, hole], zoom, CoverMode::Within);
+        let center = Tile::new(32, 32, zoom);
+        assert!(solid.contains(&center));
+        assert!(!donut.contains(&center));
+        assert!(donut.len() < solid.len());
+    }
+
+    #[test]
+    fn antimeridian() {
+        let ring = vec![
+            (170.0, -10.0),
+            (-170.0, -10.0),
+            (-170.0, 10.0),
+            (170.0, 10.0),
+            (170.0, -10.0),
+        ];
+        let tiles = cover(&[ring], 2, CoverMode::Intersects);
+        let expected: HashSet<Tile> = [(0, 1), (0, 2), (3, 1), (3, 2)]
+            .map(|(x, y)| Tile::new(x, y, 2))
+            .into();
+        assert_eq!(tiles, expected);
+        // same polygon w/ continuous longitudes > 180
+        let ring = square(170.0, -10.0, 190.0, 10.0);
+        assert_eq!(cover(&[ring], 2, CoverMode::Intersects), expected);
+    }
+
+    #[test]
+    fn polar() {
+        // ring around the north pole (not closed over the pole)
+        let ring = vec![
+            (-180.0, 70.0),
+            (-90.0, 70.0),
+            (0.0, 70.0),
+            (90.0, 70.0),
+            (180.0, 70.0),
+        ];
+        let tiles = cover(&[ring], 2, CoverMode::Intersects);
+        // the whole top row (lat > ~66.5)
+        let expected: HashSet<Tile> = (0..4).map(|x| Tile::new(x, 0, 2)).collect();
+        assert_eq!(tiles, expected);
+    }
+
+    #[test]
+    fn buffers() {
+        let tile = Tile::new(4, 4, 4);
+        assert_eq!(buffered(tile, CoverBuffer::Tiles(1)).len(), 9);
+        assert_eq!(buffered(tile, CoverBuffer::Meters(1.0)).len(), 9);
+        assert_eq!(buffered(tile, CoverBuffer::Meters(0.0)).len(), 1);
+        let size = tile_ground_size(4, 4);
+        // 5x5 minus the 4 corners (corner gap is sqrt(2) tiles)
+        assert_eq!(buffered(tile, CoverBuffer::Meters(size * 1.2)).len(), 21);
+        // wraps x, clamps y
+        let edge = buffered(Tile::new(0, 0, 2), CoverBuffer::Tiles(1));
+        assert!(edge.contains(&Tile::new(3, 1, 2)));
+        assert_eq!(edge.len(), 6);
+    }
+
+    #[test]
+    fn ranges() {
+        // rectangle at zoom 10 ~ a single range
+        let ring = square(-10.0, -10.0, 10.0, 10.0);
+        let shapes = [Shape::Polygon(vec![ring])];
+        let ranges = CoverRanges::new(&shapes, 10, CoverMode::Intersects, None)
+            .collect::<Vec<_>>();
+        assert_eq!(ranges.len(), 1);
+        let tiles = cover(
+            &[square(-10.0, -10.0, 10.0, 10.0)],
+            10,
+            CoverMode::Intersects,
+        );
+        assert_eq!(ranges[0].length(), tiles.len() as u64);
+
+        // overlapping parts are not repeated
+        let shapes = [
+            Shape::Polygon(vec![square(0.0, 0.0, 20.0, 20.0)]),
+            Shape::Polygon(vec![square(10.0, 10.0, 30.0, 30.0)]),
+            Shape::Line(vec![(-5.0, 5.0), (35.0, 5.0)]),
+        ];
+        let tiles = CoverRanges::new(&shapes, 8, CoverMode::Intersects, None)
+            .tiles()
+            .collect::<Vec<_>>();
+        let unique = tiles.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(tiles.len(), unique.len());
+    }
+}
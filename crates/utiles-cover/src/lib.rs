@@ -7,15 +7,17 @@
 #![deny(clippy::suspicious)]
 #![deny(clippy::complexity)]
 #![deny(clippy::unwrap_used)]
+mod cover_exact;
 #[cfg(feature = "geojson")]
 mod cover_geojson;
 #[cfg(feature = "geo-types")]
 mod cover_geotypes;
 mod errors;
+pub use cover_exact::{CoverBuffer, CoverMode, CoverRanges};
 #[cfg(feature = "geojson")]
-pub use cover_geojson::{GeojsonCoverOptions, geojson2tiles};
+pub use cover_geojson::{GeojsonCoverOptions, geojson2ranges, geojson2tiles};
 #[cfg(feature = "geo-types")]
-pub use cover_geotypes::{GeoTypesCoverOptions, geometry2tiles};
+pub use cover_geotypes::{GeoTypesCoverOptions, geometry2ranges, geometry2tiles};
 pub use errors::UtilesCoverError;
 
 #[cfg(test)]
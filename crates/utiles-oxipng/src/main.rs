@@ -6,13 +6,13 @@ use futures::StreamExt;
 use tokio::join;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, error, info, warn};
+use utiles::img::oxipngify;
 use utiles::internal::cli_tools::open_new_overwrite;
 use utiles::lager::{LagerConfig, LagerLevel, init_tracing};
 use utiles::mbt::{
     MbtStreamWriterSync, MbtWriterStats, MbtilesAsync, MbtilesClientAsync,
 };
 use utiles::sqlite::InsertStrategy;
-use utiles::tile_type::{TileFormat, tiletype};
 
 #[derive(Debug, Parser)]
 #[command(name = "utiles-oxipng")]
@@ -59,15 +59,6 @@ struct Cli {
     pub(crate) force: bool,
 }
 
-pub fn oxipngify(data: &[u8], options: &oxipng::Options) -> Result<Vec<u8>> {
-    if tiletype(data).format == TileFormat::Png {
-        oxipng::optimize_from_memory(data, options).map_err(std::convert::Into::into)
-    } else {
-        warn!("Unsupported image type");
-        Ok(data.to_vec())
-    }
-}
-
 #[expect(clippy::cast_possible_wrap)]
 const fn signed_size_diff(initial_size: usize, final_size: usize) -> i64 {
     final_size as i64 - initial_size as i64
@@ -0,0 +1,31 @@
+//! utiles sqlite loadable extension
+//!
+//! Registers the `utiles-sqlite` and `sqlite-hashes` functions:
+//!
+//! ```sql
+//! .load target/release/libutiles_sqlite_ext
+//! SELECT ut_quadkey(zoom_level, tile_column, ut_flipy(zoom_level, tile_row)) FROM tiles;
+//! ```
+use std::os::raw::{c_char, c_int};
+
+use rusqlite::{Connection, Result, ffi};
+
+/// Entry point for sqlite to load the extension
+///
+/// # Safety
+///
+/// Called by sqlite w/ valid db and api routine pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_extension_init(
+    db: *mut ffi::sqlite3,
+    pz_err_msg: *mut *mut c_char,
+    p_api: *mut ffi::sqlite3_api_routines,
+) -> c_int {
+    unsafe { Connection::extension_init2(db, pz_err_msg, p_api, extension_init) }
+}
+
+fn extension_init(db: Connection) -> Result<bool> {
+    sqlite_hashes::register_hash_functions(&db)?;
+    utiles_sqlite::register_utiles_functions(&db)?;
+    Ok(false)
+}
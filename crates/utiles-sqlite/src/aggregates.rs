@@ -0,0 +1,273 @@
+use md5::Digest;
+use noncrypto_digests::{Fnv, Xxh3_64, Xxh3_128, Xxh32, Xxh64};
+use rusqlite::Connection;
+use rusqlite::Error::InvalidFunctionParameterType;
+use rusqlite::functions::{Aggregate, Context, FunctionFlags};
+use rusqlite::types::ValueRef;
+use tracing::trace;
+
+use crate::tilemath::{tile_args, user_fn_error};
+
+/// Hasher of the `ut_agg_hash` aggregate (hash types of `utiles agg-hash`)
+enum TilesHasher {
+    Md5(md5::Md5),
+    Fnv1a(Fnv),
+    Xxh32(Xxh32),
+    Xxh64(Xxh64),
+    Xxh3_64(Xxh3_64),
+    Xxh3_128(Xxh3_128),
+}
+
+impl TilesHasher {
+    fn from_name(name: &str) -> rusqlite::Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "md5" => Ok(Self::Md5(md5::Md5::new())),
+            "fnv" | "fnv1a" | "fnv1a64" => Ok(Self::Fnv1a(Fnv::new())),
+            "xxh32" => Ok(Self::Xxh32(Xxh32::new())),
+            "xxh64" => Ok(Self::Xxh64(Xxh64::new())),
+            "xxh3" | "xxh3_64" | "xxh3-64" => Ok(Self::Xxh3_64(Xxh3_64::new())),
+            "xxh3_128" | "xxh3-128" => Ok(Self::Xxh3_128(Xxh3_128::new())),
+            _ => Err(user_fn_error(format!("unknown hash type: {name}"))),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(h) => h.update(data),
+            Self::Fnv1a(h) => h.update(data),
+            Self::Xxh32(h) => h.update(data),
+            Self::Xxh64(h) => h.update(data),
+            Self::Xxh3_64(h) => h.update(data),
+            Self::Xxh3_128(h) => h.update(data),
+        }
+    }
+
+    fn finalize_hex_upper(self) -> String {
+        match self {
+            Self::Md5(h) => hex::encode_upper(h.finalize()),
+            Self::Fnv1a(h) => hex::encode_upper(h.finalize()),
+            Self::Xxh32(h) => hex::encode_upper(h.finalize()),
+            Self::Xxh64(h) => hex::encode_upper(h.finalize()),
+            Self::Xxh3_64(h) => hex::encode_upper(h.finalize()),
+            Self::Xxh3_128(h) => hex::encode_upper(h.finalize()),
+        }
+    }
+}
+
+/// `ut_agg_hash([hash_type,] z, x, y, data)` ~ hash of the concatenated
+/// `z`, `x`, `y` (as text) and `data` of the rows like `utiles agg-hash`
+struct AggHash {
+    /// The first arg is the hash type (otherwise md5)
+    hash_type_arg: bool,
+}
+
+impl Aggregate<Option<TilesHasher>, Option<String>> for AggHash {
+    fn init(&self, _ctx: &mut Context<'_>) -> rusqlite::Result<Option<TilesHasher>> {
+        Ok(None)
+    }
+
+    fn step(
+        &self,
+        ctx: &mut Context<'_>,
+        hasher: &mut Option<TilesHasher>,
+    ) -> rusqlite::Result<()> {
+        let idx = usize::from(self.hash_type_arg);
+        let hasher = match hasher.take() {
+            Some(h) => hasher.insert(h),
+            None if self.hash_type_arg => {
+                hasher.insert(TilesHasher::from_name(&ctx.get::<String>(0)?)?)
+            }
+            None => hasher.insert(TilesHasher::Md5(md5::Md5::new())),
+        };
+        let z: Option<i64> = ctx.get(idx)?;
+        let x: Option<i64> = ctx.get(idx + 1)?;
+        let y: Option<i64> = ctx.get(idx + 2)?;
+        let data = match ctx.get_raw(idx + 3) {
+            ValueRef::Blob(b) | ValueRef::Text(b) => b,
+            ValueRef::Null => return Ok(()),
+            v => return Err(InvalidFunctionParameterType(idx + 3, v.data_type())),
+        };
+        if let (Some(z), Some(x), Some(y)) = (z, x, y) {
+            hasher.update(z.to_string().as_bytes());
+            hasher.update(x.to_string().as_bytes());
+            hasher.update(y.to_string().as_bytes());
+            hasher.update(data);
+        }
+        Ok(())
+    }
+
+    fn finalize(
+        &self,
+        _ctx: &mut Context<'_>,
+        hasher: Option<Option<TilesHasher>>,
+    ) -> rusqlite::Result<Option<String>> {
+        match hasher.flatten() {
+            Some(hasher) => Ok(Some(hasher.finalize_hex_upper())),
+            // no rows; the hash type is unknown if given as an arg
+            None if self.hash_type_arg => Ok(None),
+            None => Ok(Some(TilesHasher::Md5(md5::Md5::new()).finalize_hex_upper())),
+        }
+    }
+}
+
+/// Aggregate function hashing tiles (`ut_agg_hash([hash_type,] z, x, y, data)`).
+///
+/// Returns the same upper-case hex hash as `utiles agg-hash` when called w/
+/// `zoom_level, tile_column, tile_row, tile_data` ordered by
+/// `zoom_level, tile_column, tile_row`.
+pub(super) fn add_function_ut_agg_hash(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_agg_hash function");
+    for (n_arg, hash_type_arg) in [(4, false), (5, true)] {
+        db.create_aggregate_function(
+            "ut_agg_hash",
+            n_arg,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            AggHash { hash_type_arg },
+        )?;
+    }
+    Ok(())
+}
+
+/// `ut_bbox_union(z, x, y)` ~ `[west,south,east,north]` bounds of the tiles
+struct BBoxUnion;
+
+impl Aggregate<Option<(f64, f64, f64, f64)>, Option<String>> for BBoxUnion {
+    fn init(
+        &self,
+        _ctx: &mut Context<'_>,
+    ) -> rusqlite::Result<Option<(f64, f64, f64, f64)>> {
+        Ok(None)
+    }
+
+    fn step(
+        &self,
+        ctx: &mut Context<'_>,
+        acc: &mut Option<(f64, f64, f64, f64)>,
+    ) -> rusqlite::Result<()> {
+        if let Some(tile) = tile_args(ctx, 0)? {
+            let (west, south, east, north) = tile.bounds();
+            *acc = Some(match *acc {
+                Some((w, s, e, n)) => {
+                    (w.min(west), s.min(south), e.max(east), n.max(north))
+                }
+                None => (west, south, east, north),
+            });
+        }
+        Ok(())
+    }
+
+    fn finalize(
+        &self,
+        _ctx: &mut Context<'_>,
+        acc: Option<Option<(f64, f64, f64, f64)>>,
+    ) -> rusqlite::Result<Option<String>> {
+        Ok(acc.flatten().map(|(west, south, east, north)| {
+            utiles_core::BBox::new(west, south, east, north).json_arr()
+        }))
+    }
+}
+
+/// Aggregate function returning the bounds union of tiles
+/// (`ut_bbox_union(z, x, y)`); NULL if there are no tiles.
+pub(super) fn add_function_ut_bbox_union(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_bbox_union function");
+    db.create_aggregate_function(
+        "ut_bbox_union",
+        3,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        BBoxUnion,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use md5::Digest;
+    use rusqlite::Connection;
+
+    fn db() -> Connection {
+        let db = Connection::open_in_memory().unwrap();
+        crate::register_utiles_functions(&db).unwrap();
+        db.execute_batch(
+            "CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+             INSERT INTO tiles VALUES (1, 1, 0, X'0102'), (1, 0, 1, X'03'), (0, 0, 0, X'04');",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn agg_hash() {
+        let db = db();
+        let mut hasher = md5::Md5::new();
+        for bytes in [&b"000\x04"[..], b"101\x03", b"110\x01\x02"] {
+            hasher.update(bytes);
+        }
+        let expected = hex::encode_upper(hasher.finalize());
+        let hash: String = db
+            .query_row(
+                "SELECT ut_agg_hash(zoom_level, tile_column, tile_row, tile_data ORDER BY zoom_level, tile_column, tile_row) FROM tiles",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(hash, expected);
+        let (md5_hash, xxh64_hash): (String, String) = db
+            .query_row(
+                "SELECT ut_agg_hash('md5', zoom_level, tile_column, tile_row, tile_data ORDER BY zoom_level, tile_column, tile_row),
+                        ut_agg_hash('xxh64', zoom_level, tile_column, tile_row, tile_data ORDER BY zoom_level, tile_column, tile_row)
+                 FROM tiles",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(md5_hash, expected);
+        assert_eq!(xxh64_hash.len(), 16);
+
+        // no rows
+        let (empty_md5, empty_typed): (String, Option<String>) = db
+            .query_row(
+                "SELECT ut_agg_hash(zoom_level, tile_column, tile_row, tile_data),
+                        ut_agg_hash('xxh64', zoom_level, tile_column, tile_row, tile_data)
+                 FROM tiles WHERE zoom_level > 10",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(empty_md5, hex::encode_upper(md5::Md5::new().finalize()));
+        assert_eq!(empty_typed, None);
+        assert!(
+            db.query_row(
+                "SELECT ut_agg_hash('nope', zoom_level, tile_column, tile_row, tile_data) FROM tiles",
+                [],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn bbox_union() {
+        let db = db();
+        let union: String = db
+            .query_row(
+                "SELECT ut_bbox_union(zoom_level, tile_column, tile_row) FROM tiles WHERE zoom_level = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let world: String = db
+            .query_row("SELECT ut_bounds_json(0, 0, 0)", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(union, world);
+        let empty: Option<String> = db
+            .query_row(
+                "SELECT ut_bbox_union(zoom_level, tile_column, tile_row) FROM tiles WHERE zoom_level > 10",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(empty, None);
+    }
+}
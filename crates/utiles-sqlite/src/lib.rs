@@ -0,0 +1,78 @@
+//! utiles-sqlite ~ sqlite function(s) for utiles
+//!
+//! Adds the following functions:
+//!   - `ut_tiletype(blob)`   - returns the tile type of the blob
+//!   - `ut_tilesize(blob)`   - returns the size of raster tile or None
+//!   - `base64_encode(blob|str)` - returns the base64 encoded string
+//!   - `xxh3_i64(blob|str)`  - returns xxh3 hash as `i64` big-endian view
+//!   - `xxh64_i64(blob|str)` - returns xxh64 hash as `i64` big-endian view
+//!   - `fnv_i64(blob|str)`   - returns fnv1a hash as `i64` big-endian view
+//!
+//! Tile math functions (`y` is the xyz (NOT the tms/mbtiles) row; use
+//! `ut_flipy(zoom_level, tile_row)` for mbtiles `tile_row` values):
+//!   - `ut_quadkey(z, x, y)` - returns the quadkey of the tile
+//!   - `ut_pmtileid(z, x, y)` - returns the pmtiles tile-id of the tile
+//!   - `ut_flipy(z, y)` - returns the flipped (xyz <-> tms) row
+//!   - `ut_bounds_json(z, x, y)` - returns the `[west,south,east,north]` bounds
+//!   - `ut_parent(z, x, y[, zoom])` - returns the parent tile (at `zoom`) as
+//!     a `[x,y,z]` json array
+//!   - `ut_tile_intersects_bbox(z, x, y, west, south, east, north)` - returns
+//!     1 if the tile intersects the bbox otherwise 0
+//!   - `ut_lnglat2tile(lng, lat, z)` - returns the tile containing the
+//!     lng/lat as a `[x,y,z]` json array
+//!
+//! Aggregate functions:
+//!   - `ut_agg_hash([hash_type,] z, x, y, data)` - returns the hash of the
+//!     tiles (same as `utiles agg-hash`; md5 by default; use
+//!     `ORDER BY zoom_level, tile_column, tile_row` to match)
+//!   - `ut_bbox_union(z, x, y)` - returns the `[west,south,east,north]` bounds
+//!     of all the tiles
+//!
+//! Functions take NULL -> return NULL (aggregates skip NULL rows).
+use rusqlite::{Connection, Result};
+use tracing::debug;
+
+use crate::aggregates::{add_function_ut_agg_hash, add_function_ut_bbox_union};
+use crate::base64::add_function_base64_encode;
+use crate::hash_int::{
+    add_function_fnv_i64, add_function_xxh3_i64, add_function_xxh64_i64,
+};
+use crate::tilemath::{
+    add_function_ut_bounds_json, add_function_ut_flipy, add_function_ut_lnglat2tile,
+    add_function_ut_parent, add_function_ut_pmtileid, add_function_ut_quadkey,
+    add_function_ut_tile_intersects_bbox,
+};
+use crate::tilesize::add_function_ut_tilesize;
+use crate::tiletype::add_function_ut_tiletype;
+
+mod aggregates;
+mod base64;
+mod hash_int;
+mod tilemath;
+mod tilesize;
+mod tiletype;
+
+/// Register all the utiles sqlite functions w/ the connection
+pub fn register_utiles_functions(conn: &Connection) -> Result<()> {
+    debug!("registering utiles-sqlite functions...");
+    add_function_ut_tiletype(conn)?;
+    add_function_ut_tilesize(conn)?;
+    add_function_base64_encode(conn)?;
+
+    add_function_xxh3_i64(conn)?;
+    add_function_xxh64_i64(conn)?;
+    add_function_fnv_i64(conn)?;
+
+    add_function_ut_quadkey(conn)?;
+    add_function_ut_pmtileid(conn)?;
+    add_function_ut_flipy(conn)?;
+    add_function_ut_bounds_json(conn)?;
+    add_function_ut_parent(conn)?;
+    add_function_ut_tile_intersects_bbox(conn)?;
+    add_function_ut_lnglat2tile(conn)?;
+
+    add_function_ut_agg_hash(conn)?;
+    add_function_ut_bbox_union(conn)?;
+    debug!("registered utiles-sqlite functions!");
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use rusqlite::Error::UserFunctionError;
+use rusqlite::functions::{Context, FunctionFlags};
+use rusqlite::{Connection, Error};
+use tracing::trace;
+use utiles_core::{BBox, MAX_ZOOM, Tile, TileLike, flipy};
+
+pub(crate) fn user_fn_error(msg: String) -> Error {
+    UserFunctionError(msg.into())
+}
+
+fn zoom_arg(z: i64) -> rusqlite::Result<u8> {
+    u8::try_from(z)
+        .ok()
+        .filter(|z| *z <= MAX_ZOOM)
+        .ok_or_else(|| user_fn_error(format!("invalid zoom: {z}")))
+}
+
+fn tile_from_zxy(z: i64, x: i64, y: i64) -> rusqlite::Result<Tile> {
+    let zoom = zoom_arg(z)?;
+    u32::try_from(x)
+        .ok()
+        .zip(u32::try_from(y).ok())
+        .and_then(|(x, y)| Tile::new_checked(x, y, zoom))
+        .ok_or_else(|| user_fn_error(format!("invalid tile: z={z} x={x} y={y}")))
+}
+
+/// Tile from the `z, x, y` args starting at `idx`; `None` if any is NULL
+pub(crate) fn tile_args(
+    ctx: &Context<'_>,
+    idx: usize,
+) -> rusqlite::Result<Option<Tile>> {
+    let z: Option<i64> = ctx.get(idx)?;
+    let x: Option<i64> = ctx.get(idx + 1)?;
+    let y: Option<i64> = ctx.get(idx + 2)?;
+    match (z, x, y) {
+        (Some(z), Some(x), Some(y)) => tile_from_zxy(z, x, y).map(Some),
+        _ => Ok(None),
+    }
+}
+
+const fn flags() -> FunctionFlags {
+    FunctionFlags::SQLITE_UTF8.union(FunctionFlags::SQLITE_DETERMINISTIC)
+}
+
+/// Scalar function returning the quadkey of a tile.
+pub(super) fn add_function_ut_quadkey(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_quadkey function");
+    db.create_scalar_function("ut_quadkey", 3, flags(), |ctx| {
+        Ok(tile_args(ctx, 0)?.map(|tile| tile.quadkey()))
+    })
+}
+
+/// Scalar function returning the pmtiles tile-id of a tile.
+pub(super) fn add_function_ut_pmtileid(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_pmtileid function");
+    db.create_scalar_function("ut_pmtileid", 3, flags(), |ctx| {
+        tile_args(ctx, 0)?
+            .map(|tile| {
+                i64::try_from(tile.pmtileid()).map_err(|e| user_fn_error(e.to_string()))
+            })
+            .transpose()
+    })
+}
+
+/// Scalar function returning the flipped row (`ut_flipy(z, y)`); converts
+/// xyz rows to tms/mbtiles rows and vice versa.
+pub(super) fn add_function_ut_flipy(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_flipy function");
+    db.create_scalar_function("ut_flipy", 2, flags(), |ctx| {
+        let z: Option<i64> = ctx.get(0)?;
+        let y: Option<i64> = ctx.get(1)?;
+        let (Some(z), Some(y)) = (z, y) else {
+            return Ok(None);
+        };
+        let tile = tile_from_zxy(z, 0, y)?;
+        Ok(Some(i64::from(flipy(tile.y, tile.z))))
+    })
+}
+
+/// Scalar function returning the `[west,south,east,north]` bounds of a tile.
+pub(super) fn add_function_ut_bounds_json(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_bounds_json function");
+    db.create_scalar_function("ut_bounds_json", 3, flags(), |ctx| {
+        Ok(tile_args(ctx, 0)?.map(|tile| {
+            let (west, south, east, north) = tile.bounds();
+            BBox::new(west, south, east, north).json_arr()
+        }))
+    })
+}
+
+/// Scalar function returning the parent tile as a `[x,y,z]` json array;
+/// `ut_parent(z, x, y)` is the direct parent and `ut_parent(z, x, y, zoom)`
+/// the parent at `zoom` (<= z).
+pub(super) fn add_function_ut_parent(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_parent function");
+    for n_arg in [3, 4] {
+        db.create_scalar_function("ut_parent", n_arg, flags(), |ctx| {
+            let Some(tile) = tile_args(ctx, 0)? else {
+                return Ok(None);
+            };
+            let zoom = if ctx.len() == 4 {
+                match ctx.get::<Option<i64>>(3)? {
+                    Some(zoom) => zoom_arg(zoom)?,
+                    None => return Ok(None),
+                }
+            } else if tile.z == 0 {
+                return Ok(None);
+            } else {
+                tile.z - 1
+            };
+            if zoom > tile.z {
+                return Err(user_fn_error(format!(
+                    "parent zoom {zoom} > tile zoom {}",
+                    tile.z
+                )));
+            }
+            let shift = tile.z - zoom;
+            let parent = Tile::new(tile.x >> shift, tile.y >> shift, zoom);
+            Ok(Some(parent.json_arr_min()))
+        })?;
+    }
+    Ok(())
+}
+
+/// Scalar function returning 1 if a tile intersects a bbox otherwise 0
+/// (`ut_tile_intersects_bbox(z, x, y, west, south, east, north)`).
+pub(super) fn add_function_ut_tile_intersects_bbox(
+    db: &Connection,
+) -> rusqlite::Result<()> {
+    trace!("Adding ut_tile_intersects_bbox function");
+    db.create_scalar_function("ut_tile_intersects_bbox", 7, flags(), |ctx| {
+        let Some(tile) = tile_args(ctx, 0)? else {
+            return Ok(None);
+        };
+        let west: Option<f64> = ctx.get(3)?;
+        let south: Option<f64> = ctx.get(4)?;
+        let east: Option<f64> = ctx.get(5)?;
+        let north: Option<f64> = ctx.get(6)?;
+        let (Some(west), Some(south), Some(east), Some(north)) =
+            (west, south, east, north)
+        else {
+            return Ok(None);
+        };
+        let (tw, ts, te, tn) = tile.bounds();
+        let bbox = BBox::new(west, south, east, north);
+        let intersects = bbox
+            .bboxes()
+            .iter()
+            .any(|bbox| BBox::new(tw, ts, te, tn).intersects(bbox));
+        Ok(Some(intersects))
+    })
+}
+
+/// Scalar function returning the tile containing a lng/lat at a zoom as a
+/// `[x,y,z]` json array (`ut_lnglat2tile(lng, lat, z)`).
+pub(super) fn add_function_ut_lnglat2tile(db: &Connection) -> rusqlite::Result<()> {
+    trace!("Adding ut_lnglat2tile function");
+    db.create_scalar_function("ut_lnglat2tile", 3, flags(), |ctx| {
+        let lng: Option<f64> = ctx.get(0)?;
+        let lat: Option<f64> = ctx.get(1)?;
+        let z: Option<i64> = ctx.get(2)?;
+        let (Some(lng), Some(lat), Some(z)) = (lng, lat, z) else {
+            return Ok(None);
+        };
+        let tile = Tile::from_lnglat_zoom(lng, lat, zoom_arg(z)?, Some(true))
+            .map_err(|e| user_fn_error(e.to_string()))?;
+        Ok(Some(tile.json_arr_min()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use rusqlite::Connection;
+    use utiles_core::{Tile, TileLike};
+
+    fn db() -> Connection {
+        let db = Connection::open_in_memory().unwrap();
+        crate::register_utiles_functions(&db).unwrap();
+        db
+    }
+
+    fn query<T: rusqlite::types::FromSql>(db: &Connection, sql: &str) -> T {
+        db.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn tile_math_fns() {
+        let db = db();
+        let tile = Tile::new(486, 332, 10);
+        assert_eq!(
+            query::<String>(&db, "SELECT ut_quadkey(10, 486, 332)"),
+            "0313102310"
+        );
+        assert_eq!(
+            query::<i64>(&db, "SELECT ut_pmtileid(10, 486, 332)"),
+            i64::try_from(tile.pmtileid()).unwrap()
+        );
+        assert_eq!(query::<i64>(&db, "SELECT ut_pmtileid(0, 0, 0)"), 0);
+        assert_eq!(query::<i64>(&db, "SELECT ut_flipy(10, 332)"), 691);
+        assert_eq!(
+            query::<i64>(&db, "SELECT ut_flipy(10, ut_flipy(10, 332))"),
+            332
+        );
+        let (_, south, _, north) = utiles_core::bounds(0, 0, 0);
+        assert_eq!(
+            query::<String>(&db, "SELECT ut_bounds_json(0, 0, 0)"),
+            format!("[-180,{south},180,{north}]")
+        );
+        assert_eq!(
+            query::<String>(&db, "SELECT ut_parent(10, 486, 332)"),
+            "[243,166,9]"
+        );
+        assert_eq!(
+            query::<String>(&db, "SELECT ut_parent(10, 486, 332, 7)"),
+            "[60,41,7]"
+        );
+        assert_eq!(
+            query::<Option<String>>(&db, "SELECT ut_parent(0, 0, 0)"),
+            None
+        );
+        assert_eq!(
+            query::<String>(&db, "SELECT ut_lnglat2tile(-105.0, 40.0, 10)"),
+            Tile::from_lnglat_zoom(-105.0, 40.0, 10, None)
+                .unwrap()
+                .json_arr_min()
+        );
+        assert!(query::<bool>(
+            &db,
+            "SELECT ut_tile_intersects_bbox(10, 486, 332, -10, 40, 10, 60)"
+        ));
+        assert!(!query::<bool>(
+            &db,
+            "SELECT ut_tile_intersects_bbox(10, 486, 332, -8, 40, 10, 60)"
+        ));
+        // antimeridian crossing bbox
+        assert!(query::<bool>(
+            &db,
+            "SELECT ut_tile_intersects_bbox(1, 0, 0, 170, 10, -170, 20)"
+        ));
+    }
+
+    #[test]
+    fn tile_math_fns_null_and_invalid() {
+        let db = db();
+        assert_eq!(
+            query::<Option<String>>(&db, "SELECT ut_quadkey(NULL, 1, 1)"),
+            None
+        );
+        assert_eq!(query::<Option<i64>>(&db, "SELECT ut_flipy(3, NULL)"), None);
+        for sql in [
+            "SELECT ut_quadkey(1, 2, 0)",
+            "SELECT ut_quadkey(31, 0, 0)",
+            "SELECT ut_flipy(2, -1)",
+            "SELECT ut_parent(3, 0, 0, 4)",
+        ] {
+            let res = db.query_row(sql, [], |row| row.get::<_, Option<String>>(0));
+            assert!(res.is_err(), "{sql}: {res:?}");
+        }
+    }
+}
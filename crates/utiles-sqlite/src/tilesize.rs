@@ -20,7 +20,7 @@ pub(super) fn add_function_ut_tilesize(db: &Connection) -> rusqlite::Result<()>
             let size: Option<i64> = match imagesize::blob_size(blob) {
                 Ok(imgsize) => {
                     if imgsize.width == imgsize.height {
-                        Some(imgsize.width as i64)
+                        i64::try_from(imgsize.width).ok()
                     } else {
                         // -1 is there is a problem and img is not square
                         Some(-1)
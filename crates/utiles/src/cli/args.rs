@@ -1,19 +1,29 @@
 use std::path::PathBuf;
 
 use clap::{Args, Parser, Subcommand};
+use image::imageops::FilterType;
 use strum_macros::AsRefStr;
 use utiles_core::parsing::parse_bbox_ext;
+use utiles_core::resolution::parse_tile_size;
+use utiles_core::terrain::TerrainEncoding;
+use utiles_core::tile_type::TileFormat;
+use utiles_core::tms::BUILTIN_TMS_IDS;
 use utiles_core::{
-    BBox, LngLat, TileStringFormatter, VERSION, ZoomSet, geobbox_merge, zoom,
+    BBox, LngLat, TileMatrixSet, TileStringFormatter, VERSION, ZoomSet, geobbox_merge,
+    zoom,
 };
 
 use crate::cli::commands::dev::DevArgs;
 use crate::cli::commands::serve::ServeArgs;
 use crate::cli::commands::shapes::ShapesArgs;
-use crate::cli::commands::{analyze_main, header_main, vacuum_main};
+use crate::cli::commands::{
+    analyze_main, check_main, header_main, salvage_main, tileset_main, vacuum_main,
+};
 use crate::copy::CopyConfig;
-use crate::errors::UtilesResult;
+use crate::cover::{CoverBuffer, CoverMode};
+use crate::errors::{UtilesError, UtilesResult};
 use crate::hash_types::HashType;
+use crate::img::{BlendMode, CompositeLayer, HillshadeOptions, WebpOptions};
 use crate::mbt::{MbtType, TilesFilter};
 use crate::sqlite::InsertStrategy;
 
@@ -61,10 +71,41 @@ pub struct Cli {
     pub command: Commands,
 }
 
+/// Bbox/geojson input (stdin, file or arg)
+#[derive(Debug, Parser)]
+pub struct InputStreamArgs {
+    #[arg(required = false)]
+    pub input: Option<String>,
+}
+
 #[derive(Debug, Parser)]
 pub struct TileInputStreamArgs {
     #[arg(required = false)]
     pub input: Option<String>,
+
+    /// Tile input format (default: auto ~ json tiles, `z/x/y` paths, csv/tsv w/
+    /// header and geojson features)
+    #[arg(required = false, long, value_enum, default_value = "auto")]
+    pub input_format: TileInputFormat,
+}
+
+/// Tile input (line) format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TileInputFormat {
+    /// Detect the format per line (csv/tsv header on the first line)
+    #[default]
+    Auto,
+    /// Json tile arrays/objects (`[x, y, z]`, `{"x": x, "y": y, "z": z}`)
+    Json,
+    /// Comma separated rows w/ header (`z,x,y`, `zoom_level,tile_column,tile_row`
+    /// rows are TMS)
+    Csv,
+    /// Tab separated rows w/ header (see csv)
+    Tsv,
+    /// `z/x/y` urls/file-paths (e.g. `https://tiles.io/3/2/5.png`)
+    Path,
+    /// Geojson features (tile from `x`/`y`/`z` properties or feature id)
+    Geojson,
 }
 
 fn tile_fmt_string_long_help() -> String {
@@ -87,6 +128,10 @@ fmt-tokens:
     `{json_obj}`/`{obj}`   -> {x: x, y: y, z: z}
     `{quadkey}`/`{qk}`     -> quadkey string
     `{pmtileid}`/`{pmid}`  -> pmtile-id
+    `{hilbert}`            -> hilbert-curve id (per-zoom)
+    `{morton}`/`{zorder}`  -> morton/z-order id (per-zoom)
+    `{hilbert_global}`     -> hilbert-curve id (global; same as pmtile-id)
+    `{morton_global}`      -> morton/z-order id (global)
     `{x}`                  -> x tile coord
     `{y}`                  -> y tile coord
     `{z}`                  -> z/zoom level
@@ -96,6 +141,23 @@ fmt-tokens:
     `{projwin}`            -> ulx,uly,lrx,lry projwin 4 gdal (wgs84)
     `{bbox_web}`           -> [w, s, e, n] bbox web-mercator (epsg:3857)
     `{projwin_web}`        -> ulx,uly,lrx,lry projwin 4 gdal (epsg:3857)
+    `{lng}`/`{lat}`        -> tile center lng/lat
+    `{ul}`                 -> upper-left corner lng lat
+    `{ul_web}`             -> upper-left corner x y web-mercator (epsg:3857)
+    `{wkt}`                -> tile polygon WKT (wgs84)
+    `{ext}`                -> tile data extension (png, pbf.gz...; enumerate)
+
+fmt-expressions:
+    `{x:06}`/`{y:8}`       -> zero-padded/space-padded width
+    `{lng:.5}`             -> float precision (lng, lat, ul, ul_web, wkt)
+    `{z+1}`/`{x-1}`        -> integer offsets (x, y, z, -y)
+    `{parent(10).zxy}`     -> token of the parent tile at zoom 10
+    `{parent(z-2).x}`      -> token of the parent tile 2 levels up
+    `{parent.quadkey}`     -> token of the direct parent tile
+
+Example:
+    > utiles tiles 12 "[-122.45, 37.75, -122.44, 37.76]" --fmt "s3://bkt/{parent(8).zxy}/{z}_{x:05}_{y:05}.png"
+    s3://bkt/8/40/98/12_00654_01583.png
     "#
         .to_string()
 }
@@ -139,6 +201,20 @@ impl From<&TileFmtOptions> for TileStringFormatter {
     }
 }
 
+/// Parse a built-in tile matrix set id or an OGC tile matrix set json filepath
+pub(crate) fn parse_tms(s: &str) -> Result<TileMatrixSet, String> {
+    if let Some(tms) = TileMatrixSet::builtin(s) {
+        return Ok(tms);
+    }
+    let json = std::fs::read_to_string(s).map_err(|e| {
+        format!(
+            "{s}: {e} (builtin tile matrix sets: {})",
+            BUILTIN_TMS_IDS.join(", ")
+        )
+    })?;
+    TileMatrixSet::from_json(&json).map_err(|e| format!("{s}: {e}"))
+}
+
 #[derive(Debug, Parser)]
 pub struct TilesArgs {
     /// Zoom level (0-30)
@@ -146,10 +222,15 @@ pub struct TilesArgs {
     pub zoom: u8,
 
     #[command(flatten)]
-    pub inargs: TileInputStreamArgs,
+    pub inargs: InputStreamArgs,
 
     #[command(flatten)]
     pub fmtopts: TileFmtOptions,
+
+    /// Tile matrix set ~ built-in id (`WebMercatorQuad`, `WorldCRS84Quad`,
+    /// `WGS1984Quad`) or OGC tile matrix set json filepath (default: web-mercator)
+    #[arg(required = false, long, value_parser = parse_tms)]
+    pub tms: Option<TileMatrixSet>,
 }
 
 #[derive(Debug, Parser)]
@@ -160,6 +241,113 @@ pub struct TileFmtArgs {
     #[command(flatten)]
     pub fmtopts: TileFmtOptions,
 }
+
+#[derive(Debug, Parser)]
+pub struct BoundingTileArgs {
+    #[command(flatten)]
+    pub inargs: InputStreamArgs,
+
+    #[command(flatten)]
+    pub fmtopts: TileFmtOptions,
+
+    /// Tile matrix set ~ built-in id (`WebMercatorQuad`, `WorldCRS84Quad`,
+    /// `WGS1984Quad`) or OGC tile matrix set json filepath (default: web-mercator)
+    #[arg(required = false, long, value_parser = parse_tms)]
+    pub tms: Option<TileMatrixSet>,
+}
+
+#[derive(Debug, Parser)]
+pub struct TmsTileFmtArgs {
+    #[command(flatten)]
+    pub inargs: TileInputStreamArgs,
+
+    #[command(flatten)]
+    pub fmtopts: TileFmtOptions,
+
+    /// Tile matrix set ~ built-in id (`WebMercatorQuad`, `WorldCRS84Quad`,
+    /// `WGS1984Quad`) or OGC tile matrix set json filepath (default: web-mercator)
+    #[arg(required = false, long, value_parser = parse_tms)]
+    pub tms: Option<TileMatrixSet>,
+}
+/// Tile id conversion args (hilbert/morton)
+#[derive(Debug, Parser)]
+pub struct TileIdArgs {
+    #[command(flatten)]
+    pub inargs: TileInputStreamArgs,
+
+    /// Use per-zoom ids (`0..4^z`) instead of global ids
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue)]
+    pub per_zoom: bool,
+
+    /// Zoom level of per-zoom ids being converted to tiles
+    #[arg(required = false, long, short, requires = "per_zoom", value_parser = clap::value_parser!(u8).range(0..=30))]
+    pub zoom: Option<u8>,
+}
+
+/// Resolution/scale per zoom args
+#[derive(Debug, Parser)]
+pub struct ResolutionArgs {
+    /// Zoom level(s) (default: 0-22)
+    #[arg(short, long, required = false, value_delimiter = ',', value_parser = zoom::parse_zooms)]
+    pub zoom: Option<Vec<Vec<u8>>>,
+
+    /// Tile size ~ pixels (`256`, `512`) and/or retina scale (`@2x`, `512@2x`)
+    #[arg(required = false, long, short = 's', default_value = "256", value_parser = parse_tile_size)]
+    pub tile_size: u32,
+
+    /// Latitude for ground resolution (meters/pixel)
+    #[arg(required = false, long, allow_hyphen_values = true)]
+    pub lat: Option<f64>,
+
+    /// Resolution (meters/pixel; at `--lat` if given) to get the zoom for
+    #[arg(required = false, long, conflicts_with_all = ["zoom", "scale_denominator"])]
+    pub resolution: Option<f64>,
+
+    /// Scale denominator to get the zoom for
+    #[arg(required = false, long, conflicts_with = "zoom")]
+    pub scale_denominator: Option<f64>,
+}
+
+/// Coordinate space of `utiles pixel` input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PixelFrom {
+    /// Longitude/latitude (EPSG:4326)
+    #[default]
+    Lnglat,
+    /// Web-mercator meters (EPSG:3857)
+    Mercator,
+    /// Global pixel coordinates at the zoom
+    Pixel,
+    /// Tile + tile-local pixel coordinates (`x y z px py`)
+    TilePixel,
+    /// Tile + tile-local extent coordinates (`x y z ex ey`; e.g. MVT)
+    Extent,
+}
+
+/// Pixel transform args
+#[derive(Debug, Parser)]
+pub struct PixelArgs {
+    /// Point(s) (default: stdin)
+    #[arg(required = false, allow_hyphen_values = true)]
+    pub input: Option<String>,
+
+    /// Input coordinate space
+    #[arg(required = false, long, value_enum, default_value = "lnglat")]
+    pub from: PixelFrom,
+
+    /// Zoom level (0-30) of lnglat/mercator/pixel input
+    #[arg(required = false, long, short, value_parser = clap::value_parser!(u8).range(0..=30))]
+    pub zoom: Option<u8>,
+
+    /// Tile size ~ pixels (`256`, `512`) and/or retina scale (`@2x`, `512@2x`)
+    #[arg(required = false, long, short = 's', default_value = "256", value_parser = parse_tile_size)]
+    pub tile_size: u32,
+
+    /// Tile-local coordinate extent (4096 for MVT)
+    #[arg(required = false, long, default_value = "4096")]
+    pub extent: u32,
+}
+
 #[derive(Debug, Parser)]
 pub struct EdgesArgs {
     /// Wrap x/longitude across antimeridian (default: false)
@@ -180,10 +368,23 @@ pub struct BurnArgs {
     pub zoom: u8,
 
     #[command(flatten)]
-    pub inargs: TileInputStreamArgs,
+    pub inargs: InputStreamArgs,
 
     #[command(flatten)]
     pub fmtopts: TileFmtOptions,
+
+    /// Polygon coverage mode: `intersects`, `within` (tiles fully inside) or
+    /// `centroid` (tile center inside)
+    #[arg(required = false, long, default_value = "intersects")]
+    pub mode: CoverMode,
+
+    /// Expand the coverage by a buffer ~ meters (`500m`, `2km`) or tiles (`2t`)
+    #[arg(required = false, long)]
+    pub buffer: Option<CoverBuffer>,
+
+    /// Write `TileZBox` ranges (json) instead of individual tiles
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue)]
+    pub ranges: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -221,6 +422,11 @@ pub struct ParentChildrenArgs {
 
     #[arg(required = false, long, default_value = "1")]
     pub depth: u8,
+
+    /// Tile matrix set ~ built-in id (`WebMercatorQuad`, `WorldCRS84Quad`,
+    /// `WGS1984Quad`) or OGC tile matrix set json filepath (default: web-mercator)
+    #[arg(required = false, long, value_parser = parse_tms)]
+    pub tms: Option<TileMatrixSet>,
 }
 
 #[derive(Debug, Parser)]
@@ -315,6 +521,8 @@ pub enum SqliteCommands {
     Analyze(AnalyzeArgs),
     Header(SqliteHeaderArgs),
     Vacuum(VacuumArgs),
+    Check(SqliteCheckArgs),
+    Salvage(SqliteSalvageArgs),
 }
 
 impl SqliteCommands {
@@ -323,10 +531,74 @@ impl SqliteCommands {
             Self::Analyze(args) => analyze_main(args).await,
             Self::Header(args) => header_main(args).await,
             Self::Vacuum(args) => vacuum_main(args).await,
+            Self::Check(args) => check_main(args).await,
+            Self::Salvage(args) => salvage_main(args).await,
         }
     }
 }
 
+#[derive(Debug, Subcommand)]
+/// tile-set (coverage) set algebra
+pub enum TilesetCommands {
+    /// Union of tile-sets
+    Union(TilesetOpArgs),
+
+    /// Intersection of tile-sets
+    #[command(visible_alias = "intersection")]
+    Intersect(TilesetOpArgs),
+
+    /// Tiles in the first tile-set not in any of the others
+    #[command(visible_alias = "difference")]
+    Diff(TilesetOpArgs),
+}
+
+impl TilesetCommands {
+    pub fn run(&self) -> UtilesResult<()> {
+        match self {
+            Self::Union(args) => tileset_main(TilesetOp::Union, args),
+            Self::Intersect(args) => tileset_main(TilesetOp::Intersect, args),
+            Self::Diff(args) => tileset_main(TilesetOp::Diff, args),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TilesetOp {
+    Union,
+    Intersect,
+    Diff,
+}
+
+#[derive(Debug, Copy, Clone, Default, clap::ValueEnum)]
+pub enum TilesetOutput {
+    /// tiles (formatted w/ `--fmt`)
+    #[default]
+    Tiles,
+    /// tile-set json (per-zoom z-order ranges)
+    Json,
+    /// quadtree-aligned `TileZBox` json ranges
+    Ranges,
+    /// mbtiles sql `WHERE` clause
+    Sql,
+    /// number of tiles
+    Count,
+}
+
+#[derive(Debug, Parser)]
+pub struct TilesetOpArgs {
+    /// tile-set inputs (`-` for stdin) ~ tiles, `TileZBox` json lines (eg
+    /// from `burn --ranges`) or tile-set json
+    #[arg(required = true, num_args = 2..)]
+    pub inputs: Vec<String>,
+
+    /// output format
+    #[arg(required = false, long, short, value_enum, default_value_t)]
+    pub output: TilesetOutput,
+
+    #[command(flatten)]
+    pub fmtopts: TileFmtOptions,
+}
+
 // #[derive(Debug, Parser)]
 // pub struct SqliteSchemaArgs {
 //     #[command(flatten)]
@@ -350,6 +622,50 @@ pub struct SqliteHeaderArgs {
     pub common: SqliteDbCommonArgs,
 }
 
+#[derive(Debug, Parser)]
+/// Check sqlite db integrity (header, integrity-check, wal leftovers)
+///
+/// Exits w/ an error if the db has problems.
+///
+/// Examples:
+///   \> utiles sqlite check tiles.mbtiles
+///   \> utiles sqlite check tiles.mbtiles --quick
+#[command(verbatim_doc_comment)]
+pub struct SqliteCheckArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// run `quick_check` instead of `integrity_check` (faster; skips index
+    /// content checks)
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue)]
+    pub quick: bool,
+
+    /// max number of problems reported by the check
+    #[arg(required = false, long, default_value = "100")]
+    pub max_errors: u32,
+}
+
+#[derive(Debug, Parser)]
+/// Salvage readable tiles/metadata of a damaged mbtiles db into a new db
+///
+/// Rows are read by rowid; unreadable rows are skipped and reported.
+///
+/// Examples:
+///   \> utiles sqlite salvage broken.mbtiles salvaged.mbtiles
+#[command(verbatim_doc_comment)]
+pub struct SqliteSalvageArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// destination mbtiles filepath (must not exist)
+    #[arg(required = true)]
+    pub dst: String,
+
+    /// rows read per batch
+    #[arg(required = false, long, default_value = "1000")]
+    pub batch_size: i64,
+}
+
 #[derive(Debug, Parser)]
 /// vacuum sqlite db inplace/into
 pub struct VacuumArgs {
@@ -369,6 +685,29 @@ pub struct VacuumArgs {
     pub page_size: Option<i64>,
 }
 
+#[derive(Debug, Parser)]
+/// convert mbtiles db schema (flat/hash/norm) in place
+pub struct ConvertSchemaArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// db-type to convert to (flat, hash, norm)
+    #[arg(required = true, long, aliases = ["dst-type", "dbtype", "mbtype"])]
+    pub to: DbtypeOption,
+
+    /// tiles per batch (committed w/ a resumable checkpoint)
+    #[arg(required = false, long, default_value = "10000")]
+    pub batch_size: usize,
+
+    /// hash type for the agg-tiles-hash verification (default: md5)
+    #[arg(required = false, long)]
+    pub hash: Option<HashType>,
+
+    /// quiet
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub(crate) quiet: bool,
+}
+
 #[derive(Debug, Parser)]
 pub struct MetadataArgs {
     #[command(flatten)]
@@ -487,17 +826,74 @@ pub struct EnumerateArgs {
     /// tippecanoe-enumerate like output '{relpath} {x} {y} {z}'
     #[arg(required = false, long, short = 't', action = clap::ArgAction::SetTrue)]
     pub(crate) tippecanoe: bool,
+
+    /// Format string for tiles (see `utiles tiles --help`); `{ext}` is
+    /// resolved from the tile data
+    #[arg(required = false, long, short = 'F', conflicts_with = "tippecanoe")]
+    pub(crate) fmt: Option<String>,
+
+    /// Sort tiles (per db) by zoom and then zxy/hilbert/morton order
+    #[arg(required = false, long, value_enum)]
+    pub(crate) sort: Option<TileSortOrder>,
+}
+
+/// Tile sort order (within zoom level)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TileSortOrder {
+    /// Sort by x then y
+    Zxy,
+    /// Sort by hilbert curve id
+    Hilbert,
+    /// Sort by morton (z-order) id
+    Morton,
 }
 
 #[derive(Debug, Parser)]
 /// Optimize tiles-db
+#[expect(clippy::struct_excessive_bools)]
 pub struct OptimizeArgs {
     #[command(flatten)]
     pub common: SqliteDbCommonArgs,
 
-    /// destination dataset fspath (mbtiles, dirpath)
+    /// destination mbtiles fspath
     #[arg(required = true)]
     pub dst: String,
+
+    /// db-type of dst (default: norm if de-duping saves space else flat)
+    #[arg(
+        required = false,
+        long = "dst-type",
+        aliases = ["dbtype", "dsttype", "mbtype", "mbt-type"]
+    )]
+    pub dst_type: Option<DbtypeOption>,
+
+    /// do not optimize png tiles w/ oxipng
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue)]
+    pub no_oxipng: bool,
+
+    /// oxipng optimize level (0-6)
+    #[arg(required = false, long, short, default_value = "2", value_parser = clap::value_parser!(u8).range(0..=6))]
+    pub opt: u8,
+
+    /// oxipng optimize alpha channel (default: false)
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub alpha: bool,
+
+    /// drop fully transparent/empty raster tiles (default: false)
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue)]
+    pub drop_transparent: bool,
+
+    /// do not recompress (gzip/zlib) vector tiles at the best level
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue)]
+    pub no_recompress: bool,
+
+    /// n-jobs ~ 0=ncpus (default: 4)
+    #[arg(required = false, long, short)]
+    pub jobs: Option<u8>,
+
+    /// quiet
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub(crate) quiet: bool,
 }
 
 // #[derive(Debug, Parser)]
@@ -531,7 +927,397 @@ pub struct WebpifyArgs {
     #[arg(required = true)]
     pub dst: String,
 
-    /// n-jobs ~ 0=ncpus (default: max(4, ncpus))
+    /// n-jobs ~ 0=ncpus (default: 4)
+    #[arg(required = false, long, short)]
+    pub jobs: Option<u8>,
+
+    /// quiet
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub(crate) quiet: bool,
+
+    /// lossless webp (default; conflicts w/ the lossy quality options)
+    #[arg(
+        required = false,
+        long,
+        action = clap::ArgAction::SetTrue,
+        conflicts_with_all = ["quality", "alpha_quality"]
+    )]
+    pub lossless: bool,
+
+    /// lossy webp quality 0-100 (lossless if not given)
+    #[arg(required = false, long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub quality: Option<u8>,
+
+    /// compression effort 0-6 (0 = fastest, 6 = smallest)
+    #[arg(required = false, long, alias = "effort", default_value = "4", value_parser = clap::value_parser!(u8).range(0..=6))]
+    pub method: u8,
+
+    /// lossy alpha quality 0-100
+    #[arg(required = false, long, default_value = "100", requires = "quality", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub alpha_quality: u8,
+
+    /// near-lossless preprocessing level 0-100 (100 = off; lossless only)
+    #[arg(required = false, long, default_value = "100", conflicts_with = "quality", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub near_lossless: u8,
+
+    /// keep the original tile if the webp is larger
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue)]
+    pub keep_larger: bool,
+}
+
+impl WebpifyArgs {
+    #[must_use]
+    pub const fn webp_options(&self) -> WebpOptions {
+        WebpOptions {
+            quality: if self.lossless { None } else { self.quality },
+            method: self.method,
+            alpha_quality: self.alpha_quality,
+            near_lossless: self.near_lossless,
+        }
+    }
+}
+
+/// Resampling filter (see `image::imageops::FilterType`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResampleFilter {
+    /// Nearest neighbor
+    Nearest,
+    /// Linear (bilinear)
+    Triangle,
+    /// Cubic (Catmull-Rom)
+    CatmullRom,
+    /// Gaussian
+    Gaussian,
+    /// Lanczos w/ window 3
+    #[default]
+    Lanczos3,
+}
+
+impl From<ResampleFilter> for FilterType {
+    fn from(filter: ResampleFilter) -> Self {
+        match filter {
+            ResampleFilter::Nearest => Self::Nearest,
+            ResampleFilter::Triangle => Self::Triangle,
+            ResampleFilter::CatmullRom => Self::CatmullRom,
+            ResampleFilter::Gaussian => Self::Gaussian,
+            ResampleFilter::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// Raster tile output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RasterFormat {
+    Png,
+    #[value(alias = "jpeg")]
+    Jpg,
+    Webp,
+}
+
+impl From<RasterFormat> for TileFormat {
+    fn from(format: RasterFormat) -> Self {
+        match format {
+            RasterFormat::Png => Self::Png,
+            RasterFormat::Jpg => Self::Jpg,
+            RasterFormat::Webp => Self::Webp,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct ResizeArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// destination mbtiles fspath
+    #[arg(required = true)]
+    pub dst: String,
+
+    /// destination tile size (2x or 1/2 the source tile size)
+    #[arg(required = true, long, short = 's', value_parser = parse_tile_size)]
+    pub tile_size: u32,
+
+    /// resampling filter for tiles that need resampling
+    #[arg(required = false, long, value_enum, default_value_t = ResampleFilter::default())]
+    pub filter: ResampleFilter,
+
+    /// output tile format (default: source format)
+    #[arg(required = false, long, value_enum)]
+    pub format: Option<RasterFormat>,
+
+    /// n-jobs ~ 0=ncpus (default: 4)
+    #[arg(required = false, long, short)]
+    pub jobs: Option<u8>,
+
+    /// quiet
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub(crate) quiet: bool,
+}
+
+/// Blend mode for compositing a layer onto the layers below it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BlendOption {
+    /// Layer color over the backdrop
+    #[default]
+    Normal,
+    /// Backdrop * layer (darkens; e.g. hillshade)
+    Multiply,
+    /// Inverse multiply (lightens)
+    Screen,
+}
+
+impl From<BlendOption> for BlendMode {
+    fn from(blend: BlendOption) -> Self {
+        match blend {
+            BlendOption::Normal => Self::Normal,
+            BlendOption::Multiply => Self::Multiply,
+            BlendOption::Screen => Self::Screen,
+        }
+    }
+}
+
+/// Parse an opacity (0.0-1.0)
+fn parse_opacity(s: &str) -> Result<f32, String> {
+    let opacity = s
+        .trim()
+        .parse::<f32>()
+        .map_err(|e| format!("invalid opacity {s}: {e}"))?;
+    if (0.0..=1.0).contains(&opacity) {
+        Ok(opacity)
+    } else {
+        Err(format!("opacity must be between 0 and 1: {s}"))
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct CompositeArgs {
+    /// destination mbtiles fspath
+    #[arg(required = true)]
+    pub dst: String,
+
+    /// source mbtiles fspaths (bottom to top; first is the base)
+    #[arg(required = true, num_args = 2..)]
+    pub srcs: Vec<String>,
+
+    /// overlay opacity 0-1 (one value for all overlays or one per overlay)
+    #[arg(required = false, long, value_delimiter = ',', default_value = "1", value_parser = parse_opacity)]
+    pub opacity: Vec<f32>,
+
+    /// overlay blend mode (one value for all overlays or one per overlay)
+    #[arg(
+        required = false,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "normal"
+    )]
+    pub blend: Vec<BlendOption>,
+
+    /// output tile format (default: base format)
+    #[arg(required = false, long, value_enum)]
+    pub format: Option<RasterFormat>,
+
+    /// n-jobs ~ 0=ncpus (default: 4)
+    #[arg(required = false, long, short)]
+    pub jobs: Option<u8>,
+
+    /// quiet
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub(crate) quiet: bool,
+
+    /// compact/minified json (default: false)
+    #[arg(required = false, short, long, action = clap::ArgAction::SetTrue)]
+    pub min: bool,
+}
+
+impl CompositeArgs {
+    /// Blend/opacity per source (the base is always normal w/ opacity 1)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `--opacity`/`--blend` are not given once or once
+    /// per overlay.
+    pub fn layers(&self) -> UtilesResult<Vec<CompositeLayer>> {
+        let noverlays = self.srcs.len().saturating_sub(1);
+        for (name, n) in [("opacity", self.opacity.len()), ("blend", self.blend.len())]
+        {
+            if n != 1 && n != noverlays {
+                return Err(UtilesError::AdHoc(format!(
+                    "--{name} must be given once or once per overlay ({noverlays}); got {n}"
+                )));
+            }
+        }
+        let overlays = (0..noverlays).map(|i| CompositeLayer {
+            blend: self.blend[i.min(self.blend.len() - 1)].into(),
+            opacity: self.opacity[i.min(self.opacity.len() - 1)],
+        });
+        Ok(std::iter::once(CompositeLayer::default())
+            .chain(overlays)
+            .collect())
+    }
+}
+
+/// Elevation (dem) tile encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TerrainEncodingOption {
+    /// Mapbox Terrain-RGB
+    #[value(alias = "mapbox")]
+    TerrainRgb,
+    /// Mapzen/AWS Terrarium
+    #[value(alias = "mapzen")]
+    Terrarium,
+}
+
+impl From<TerrainEncodingOption> for TerrainEncoding {
+    fn from(encoding: TerrainEncodingOption) -> Self {
+        match encoding {
+            TerrainEncodingOption::TerrainRgb => Self::TerrainRgb,
+            TerrainEncodingOption::Terrarium => Self::Terrarium,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TerrainCommands {
+    /// Sample the elevation at a lng/lat
+    Sample(TerrainSampleArgs),
+
+    /// Convert elevation tiles between Terrain-RGB and Terrarium
+    Convert(TerrainConvertArgs),
+
+    /// Render hillshade tiles from elevation tiles
+    ///
+    /// Tiles are rendered one at a time w/ the edge pixels repeated (the
+    /// neighboring tiles are not sampled) so there may be seams at the tile
+    /// edges.
+    Hillshade(TerrainHillshadeArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct TerrainSampleArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// lng,lat (e.g. `-122.4,37.8`)
+    #[arg(required = true, allow_hyphen_values = true)]
+    pub lnglat: LngLat,
+
+    /// zoom to sample at (default: maxzoom)
+    #[arg(required = false, long, short)]
+    pub zoom: Option<u8>,
+
+    /// elevation encoding (default: metadata `encoding` or terrain-rgb)
+    #[arg(required = false, long, short, value_enum)]
+    pub encoding: Option<TerrainEncodingOption>,
+}
+
+#[derive(Debug, Parser)]
+pub struct TerrainConvertArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// destination mbtiles fspath
+    #[arg(required = true)]
+    pub dst: String,
+
+    /// destination encoding
+    #[arg(required = true, long, value_enum)]
+    pub to: TerrainEncodingOption,
+
+    /// source encoding (default: metadata `encoding` or terrain-rgb)
+    #[arg(required = false, long, value_enum)]
+    pub from: Option<TerrainEncodingOption>,
+
+    /// output tile format (default: source format; jpg is not allowed)
+    #[arg(required = false, long, value_enum)]
+    pub format: Option<RasterFormat>,
+
+    /// n-jobs ~ 0=ncpus (default: 4)
+    #[arg(required = false, long, short)]
+    pub jobs: Option<u8>,
+
+    /// quiet
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub(crate) quiet: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct TerrainHillshadeArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// destination mbtiles fspath
+    #[arg(required = true)]
+    pub dst: String,
+
+    /// elevation encoding (default: metadata `encoding` or terrain-rgb)
+    #[arg(required = false, long, short, value_enum)]
+    pub encoding: Option<TerrainEncodingOption>,
+
+    /// light direction (degrees clockwise from north)
+    #[arg(required = false, long, default_value = "315")]
+    pub azimuth: f64,
+
+    /// light altitude (degrees above the horizon)
+    #[arg(required = false, long, default_value = "45")]
+    pub altitude: f64,
+
+    /// vertical exaggeration
+    #[arg(required = false, long, short = 'z', default_value = "1")]
+    pub z_factor: f64,
+
+    /// output tile format
+    #[arg(required = false, long, value_enum, default_value = "png")]
+    pub format: RasterFormat,
+
+    /// n-jobs ~ 0=ncpus (default: 4)
+    #[arg(required = false, long, short)]
+    pub jobs: Option<u8>,
+
+    /// quiet
+    #[arg(required = false, long, short, action = clap::ArgAction::SetTrue)]
+    pub(crate) quiet: bool,
+}
+
+impl TerrainHillshadeArgs {
+    #[must_use]
+    pub const fn hillshade_options(&self) -> HillshadeOptions {
+        HillshadeOptions {
+            azimuth: self.azimuth,
+            altitude: self.altitude,
+            z_factor: self.z_factor,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct RasterStatsArgs {
+    #[command(flatten)]
+    pub common: SqliteDbCommonArgs,
+
+    /// histogram bins per band (1-256)
+    #[arg(required = false, long, default_value = "16", value_parser = clap::value_parser!(u16).range(1..=256))]
+    pub bins: u16,
+
+    /// number of dominant colors to report
+    #[arg(required = false, long, default_value = "8")]
+    pub top: usize,
+
+    /// number of single-color tiles to list (by z/x/y; the count is always
+    /// reported)
+    #[arg(required = false, long, default_value = "100")]
+    pub list_blank: usize,
+
+    /// delete single-color tiles of `--fill-color` (in place) and record the
+    /// fill color in the metadata (`fill_color`)
+    #[arg(required = false, long, action = clap::ArgAction::SetTrue, requires = "fill_color")]
+    pub delete_fill: bool,
+
+    /// fill color of the single-color tiles to delete (`#rrggbb[aa]`)
+    #[arg(required = false, long, requires = "delete_fill")]
+    pub fill_color: Option<String>,
+
+    /// n-jobs ~ 0=ncpus (default: 4)
     #[arg(required = false, long, short)]
     pub jobs: Option<u8>,
 
@@ -580,6 +1366,19 @@ pub enum Commands {
     #[command(name = "copy", visible_alias = "cp")]
     Copy(CopyArgs),
 
+    /// Convert mbtiles db schema (flat/hash/norm) in place
+    ///
+    /// Tiles are moved in batches (w/ checkpoints) so the db does not double
+    /// in size; re-run an interrupted conversion to resume it. The converted
+    /// db is verified w/ the agg-tiles-hash before the schema swap is
+    /// committed.
+    ///
+    /// Examples:
+    ///   \> utiles convert-schema tiles.mbtiles --to norm
+    ///   \> utiles convert-schema tiles.mbtiles --to flat --batch-size 50000
+    #[command(name = "convert-schema", verbatim_doc_comment)]
+    ConvertSchema(ConvertSchemaArgs),
+
     /// Lint mbtiles file(s) (wip)
     #[command(name = "lint")]
     Lint(LintArgs),
@@ -650,6 +1449,16 @@ pub enum Commands {
     ///     `{z}`                  -> z/zoom level
     ///     `{-y}`/`{yup}`         -> y tile coord flipped/tms
     ///     `{zxy}`                -> z/x/y
+    ///     `{lng}`/`{lat}`        -> tile center lng/lat
+    ///     `{ul_web}`             -> upper-left corner x y web-mercator (epsg:3857)
+    ///     `{wkt}`                -> tile polygon WKT (wgs84)
+    ///     `{ext}`                -> tile data extension (png, pbf.gz...; enumerate)
+    ///
+    /// fmt-expressions:
+    ///     `{x:06}`/`{y:8}`       -> zero-padded/space-padded width
+    ///     `{z+1}`/`{x-1}`        -> integer offsets (x, y, z, -y)
+    ///     `{parent(10).zxy}`     -> token of the parent tile at zoom 10
+    ///     `{parent(z-2).x}`      -> token of the parent tile 2 levels up
     ///
     ///
     /// Example:
@@ -686,7 +1495,7 @@ pub enum Commands {
         verbatim_doc_comment,
         about = "Echo bounding tile at zoom for bbox / geojson"
     )]
-    BoundingTile(TileFmtArgs),
+    BoundingTile(BoundingTileArgs),
 
     /// Converts tiles to/from quadkey/[x, y, z]
     ///
@@ -724,6 +1533,73 @@ pub enum Commands {
     #[command(name = "pmtileid", verbatim_doc_comment, visible_alias = "pmid")]
     Pmtileid(TileFmtArgs),
 
+    /// Converts tile(s) to/from hilbert-curve-id/[x, y, z]
+    ///
+    /// Ids are global (unique across zooms; same as pmtile-ids) by default;
+    /// use `--per-zoom` for ids within the zoom level (`--zoom` for ids -> tiles).
+    ///
+    /// Examples:
+    ///
+    ///   \> echo "[486, 332, 10]" | utiles hilbert
+    ///   506307
+    ///   \> echo "[486, 332, 10]" | utiles hilbert --per-zoom
+    ///   156782
+    ///   \> utiles hilbert --per-zoom --zoom 10 156782
+    ///   [486, 332, 10]
+    #[command(name = "hilbert", verbatim_doc_comment)]
+    Hilbert(TileIdArgs),
+
+    /// Converts tile(s) to/from morton-id (z-order)/[x, y, z]
+    ///
+    /// Ids are global (unique across zooms) by default; use `--per-zoom` for
+    /// ids within the zoom level (`--zoom` for ids -> tiles).
+    ///
+    /// Examples:
+    ///
+    ///   \> echo "[486, 332, 10]" | utiles morton
+    ///   576009
+    ///   \> echo "[486, 332, 10]" | utiles morton --per-zoom
+    ///   226484
+    ///   \> utiles morton 576009
+    ///   [486, 332, 10]
+    #[command(name = "morton", verbatim_doc_comment, visible_alias = "zorder")]
+    Morton(TileIdArgs),
+
+    /// Echo resolution/scale per zoom for a tile size (256, 512, @2x)
+    ///
+    /// Outputs NDJSON objects w/ the resolution (meters/pixel at the
+    /// equator), the scale denominator and the ground resolution
+    /// (meters/pixel) at `--lat`; `--resolution`/`--scale-denominator`
+    /// output the (fractional) zoom instead.
+    ///
+    /// Examples:
+    ///
+    ///   \> utiles resolution -z 0 --tile-size 512
+    ///   {"zoom":0,"tile_size":512,"resolution":78271.51696402048,"scale_denominator":279541132.0143589}
+    ///   \> utiles resolution --resolution 10 --tile-size @2x
+    ///   {"resolution":10.0,"tile_size":512,"zoom":12.934271690382634}
+    #[expect(clippy::doc_markdown)]
+    #[command(name = "resolution", verbatim_doc_comment, visible_alias = "res")]
+    Resolution(ResolutionArgs),
+
+    /// Transform points between lnglat, web-mercator, global pixel and
+    /// tile-local (pixel/extent) coordinates
+    ///
+    /// Input lines are 2 numbers (`--from lnglat|mercator|pixel`; a 3rd
+    /// number is the zoom) or 5 numbers `x y z u v` (tile + tile-local
+    /// coords; `--from tile-pixel|extent`); outputs NDJSON objects w/ all the
+    /// coordinates.
+    ///
+    /// Examples:
+    ///
+    ///   \> utiles pixel -z 1 "[-90, 0]"
+    ///   {"zoom":1,"tile_size":256,"lnglat":[-90.0,0.0],"mercator":[-10018754.171394622,0.0],"pixel":[128.0,256.0],"tile":[0,1,1],"tile_pixel":[128.0,0.0],"extent":[2048.0,0.0]}
+    ///   \> echo "0 1 1 2048 0" | utiles pixel --from extent
+    ///   {"zoom":1,"tile_size":256,"lnglat":[-90.0,0.0],...}
+    #[expect(clippy::doc_markdown)]
+    #[command(name = "pixel", verbatim_doc_comment)]
+    Pixel(PixelArgs),
+
     /// Echos web-mercator tiles at zoom level intersecting given geojson-bbox [west, south,
     /// east, north], geojson-features, or geojson-collections read from stdin.
     ///
@@ -772,7 +1648,7 @@ pub enum Commands {
     /// <https://tools.ietf.org/html/rfc8142> and
     /// <https://tools.ietf.org/html/rfc7159>).
     #[command(name = "neighbors")]
-    Neighbors(TileFmtArgs),
+    Neighbors(TmsTileFmtArgs),
 
     /// Echo children tiles of input tiles
     ///
@@ -818,6 +1694,10 @@ pub enum Commands {
     #[command(name = "edges")]
     Edges(EdgesArgs),
 
+    /// Tile-set union/intersect/diff
+    #[command(subcommand, name = "tileset", visible_alias = "ts")]
+    Tileset(TilesetCommands),
+
     /// Convert raster mbtiles to webp format
     #[command(
         name = "webpify",
@@ -826,15 +1706,75 @@ pub enum Commands {
     )]
     Webpify(WebpifyArgs),
 
-    /// Convert raster mbtiles to webp format
+    /// Optimize tiles-db for size
+    ///
+    /// Steps (each reported w/ bytes saved):
+    ///   - dedupe: write a norm db if de-duping tiles saves space (else flat)
+    ///   - oxipng: optimize png tiles w/ oxipng
+    ///   - transparent: drop fully transparent/empty raster tiles (opt-in)
+    ///   - recompress: recompress gzip/zlib vector tiles at the best level
+    ///   - vacuum: `VACUUM INTO` the destination
+    ///
+    /// Examples:
+    ///   \> utiles optimize tiles.mbtiles tiles.optimized.mbtiles
+    ///   \> utiles optimize tiles.mbtiles out.mbtiles --drop-transparent -o 4
+    ///   \> utiles optimize tiles.mbtiles out.mbtiles --dst-type flat --no-oxipng
     #[command(
         name = "optimize",
-        about = "Optimize tiles-db",
         aliases = ["opt"],
-        hide = true
+        verbatim_doc_comment
     )]
     Optimize(OptimizeArgs),
 
+    /// Resize raster tiles (e.g. 256 <-> 512)
+    ///
+    /// Mosaics the 4 children of each tile into a larger tile (256 -> 512;
+    /// maxzoom - 1) or splits each tile into its 4 smaller children at
+    /// zoom + 1 (512 -> 256; minzoom tiles are also downsampled so the
+    /// minzoom is kept).
+    ///
+    /// Examples:
+    ///   \> utiles resize tiles-256.mbtiles tiles-512.mbtiles --tile-size 512
+    ///   \> utiles resize tiles-512.mbtiles tiles-256.mbtiles -s 256 --filter catmull-rom
+    ///   \> utiles resize tiles.mbtiles tiles-512.webp.mbtiles -s 512 --format webp
+    #[command(name = "resize", verbatim_doc_comment)]
+    Resize(ResizeArgs),
+
+    /// Composite (alpha blend) raster tiles of multiple mbtiles into one
+    ///
+    /// Sources are blended in stack order (first is the base) w/ per-overlay
+    /// opacity and blend mode (normal/multiply/screen); tiles present in only
+    /// some sources are filled in from those sources.
+    ///
+    /// Examples:
+    ///   \> utiles composite out.mbtiles landcover.mbtiles hillshade.mbtiles --opacity 0.5 --blend multiply
+    ///   \> utiles composite out.mbtiles base.mbtiles a.mbtiles b.mbtiles --opacity 0.5,1 --format webp
+    #[command(name = "composite", verbatim_doc_comment)]
+    Composite(CompositeArgs),
+
+    /// Terrain-RGB/Terrarium elevation tiles (sample/convert/hillshade)
+    ///
+    /// Examples:
+    ///   \> utiles terrain sample dem.mbtiles -122.4,37.8
+    ///   \> utiles terrain convert dem.mbtiles dem.terrarium.mbtiles --to terrarium
+    ///   \> utiles terrain hillshade dem.mbtiles hillshade.mbtiles --z-factor 2
+    #[command(subcommand, name = "terrain", verbatim_doc_comment)]
+    Terrain(TerrainCommands),
+
+    /// Raster tile statistics (histograms, blank tiles, dominant colors)
+    ///
+    /// Reports per-zoom band min/max/mean/histograms, the proportion of
+    /// fully transparent and single-color tiles, dominant colors and the
+    /// single-color (blank) tiles. `--delete-fill` deletes the single-color
+    /// tiles of the fill color (e.g. ocean) and records it in the metadata.
+    ///
+    /// Examples:
+    ///   \> utiles raster-stats imagery.mbtiles
+    ///   \> utiles raster-stats imagery.mbtiles --bins 256 --top 16
+    ///   \> utiles raster-stats imagery.mbtiles --delete-fill --fill-color '#aad3df'
+    #[command(name = "raster-stats", verbatim_doc_comment)]
+    RasterStats(RasterStatsArgs),
+
     /// utiles server (wip)
     #[command(name = "serve", hide = true)]
     Serve(ServeArgs),
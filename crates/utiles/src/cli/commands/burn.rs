@@ -1,11 +1,14 @@
+use std::io;
+use std::io::{BufWriter, Write};
+
 use geojson::GeoJson;
-use utiles_core::TileLike;
+use utiles_core::TileStringFormatter;
 
+use crate::UtilesError;
 use crate::cli::args::BurnArgs;
 use crate::cli::stdinterator_filter;
-use crate::cover::geojson2tiles;
+use crate::cover::{GeojsonCoverOptions, geojson2ranges};
 use crate::errors::UtilesResult;
-use crate::{UtilesError, asleep0};
 
 pub(crate) async fn burn_main(args: BurnArgs) -> UtilesResult<()> {
     let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
@@ -18,13 +21,39 @@ pub(crate) async fn burn_main(args: BurnArgs) -> UtilesResult<()> {
         .parse::<GeoJson>()
         .map_err(|e| UtilesError::GeojsonError(e.to_string()));
     let geojson = geojson_parse_res?;
-    let tiles = geojson2tiles(&geojson, args.zoom)?;
-    for (i, tile) in tiles.iter().enumerate() {
-        let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
-        safe_println!("{}{}", rs, tile.json_arr());
-        if i % 2048 == 0 {
-            asleep0!();
+    let opts = GeojsonCoverOptions {
+        zoom: args.zoom,
+        minzoom: None,
+        mode: args.mode,
+        buffer: args.buffer,
+    };
+    // tiles are written as the coverage is rasterized (row by row)
+    let ranges = geojson2ranges(&geojson, opts)?;
+    let formatter = TileStringFormatter::from(&args.fmtopts);
+    let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
+    let mut stdout = io::stdout();
+    let lock = stdout.lock();
+    let mut buf = BufWriter::with_capacity(32 * 1024, lock);
+    if args.ranges {
+        for (i, zbox) in ranges.enumerate() {
+            let zbox_str = serde_json::to_string(&zbox)?;
+            buf.write_all(format!("{rs}{zbox_str}\n").as_bytes())?;
+            if i % 1024 == 0 {
+                buf.flush()?;
+                tokio::time::sleep(tokio::time::Duration::from_secs(0)).await;
+            }
+        }
+    } else {
+        for (i, tile) in ranges.tiles().enumerate() {
+            let tile_str = formatter.fmt_tile(&tile);
+            buf.write_all(format!("{rs}{tile_str}\n").as_bytes())?;
+            if i % 2048 == 0 {
+                buf.flush()?;
+                tokio::time::sleep(tokio::time::Duration::from_secs(0)).await;
+            }
         }
     }
+    buf.flush()?;
+    stdout.flush()?;
     Ok(())
 }
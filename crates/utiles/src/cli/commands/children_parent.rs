@@ -1,19 +1,24 @@
-use utiles_core::{Tile, TileLike};
+use utiles_core::constants::MAX_ZOOM;
+use utiles_core::tms::TileMatrixSet;
+use utiles_core::{Tile, TileLike, UtilesCoreError};
 
 use crate::cli::args::ParentChildrenArgs;
-use crate::cli::stdinterator_filter;
+use crate::cli::tile_input::stdin_tms_tiles;
 use crate::errors::UtilesResult;
 
 pub(crate) fn parent_main(args: ParentChildrenArgs) -> UtilesResult<()> {
-    let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
-    for line in lines {
-        let lstr = line?.trim_matches(|c| c == '"' || c == '\'').to_string();
-        let tile = Tile::from_json(&lstr)?;
+    let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
+    for tile in stdin_tms_tiles(args.inargs, args.tms.clone()) {
+        let tile = tile?;
+        if let Some(tms) = &args.tms {
+            let parent = tms.parent(&tile, args.depth)?;
+            safe_println!("{}{}", rs, parent.json_arr());
+            continue;
+        }
         let nup = i32::from(tile.z) - i32::from(args.depth);
         // error
         assert!(nup >= 0, "depth must be less than or equal to tile zoom");
         if let Some(parent) = tile.parent(Option::from(args.depth - 1)) {
-            let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
             safe_println!("{}{}", rs, parent.json_arr());
         }
     }
@@ -21,12 +26,24 @@ pub(crate) fn parent_main(args: ParentChildrenArgs) -> UtilesResult<()> {
 }
 
 pub(crate) fn children_main(args: ParentChildrenArgs) -> UtilesResult<()> {
-    let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
     let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
-    for line in lines {
-        let lstr = line?.trim_matches(|c| c == '"' || c == '\'').to_string();
-        let tile = Tile::from_json(&lstr)?;
-        let tile_zbox = tile.children_zbox(Option::from(args.depth));
+    for tile in stdin_tms_tiles(args.inargs, args.tms.clone()) {
+        let tile = tile?;
+        let maxzoom = args.tms.as_ref().map_or(MAX_ZOOM, TileMatrixSet::maxzoom);
+        let zoom = tile
+            .z
+            .checked_add(args.depth)
+            .filter(|zoom| *zoom <= maxzoom)
+            .ok_or_else(|| {
+                UtilesCoreError::InvalidZoom(format!(
+                    "tile zoom {} + depth {} > max zoom {maxzoom}",
+                    tile.z, args.depth
+                ))
+            })?;
+        let tile_zbox = match &args.tms {
+            Some(tms) => tms.children(&tile, Some(zoom))?,
+            None => tile.children_zbox(Option::from(args.depth)),
+        };
 
         let children = tile_zbox.into_iter().map(Tile::from);
         for child in children {
@@ -0,0 +1,237 @@
+use serde::Serialize;
+use tracing::{debug, info};
+use utiles_core::tile_type::{TileFormat, tiletype};
+use utiles_core::{BBox, Tile, geobbox_merge, utile};
+
+use crate::cli::args::CompositeArgs;
+use crate::cli::commands::tile_jobs::{TileJob, TileJobItem, TileJobs, resolve_jobs};
+use crate::errors::UtilesResult;
+use crate::img::{
+    CompositeLayer, composite_images, encode_tile, image_is_empty, load_from_memory,
+};
+use crate::mbt::{Mbtiles, MbtilesAsync, MbtilesClientAsync};
+use crate::sqlite::streams::sqlite_query_tokio_receiver_stream;
+use crate::sqlite::{AsyncSqliteConn, attach_db};
+
+#[derive(Debug, Default, Serialize)]
+struct CompositeSummary {
+    dst: String,
+    srcs: Vec<String>,
+    format: String,
+    /// tiles written
+    ntiles: u64,
+    /// tiles blended from 2+ sources
+    ncomposited: u64,
+    /// tiles filled in from a single source
+    nfilled: u64,
+    /// fully transparent tiles dropped
+    nempty: u64,
+    /// tiles that failed to composite (missing from the dst)
+    nfailed: u64,
+    total_time_ms: u128,
+}
+
+/// Composite result of a single tile
+#[derive(Debug, Clone, Copy)]
+enum Composited {
+    Blended,
+    Filled,
+    Empty,
+}
+
+struct CompositeRow {
+    tile: Tile,
+    layers: Vec<Option<Vec<u8>>>,
+}
+
+impl TileJobItem for CompositeRow {
+    fn tile(&self) -> Tile {
+        self.tile
+    }
+
+    /// Number of source tiles (the progress total is the sum of the source
+    /// tile counts)
+    fn weight(&self) -> u64 {
+        self.layers.iter().flatten().count() as u64
+    }
+}
+
+impl CompositeSummary {
+    const fn add(&mut self, composited: Composited) {
+        match composited {
+            Composited::Blended => self.ncomposited += 1,
+            Composited::Filled => self.nfilled += 1,
+            Composited::Empty => self.nempty += 1,
+        }
+    }
+}
+
+/// Schema name of the nth source (the base is `main`; others are attached)
+fn schema(i: usize) -> String {
+    if i == 0 {
+        "main".to_string()
+    } else {
+        format!("src{i}")
+    }
+}
+
+/// Union of the tile keys of all sources w/ the tile data of each source
+fn composite_query(nsrcs: usize) -> String {
+    let keys = (0..nsrcs)
+        .map(|i| {
+            format!(
+                "SELECT zoom_level, tile_column, tile_row FROM {}.tiles",
+                schema(i)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\nUNION\n");
+    let columns = (0..nsrcs)
+        .map(|i| format!("t{i}.tile_data AS data_{i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let joins = (0..nsrcs)
+        .map(|i| {
+            format!(
+                "LEFT JOIN {}.tiles t{i} ON t{i}.zoom_level = k.zoom_level \
+                 AND t{i}.tile_column = k.tile_column AND t{i}.tile_row = k.tile_row",
+                schema(i)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "WITH k AS ({keys})\nSELECT k.zoom_level, k.tile_column, k.tile_row, {columns}\nFROM k\n{joins}"
+    )
+}
+
+/// Composite the source tiles of a row (bottom to top)
+///
+/// A tile in a single (fully opaque) source already in the output format is
+/// copied as is.
+fn composite_row(
+    row: &CompositeRow,
+    layers: &[CompositeLayer],
+    format: TileFormat,
+) -> UtilesResult<(Option<Vec<u8>>, Composited)> {
+    let present: Vec<(&Vec<u8>, CompositeLayer)> = row
+        .layers
+        .iter()
+        .zip(layers)
+        .filter_map(|(data, layer)| data.as_ref().map(|d| (d, *layer)))
+        .collect();
+    if let [(data, layer)] = present.as_slice()
+        && layer.opacity >= 1.0
+        && tiletype(data).format == format
+    {
+        return Ok((Some((*data).clone()), Composited::Filled));
+    }
+    let kind = if present.len() > 1 {
+        Composited::Blended
+    } else {
+        Composited::Filled
+    };
+    let images = present
+        .into_iter()
+        .map(|(data, layer)| Ok((load_from_memory(data)?, layer)))
+        .collect::<UtilesResult<Vec<_>>>()?;
+    match composite_images(images) {
+        Some(img) if !image_is_empty(&img) => {
+            Ok((Some(encode_tile(&img, format)?), kind))
+        }
+        _ => Ok((None, Composited::Empty)),
+    }
+}
+
+pub(crate) async fn composite_main(args: CompositeArgs) -> UtilesResult<()> {
+    let start_time = std::time::Instant::now();
+    let layers = args.layers()?;
+    let nsrcs = args.srcs.len();
+    let base = MbtilesClientAsync::open_existing(args.srcs[0].as_str()).await?;
+    base.assert_mbtiles().await?;
+    let mut bounds: Vec<BBox> = base.bbox().await.ok().into_iter().collect();
+    let mut total_count = base.tiles_count().await? as u64;
+    for (i, src) in args.srcs.iter().enumerate().skip(1) {
+        let mbt = MbtilesClientAsync::open_existing(src.as_str()).await?;
+        mbt.assert_mbtiles().await?;
+        bounds.extend(mbt.bbox().await.ok());
+        total_count += mbt.tiles_count().await? as u64;
+        let (src, name) = (src.clone(), schema(i));
+        base.conn(move |c| attach_db(c, &src, &name)).await?;
+    }
+    let base_format = base
+        .query_metadata_format()
+        .await?
+        .as_deref()
+        .and_then(TileFormat::try_parse)
+        .unwrap_or(TileFormat::Png);
+    let format: TileFormat = args.format.map_or(base_format, Into::into);
+    let query = composite_query(nsrcs);
+    debug!("composite query: {query}");
+    info!("composite ~ {nsrcs} sources ~ {total_count} source tiles ~ {layers:?}");
+
+    let dst_mbtiles = Mbtiles::open_new(&args.dst, None)?;
+    dst_mbtiles.metadata_set_many(&base.metadata_rows().await?)?;
+    dst_mbtiles.metadata_set("format", &format.to_string())?;
+    if !bounds.is_empty() {
+        let bbox = geobbox_merge(&bounds);
+        dst_mbtiles.metadata_set(
+            "bounds",
+            &format!("{},{},{},{}", bbox.west, bbox.south, bbox.east, bbox.north),
+        )?;
+    }
+
+    let rows = sqlite_query_tokio_receiver_stream(&base, &query, move |row| {
+        let z: u8 = row.get(0)?;
+        let x: u32 = row.get(1)?;
+        let yup: u32 = row.get(2)?;
+        let layers = (0..nsrcs)
+            .map(|i| row.get(3 + i))
+            .collect::<rusqlite::Result<Vec<Option<Vec<u8>>>>>()?;
+        Ok(CompositeRow {
+            tile: utile!(x, (1 << z) - 1 - yup, z),
+            layers,
+        })
+    })?;
+    let tile_jobs = TileJobs {
+        name: "composite",
+        total: total_count,
+        jobs: resolve_jobs(args.jobs),
+        quiet: args.quiet,
+    };
+    let mut output = tile_jobs
+        .run(
+            rows,
+            Some(dst_mbtiles),
+            move |row: CompositeRow| {
+                let (data, composited) = composite_row(&row, &layers, format)?;
+                let tiles = data
+                    .map(|data| (row.tile, data, None).into())
+                    .into_iter()
+                    .collect();
+                Ok(TileJob::new(tiles, composited))
+            },
+            CompositeSummary::default(),
+            |summary, composited, _pb| summary.add(composited),
+        )
+        .await?;
+    // close the dst before reopening it
+    drop(output.dst.take());
+    let mut summary = std::mem::take(&mut output.acc);
+    summary.ntiles = summary.ncomposited + summary.nfilled;
+    summary.nfailed = output.nfailed;
+
+    let dst = MbtilesClientAsync::open_existing(args.dst.as_str()).await?;
+    dst.update_minzoom_maxzoom().await?;
+    summary.dst = args.dst;
+    summary.srcs = args.srcs;
+    summary.format = format.to_string();
+    summary.total_time_ms = start_time.elapsed().as_millis();
+    let out_str = if args.min {
+        serde_json::to_string(&summary)
+    } else {
+        serde_json::to_string_pretty(&summary)
+    }?;
+    println!("{out_str}");
+    output.check_failed("composite")
+}
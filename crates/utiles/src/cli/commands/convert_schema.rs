@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use indicatif::ProgressStyle;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::cli::args::ConvertSchemaArgs;
+use crate::errors::UtilesResult;
+use crate::fs_async::filesize_async;
+use crate::hash_types::HashType;
+use crate::mbt::{ConvertSchemaResult, MbtType, Mbtiles, mbt_convert_schema};
+use crate::sqlite::Sqlike3;
+
+#[derive(Debug, Serialize)]
+struct ConvertSchemaInfo {
+    fspath: String,
+    #[serde(flatten)]
+    result: ConvertSchemaResult,
+    initial_size: u64,
+    final_size: u64,
+    total_time_ms: u128,
+}
+
+pub(crate) async fn convert_schema_main(args: &ConvertSchemaArgs) -> UtilesResult<()> {
+    let start_time = std::time::Instant::now();
+    let fspath = args.common.filepath.clone();
+    let initial_size = filesize_async(&fspath).await.unwrap_or(0);
+    let dst: MbtType = (&args.to).into();
+    let hash_type = args.hash.unwrap_or(HashType::Md5);
+    let batch_size = args.batch_size;
+    let quiet = args.quiet;
+    info!("convert-schema: {fspath} -> {dst}");
+    let path = fspath.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut mbt = Mbtiles::open_existing(&path)?;
+        let pb = indicatif::ProgressBar::new(0);
+        if quiet {
+            pb.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+        match ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
+        ) {
+            Err(e) => {
+                warn!("pb_style error: {:?}", e);
+            }
+            Ok(s) => {
+                pb.set_style(s);
+            }
+        }
+        pb.set_message(format!("convert-schema ~ {dst}"));
+        pb.enable_steady_tick(Duration::from_millis(100));
+        let res = mbt_convert_schema(
+            mbt.conn_mut(),
+            dst,
+            batch_size,
+            hash_type,
+            |nconverted, ntiles| {
+                pb.set_length(ntiles as u64);
+                pb.set_position(nconverted as u64);
+            },
+        );
+        pb.finish_and_clear();
+        res
+    })
+    .await??;
+    let info = ConvertSchemaInfo {
+        fspath: fspath.clone(),
+        result,
+        initial_size,
+        final_size: filesize_async(&fspath).await.unwrap_or(0),
+        total_time_ms: start_time.elapsed().as_millis(),
+    };
+    let out_str = if args.common.min {
+        serde_json::to_string(&info)
+    } else {
+        serde_json::to_string_pretty(&info)
+    }?;
+    println!("{out_str}");
+    Ok(())
+}
@@ -1,35 +1,66 @@
 use std::io;
 use std::io::{BufWriter, Write};
+use std::pin::Pin;
 
 use tokio::task::JoinHandle;
-use tokio_stream::StreamExt;
 use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
 use tracing::debug;
+use utiles_core::Tile;
+use utiles_core::tile_type::tiletype_str;
 
-use crate::cli::args::EnumerateArgs;
-use crate::mbt::TilesFilter;
+use crate::cli::args::{EnumerateArgs, TileSortOrder};
+use crate::mbt::{MbtilesAsync, TilesFilter};
 use crate::{TileStringFormatter, UtilesResult};
 
 async fn enumerate_db(
     fspath: &str,
     tformatter: TileStringFormatter,
     tfilter: Option<&TilesFilter>,
+    sort: Option<TileSortOrder>,
     tx: tokio::sync::mpsc::Sender<String>,
 ) -> UtilesResult<()> {
     let mbt = crate::mbt::MbtilesClientAsync::open_existing(fspath).await?;
+    // `{ext}` is resolved from the tile data
+    let uses_ext = tformatter.uses_ext();
+    let columns = if uses_ext {
+        "zoom_level, tile_column, tile_row, tile_data"
+    } else {
+        "zoom_level, tile_column, tile_row"
+    };
+    // sorted in sqlite; `tile_row` is flipped (tms) so xyz rows are descending,
+    // hilbert global ids are pmtile-ids and morton order is quadkey order
+    let order_by = match sort {
+        None => "",
+        Some(TileSortOrder::Zxy) => " ORDER BY zoom_level, tile_column, tile_row DESC",
+        Some(TileSortOrder::Hilbert) => {
+            " ORDER BY ut_pmtileid(zoom_level, tile_column, ut_flipy(zoom_level, tile_row))"
+        }
+        Some(TileSortOrder::Morton) => {
+            " ORDER BY zoom_level, ut_quadkey(zoom_level, tile_column, ut_flipy(zoom_level, tile_row))"
+        }
+    };
+    if matches!(sort, Some(TileSortOrder::Hilbert | TileSortOrder::Morton)) {
+        mbt.register_utiles_sqlite_functions().await?;
+    }
     let query: String = match tfilter {
         Some(tfilter) => {
             let where_clause = tfilter.where_clause(Some("tiles."))?;
-            format!(
-                "SELECT zoom_level, tile_column, tile_row FROM tiles {where_clause}"
-            )
+            format!("SELECT {columns} FROM tiles {where_clause}{order_by}")
         }
-        None => "SELECT zoom_level, tile_column, tile_row FROM tiles".to_string(),
+        None => format!("SELECT {columns} FROM tiles{order_by}"),
+    };
+    let mut tiles: Pin<Box<dyn Stream<Item = (Tile, String)> + Send>> = if uses_ext {
+        let rx = mbt.tiles_rx(Some(&query))?;
+        Box::pin(
+            ReceiverStream::new(rx).map(|(tile, data)| (tile, tiletype_str(&data))),
+        )
+    } else {
+        let rx = mbt.enumerate_rx(Some(&query))?;
+        Box::pin(ReceiverStream::new(rx).map(|tile| (tile, String::new())))
     };
-    let s = mbt.enumerate_rx(Some(&query))?;
-    let mut tiles = ReceiverStream::new(s);
-    while let Some(tile) = tiles.next().await {
-        let tile_str = tformatter.fmt_tile(&tile);
+    while let Some((tile, ext)) = tiles.next().await {
+        let tile_str = tformatter.fmt_tile_ext(&tile, &ext);
         if let Err(e) = tx.send(tile_str).await {
             debug!("recv dropped: {:?}", e);
             break;
@@ -81,6 +112,8 @@ pub(crate) async fn enumerate_main(args: &EnumerateArgs) -> UtilesResult<()> {
     let tfilter = args.filter_args.tiles_filter_maybe();
     let fspaths = args.fspaths.clone();
     let tippecanoe = args.tippecanoe;
+    let sort = args.sort;
+    let fmt = args.fmt.clone();
     let enum_task: JoinHandle<UtilesResult<()>> = tokio::task::spawn(async move {
         let tf = tfilter.clone();
         let nfiles = fspaths.len();
@@ -92,13 +125,14 @@ pub(crate) async fn enumerate_main(args: &EnumerateArgs) -> UtilesResult<()> {
                 let fmt_str = format!("{fspath} {xyz_fmt_str}");
                 TileStringFormatter::new(&fmt_str)
             } else if nfiles == 1 {
-                TileStringFormatter::default()
+                fmt.as_deref()
+                    .map_or_else(TileStringFormatter::default, TileStringFormatter::new)
             } else {
-                let xyz_fmt_str = "{json_arr}";
+                let xyz_fmt_str = fmt.as_deref().unwrap_or("{json_arr}");
                 let fmt_str = format!("{fspath} {xyz_fmt_str}");
                 TileStringFormatter::new(&fmt_str)
             };
-            enumerate_db(&fspath, formatter, tf.as_ref(), tx.clone()).await?;
+            enumerate_db(&fspath, formatter, tf.as_ref(), sort, tx.clone()).await?;
         }
         Ok(())
     });
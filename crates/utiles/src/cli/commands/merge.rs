@@ -3,17 +3,11 @@ use std::collections::HashSet;
 use utiles_core::{Tile, TileLike, simplify};
 
 use crate::cli::args::MergeArgs;
-use crate::cli::stdinterator_filter;
+use crate::cli::tile_input::stdin_tiles;
 use crate::errors::UtilesResult;
 
 pub(crate) async fn merge_main(args: MergeArgs) -> UtilesResult<()> {
-    let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
-    let mut tiles: HashSet<Tile> = HashSet::new();
-    for line_res in lines {
-        let line = line_res?;
-        let tile = Tile::from_json(&line)?;
-        tiles.insert(tile);
-    }
+    let tiles = stdin_tiles(args.inargs).collect::<UtilesResult<HashSet<Tile>>>()?;
     let tile_formatter = args.fmtopts.formatter();
     let merged_tiles = simplify(&tiles, Some(args.minzoom));
     if args.sort {
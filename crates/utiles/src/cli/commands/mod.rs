@@ -5,7 +5,9 @@ pub(crate) use addo::addo_main;
 pub(crate) use agg_hash::agg_hash_main;
 pub(crate) use burn::burn_main;
 pub(crate) use children_parent::{children_main, parent_main};
+pub(crate) use composite::composite_main;
 pub(crate) use contains::contains_main;
+pub(crate) use convert_schema::convert_schema_main;
 pub(crate) use copy::copy_main;
 pub(crate) use dev::dev_main;
 pub(crate) use edges::edges_main;
@@ -15,15 +17,22 @@ pub(crate) use lint::lint_main;
 pub(crate) use merge::merge_main;
 pub(crate) use metadata::{metadata_main, metadata_set_main};
 pub(crate) use optimize::optimize_main;
+pub(crate) use pixel::pixel_main;
+pub(crate) use raster_stats::raster_stats_main;
+pub(crate) use resize::resize_main;
+pub(crate) use resolution::resolution_main;
 pub(crate) use rimraf::rimraf_main;
 pub(crate) use serve::serve_main;
 pub(crate) use shapes::shapes_main;
-pub(crate) use sqlite::{analyze_main, header_main};
+pub(crate) use sqlite::{analyze_main, check_main, header_main, salvage_main};
+pub(crate) use terrain::terrain_main;
 pub(crate) use tile_stream_cmds::{
     bounding_tile_main, fmtstr_main, neighbors_main, pmtileid_main, quadkey_main,
+    tileid_main,
 };
 pub(crate) use tilejson::tilejson_main;
 pub(crate) use tiles::tiles_main;
+pub(crate) use tileset::tileset_main;
 pub(crate) use touch::touch_main;
 pub(crate) use translate::translate_main;
 pub(crate) use update::update_main;
@@ -34,7 +43,9 @@ pub(crate) use zxyify::zxyify_main;
 mod about;
 mod agg_hash;
 mod children_parent;
+mod composite;
 mod contains;
+mod convert_schema;
 pub(crate) mod copy;
 pub(crate) mod dev;
 mod info;
@@ -50,12 +61,19 @@ mod burn;
 mod edges;
 mod enumerate;
 mod merge;
+mod pixel;
+mod raster_stats;
+mod resize;
+mod resolution;
 pub(crate) mod rimraf;
 pub(crate) mod serve;
 pub(crate) mod shapes;
+mod terrain;
+pub(crate) mod tile_jobs;
 mod tile_stream_cmds;
 mod tilejson;
 pub(crate) mod tiles;
+mod tileset;
 mod touch;
 mod translate;
 mod update;
@@ -1,18 +1,340 @@
 //! Optimize command (optimizes for size)
 //!
-//! Will optimize db containing tiles (eg mbtiles) but not implemented yet!
+//! Copies a tiles-db to a new mbtiles db while:
+//!   - de-duping tiles (norm db) if that saves space
+//!   - optimizing png tiles w/ oxipng
+//!   - (optionally) dropping fully transparent/empty raster tiles
+//!   - recompressing gzip/zlib vector tiles at the best compression level
+//!   - vacuuming the result into the destination
 //!
-//! Plan on using oxipng for pngs and checking if de-duping tiles is worth it.
-use tracing::{info, warn};
+//! The bytes saved by each step are reported as json.
+use std::collections::HashSet;
+use std::path::Path;
+
+use futures::StreamExt;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+use utiles_core::Tile;
+use utiles_core::tile_type::{TileFormat, tiletype};
 
 use crate::UtilesResult;
 use crate::cli::args::OptimizeArgs;
-use crate::mbt::{MbtilesAsync, MbtilesClientAsync};
+use crate::cli::commands::tile_jobs::{TileJob, TileJobFailed, TileJobs, resolve_jobs};
+use crate::codec::recompress_tile_best;
+use crate::errors::UtilesError;
+use crate::fs_async::filesize_async;
+use crate::hash::{xxh64_be, xxh64_be_hex_upper};
+use crate::img::{image_is_empty, load_from_memory, oxipngify};
+use crate::mbt::{MbtType, Mbtiles, MbtilesAsync, MbtilesClientAsync};
+use crate::sqlite::{Sqlike3Async, SqliteDbAsyncClient};
+
+/// Approximate per-tile overhead (bytes) of a norm db `map` row + index
+const NORM_TILE_OVERHEAD: u64 = 24;
+/// Approximate per-blob overhead (bytes) of a norm db `images` row + index
+const NORM_BLOB_OVERHEAD: u64 = 48;
+
+/// Duplicate-blob stats of a tiles stream
+#[derive(Debug, Default)]
+struct DedupeScan {
+    tiles: u64,
+    unique: u64,
+    duplicate_bytes: u64,
+}
+
+impl DedupeScan {
+    /// Norm if the duplicate bytes outweigh the norm-db overhead else flat
+    const fn dbtype(&self) -> MbtType {
+        let overhead =
+            self.tiles * NORM_TILE_OVERHEAD + self.unique * NORM_BLOB_OVERHEAD;
+        if self.duplicate_bytes > overhead {
+            MbtType::Norm
+        } else {
+            MbtType::Flat
+        }
+    }
+}
+
+/// Per-step size changes of optimizing a single tile
+#[derive(Debug, Default)]
+struct TileSteps {
+    initial_size: usize,
+    /// `None` if the tile was dropped (transparent/empty)
+    final_size: Option<usize>,
+    oxipng_saved: i64,
+    recompress_saved: i64,
+    hash: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct OptimizeStep {
+    tiles: u64,
+    bytes_saved: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct OptimizeSteps {
+    dedupe: OptimizeStep,
+    oxipng: OptimizeStep,
+    transparent: OptimizeStep,
+    recompress: OptimizeStep,
+    vacuum: OptimizeStep,
+}
+
+#[derive(Debug, Serialize)]
+struct OptimizeInfo {
+    src: String,
+    dst: String,
+    src_dbtype: MbtType,
+    dst_dbtype: MbtType,
+    ntiles: u64,
+    ntiles_written: u64,
+    /// tiles that failed to optimize (written as is)
+    nfailed: u64,
+    initial_size: u64,
+    final_size: u64,
+    /// initial - final size (same sign as the per-step `bytes_saved`)
+    bytes_saved: i64,
+    steps: OptimizeSteps,
+    total_time_ms: u128,
+}
+
+#[expect(clippy::cast_possible_wrap)]
+const fn size_diff(initial_size: usize, final_size: usize) -> i64 {
+    initial_size as i64 - final_size as i64
+}
 
+struct TileOptimizer {
+    oxipng: Option<oxipng::Options>,
+    drop_transparent: bool,
+    recompress: bool,
+    hash: bool,
+}
+
+impl TileOptimizer {
+    /// Optimize tile data; returns `None` data if the tile is dropped
+    fn optimize(&self, data: Vec<u8>) -> UtilesResult<(Option<Vec<u8>>, TileSteps)> {
+        let mut steps = TileSteps {
+            initial_size: data.len(),
+            ..TileSteps::default()
+        };
+        if data.is_empty() && self.drop_transparent {
+            return Ok((None, steps));
+        }
+        let format = tiletype(&data).format;
+        let is_raster =
+            matches!(format, TileFormat::Png | TileFormat::Webp | TileFormat::Gif);
+        if self.drop_transparent
+            && is_raster
+            && image_is_empty(&load_from_memory(&data)?)
+        {
+            return Ok((None, steps));
+        }
+        let mut data = data;
+        if let Some(options) = &self.oxipng
+            && format == TileFormat::Png
+        {
+            let png = oxipngify(&data, options)?;
+            if png.len() < data.len() {
+                steps.oxipng_saved = size_diff(data.len(), png.len());
+                data = png;
+            }
+        }
+        if self.recompress
+            && !is_raster
+            && format != TileFormat::Jpg
+            && let Some(recompressed) = recompress_tile_best(&data)?
+        {
+            steps.recompress_saved = size_diff(data.len(), recompressed.len());
+            data = recompressed;
+        }
+        steps.final_size = Some(data.len());
+        steps.hash = self.hash.then(|| xxh64_be_hex_upper(&data));
+        Ok((Some(data), steps))
+    }
+}
+
+async fn dedupe_scan(mbt: &MbtilesClientAsync) -> UtilesResult<DedupeScan> {
+    let mut scan = DedupeScan::default();
+    let mut seen = HashSet::new();
+    let mut tiles_stream = mbt.tiles_stream(None)?;
+    while let Some((_tile, data)) = tiles_stream.next().await {
+        scan.tiles += 1;
+        if seen.insert(xxh64_be(&data)) {
+            scan.unique += 1;
+        } else {
+            scan.duplicate_bytes += data.len() as u64;
+        }
+    }
+    Ok(scan)
+}
+
+/// Progress of the optimize steps folded over the tiles
+#[derive(Debug, Default)]
+struct OptimizeProgress {
+    steps: OptimizeSteps,
+    written: u64,
+    bytes_saved: i64,
+}
+
+impl OptimizeProgress {
+    const fn add(&mut self, tile_steps: &TileSteps) {
+        if tile_steps.oxipng_saved != 0 {
+            self.steps.oxipng.tiles += 1;
+            self.steps.oxipng.bytes_saved += tile_steps.oxipng_saved;
+        }
+        if tile_steps.recompress_saved != 0 {
+            self.steps.recompress.tiles += 1;
+            self.steps.recompress.bytes_saved += tile_steps.recompress_saved;
+        }
+        if let Some(final_size) = tile_steps.final_size {
+            self.written += 1;
+            self.bytes_saved += size_diff(tile_steps.initial_size, final_size);
+        } else {
+            self.steps.transparent.tiles += 1;
+            self.steps.transparent.bytes_saved += size_diff(tile_steps.initial_size, 0);
+            self.bytes_saved += size_diff(tile_steps.initial_size, 0);
+        }
+    }
+}
+
+#[expect(clippy::too_many_lines)]
 pub(crate) async fn optimize_main(args: OptimizeArgs) -> UtilesResult<()> {
-    info!("Optimizing mbtiles file: {}", args.common.filepath);
-    warn!("NOT IMPLEMENTED YET");
-    let mbt = MbtilesClientAsync::open_existing(args.common.filepath.as_str()).await?;
+    let start_time = std::time::Instant::now();
+    let src = args.common.filepath.clone();
+    if Path::new(&args.dst).exists() {
+        return Err(UtilesError::PathExistsError(args.dst));
+    }
+    // a tmp db left by an interrupted run is overwritten
+    let tmp_path = format!("{}.optimize-tmp", args.dst);
+    if Path::new(&tmp_path).exists() {
+        warn!("removing stale optimize tmp db: {tmp_path}");
+        tokio::fs::remove_file(&tmp_path).await?;
+    }
+    info!("Optimizing mbtiles file: {src} -> {}", args.dst);
+    let mbt = MbtilesClientAsync::open_existing(src.as_str()).await?;
     mbt.assert_mbtiles().await?;
+    let src_dbtype = mbt.query_mbt_type().await?;
+    let initial_size = filesize_async(&src).await.unwrap_or(0);
+
+    // dedupe ~ scan for duplicate blobs to pick the dst db-type
+    let scan = dedupe_scan(&mbt).await?;
+    debug!("dedupe scan: {scan:?}");
+    let dst_dbtype = args
+        .dst_type
+        .as_ref()
+        .map_or_else(|| scan.dbtype(), Into::into);
+    info!("optimize ~ src-dbtype: {src_dbtype} ~ dst-dbtype: {dst_dbtype}");
+
+    // the tmp db is removed if any step fails
+    let res: UtilesResult<_> = async {
+        let dst_mbtiles = Mbtiles::open_new(&tmp_path, Some(dst_dbtype))?;
+        dst_mbtiles.metadata_set_many(&mbt.metadata_rows().await?)?;
+        let optimizer = TileOptimizer {
+            oxipng: (!args.no_oxipng).then(|| {
+                let mut options = oxipng::Options::from_preset(args.opt);
+                options.optimize_alpha = args.alpha;
+                options
+            }),
+            drop_transparent: args.drop_transparent,
+            recompress: !args.no_recompress,
+            hash: dst_dbtype != MbtType::Flat,
+        };
+
+        let tile_jobs = TileJobs {
+            name: "optimize",
+            total: scan.tiles,
+            jobs: resolve_jobs(args.jobs),
+            quiet: args.quiet,
+        };
+        let output = tile_jobs
+            .run(
+                mbt.tiles_stream(None)?,
+                Some(dst_mbtiles),
+                move |(tile, tile_data): (Tile, Vec<u8>)| {
+                    // the original tile is written if optimizing fails
+                    match optimizer.optimize(tile_data.clone()) {
+                        Ok((data, steps)) => {
+                            let tiles = data
+                                .map(|data| (tile, data, steps.hash.clone()).into())
+                                .into_iter()
+                                .collect();
+                            Ok(TileJob::new(tiles, steps))
+                        }
+                        Err(error) => Err(TileJobFailed {
+                            error,
+                            fallback: vec![(tile, tile_data, None).into()],
+                        }),
+                    }
+                },
+                OptimizeProgress::default(),
+                |progress, tile_steps, pb| {
+                    progress.add(&tile_steps);
+                    pb.set_message(format!(
+                        "optimize ~ saved: {}",
+                        size::Size::from_bytes(progress.bytes_saved)
+                    ));
+                },
+            )
+            .await?;
+        // release the tmp db (exclusive lock) before vacuuming it
+        drop(output.dst);
+        let OptimizeProgress {
+            mut steps, written, ..
+        } = output.acc;
+        if let Some(writer_stats) = &output.writer_stats
+            && dst_dbtype == MbtType::Norm
+        {
+            steps.dedupe = OptimizeStep {
+                tiles: writer_stats.ndeduped as u64,
+                bytes_saved: writer_stats.nbytes_deduped as i64,
+            };
+        }
+
+        // vacuum ~ VACUUM INTO the destination and remove the tmp db
+        let tmp_size = filesize_async(&tmp_path).await.unwrap_or(0);
+        let tmp_db = SqliteDbAsyncClient::open_existing(&tmp_path, None).await?;
+        info!("vacuuming: {tmp_path} -> {}", args.dst);
+        tmp_db.vacuum_into(args.dst.clone()).await?;
+        drop(tmp_db);
+        Ok((steps, written, output.nfailed, tmp_size))
+    }
+    .await;
+    if res.is_err() {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+    }
+    let (mut steps, written, nfailed, tmp_size) = res?;
+    tokio::fs::remove_file(&tmp_path).await?;
+    let final_size = filesize_async(&args.dst).await.unwrap_or(0);
+    steps.vacuum = OptimizeStep {
+        tiles: 0,
+        bytes_saved: size_diff(tmp_size as usize, final_size as usize),
+    };
+
+    let info = OptimizeInfo {
+        src,
+        dst: args.dst,
+        src_dbtype,
+        dst_dbtype,
+        ntiles: scan.tiles,
+        ntiles_written: written + nfailed,
+        nfailed,
+        initial_size,
+        final_size,
+        bytes_saved: size_diff(initial_size as usize, final_size as usize),
+        steps,
+        total_time_ms: start_time.elapsed().as_millis(),
+    };
+    let out_str = if args.common.min {
+        serde_json::to_string(&info)
+    } else {
+        serde_json::to_string_pretty(&info)
+    }?;
+    println!("{out_str}");
+    if info.nfailed > 0 {
+        warn!(
+            "optimize: {} tiles could not be optimized and were copied as is",
+            info.nfailed
+        );
+    }
     Ok(())
 }
@@ -0,0 +1,113 @@
+use std::io::{self, BufWriter, Write};
+
+use serde_json::{Value, json};
+use utiles_core::Tile;
+use utiles_core::parsing::parse_float_blocks;
+use utiles_core::pixel::{
+    lnglat2pixel, pixel2lnglat, pixel2tile, pixel2webmercator, tile_pixel2pixel,
+    webmercator2pixel,
+};
+
+use crate::cli::args::{PixelArgs, PixelFrom};
+use crate::cli::stdinterator_filter::stdin_filtered;
+use crate::errors::{UtilesError, UtilesResult};
+
+fn parse_numbers(line: &str) -> UtilesResult<Vec<f64>> {
+    parse_float_blocks(line)
+        .into_iter()
+        .map(|n| {
+            n.parse::<f64>().map_err(|e| {
+                UtilesError::ParsingError(format!("invalid number {n} ({e}): {line}"))
+            })
+        })
+        .collect()
+}
+
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
+fn parse_tile(x: f64, y: f64, z: f64, line: &str) -> UtilesResult<Tile> {
+    if [x, y, z].iter().any(|n| n.fract() != 0.0 || *n < 0.0) || z > 30.0 {
+        return Err(UtilesError::ParsingError(format!("invalid tile: {line}")));
+    }
+    Ok(Tile::try_new(x as u32, y as u32, z as u8)?)
+}
+
+/// Global pixel + zoom of an input line
+fn line_pixel(args: &PixelArgs, line: &str) -> UtilesResult<(f64, f64, u8)> {
+    let nums = parse_numbers(line)?;
+    let size = f64::from(args.tile_size);
+    match args.from {
+        PixelFrom::TilePixel | PixelFrom::Extent => {
+            let [tx, ty, tz, u, v] = nums[..] else {
+                return Err(UtilesError::ParsingError(format!(
+                    "expected `x y z u v` (tile + tile-local coords): {line}"
+                )));
+            };
+            let tile = parse_tile(tx, ty, tz, line)?;
+            let (u, v) = if args.from == PixelFrom::Extent {
+                let scale = size / f64::from(args.extent);
+                (u * scale, v * scale)
+            } else {
+                (u, v)
+            };
+            let (px, py) = tile_pixel2pixel(&tile, u, v, args.tile_size);
+            Ok((px, py, tile.z))
+        }
+        PixelFrom::Lnglat | PixelFrom::Mercator | PixelFrom::Pixel => {
+            let (a, b, zoom) = match nums[..] {
+                [a, b] => (a, b, args.zoom),
+                [a, b, z] if z.fract() == 0.0 && (0.0..=30.0).contains(&z) => {
+                    (a, b, Some(z as u8))
+                }
+                _ => {
+                    return Err(UtilesError::ParsingError(format!(
+                        "expected 2 coords (+ zoom): {line}"
+                    )));
+                }
+            };
+            let zoom = zoom.ok_or_else(|| {
+                UtilesError::AdHoc(format!(
+                    "--zoom required (or `x y z` input): {line}"
+                ))
+            })?;
+            let (px, py) = match args.from {
+                PixelFrom::Lnglat => lnglat2pixel(a, b, zoom, args.tile_size)?,
+                PixelFrom::Mercator => webmercator2pixel(a, b, zoom, args.tile_size),
+                _ => (a, b),
+            };
+            Ok((px, py, zoom))
+        }
+    }
+}
+
+fn pixel_obj(args: &PixelArgs, px: f64, py: f64, zoom: u8) -> Value {
+    let lnglat = pixel2lnglat(px, py, zoom, args.tile_size);
+    let (mx, my) = pixel2webmercator(px, py, zoom, args.tile_size);
+    let (tile, x, y) = pixel2tile(px, py, zoom, args.tile_size);
+    let scale = f64::from(args.extent) / f64::from(args.tile_size);
+    json!({
+        "zoom": zoom,
+        "tile_size": args.tile_size,
+        "lnglat": [lnglat.lng(), lnglat.lat()],
+        "mercator": [mx, my],
+        "pixel": [px, py],
+        "tile": [tile.x, tile.y, tile.z],
+        "tile_pixel": [x, y],
+        "extent": [x * scale, y * scale],
+    })
+}
+
+pub(crate) fn pixel_main(args: &PixelArgs) -> UtilesResult<()> {
+    if args.extent == 0 {
+        return Err(UtilesError::AdHoc("--extent must be > 0".to_string()));
+    }
+    let stdout = io::stdout();
+    let mut buf = BufWriter::with_capacity(32 * 1024, stdout.lock());
+    for line in stdin_filtered(args.input.clone()) {
+        let line = line?;
+        let (px, py, zoom) = line_pixel(args, &line)?;
+        writeln!(buf, "{}", pixel_obj(args, px, py, zoom))?;
+    }
+    buf.flush()?;
+    Ok(())
+}
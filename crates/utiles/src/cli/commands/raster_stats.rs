@@ -0,0 +1,206 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use tracing::info;
+use utiles_core::Tile;
+
+use crate::cli::args::RasterStatsArgs;
+use crate::cli::commands::tile_jobs::{TileJob, TileJobs, resolve_jobs};
+use crate::errors::UtilesResult;
+use crate::img::load_from_memory;
+use crate::img::stats::{
+    BandSummary, ColorCount, RasterStats, TileRasterStats, color_hex, parse_color_hex,
+    tile_raster_stats,
+};
+use crate::mbt::{Mbtiles, MbtilesAsync, MbtilesClientAsync};
+
+#[derive(Debug, Serialize)]
+struct RasterStatsSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    zoom: Option<u8>,
+    ntiles: u64,
+    ntransparent: u64,
+    nsingle_color: u64,
+    transparent_ratio: f64,
+    single_color_ratio: f64,
+    bands: Vec<BandSummary>,
+    dominant_colors: Vec<ColorCount>,
+}
+
+impl RasterStatsSummary {
+    #[expect(clippy::cast_precision_loss)]
+    fn new(zoom: Option<u8>, stats: &RasterStats, top: usize) -> Self {
+        let ratio = |n: u64| {
+            if stats.ntiles == 0 {
+                0.0
+            } else {
+                n as f64 / stats.ntiles as f64
+            }
+        };
+        Self {
+            zoom,
+            ntiles: stats.ntiles,
+            ntransparent: stats.ntransparent,
+            nsingle_color: stats.nsingle_color,
+            transparent_ratio: ratio(stats.ntransparent),
+            single_color_ratio: ratio(stats.nsingle_color),
+            bands: stats.band_summaries(),
+            dominant_colors: stats.dominant_colors(top),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BlankTile {
+    tile: Tile,
+    color: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RasterStatsReport {
+    fspath: String,
+    #[serde(flatten)]
+    summary: RasterStatsSummary,
+    /// non-raster/undecodable tiles
+    nskipped: u64,
+    zooms: Vec<RasterStatsSummary>,
+    /// number of single-color (incl. fully transparent) tiles
+    nblank: u64,
+    /// first (by z/x/y) `--list-blank` single-color tiles
+    blank_tiles: Vec<BlankTile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fill_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ndeleted: Option<usize>,
+    total_time_ms: u128,
+}
+
+/// Stats accumulated over the tiles stream
+struct Accumulated {
+    total: RasterStats,
+    zooms: BTreeMap<u8, RasterStats>,
+    nblank: u64,
+    /// first `list_blank` single-color tiles (by z/x/y)
+    blank: BTreeSet<(u8, u32, u32, [u8; 4])>,
+    list_blank: usize,
+    /// single-color tiles of the fill color (to delete)
+    fill: Vec<Tile>,
+    fill_color: Option<[u8; 4]>,
+}
+
+impl Accumulated {
+    fn add(&mut self, tile: Tile, stats: &TileRasterStats, bins: usize) {
+        self.total.add(stats);
+        self.zooms
+            .entry(tile.z)
+            .or_insert_with(|| RasterStats::new(bins))
+            .add(stats);
+        if let Some(color) = stats.single_color {
+            self.nblank += 1;
+            if self.fill_color == Some(color) {
+                self.fill.push(tile);
+            }
+            if self.list_blank > 0 {
+                self.blank.insert((tile.z, tile.x, tile.y, color));
+                if self.blank.len() > self.list_blank {
+                    self.blank.pop_last();
+                }
+            }
+        }
+    }
+}
+
+pub(crate) async fn raster_stats_main(args: &RasterStatsArgs) -> UtilesResult<()> {
+    let start_time = std::time::Instant::now();
+    let fill_color = args
+        .fill_color
+        .as_deref()
+        .map(parse_color_hex)
+        .transpose()?;
+    let mbt = MbtilesClientAsync::open_existing(args.common.filepath.as_str()).await?;
+    mbt.assert_mbtiles().await?;
+    let total_count = mbt.tiles_count().await?;
+    let bins = usize::from(args.bins);
+    // keep more colors per tile than reported so the totals are closer
+    let ncolors = args.top.max(1) * 4;
+    let jobs = resolve_jobs(args.jobs);
+    info!("raster-stats ~ total_count: {total_count} ~ bins: {bins} ~ jobs: {jobs}");
+
+    let tile_jobs = TileJobs {
+        name: "raster-stats",
+        total: total_count as u64,
+        jobs,
+        quiet: args.quiet,
+    };
+    let acc = Accumulated {
+        total: RasterStats::new(bins),
+        zooms: BTreeMap::new(),
+        nblank: 0,
+        blank: BTreeSet::new(),
+        list_blank: args.list_blank,
+        fill: vec![],
+        fill_color: fill_color.filter(|_| args.delete_fill),
+    };
+    // non-raster/undecodable tiles are the failed (skipped) tiles
+    let output = tile_jobs
+        .run(
+            mbt.tiles_stream(None)?,
+            None,
+            move |(tile, tile_data): (Tile, Vec<u8>)| {
+                let img = load_from_memory(&tile_data)?;
+                let stats = tile_raster_stats(&img, bins, ncolors);
+                Ok(TileJob::new(vec![], (tile, stats)))
+            },
+            acc,
+            move |acc, (tile, stats), _pb| acc.add(tile, &stats, bins),
+        )
+        .await?;
+    let mut acc = output.acc;
+
+    // delete the single-color tiles of the fill color
+    let ndeleted = if let Some(fill) = acc.fill_color {
+        let tiles = std::mem::take(&mut acc.fill);
+        let fspath = args.common.filepath.clone();
+        let ndeleted = tokio::task::spawn_blocking(move || -> UtilesResult<usize> {
+            let mut mbt = Mbtiles::open_existing(&fspath)?;
+            let ndeleted = mbt.delete_tiles(&tiles)?;
+            mbt.metadata_set("fill_color", &color_hex(fill))?;
+            Ok(ndeleted)
+        })
+        .await??;
+        info!("deleted {ndeleted} {} tiles", color_hex(fill));
+        Some(ndeleted)
+    } else {
+        None
+    };
+
+    let report = RasterStatsReport {
+        fspath: args.common.filepath.clone(),
+        summary: RasterStatsSummary::new(None, &acc.total, args.top),
+        nskipped: output.nfailed,
+        zooms: acc
+            .zooms
+            .iter()
+            .map(|(z, stats)| RasterStatsSummary::new(Some(*z), stats, args.top))
+            .collect(),
+        nblank: acc.nblank,
+        blank_tiles: acc
+            .blank
+            .iter()
+            .map(|(z, x, y, color)| BlankTile {
+                tile: Tile::new(*x, *y, *z),
+                color: color_hex(*color),
+            })
+            .collect(),
+        fill_color: acc.fill_color.map(color_hex),
+        ndeleted,
+        total_time_ms: start_time.elapsed().as_millis(),
+    };
+    let out_str = if args.common.min {
+        serde_json::to_string(&report)
+    } else {
+        serde_json::to_string_pretty(&report)
+    }?;
+    println!("{out_str}");
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use image::GenericImageView;
+use image::imageops::FilterType;
+use indoc::indoc;
+use rusqlite::OptionalExtension;
+use serde::Serialize;
+use tracing::{debug, info};
+use utiles_core::tile_type::{TileFormat, tiletype};
+use utiles_core::{Tile, utile};
+
+use crate::cli::args::ResizeArgs;
+use crate::cli::commands::tile_jobs::{
+    TileJob, TileJobItem, TileJobResult, TileJobs, resolve_jobs,
+};
+use crate::errors::{UtilesError, UtilesResult};
+use crate::img::{
+    encode_tile, image_is_empty, load_from_memory, mosaic_tiles, resize_tile,
+    split_tile,
+};
+use crate::mbt::{Mbtiles, MbtilesAsync, MbtilesClientAsync};
+use crate::sqlite::AsyncSqliteConn;
+use crate::sqlite::streams::sqlite_query_tokio_receiver_stream;
+
+/// Parent tiles w/ their 4 children (tl, tr, bl, br); rows are TMS
+const CHILDREN_QUERY: &str = indoc! {r"
+WITH parent AS (SELECT DISTINCT (zoom_level - 1)  AS p_z,
+                                (tile_column / 2) AS p_x,
+                                (tile_row / 2)    AS p_y
+                FROM tiles
+                WHERE zoom_level > 0)
+SELECT parent.p_z        AS parent_z,
+       parent.p_x        AS parent_x,
+       parent.p_y        AS parent_y,
+       child_0.tile_data AS child_0,
+       child_1.tile_data AS child_1,
+       child_2.tile_data AS child_2,
+       child_3.tile_data AS child_3
+FROM parent
+         LEFT JOIN tiles child_0 ON child_0.zoom_level = parent.p_z + 1
+    AND child_0.tile_column = parent.p_x * 2
+    AND child_0.tile_row = parent.p_y * 2 + 1
+         LEFT JOIN tiles child_1 ON child_1.zoom_level = parent.p_z + 1
+    AND child_1.tile_column = parent.p_x * 2 + 1
+    AND child_1.tile_row = parent.p_y * 2 + 1
+         LEFT JOIN tiles child_2 ON child_2.zoom_level = parent.p_z + 1
+    AND child_2.tile_column = parent.p_x * 2
+    AND child_2.tile_row = parent.p_y * 2
+         LEFT JOIN tiles child_3 ON child_3.zoom_level = parent.p_z + 1
+    AND child_3.tile_column = parent.p_x * 2 + 1
+    AND child_3.tile_row = parent.p_y * 2
+"};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ResizeMode {
+    /// 4 children at z+1 -> 1 tile (2x size) at z
+    Mosaic,
+    /// 1 tile at z -> 4 children (1/2 size) at z+1
+    Split,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ResizeOptions {
+    tile_size: u32,
+    filter: FilterType,
+    format: TileFormat,
+    minzoom: u8,
+}
+
+#[derive(Debug, Serialize)]
+struct ResizeSummary {
+    src: String,
+    dst: String,
+    mode: ResizeMode,
+    src_tile_size: u32,
+    tile_size: u32,
+    format: String,
+    ntiles_src: u64,
+    ntiles_dst: u64,
+    /// source tiles (mosaic: parent tiles) that failed to resize
+    nfailed: u64,
+    minzoom: Option<u8>,
+    maxzoom: Option<u8>,
+    total_time_ms: u128,
+}
+
+/// Resized (tile, data) pairs to write
+type ResizedTiles = Vec<(Tile, Vec<u8>)>;
+
+struct TileChildrenRow {
+    parent: Tile,
+    children: [Option<Vec<u8>>; 4],
+}
+
+impl TileJobItem for TileChildrenRow {
+    fn tile(&self) -> Tile {
+        self.parent
+    }
+
+    /// Number of source (child) tiles
+    fn weight(&self) -> u64 {
+        self.children.iter().flatten().count() as u64
+    }
+}
+
+fn map_children_row(row: &rusqlite::Row) -> rusqlite::Result<TileChildrenRow> {
+    let parent_z: u8 = row.get("parent_z")?;
+    let parent_x: u32 = row.get("parent_x")?;
+    let parent_yup: u32 = row.get("parent_y")?;
+    let parent_y = (1 << parent_z) - 1 - parent_yup;
+    Ok(TileChildrenRow {
+        parent: utile!(parent_x, parent_y, parent_z),
+        children: [
+            row.get("child_0")?,
+            row.get("child_1")?,
+            row.get("child_2")?,
+            row.get("child_3")?,
+        ],
+    })
+}
+
+fn decode_opt(data: Option<&Vec<u8>>) -> UtilesResult<Option<image::DynamicImage>> {
+    data.map(|d| load_from_memory(d)).transpose()
+}
+
+/// Mosaic the children of a parent tile (fully transparent mosaics are
+/// skipped like fully transparent split children)
+fn mosaic_row(
+    row: &TileChildrenRow,
+    options: ResizeOptions,
+) -> UtilesResult<ResizedTiles> {
+    let [c0, c1, c2, c3] = &row.children;
+    let children = [
+        decode_opt(c0.as_ref())?,
+        decode_opt(c1.as_ref())?,
+        decode_opt(c2.as_ref())?,
+        decode_opt(c3.as_ref())?,
+    ];
+    let img = mosaic_tiles(children, options.tile_size, options.filter)?;
+    match img {
+        Some(img) if !image_is_empty(&img) => {
+            Ok(vec![(row.parent, encode_tile(&img, options.format)?)])
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+/// Split a tile into its children (+ downsample the tile itself at minzoom)
+fn split_row(
+    tile: Tile,
+    data: &[u8],
+    options: ResizeOptions,
+) -> UtilesResult<ResizedTiles> {
+    let img = load_from_memory(data)?;
+    let mut out = Vec::with_capacity(5);
+    if tile.z == options.minzoom {
+        let img = resize_tile(img.clone(), options.tile_size, options.filter);
+        out.push((tile, encode_tile(&img, options.format)?));
+    }
+    let children = split_tile(img, options.tile_size, options.filter);
+    for (child, child_img) in tile.children1().into_iter().zip(children) {
+        if image_is_empty(&child_img) {
+            continue;
+        }
+        out.push((child, encode_tile(&child_img, options.format)?));
+    }
+    Ok(out)
+}
+
+#[expect(clippy::too_many_lines)]
+pub(crate) async fn resize_main(args: ResizeArgs) -> UtilesResult<()> {
+    let start_time = std::time::Instant::now();
+    let mbt = MbtilesClientAsync::open_existing(args.common.filepath.as_str()).await?;
+    mbt.assert_mbtiles().await?;
+    let total_count = mbt.tiles_count().await?;
+    let first_tile: Option<Vec<u8>> = mbt
+        .conn(|c| {
+            c.query_row("SELECT tile_data FROM tiles LIMIT 1", [], |row| row.get(0))
+                .optional()
+        })
+        .await?;
+    let Some(first_tile) = first_tile else {
+        return Err(UtilesError::AdHoc("no tiles to resize".to_string()));
+    };
+    let src_format = tiletype(&first_tile).format;
+    let src_tile_size = match mbt.query_metadata_value("tilesize").await? {
+        Some(v) => v.trim().parse::<u32>().map_err(|_| {
+            UtilesError::ParsingError(format!("invalid tilesize metadata: {v}"))
+        })?,
+        None => load_from_memory(&first_tile)?.dimensions().0,
+    };
+    let tile_size = args.tile_size;
+    let mode = if tile_size == src_tile_size * 2 {
+        ResizeMode::Mosaic
+    } else if tile_size * 2 == src_tile_size {
+        ResizeMode::Split
+    } else {
+        return Err(UtilesError::Unsupported(format!(
+            "resize {src_tile_size} -> {tile_size} (tile size must be 2x or 1/2 the source tile size)"
+        )));
+    };
+    let format: TileFormat = args.format.map_or(src_format, Into::into);
+    let minzoom = mbt
+        .query_minzoom_maxzoom()
+        .await?
+        .map_or(0, |minmax| minmax.minzoom);
+    let options = ResizeOptions {
+        tile_size,
+        filter: args.filter.into(),
+        format,
+        minzoom,
+    };
+    info!(
+        "resize ~ {mode:?} ~ {src_tile_size} -> {tile_size} ~ total_count: {total_count} ~ {options:?}"
+    );
+
+    let dst_mbtiles = Mbtiles::open_new(&args.dst, None)?;
+    dst_mbtiles.metadata_set_many(&mbt.metadata_rows().await?)?;
+    dst_mbtiles.metadata_set("tilesize", &tile_size.to_string())?;
+    dst_mbtiles.metadata_set("format", &format.to_string())?;
+
+    let tile_jobs = TileJobs {
+        name: "resize",
+        total: total_count as u64,
+        jobs: resolve_jobs(args.jobs),
+        quiet: args.quiet,
+    };
+    // mosaic: one job per parent tile; split: one job per source tile
+    let mut output = match mode {
+        ResizeMode::Mosaic => {
+            let rows = sqlite_query_tokio_receiver_stream(
+                &mbt,
+                CHILDREN_QUERY,
+                map_children_row,
+            )?;
+            tile_jobs
+                .run(
+                    rows,
+                    Some(dst_mbtiles),
+                    move |row: TileChildrenRow| {
+                        let nsrc = row.weight();
+                        resized_job(mosaic_row(&row, options), nsrc)
+                    },
+                    (0, 0),
+                    fold_resized,
+                )
+                .await?
+        }
+        ResizeMode::Split => {
+            tile_jobs
+                .run(
+                    mbt.tiles_stream(None)?,
+                    Some(dst_mbtiles),
+                    move |(tile, tile_data): (Tile, Vec<u8>)| {
+                        resized_job(split_row(tile, &tile_data, options), 1)
+                    },
+                    (0, 0),
+                    fold_resized,
+                )
+                .await?
+        }
+    };
+    let (ntiles_src, ntiles_dst) = output.acc;
+    // close the dst before reopening it
+    drop(output.dst.take());
+
+    let dst = MbtilesClientAsync::open_existing(args.dst.as_str()).await?;
+    let changes = dst.update_minzoom_maxzoom().await?;
+    debug!("minzoom/maxzoom changes: {changes:?}");
+    let minmax = dst.query_minzoom_maxzoom().await?;
+    let summary = ResizeSummary {
+        src: args.common.filepath,
+        dst: args.dst,
+        mode,
+        src_tile_size,
+        tile_size,
+        format: format.to_string(),
+        ntiles_src,
+        ntiles_dst,
+        nfailed: output.nfailed,
+        minzoom: minmax.as_ref().map(|m| m.minzoom),
+        maxzoom: minmax.as_ref().map(|m| m.maxzoom),
+        total_time_ms: start_time.elapsed().as_millis(),
+    };
+    let out_str = if args.common.min {
+        serde_json::to_string(&summary)
+    } else {
+        serde_json::to_string_pretty(&summary)
+    }?;
+    println!("{out_str}");
+    output.check_failed("resize")
+}
+
+/// Job of resized tiles w/ (n source, n resized) as progress
+fn resized_job(
+    res: UtilesResult<ResizedTiles>,
+    nsrc: u64,
+) -> TileJobResult<(u64, u64)> {
+    let tiles = res?;
+    let ndst = tiles.len() as u64;
+    let tiles = tiles
+        .into_iter()
+        .map(|(tile, data)| (tile, data, None).into())
+        .collect();
+    Ok(TileJob::new(tiles, (nsrc, ndst)))
+}
+
+fn fold_resized(
+    acc: &mut (u64, u64),
+    (nsrc, ndst): (u64, u64),
+    pb: &indicatif::ProgressBar,
+) {
+    acc.0 += nsrc;
+    acc.1 += ndst;
+    pb.set_message(format!("resize ~ {} tiles", acc.1));
+}
@@ -0,0 +1,78 @@
+use std::io::{self, BufWriter, Write};
+
+use serde_json::{Map, Value, json};
+use utiles_core::resolution::{
+    meters_per_pixel, resolution, scale_denominator, zoom_for_resolution,
+    zoom_for_scale_denominator,
+};
+
+use crate::cli::args::ResolutionArgs;
+use crate::errors::{UtilesError, UtilesResult};
+
+/// Zoom for a (ground) resolution or scale denominator
+fn zoom_obj(args: &ResolutionArgs) -> Option<Value> {
+    if let Some(res) = args.resolution {
+        // ground resolution at lat -> resolution at the equator
+        let equator_res = args.lat.map_or(res, |lat| res / lat.to_radians().cos());
+        let mut obj = json!({
+            "resolution": res,
+            "tile_size": args.tile_size,
+            "zoom": zoom_for_resolution(equator_res, args.tile_size),
+        });
+        if let Some(lat) = args.lat {
+            obj["lat"] = json!(lat);
+        }
+        Some(obj)
+    } else {
+        args.scale_denominator.map(|sd| {
+            json!({
+                "scale_denominator": sd,
+                "tile_size": args.tile_size,
+                "zoom": zoom_for_scale_denominator(sd, args.tile_size),
+            })
+        })
+    }
+}
+
+pub(crate) fn resolution_main(args: &ResolutionArgs) -> UtilesResult<()> {
+    if let Some(lat) = args.lat
+        && !(-90.0..=90.0).contains(&lat)
+    {
+        return Err(UtilesError::AdHoc(format!("invalid latitude: {lat}")));
+    }
+    let stdout = io::stdout();
+    let mut buf = BufWriter::with_capacity(32 * 1024, stdout.lock());
+    if let Some(obj) = zoom_obj(args) {
+        writeln!(buf, "{obj}")?;
+        buf.flush()?;
+        return Ok(());
+    }
+    let zooms: Vec<u8> = args.zoom.as_ref().map_or_else(
+        || (0..=22).collect(),
+        |zooms| zooms.iter().flatten().copied().collect(),
+    );
+    for z in zooms {
+        let zoom = f64::from(z);
+        let mut obj = Map::new();
+        obj.insert("zoom".to_string(), json!(z));
+        obj.insert("tile_size".to_string(), json!(args.tile_size));
+        obj.insert(
+            "resolution".to_string(),
+            json!(resolution(zoom, args.tile_size)),
+        );
+        obj.insert(
+            "scale_denominator".to_string(),
+            json!(scale_denominator(zoom, args.tile_size)),
+        );
+        if let Some(lat) = args.lat {
+            obj.insert("lat".to_string(), json!(lat));
+            obj.insert(
+                "meters_per_pixel".to_string(),
+                json!(meters_per_pixel(lat, zoom, args.tile_size)),
+            );
+        }
+        writeln!(buf, "{}", Value::Object(obj))?;
+    }
+    buf.flush()?;
+    Ok(())
+}
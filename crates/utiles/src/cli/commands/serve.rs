@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 use tracing::{debug, warn};
 
@@ -6,17 +8,28 @@ use crate::server::{UtilesServerConfig, utiles_serve};
 
 #[derive(Debug, Parser)]
 pub struct ServeArgs {
-    /// Filesystem paths to serve from
+    /// Filesystem paths to serve from (mbtiles files, dirs of mbtiles and/or
+    /// `z/x/y.ext` tile pyramid dirs w/ optional `metadata.json`); replace
+    /// the config file's `fspaths`
     #[arg(required = false)]
     fspaths: Option<Vec<String>>,
 
-    /// Port to server on
-    #[arg(long, short = 'p', default_value = "3333")]
-    port: u16,
+    /// Server config file (toml or json); cli options take precedence
+    #[arg(long, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// Port to server on [default: 3333]
+    #[arg(long, short = 'p')]
+    port: Option<u16>,
+
+    /// Host bind address [default: 0.0.0.0]
+    #[arg(long, short = 'H')]
+    host: Option<String>,
 
-    /// Host bind address
-    #[arg(long, short = 'H', default_value = "0.0.0.0")]
-    host: String,
+    /// Public base url for tilejson `tiles` urls (e.g. when behind a proxy);
+    /// defaults to `X-Forwarded-*`/`Host` request headers
+    #[arg(long)]
+    public_url: Option<String>,
 
     /// strict mode (default: false)
     #[arg(long, short = 's', default_value = "false", action = clap::ArgAction::SetTrue)]
@@ -24,30 +37,37 @@ pub struct ServeArgs {
 }
 
 impl ServeArgs {
-    pub fn to_cfg(&self) -> UtilesServerConfig {
-        UtilesServerConfig::new(
-            self.host.clone(),
-            self.port,
-            self.fspaths.clone().unwrap_or_default(),
-        )
+    pub fn to_cfg(&self) -> UtilesResult<UtilesServerConfig> {
+        let mut cfg = match &self.config {
+            Some(config_fspath) => UtilesServerConfig::from_file(config_fspath)?,
+            None => UtilesServerConfig::new("0.0.0.0".to_string(), 3333, vec![]),
+        };
+        if let Some(host) = &self.host {
+            cfg.host.clone_from(host);
+        }
+        if let Some(port) = self.port {
+            cfg.port = port;
+        }
+        if let Some(fspaths) = &self.fspaths {
+            cfg.fspaths.clone_from(fspaths);
+        }
+        if self.public_url.is_some() {
+            cfg.public_url.clone_from(&self.public_url);
+        }
+        Ok(cfg)
     }
 }
 
-pub(crate) async fn serve_main(args: ServeArgs) -> UtilesResult<()> {
-    debug!("args: {:?}", args);
-    if let Some(ref fspaths) = args.fspaths {
-        if fspaths.is_empty() {
-            warn!("fspaths is empty");
-        }
-        for fspath in fspaths {
-            if !std::path::Path::new(fspath).exists() {
-                warn!("fspath does not exist: {:?}", fspath);
-            }
-        }
-    } else {
+pub(crate) async fn serve_main(cfg: UtilesServerConfig) -> UtilesResult<()> {
+    debug!("cfg: {:?}", cfg);
+    if cfg.fspaths.is_empty() && cfg.datasets.is_empty() {
         warn!("no fspaths provided");
     }
-    let cfg = args.to_cfg();
+    for fspath in &cfg.fspaths {
+        if !std::path::Path::new(fspath).exists() {
+            warn!("fspath does not exist: {:?}", fspath);
+        }
+    }
     utiles_serve(cfg).await?;
     Ok(())
 }
@@ -1,13 +1,20 @@
-use clap::{Args, Parser};
+use std::io::{self, BufWriter, Write};
+
+use clap::{Args, Parser, ValueEnum};
+use geojson::{Feature, GeoJson, Geometry, GeometryValue, PolygonType, Position};
 use serde_json::{Map, Value};
 use tracing::{debug, error};
 use utiles_core::projection::Projection;
 use utiles_core::tile::FeatureOptions;
-use utiles_core::{Tile, UtilesCoreError};
+use utiles_core::{Tile, TileMatrixSet, UtilesCoreError, xy};
 
-use crate::cli::args::TileInputStreamArgs;
+use crate::cli::args::{TileInputFormat, TileInputStreamArgs, parse_tms};
 use crate::cli::stdinterator_filter;
-use crate::errors::UtilesResult;
+use crate::cli::tile_input::{TileLineParser, tms_json_tile};
+use crate::errors::{UtilesError, UtilesResult};
+use crate::gj::dissolve::dissolve_tiles;
+use crate::gj::fgb::write_fgb;
+use crate::gj::wkt::geometry_wkt;
 
 // #[group(required = false, id="projected")]
 #[derive(Args, Debug)]
@@ -60,6 +67,31 @@ impl Default for ShapesOutputMode {
     }
 }
 
+/// Shape geometry type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub(crate) enum ShapesGeometry {
+    /// Tile polygons
+    #[default]
+    Polygon,
+    /// Tile outlines as (multi)linestrings
+    Outline,
+    /// Tile centroids as (multi)points
+    Centroid,
+}
+
+/// Shapes output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub(crate) enum ShapesFormat {
+    /// `GeoJSON` features (or collection w/ `--collect`)
+    #[default]
+    Geojson,
+    /// WKT geometry per line
+    Wkt,
+    /// `FlatGeobuf` (binary; written to stdout)
+    Fgb,
+}
+
+#[expect(clippy::struct_excessive_bools)]
 #[derive(Debug, Parser)] // requires `derive` feature
 #[command(name = "shapes", about = "echo shapes of tile(s) as GeoJSON", long_about = None)]
 pub struct ShapesArgs {
@@ -90,12 +122,32 @@ pub struct ShapesArgs {
     /// Shift shape x and y values by a constant number.
     #[arg(long, value_parser)]
     buffer: Option<f64>,
+
+    /// Tile matrix set ~ built-in id or OGC tile matrix set json filepath;
+    /// `--mercator` outputs the tile matrix set's CRS coordinates
+    #[arg(required = false, long, value_parser = parse_tms)]
+    tms: Option<TileMatrixSet>,
+
+    /// Dissolve (merge) adjacent tiles into (multi)polygon(s)
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["tms", "buffer", "extents"])]
+    dissolve: bool,
+
+    /// Geometry type ~ tile polygons, outlines (lines) or centroids (points)
+    #[arg(long, value_enum, default_value_t = ShapesGeometry::Polygon)]
+    geometry: ShapesGeometry,
+
+    /// Output format ~ geojson, wkt or fgb (`FlatGeobuf`)
+    #[arg(long, value_enum, default_value_t = ShapesFormat::Geojson)]
+    format: ShapesFormat,
 }
 
 impl Default for ShapesArgs {
     fn default() -> Self {
         Self {
-            inargs: TileInputStreamArgs { input: None },
+            inargs: TileInputStreamArgs {
+                input: None,
+                input_format: TileInputFormat::Auto,
+            },
             seq: false,
             precision: None,
             project: Some(ShapesProject::default()),
@@ -103,7 +155,46 @@ impl Default for ShapesArgs {
             collect: false,
             extents: false,
             buffer: None,
+            tms: None,
+            dissolve: false,
+            geometry: ShapesGeometry::Polygon,
+            format: ShapesFormat::Geojson,
+        }
+    }
+}
+
+/// Properties of an input line ~ `properties` member, other members of tile
+/// objects (`{"x": 1, "y": 2, "z": 3, "count": 4}`) and a trailing object in
+/// tile arrays (`[1, 2, 3, {"count": 4}]`)
+fn input_properties(val: &Value) -> Option<Map<String, Value>> {
+    let mut properties = Map::new();
+    match val {
+        Value::Object(obj) => {
+            for (k, v) in obj {
+                match k.as_str() {
+                    "properties" => {
+                        if let Some(props) = v.as_object() {
+                            properties.extend(props.clone());
+                        }
+                    }
+                    "x" | "y" | "z" | "tile" | "id" | "type" | "geometry" | "bbox" => {}
+                    _ => {
+                        properties.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+        }
+        Value::Array(arr) => {
+            if let Some(props) = arr.get(3).and_then(Value::as_object) {
+                properties.extend(props.clone());
+            }
         }
+        _ => {}
+    }
+    if properties.is_empty() {
+        None
+    } else {
+        Some(properties)
     }
 }
 
@@ -115,20 +206,43 @@ struct TileWithProperties {
 
 pub(crate) fn shapes_main(args: ShapesArgs) -> UtilesResult<()> {
     debug!("{:?}", args);
-    let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
-    let parsed_lines = lines.map(|line_res| {
-        let ln = line_res.map_err(|e| UtilesCoreError::ParseError(e.to_string()))?;
-        let val: Value = serde_json::from_str::<Value>(&ln)?;
-        let properties: Option<Map<String, Value>> = if val["properties"].is_object() {
-            let properties = val["properties"].as_object();
-            if let Some(properties) = properties {
-                Option::from(properties.clone())
-            } else {
-                None
-            }
-        } else {
-            None
+    let lines = stdinterator_filter::stdin_filtered(args.inargs.input.clone());
+    let tms = args.tms.clone();
+    let mut parser =
+        TileLineParser::new(args.inargs.input_format).with_tms(args.tms.clone());
+    let parsed_lines = lines.flat_map(move |line_res| {
+        let ln = match line_res {
+            Ok(ln) => ln,
+            Err(e) => return vec![Err(UtilesCoreError::ParseError(e.to_string()))],
         };
+        let json_tile = matches!(
+            args.inargs.input_format,
+            TileInputFormat::Auto | TileInputFormat::Json
+        ) && ln.trim_start().starts_with(['[', '{'])
+            && !ln.contains("\"Feature");
+        if !json_tile {
+            // csv/tsv rows, z/x/y paths and geojson features
+            return match parser.parse_line(&ln) {
+                Ok(tiles) => tiles
+                    .into_iter()
+                    .map(|tile| {
+                        Ok(TileWithProperties {
+                            tile,
+                            id: None,
+                            properties: None,
+                        })
+                    })
+                    .collect(),
+                Err(e) => vec![Err(UtilesCoreError::TileParseError(format!(
+                    "line: {ln}, error: {e}"
+                )))],
+            };
+        }
+        let val: Value = match serde_json::from_str::<Value>(&ln) {
+            Ok(val) => val,
+            Err(e) => return vec![Err(e.into())],
+        };
+        let properties = input_properties(&val);
         let id = if val["id"].is_string() {
             let id = val["id"].as_str();
             if let Some(id) = id {
@@ -139,7 +253,11 @@ pub(crate) fn shapes_main(args: ShapesArgs) -> UtilesResult<()> {
         } else {
             None
         };
-        let t = Tile::try_from(&val);
+        let t = match &tms {
+            Some(tms) => tms_json_tile(&val, tms)
+                .map_err(|e| UtilesCoreError::TileParseError(e.to_string())),
+            None => Tile::try_from(&val),
+        };
         match t {
             Ok(tile) => {
                 let tile_with_properties = TileWithProperties {
@@ -147,18 +265,24 @@ pub(crate) fn shapes_main(args: ShapesArgs) -> UtilesResult<()> {
                     id,
                     properties,
                 };
-                Ok(tile_with_properties)
+                vec![Ok(tile_with_properties)]
             }
             Err(e) => {
                 error!("Error parsing tile: {}", e);
                 // throw the error here
                 let e_str = e.to_string();
-                Err(UtilesCoreError::TileParseError(format!(
+                vec![Err(UtilesCoreError::TileParseError(format!(
                     "line: {ln}, error: {e_str}",
-                )))
+                )))]
             }
         }
     });
+    if args.dissolve
+        || args.geometry != ShapesGeometry::Polygon
+        || args.format != ShapesFormat::Geojson
+    {
+        return shapes_geometries(&args, parsed_lines);
+    }
     let feature_options: FeatureOptions = FeatureOptions {
         fid: None,
         projection: match args.project {
@@ -197,7 +321,10 @@ pub(crate) fn shapes_main(args: ShapesArgs) -> UtilesResult<()> {
         let tile = tile_n_properties.tile;
         let properties = tile_n_properties.properties;
 
-        let mut f = tile.feature(&feature_options)?;
+        let mut f = match &args.tms {
+            Some(tms) => tms.feature(&tile, &feature_options)?,
+            None => tile.feature(&feature_options)?,
+        };
         if let Some(properties) = properties {
             f.properties.extend(properties);
         }
@@ -229,3 +356,242 @@ pub(crate) fn shapes_main(args: ShapesArgs) -> UtilesResult<()> {
     }
     Ok(())
 }
+
+/// Round a coordinate to `precision` decimals (if non-negative)
+fn round_coord(v: f64, precision: Option<i32>) -> f64 {
+    match precision {
+        Some(p) if p >= 0 => {
+            let factor = 10_f64.powi(p);
+            (v * factor).round() / factor
+        }
+        _ => v,
+    }
+}
+
+/// Area-weighted centroid of a polygon (rings w/ rfc-7946 orientation)
+fn polygon_centroid(polygon: &PolygonType) -> Position {
+    let (mut a2, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    for ring in polygon {
+        for w in ring.windows(2) {
+            let (p, q) = (w[0].as_slice(), w[1].as_slice());
+            let cross = p[0] * q[1] - q[0] * p[1];
+            a2 += cross;
+            cx += (p[0] + q[0]) * cross;
+            cy += (p[1] + q[1]) * cross;
+        }
+    }
+    Position::from([cx / (3.0 * a2), cy / (3.0 * a2)])
+}
+
+/// Geometry of tile polygons (exterior rings only for a single tile)
+fn shape_geometry(
+    polygons: Vec<PolygonType>,
+    geometry: ShapesGeometry,
+) -> GeometryValue {
+    match geometry {
+        ShapesGeometry::Polygon => match <[PolygonType; 1]>::try_from(polygons) {
+            Ok([coordinates]) => GeometryValue::Polygon { coordinates },
+            Err(coordinates) => GeometryValue::MultiPolygon { coordinates },
+        },
+        ShapesGeometry::Outline => {
+            match <[Vec<Position>; 1]>::try_from(
+                polygons.into_iter().flatten().collect::<Vec<_>>(),
+            ) {
+                Ok([coordinates]) => GeometryValue::LineString { coordinates },
+                Err(coordinates) => GeometryValue::MultiLineString { coordinates },
+            }
+        }
+        ShapesGeometry::Centroid => {
+            match <[Position; 1]>::try_from(
+                polygons.iter().map(polygon_centroid).collect::<Vec<_>>(),
+            ) {
+                Ok([coordinates]) => GeometryValue::Point { coordinates },
+                Err(coordinates) => GeometryValue::MultiPoint { coordinates },
+            }
+        }
+    }
+}
+
+fn geometry_bbox(geometry: &GeometryValue) -> Vec<f64> {
+    let points =
+        crate::gj::geojson_geometry_points_vec(Geometry::new(geometry.clone()));
+    points.iter().fold(
+        vec![
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ],
+        |b, p| {
+            let (x, y) = (p.as_slice()[0], p.as_slice()[1]);
+            vec![b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)]
+        },
+    )
+}
+
+/// Shapes as geojson features w/ dissolve/geometry options; features are
+/// passed to `emit` as they're built (all at once when dissolving)
+fn shape_features(
+    args: &ShapesArgs,
+    tiles: impl Iterator<Item = Result<TileWithProperties, UtilesCoreError>>,
+    mut emit: impl FnMut(Feature) -> UtilesResult<()>,
+) -> UtilesResult<()> {
+    let mercator = matches!(
+        args.project,
+        Some(ShapesProject {
+            geographic: false,
+            mercator: true,
+        })
+    );
+    let project = |polygons: Vec<PolygonType>| -> Vec<PolygonType> {
+        polygons
+            .into_iter()
+            .map(|rings| {
+                rings
+                    .into_iter()
+                    .map(|ring| {
+                        ring.into_iter()
+                            .map(|p| {
+                                let (mut x, mut y) = (p.as_slice()[0], p.as_slice()[1]);
+                                if mercator {
+                                    (x, y) = xy(x, y, None);
+                                }
+                                Position::from([
+                                    round_coord(x, args.precision),
+                                    round_coord(y, args.precision),
+                                ])
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    let feature =
+        |id: String, geometry: GeometryValue, properties: Map<String, Value>| {
+            let bbox = geometry_bbox(&geometry);
+            Feature {
+                bbox: Some(bbox),
+                geometry: Some(Geometry::new(geometry)),
+                id: Some(geojson::feature::Id::String(id)),
+                properties: Some(properties),
+                foreign_members: None,
+            }
+        };
+
+    if args.dissolve {
+        let tiles = tiles
+            .map(|t| t.map(|t| t.tile))
+            .collect::<Result<Vec<_>, _>>()?;
+        let polygons = project(dissolve_tiles(&tiles));
+        let mut properties = Map::new();
+        properties.insert("title".to_string(), Value::from("dissolved tiles"));
+        properties.insert("ntiles".to_string(), Value::from(tiles.len()));
+        if !polygons.is_empty() {
+            emit(feature(
+                "dissolved".to_string(),
+                shape_geometry(polygons, args.geometry),
+                properties,
+            ))?;
+        }
+        return Ok(());
+    }
+
+    let feature_options = FeatureOptions {
+        fid: None,
+        projection: if mercator {
+            Projection::Mercator
+        } else {
+            Projection::Geographic
+        },
+        props: None,
+        buffer: args.buffer,
+        precision: args.precision,
+    };
+    for tile_n_properties in tiles {
+        let tile_n_properties = tile_n_properties?;
+        let tile = tile_n_properties.tile;
+        let f = match &args.tms {
+            Some(tms) => tms.feature(&tile, &feature_options)?,
+            None => tile.feature(&feature_options)?,
+        };
+        let mut properties = f.properties;
+        properties.extend(tile_n_properties.properties.unwrap_or_default());
+        let ring = f
+            .geometry
+            .coordinates
+            .into_iter()
+            .flatten()
+            .map(Position::from)
+            .collect::<Vec<_>>();
+        let geometry = shape_geometry(vec![vec![ring]], args.geometry);
+        emit(feature(
+            tile_n_properties.id.unwrap_or(f.id),
+            geometry,
+            properties,
+        ))?;
+    }
+    Ok(())
+}
+
+/// Write shapes w/ dissolve/geometry/format options
+fn shapes_geometries(
+    args: &ShapesArgs,
+    tiles: impl Iterator<Item = Result<TileWithProperties, UtilesCoreError>>,
+) -> UtilesResult<()> {
+    if args.tms.is_some() && args.project.as_ref().is_some_and(|p| p.mercator) {
+        return Err(UtilesError::AdHoc(
+            "--tms w/ --mercator only supported for geojson tile polygons".to_string(),
+        ));
+    }
+    let stdout = io::stdout();
+    let mut buf = BufWriter::with_capacity(32 * 1024, stdout.lock());
+    match args.format {
+        // feature collections and flatgeobuf headers need every feature
+        ShapesFormat::Geojson if args.collect => {
+            let mut features = Vec::new();
+            shape_features(args, tiles, |f| {
+                features.push(f);
+                Ok(())
+            })?;
+            let fc = GeoJson::FeatureCollection(geojson::FeatureCollection {
+                bbox: None,
+                features,
+                foreign_members: None,
+            });
+            writeln!(buf, "{fc}")?;
+        }
+        ShapesFormat::Fgb => {
+            let mut features = Vec::new();
+            shape_features(args, tiles, |f| {
+                features.push(f);
+                Ok(())
+            })?;
+            let epsg = if args.project.as_ref().is_some_and(|p| p.mercator) {
+                3857
+            } else {
+                4326
+            };
+            write_fgb(&mut buf, "tiles", &features, epsg)?;
+        }
+        ShapesFormat::Geojson => {
+            shape_features(args, tiles, |f| {
+                if args.seq {
+                    writeln!(buf, "\x1e")?;
+                }
+                writeln!(buf, "{}", serde_json::to_string(&f)?)?;
+                Ok(())
+            })?;
+        }
+        ShapesFormat::Wkt => {
+            shape_features(args, tiles, |f| {
+                if let Some(geometry) = &f.geometry {
+                    writeln!(buf, "{}", geometry_wkt(&geometry.value))?;
+                }
+                Ok(())
+            })?;
+        }
+    }
+    buf.flush()?;
+    Ok(())
+}
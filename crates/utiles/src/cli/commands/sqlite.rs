@@ -1,11 +1,22 @@
-use tracing::{debug, info, trace};
+use std::path::Path;
 
-use crate::cli::args::{AnalyzeArgs, SqliteHeaderArgs};
-use crate::errors::UtilesResult;
+use serde::Serialize;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, info, trace, warn};
+
+use crate::cli::args::{
+    AnalyzeArgs, SqliteCheckArgs, SqliteHeaderArgs, SqliteSalvageArgs,
+};
+use crate::errors::{UtilesError, UtilesResult};
 use crate::fs_async::read_nbytes;
+use crate::mbt::query::query_mbtiles_type;
+use crate::mbt::{
+    MbtStreamWriterSync, MbtType, MbtWriterStats, Mbtiles, SalvageTableStats,
+    open_salvage_src, salvage_metadata, salvage_tiles,
+};
 use crate::sqlite::{
-    AsyncSqliteConn, Sqlike3Async, SqliteDbAsyncClient, SqliteHeader,
-    analysis_limit_set,
+    AsyncSqliteConn, InsertStrategy, Sqlike3Async, SqliteDbAsyncClient, SqliteHeader,
+    analysis_limit_set, sqlite_check,
 };
 
 pub(crate) async fn analyze_main(args: &AnalyzeArgs) -> UtilesResult<()> {
@@ -42,3 +53,89 @@ pub(crate) async fn header_main(args: &SqliteHeaderArgs) -> UtilesResult<()> {
     println!("{json_str}");
     Ok(())
 }
+
+pub(crate) async fn check_main(args: &SqliteCheckArgs) -> UtilesResult<()> {
+    let fspath = args.common.filepath.clone();
+    let (quick, max_errors) = (args.quick, args.max_errors);
+    let check = tokio::task::spawn_blocking(move || {
+        sqlite_check(Path::new(&fspath), quick, max_errors)
+    })
+    .await??;
+    for warning in &check.warnings {
+        warn!("{warning}");
+    }
+    let json_str = if args.common.min {
+        serde_json::to_string(&check)
+    } else {
+        serde_json::to_string_pretty(&check)
+    }?;
+    println!("{json_str}");
+    if check.ok {
+        Ok(())
+    } else {
+        Err(UtilesError::AdHoc(format!(
+            "sqlite check failed: {}",
+            args.common.filepath
+        )))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SalvageSummary {
+    src: String,
+    dst: String,
+    mbtype: MbtType,
+    metadata: SalvageTableStats,
+    tiles: SalvageTableStats,
+    total_time_ms: u128,
+}
+
+pub(crate) async fn salvage_main(args: &SqliteSalvageArgs) -> UtilesResult<()> {
+    let start_time = std::time::Instant::now();
+    let src = args.common.filepath.clone();
+    let src_conn = open_salvage_src(&src)?;
+    let mbt_type = query_mbtiles_type(&src_conn)?;
+    let (metadata, metadata_stats) = salvage_metadata(&src_conn)?;
+    info!(
+        "salvaging {src} ({mbt_type}) -> {} ~ {} metadata rows",
+        args.dst,
+        metadata.len()
+    );
+    let dst = Mbtiles::open_new(&args.dst, Some(mbt_type))?;
+    dst.metadata_set_many(&metadata)?;
+
+    let (tx, rx) = tokio::sync::mpsc::channel(100);
+    let mut writer = MbtStreamWriterSync {
+        stream: ReceiverStream::new(rx),
+        mbt: dst,
+        on_conflict: InsertStrategy::Ignore,
+        stats: MbtWriterStats::default(),
+    };
+    let batch_size = args.batch_size;
+    let salvage_future = tokio::task::spawn_blocking(move || {
+        salvage_tiles(&src_conn, &mbt_type, batch_size, |tile, tile_data| {
+            if let Err(e) = tx.blocking_send((tile, tile_data, None).into()) {
+                warn!("send_res: {:?}", e);
+            }
+        })
+    });
+    let (salvage_res, writer_res) = tokio::join!(salvage_future, writer.write());
+    let tiles_stats = salvage_res??;
+    writer_res?;
+    debug!("writer stats: {:?}", writer.stats);
+    let summary = SalvageSummary {
+        src,
+        dst: args.dst.clone(),
+        mbtype: mbt_type,
+        metadata: metadata_stats,
+        tiles: tiles_stats,
+        total_time_ms: start_time.elapsed().as_millis(),
+    };
+    let json_str = if args.common.min {
+        serde_json::to_string(&summary)
+    } else {
+        serde_json::to_string_pretty(&summary)
+    }?;
+    println!("{json_str}");
+    Ok(())
+}
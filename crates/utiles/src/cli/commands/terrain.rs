@@ -0,0 +1,268 @@
+use rusqlite::OptionalExtension;
+use serde::Serialize;
+use tracing::info;
+use utiles_core::Tile;
+use utiles_core::resolution::{lnglat2tile_pixel, meters_per_pixel, tile_pixel2lnglat};
+use utiles_core::terrain::TerrainEncoding;
+use utiles_core::tile_type::{TileFormat, tiletype};
+
+use crate::cli::args::{
+    TerrainCommands, TerrainConvertArgs, TerrainEncodingOption, TerrainHillshadeArgs,
+    TerrainSampleArgs,
+};
+use crate::cli::commands::tile_jobs::{TileJob, TileJobs, resolve_jobs};
+use crate::errors::{UtilesError, UtilesResult};
+use crate::img::{
+    ElevationTile, HillshadeOptions, convert_terrain_tile, encode_tile, hillshade,
+};
+use crate::mbt::{Mbtiles, MbtilesAsync, MbtilesClientAsync};
+use crate::sqlite::AsyncSqliteConn;
+
+#[derive(Debug, Serialize)]
+struct TerrainSample {
+    lnglat: [f64; 2],
+    zoom: u8,
+    tile: Tile,
+    pixel: [u32; 2],
+    encoding: String,
+    elevation: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct TerrainSummary {
+    src: String,
+    dst: String,
+    ntiles: u64,
+    nfailed: u64,
+    total_time_ms: u128,
+}
+
+/// Encoding from the cli option, else the metadata `encoding`, else
+/// Terrain-RGB
+async fn terrain_encoding(
+    mbt: &MbtilesClientAsync,
+    option: Option<TerrainEncodingOption>,
+) -> UtilesResult<TerrainEncoding> {
+    if let Some(encoding) = option {
+        return Ok(encoding.into());
+    }
+    let encoding = mbt.query_metadata_value("encoding").await?;
+    Ok(encoding
+        .and_then(|e| e.parse::<TerrainEncoding>().ok())
+        .unwrap_or_default())
+}
+
+async fn terrain_sample(args: &TerrainSampleArgs) -> UtilesResult<()> {
+    let mbt = MbtilesClientAsync::open_existing(args.common.filepath.as_str()).await?;
+    mbt.assert_mbtiles().await?;
+    let encoding = terrain_encoding(&mbt, args.encoding).await?;
+    let zoom = match args.zoom {
+        Some(z) => z,
+        None => mbt
+            .query_minzoom_maxzoom()
+            .await?
+            .map(|minmax| minmax.maxzoom)
+            .ok_or_else(|| UtilesError::AdHoc("no tiles to sample".to_string()))?,
+    };
+    let (lng, lat) = (args.lnglat.lng(), args.lnglat.lat());
+    // tile-size 1 -> pixel position as a fraction of the tile
+    let (tile, fx, fy) = lnglat2tile_pixel(lng, lat, zoom, 1)?;
+    let data = mbt.query_tile(&tile).await?;
+    let (pixel, elevation) = match data {
+        Some(data) => {
+            let elevation = ElevationTile::decode(&data, encoding)?;
+            let px = (fx * f64::from(elevation.width)).floor() as i64;
+            let py = (fy * f64::from(elevation.height)).floor() as i64;
+            ([px as u32, py as u32], Some(elevation.get(px, py)))
+        }
+        None => ([0, 0], None),
+    };
+    let sample = TerrainSample {
+        lnglat: [lng, lat],
+        zoom,
+        tile,
+        pixel,
+        encoding: encoding.to_string(),
+        elevation,
+    };
+    let out_str = if args.common.min {
+        serde_json::to_string(&sample)
+    } else {
+        serde_json::to_string_pretty(&sample)
+    }?;
+    println!("{out_str}");
+    Ok(())
+}
+
+/// Format of the src tiles from the metadata `format`, else the first tile
+async fn src_format(mbt: &MbtilesClientAsync) -> UtilesResult<Option<TileFormat>> {
+    if let Some(format) = mbt
+        .query_metadata_format()
+        .await?
+        .as_deref()
+        .and_then(TileFormat::try_parse)
+    {
+        return Ok(Some(format));
+    }
+    let first_tile: Option<Vec<u8>> = mbt
+        .conn(|c| {
+            c.query_row("SELECT tile_data FROM tiles LIMIT 1", [], |row| row.get(0))
+                .optional()
+        })
+        .await?;
+    Ok(first_tile.map(|data| tiletype(&data).format))
+}
+
+/// Write `map_tile(tile, data)` of every tile of `src` to a new mbtiles
+/// w/ the src metadata updated w/ `metadata` (and w/o the `drop_metadata`
+/// keys)
+async fn terrain_map_tiles<F>(
+    src: &MbtilesClientAsync,
+    dst: &str,
+    metadata: &[(&str, String)],
+    drop_metadata: &[&str],
+    jobs: Option<u8>,
+    quiet: bool,
+    map_tile: F,
+) -> UtilesResult<TerrainSummary>
+where
+    F: Fn(Tile, &[u8]) -> UtilesResult<Vec<u8>> + Send + Sync + 'static,
+{
+    let start_time = std::time::Instant::now();
+    let total_count = src.tiles_count().await?;
+    let dst_mbtiles = Mbtiles::open_new(dst, None)?;
+    let src_metadata = src
+        .metadata_rows()
+        .await?
+        .into_iter()
+        .filter(|row| !drop_metadata.contains(&row.name.as_str()))
+        .collect::<Vec<_>>();
+    dst_mbtiles.metadata_set_many(&src_metadata)?;
+    for (name, value) in metadata {
+        dst_mbtiles.metadata_set(name, value)?;
+    }
+    let tile_jobs = TileJobs {
+        name: "terrain",
+        total: total_count as u64,
+        jobs: resolve_jobs(jobs),
+        quiet,
+    };
+    let output = tile_jobs
+        .run(
+            src.tiles_stream(None)?,
+            Some(dst_mbtiles),
+            move |(tile, tile_data): (Tile, Vec<u8>)| {
+                let data = map_tile(tile, &tile_data)?;
+                Ok(TileJob::new(vec![(tile, data, None).into()], ()))
+            },
+            0_u64,
+            |ntiles, (), _pb| *ntiles += 1,
+        )
+        .await?;
+    Ok(TerrainSummary {
+        src: src.filepath().to_string(),
+        dst: dst.to_string(),
+        ntiles: output.acc,
+        nfailed: output.nfailed,
+        total_time_ms: start_time.elapsed().as_millis(),
+    })
+}
+
+async fn terrain_convert(args: &TerrainConvertArgs) -> UtilesResult<TerrainSummary> {
+    let mbt = MbtilesClientAsync::open_existing(args.common.filepath.as_str()).await?;
+    mbt.assert_mbtiles().await?;
+    let from = terrain_encoding(&mbt, args.from).await?;
+    let to: TerrainEncoding = args.to.into();
+    let format: Option<TileFormat> = args.format.map(Into::into);
+    if format == Some(TileFormat::Jpg) {
+        return Err(UtilesError::Unsupported(
+            "jpg elevation tiles (lossy)".to_string(),
+        ));
+    }
+    // w/o --format the tiles are written in the src format
+    if format.is_none() && src_format(&mbt).await? == Some(TileFormat::Jpg) {
+        return Err(UtilesError::Unsupported(
+            "jpg elevation tiles (lossy); use --format png|webp".to_string(),
+        ));
+    }
+    info!("terrain convert ~ {from} -> {to} ~ format: {format:?}");
+    let mut metadata = vec![("encoding", to.style_encoding().to_string())];
+    if let Some(format) = format {
+        metadata.push(("format", format.to_string()));
+    }
+    terrain_map_tiles(
+        &mbt,
+        &args.dst,
+        &metadata,
+        &[],
+        args.jobs,
+        args.quiet,
+        move |_, data| {
+            let format = format.unwrap_or_else(|| tiletype(data).format);
+            convert_terrain_tile(data, from, to, format)
+        },
+    )
+    .await
+}
+
+/// Hillshade a tile w/ the ground resolution at the tile center
+fn hillshade_tile(
+    tile: Tile,
+    data: &[u8],
+    encoding: TerrainEncoding,
+    options: &HillshadeOptions,
+    format: TileFormat,
+) -> UtilesResult<Vec<u8>> {
+    let elevation = ElevationTile::decode(data, encoding)?;
+    let center = tile_pixel2lnglat(&tile, 0.5, 0.5, 1);
+    let cell_size = meters_per_pixel(center.lat(), f64::from(tile.z), elevation.width);
+    let img = hillshade(&elevation, cell_size, options);
+    encode_tile(&image::DynamicImage::ImageLuma8(img), format)
+}
+
+async fn terrain_hillshade(
+    args: &TerrainHillshadeArgs,
+) -> UtilesResult<TerrainSummary> {
+    let mbt = MbtilesClientAsync::open_existing(args.common.filepath.as_str()).await?;
+    mbt.assert_mbtiles().await?;
+    let encoding = terrain_encoding(&mbt, args.encoding).await?;
+    let options = args.hillshade_options();
+    let format: TileFormat = args.format.into();
+    info!("terrain hillshade ~ {encoding} ~ {options:?} ~ format: {format}");
+    // hillshade tiles are not elevation tiles (no `encoding`)
+    terrain_map_tiles(
+        &mbt,
+        &args.dst,
+        &[("format", format.to_string())],
+        &["encoding"],
+        args.jobs,
+        args.quiet,
+        move |tile, data| hillshade_tile(tile, data, encoding, &options, format),
+    )
+    .await
+}
+
+pub(crate) async fn terrain_main(cmd: &TerrainCommands) -> UtilesResult<()> {
+    let (summary, min) = match cmd {
+        TerrainCommands::Sample(args) => return terrain_sample(args).await,
+        TerrainCommands::Convert(args) => {
+            (terrain_convert(args).await?, args.common.min)
+        }
+        TerrainCommands::Hillshade(args) => {
+            (terrain_hillshade(args).await?, args.common.min)
+        }
+    };
+    let out_str = if min {
+        serde_json::to_string(&summary)
+    } else {
+        serde_json::to_string_pretty(&summary)
+    }?;
+    println!("{out_str}");
+    if summary.nfailed > 0 {
+        return Err(UtilesError::AdHoc(format!(
+            "terrain: {} tiles failed (see warnings above)",
+            summary.nfailed
+        )));
+    }
+    Ok(())
+}
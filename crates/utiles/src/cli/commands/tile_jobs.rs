@@ -0,0 +1,246 @@
+//! Tile jobs ~ pipeline shared by the commands that map every tile of a db
+//! (optimize, webpify, resize, composite, terrain and raster-stats)
+//!
+//! Items are mapped concurrently (`jobs`) on the blocking thread pool, the
+//! mapped tiles are written to the dst db and the progress value of every
+//! item is folded into the command summary. Failed items are logged and
+//! counted; a failed item may provide fallback tiles to write instead (eg
+//! the original tile).
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use tokio::join;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
+use utiles_core::Tile;
+
+use crate::errors::{UtilesError, UtilesResult};
+use crate::mbt::{MbtStreamWriterSync, MbtWriterStats, MbtWriterStreamData, Mbtiles};
+use crate::sqlite::InsertStrategy;
+
+/// Number of concurrent jobs for a `--jobs` option (0 = ncpus; default 4)
+pub(crate) fn resolve_jobs(jobs: Option<u8>) -> usize {
+    match jobs {
+        Some(0) => num_cpus::get(),
+        Some(j) => usize::from(j),
+        None => 4,
+    }
+}
+
+/// Item mapped by a tile job
+pub(crate) trait TileJobItem: Send + 'static {
+    /// Tile of the item (for logging failures)
+    fn tile(&self) -> Tile;
+
+    /// Progress bar increment of the item
+    fn weight(&self) -> u64 {
+        1
+    }
+}
+
+impl TileJobItem for (Tile, Vec<u8>) {
+    fn tile(&self) -> Tile {
+        self.0
+    }
+}
+
+/// Mapped item ~ tiles to write and the progress value of the item
+pub(crate) struct TileJob<R> {
+    pub tiles: Vec<MbtWriterStreamData>,
+    pub progress: R,
+}
+
+impl<R> TileJob<R> {
+    pub(crate) const fn new(tiles: Vec<MbtWriterStreamData>, progress: R) -> Self {
+        Self { tiles, progress }
+    }
+}
+
+/// Failed item w/ the tiles to write in place of the mapped tiles
+pub(crate) struct TileJobFailed {
+    pub error: UtilesError,
+    pub fallback: Vec<MbtWriterStreamData>,
+}
+
+impl From<UtilesError> for TileJobFailed {
+    fn from(error: UtilesError) -> Self {
+        Self {
+            error,
+            fallback: vec![],
+        }
+    }
+}
+
+pub(crate) type TileJobResult<R> = Result<TileJob<R>, TileJobFailed>;
+
+enum TileJobProgress<R> {
+    Done(R, u64),
+    Failed(u64),
+}
+
+pub(crate) struct TileJobsOutput<S> {
+    /// Folded progress values
+    pub acc: S,
+    /// Number of failed items
+    pub nfailed: u64,
+    /// Stats of the dst writer (`None` w/o a dst)
+    pub writer_stats: Option<MbtWriterStats>,
+    /// The dst db (eg for updating the metadata afterwards)
+    pub dst: Option<Mbtiles>,
+}
+
+impl<S> TileJobsOutput<S> {
+    /// Error if any item failed (eg tiles missing from the dst)
+    pub(crate) fn check_failed(&self, name: &str) -> UtilesResult<()> {
+        if self.nfailed > 0 {
+            return Err(UtilesError::AdHoc(format!(
+                "{name}: {} tiles failed (see warnings above)",
+                self.nfailed
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct TileJobs {
+    /// Name of the command (progress bar message and log prefix)
+    pub name: &'static str,
+    /// Progress bar length
+    pub total: u64,
+    pub jobs: usize,
+    pub quiet: bool,
+}
+
+impl TileJobs {
+    fn progress_bar(&self) -> ProgressBar {
+        let pb = ProgressBar::new(self.total);
+        if self.quiet {
+            pb.set_draw_target(ProgressDrawTarget::hidden());
+        }
+        match ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
+        ) {
+            Err(e) => {
+                warn!("pb_style error: {:?}", e);
+            }
+            Ok(s) => {
+                pb.set_style(s);
+            }
+        }
+        pb.set_message(self.name);
+        pb.enable_steady_tick(Duration::from_millis(100));
+        pb
+    }
+
+    /// Map every item w/ `map`, write the mapped tiles to `dst` and fold
+    /// the progress values into `init` w/ `fold`
+    pub(crate) async fn run<I, R, S, F, G>(
+        self,
+        items: impl Stream<Item = I> + Send + 'static,
+        dst: Option<Mbtiles>,
+        map: F,
+        init: S,
+        mut fold: G,
+    ) -> UtilesResult<TileJobsOutput<S>>
+    where
+        I: TileJobItem,
+        R: Send + 'static,
+        S: Send + 'static,
+        F: Fn(I) -> TileJobResult<R> + Send + Sync + 'static,
+        G: FnMut(&mut S, R, &ProgressBar) + Send + 'static,
+    {
+        let name = self.name;
+        let jobs = self.jobs;
+        let map = Arc::new(map);
+        let (tx_progress, mut rx_progress) = tokio::sync::mpsc::channel(100);
+        let (tx_writer, rx_writer) = tokio::sync::mpsc::channel(100);
+        let proc_future = tokio::spawn(async move {
+            items
+                .for_each_concurrent(jobs, |item| {
+                    let tx_writer = tx_writer.clone();
+                    let tx_progress = tx_progress.clone();
+                    let map = map.clone();
+                    async move {
+                        let tile = item.tile();
+                        let weight = item.weight();
+                        let res = tokio::task::spawn_blocking(move || map(item)).await;
+                        let (tiles, progress) = match res {
+                            Err(je) => {
+                                warn!("{name} {tile}: join-error: {:?}", je);
+                                (vec![], TileJobProgress::Failed(weight))
+                            }
+                            Ok(Err(failed)) => {
+                                warn!("{name} {tile}: {}", failed.error);
+                                (failed.fallback, TileJobProgress::Failed(weight))
+                            }
+                            Ok(Ok(job)) => {
+                                (job.tiles, TileJobProgress::Done(job.progress, weight))
+                            }
+                        };
+                        for data in tiles {
+                            if let Err(e) = tx_writer.send(data).await {
+                                warn!("send_res: {:?}", e);
+                            }
+                        }
+                        if let Err(e) = tx_progress.send(progress).await {
+                            warn!("progress send_res: {:?}", e);
+                        }
+                    }
+                })
+                .await;
+        });
+
+        let pb = self.progress_bar();
+        let progress_future = tokio::spawn(async move {
+            let mut acc = init;
+            let (mut ndone, mut nfailed) = (0_u64, 0_u64);
+            while let Some(progress) = rx_progress.recv().await {
+                match progress {
+                    TileJobProgress::Done(value, weight) => {
+                        ndone += 1;
+                        pb.inc(weight);
+                        fold(&mut acc, value, &pb);
+                    }
+                    TileJobProgress::Failed(weight) => {
+                        nfailed += 1;
+                        pb.inc(weight);
+                    }
+                }
+            }
+            pb.finish_with_message(format!("{name} ~ {ndone} done, {nfailed} failed"));
+            (acc, nfailed)
+        });
+
+        let writer_future = async move {
+            if let Some(mbt) = dst {
+                let mut writer = MbtStreamWriterSync {
+                    stream: ReceiverStream::new(rx_writer),
+                    mbt,
+                    on_conflict: InsertStrategy::None,
+                    stats: MbtWriterStats::default(),
+                };
+                writer.write().await?;
+                Ok::<_, UtilesError>(Some((writer.stats, writer.mbt)))
+            } else {
+                drop(rx_writer);
+                Ok(None)
+            }
+        };
+        let (proc_res, writer_res, progress_res) =
+            join!(proc_future, writer_future, progress_future);
+        proc_res?;
+        let (writer_stats, dst) = writer_res?.unzip();
+        let (acc, nfailed) = progress_res?;
+        if nfailed > 0 {
+            warn!("{name}: {nfailed} tiles failed (see warnings above)");
+        }
+        Ok(TileJobsOutput {
+            acc,
+            nfailed,
+            writer_stats,
+            dst,
+        })
+    }
+}
@@ -1,18 +1,19 @@
 use tracing::error;
-use utiles_core::{Tile, TileLike, bounding_tile};
+use utiles_core::{Tile, TileId, TileLike, bounding_tile};
 
 use crate::TileStringFormatter;
-use crate::cli::args::TileFmtArgs;
+use crate::cli::args::{
+    BoundingTileArgs, TileFmtArgs, TileIdArgs, TileInputFormat, TmsTileFmtArgs,
+};
 use crate::cli::stdinterator_filter;
-use crate::errors::UtilesResult;
+use crate::cli::tile_input::{TileLineParser, stdin_tiles, stdin_tms_tiles};
+use crate::errors::{UtilesError, UtilesResult};
 use crate::gj::parsing::parse_bbox_geojson;
 
 pub(crate) fn fmtstr_main(args: TileFmtArgs) -> UtilesResult<()> {
     let tile_formatter = TileStringFormatter::from(&args.fmtopts);
-    let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
-    for line_res in lines {
-        let line = line_res?;
-        let tile = Tile::from_json(&line)?;
+    for tile in stdin_tiles(args.inargs) {
+        let tile = tile?;
         let tile_str = tile_formatter.fmt_tile(&tile);
         let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
         println!("{rs}{tile_str}");
@@ -20,13 +21,14 @@ pub(crate) fn fmtstr_main(args: TileFmtArgs) -> UtilesResult<()> {
     Ok(())
 }
 
-pub(crate) fn neighbors_main(args: TileFmtArgs) -> UtilesResult<()> {
-    let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
-    for line_res in lines {
-        let line = line_res?;
-        let tile = Tile::from_json(&line)?;
+pub(crate) fn neighbors_main(args: TmsTileFmtArgs) -> UtilesResult<()> {
+    for tile in stdin_tms_tiles(args.inargs, args.tms.clone()) {
+        let tile = tile?;
         // TODO: add --wrapx flag?
-        let neighbors = tile.neighbors(false);
+        let neighbors = match &args.tms {
+            Some(tms) => tms.neighbors(&tile)?,
+            None => tile.neighbors(false),
+        };
         for neighbor in neighbors {
             let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
             println!("{}{}", rs, neighbor.json_arr());
@@ -35,28 +37,35 @@ pub(crate) fn neighbors_main(args: TileFmtArgs) -> UtilesResult<()> {
     Ok(())
 }
 
-pub(crate) fn bounding_tile_main(args: TileFmtArgs) -> UtilesResult<()> {
+pub(crate) fn bounding_tile_main(args: BoundingTileArgs) -> UtilesResult<()> {
     let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
     for line_res in lines {
         let line = line_res?;
         let bbox = parse_bbox_geojson(&line)?;
-        let tile = bounding_tile(bbox, None)?;
+        let tile = match &args.tms {
+            Some(tms) => tms.bounding_tile(bbox)?,
+            None => bounding_tile(bbox, None)?,
+        };
         let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
         println!("{}{}", rs, tile.json_arr());
     }
     Ok(())
 }
 
+/// Return true if the line is a tile id (quadkey, pmtileid, ...) rather than
+/// a tile; only auto mode lines of digits are ids
+fn is_id_line(parser: &TileLineParser, line: &str) -> bool {
+    parser.format() == TileInputFormat::Auto
+        && !line.is_empty()
+        && line.bytes().all(|b| b.is_ascii_digit())
+}
+
 pub(crate) fn pmtileid_main(args: TileFmtArgs) -> UtilesResult<()> {
     let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
+    let mut parser = TileLineParser::new(args.inargs.input_format);
     for line in lines {
-        // if the line bgins w '[' treat as tile
         let lstr = line?.trim_matches(|c| c == '"' || c == '\'').to_string();
-        if lstr.starts_with('[') || lstr.starts_with('{') {
-            // treat as tile
-            let tile = Tile::from_json(&lstr)?;
-            println!("{}", tile.pmtileid());
-        } else {
+        if is_id_line(&parser, &lstr) {
             // treat as pmtileid
             let pmid = lstr.parse::<u64>();
             if let Ok(pmid) = pmid {
@@ -66,38 +75,82 @@ pub(crate) fn pmtileid_main(args: TileFmtArgs) -> UtilesResult<()> {
                 error!("Invalid pmtileid: {lstr}");
                 println!("Invalid pmtileid: {lstr}");
             }
+        } else {
+            // treat as tile(s)
+            for tile in parser.parse_line(&lstr)? {
+                println!("{}", tile.pmtileid());
+            }
         }
     }
     Ok(())
 }
 
-pub(crate) fn quadkey_main(args: TileFmtArgs) -> UtilesResult<()> {
+/// Convert tiles to/from tile-ids of the id family `T`
+pub(crate) fn tileid_main<T: TileId>(args: TileIdArgs) -> UtilesResult<()> {
     let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
+    let mut parser = TileLineParser::new(args.inargs.input_format);
     for line in lines {
-        // if the line begins w/ '['/'{' treat as json-tile
-        // otherwise treat as quadkey
         let lstr = line?
             .trim_matches(|c| c == ' ' || c == '"' || c == '\'')
             .to_string();
-        let maybe_first_char = lstr.chars().next();
-        if let Some(first_char) = maybe_first_char {
-            match first_char {
-                '[' | '{' => {
-                    // treat as tile
-                    let tile = Tile::from_json(&lstr)?;
-                    println!("{}", tile.quadkey());
-                }
-                _ => {
-                    // treat as quadkey
-                    let qk = lstr;
-                    let tile = Tile::from_quadkey(&qk);
-                    if let Ok(tile) = tile {
-                        println!("{}", tile.json_arr());
-                    } else {
-                        error!("Invalid quadkey: {qk}");
-                        println!("Invalid quadkey: {qk}");
-                    }
+        if !is_id_line(&parser, &lstr) {
+            // treat as tile(s)
+            for tile in parser.parse_line(&lstr)? {
+                let id = if args.per_zoom {
+                    T::zoom_id(tile.x, tile.y, tile.z)
+                } else {
+                    T::global_id(tile.x, tile.y, tile.z)
+                };
+                println!("{id}");
+            }
+        } else if let Ok(id) = lstr.parse::<u64>() {
+            let (x, y, z) = if args.per_zoom {
+                let z = args.zoom.ok_or_else(|| {
+                    UtilesError::AdHoc(format!(
+                        "--zoom required to convert per-zoom {} ids to tiles",
+                        T::NAME
+                    ))
+                })?;
+                if id >= 1u64 << (2 * u32::from(z)) {
+                    error!("Invalid {} id for zoom {z}: {lstr}", T::NAME);
+                    println!("Invalid {} id for zoom {z}: {lstr}", T::NAME);
+                    continue;
                 }
+                let (x, y) = T::from_zoom_id(id, z);
+                (x, y, z)
+            } else {
+                T::from_global_id(id)
+            };
+            println!("{}", Tile { x, y, z }.json_arr());
+        } else {
+            error!("Invalid {} id: {lstr}", T::NAME);
+            println!("Invalid {} id: {lstr}", T::NAME);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn quadkey_main(args: TileFmtArgs) -> UtilesResult<()> {
+    let lines = stdinterator_filter::stdin_filtered(args.inargs.input);
+    let mut parser = TileLineParser::new(args.inargs.input_format);
+    for line in lines {
+        // quadkeys are digit lines; otherwise treat as tile(s)
+        let lstr = line?
+            .trim_matches(|c| c == ' ' || c == '"' || c == '\'')
+            .to_string();
+        if is_id_line(&parser, &lstr) {
+            // treat as quadkey
+            let qk = lstr;
+            let tile = Tile::from_quadkey(&qk);
+            if let Ok(tile) = tile {
+                println!("{}", tile.json_arr());
+            } else {
+                error!("Invalid quadkey: {qk}");
+                println!("Invalid quadkey: {qk}");
+            }
+        } else {
+            for tile in parser.parse_line(&lstr)? {
+                println!("{}", tile.quadkey());
             }
         }
     }
@@ -4,7 +4,7 @@ use std::io::{BufWriter, Write};
 use tracing::{debug, error};
 use utiles_core::parsing::parse_bbox_ext;
 use utiles_core::zoom::ZoomOrZooms;
-use utiles_core::{TileStringFormatter, tiles};
+use utiles_core::{Tile, TileStringFormatter, tiles};
 
 use crate::cli::args::TilesArgs;
 use crate::cli::stdinterator_filter::stdin_filtered;
@@ -40,14 +40,20 @@ pub(crate) async fn tiles_main(
                     })
             })
         })
-        .map(|parse_result| {
-            parse_result.map(|b| {
-                tiles(
-                    (b.west, b.south, b.east, b.north),
-                    ZoomOrZooms::Zoom(args.zoom),
-                )
-            })
-        });
+        .map(
+            |parse_result| -> UtilesResult<Box<dyn Iterator<Item = Tile>>> {
+                let b = parse_result?;
+                match &args.tms {
+                    Some(tms) => {
+                        Ok(Box::new(tms.tiles(b, ZoomOrZooms::Zoom(args.zoom))?))
+                    }
+                    None => Ok(Box::new(tiles(
+                        (b.west, b.south, b.east, b.north),
+                        ZoomOrZooms::Zoom(args.zoom),
+                    ))),
+                }
+            },
+        );
     let formatter = TileStringFormatter::from(&args.fmtopts);
     let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
     for tiles_iterator in tiles_iterators {
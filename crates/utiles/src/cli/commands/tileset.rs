@@ -0,0 +1,97 @@
+use std::io;
+use std::io::{BufWriter, Write};
+
+use tracing::debug;
+use utiles_core::{Tile, TileSet, TileStringFormatter, TileZBox};
+
+use crate::cli::args::{TilesetOp, TilesetOpArgs, TilesetOutput};
+use crate::cli::stdinterator_filter::stdin_filtered;
+use crate::errors::{UtilesError, UtilesResult};
+
+/// Load a tile-set from tiles, `TileZBox` json lines or tile-set json
+fn load_tileset(input: &str) -> UtilesResult<TileSet> {
+    let lines = stdin_filtered(Some(input.to_string()))
+        .collect::<Result<Vec<String>, io::Error>>()?;
+    let content = lines.join("\n");
+    if content.trim_start().starts_with('{')
+        && let Ok(tileset) = TileSet::from_json(&content)
+    {
+        return Ok(tileset);
+    }
+    let mut tileset = TileSet::new();
+    let mut tiles: Vec<Tile> = Vec::new();
+    for line in &lines {
+        let line = line.trim();
+        if line.starts_with('{')
+            && let Ok(zbox) = serde_json::from_str::<TileZBox>(line)
+        {
+            tileset.insert_zbox(&zbox);
+        } else {
+            let tile = Tile::from_json(line).map_err(|e| {
+                UtilesError::ParsingError(format!(
+                    "invalid tile-set input line ({input}): {line} ~ {e}"
+                ))
+            })?;
+            tiles.push(tile);
+        }
+    }
+    tileset.extend(tiles);
+    debug!(
+        "loaded tile-set {input}: {} tiles, {} ranges",
+        tileset.len(),
+        tileset.nranges()
+    );
+    Ok(tileset)
+}
+
+pub(crate) fn tileset_main(op: TilesetOp, args: &TilesetOpArgs) -> UtilesResult<()> {
+    if args.inputs.iter().filter(|input| *input == "-").count() > 1 {
+        return Err(UtilesError::AdHoc(
+            "only one tile-set input can be read from stdin".to_string(),
+        ));
+    }
+    let mut tilesets = args.inputs.iter().map(|input| load_tileset(input));
+    let mut result = tilesets
+        .next()
+        .ok_or_else(|| UtilesError::AdHoc("no tile-set inputs".to_string()))??;
+    for tileset in tilesets {
+        let tileset = tileset?;
+        result = match op {
+            TilesetOp::Union => result.union(&tileset),
+            TilesetOp::Intersect => result.intersection(&tileset),
+            TilesetOp::Diff => result.difference(&tileset),
+        };
+    }
+
+    let mut stdout = io::stdout();
+    let lock = stdout.lock();
+    let mut buf = BufWriter::with_capacity(32 * 1024, lock);
+    let rs = if args.fmtopts.seq { "\x1e\n" } else { "" };
+    match args.output {
+        TilesetOutput::Tiles => {
+            let formatter = TileStringFormatter::from(&args.fmtopts);
+            for tile in result.iter() {
+                let tile_str = formatter.fmt_tile(&tile);
+                buf.write_all(format!("{rs}{tile_str}\n").as_bytes())?;
+            }
+        }
+        TilesetOutput::Json => {
+            writeln!(buf, "{}", result.to_json()?)?;
+        }
+        TilesetOutput::Ranges => {
+            for zbox in result.zboxes() {
+                let zbox_str = serde_json::to_string(&zbox)?;
+                buf.write_all(format!("{rs}{zbox_str}\n").as_bytes())?;
+            }
+        }
+        TilesetOutput::Sql => {
+            writeln!(buf, "{}", result.mbtiles_sql_where())?;
+        }
+        TilesetOutput::Count => {
+            writeln!(buf, "{}", result.len())?;
+        }
+    }
+    buf.flush()?;
+    stdout.flush()?;
+    Ok(())
+}
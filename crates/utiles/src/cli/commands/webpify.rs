@@ -1,18 +1,86 @@
-use std::time::Duration;
-
-use futures::StreamExt;
-use indicatif::ProgressStyle;
-use tokio::join;
-use tokio_stream::wrappers::ReceiverStream;
+use serde::Serialize;
 use tracing::{info, warn};
+use utiles_core::Tile;
 
 use crate::UtilesResult;
 use crate::cli::args::WebpifyArgs;
-use crate::img::webpify_image;
-use crate::mbt::{
-    MbtStreamWriterSync, MbtWriterStats, Mbtiles, MbtilesAsync, MbtilesClientAsync,
-};
-use crate::sqlite::InsertStrategy;
+use crate::cli::commands::tile_jobs::{TileJob, TileJobFailed, TileJobs, resolve_jobs};
+use crate::img::{WebpOptions, webpify_image_with};
+use crate::mbt::{Mbtiles, MbtilesAsync, MbtilesClientAsync};
+
+#[derive(Debug, Default, Serialize)]
+struct WebpifySummary {
+    src: String,
+    dst: String,
+    ntiles: u64,
+    nwebp: u64,
+    /// tiles kept as is b/c the webp was larger (`--keep-larger`)
+    nkept: u64,
+    nlarger: u64,
+    /// tiles that failed to convert (written as is)
+    nfailed: u64,
+    initial_size: u64,
+    final_size: u64,
+    size_diff: i64,
+    total_time_ms: u128,
+}
+
+/// Size change of a single tile
+struct TileSizes {
+    initial: usize,
+    final_: usize,
+    kept: bool,
+    larger: bool,
+}
+
+fn size_str(size_diff: i64) -> String {
+    if size_diff > 0 {
+        format!("+{}", size::Size::from_bytes(size_diff.unsigned_abs()))
+    } else {
+        format!("-{}", size::Size::from_bytes(size_diff.unsigned_abs()))
+    }
+}
+
+/// Webpify a tile; returns the data to write and its size change
+fn webpify_tile(
+    data: Vec<u8>,
+    options: WebpOptions,
+    keep_larger: bool,
+) -> UtilesResult<(Vec<u8>, TileSizes)> {
+    let initial = data.len();
+    let webp = webpify_image_with(&data, &options)?;
+    let larger = webp.len() > data.len();
+    let kept = larger && keep_larger;
+    let out = if kept { data } else { webp };
+    let sizes = TileSizes {
+        initial,
+        final_: out.len(),
+        kept,
+        larger,
+    };
+    Ok((out, sizes))
+}
+
+impl WebpifySummary {
+    const fn add(&mut self, sizes: &TileSizes) {
+        self.ntiles += 1;
+        self.initial_size += sizes.initial as u64;
+        self.final_size += sizes.final_ as u64;
+        if sizes.kept {
+            self.nkept += 1;
+        } else {
+            self.nwebp += 1;
+        }
+        if sizes.larger {
+            self.nlarger += 1;
+        }
+    }
+
+    #[expect(clippy::cast_possible_wrap)]
+    const fn size_diff(&self) -> i64 {
+        self.final_size as i64 - self.initial_size as i64
+    }
+}
 
 pub(crate) async fn webpify_main(args: WebpifyArgs) -> UtilesResult<()> {
     let mbt = MbtilesClientAsync::open_existing(args.common.filepath.as_str()).await?;
@@ -20,123 +88,71 @@ pub(crate) async fn webpify_main(args: WebpifyArgs) -> UtilesResult<()> {
     let total_count = mbt.tiles_count().await?;
 
     let mbt_metadata = mbt.metadata_rows().await?;
-    let dst_mbtiles = Mbtiles::open_new(args.dst, None)?;
+    let dst_mbtiles = Mbtiles::open_new(&args.dst, None)?;
     dst_mbtiles.metadata_set_many(&mbt_metadata)?;
-    dst_mbtiles.metadata_set("format", "webp")?;
-    let tiles_stream = mbt.tiles_stream(None)?;
-    let (tx_progress, mut rx_progress) = tokio::sync::mpsc::channel(100);
-    let (tx_writer, rx_writer) = tokio::sync::mpsc::channel(100);
     let start_time = std::time::Instant::now();
-    let mut writer = MbtStreamWriterSync {
-        stream: ReceiverStream::new(rx_writer),
-        mbt: dst_mbtiles,
-        on_conflict: InsertStrategy::None,
-        stats: MbtWriterStats::default(),
+    let jobs = resolve_jobs(args.jobs);
+    let options = args.webp_options();
+    let keep_larger = args.keep_larger;
+    info!("webpify ~ total_count: {total_count} ~ jobs: {jobs} ~ {options:?}");
+    let tile_jobs = TileJobs {
+        name: "webpify",
+        total: total_count as u64,
+        jobs,
+        quiet: args.quiet,
     };
-    let jobs: usize = args.jobs.unwrap_or(4) as usize;
-    info!("webpify ~ total_count: {total_count} ~ jobs: {jobs}");
-    let proc_future = tokio::spawn(async move {
-        // TODO: cli flag for concurrency
-        tiles_stream
-            .for_each_concurrent(jobs, |(tile, tile_data)| {
-                let tx_writer = tx_writer.clone();
-                let tx_progress = tx_progress.clone();
-                let initial_size = tile_data.len() as i64;
-
-                async move {
-                    let blocking_res =
-                        tokio::task::spawn_blocking(move || webpify_image(&tile_data))
-                            .await;
-                    match blocking_res {
-                        Err(je) => {
-                            warn!("join-error: {:?}", je);
-                        }
-                        Ok(webpify_result) => match webpify_result {
-                            Ok(webp_bytes) => {
-                                let size_diff =
-                                    initial_size - (webp_bytes.len() as i64);
-                                let send_res = tx_writer
-                                    .send((tile, webp_bytes, None).into())
-                                    .await;
-                                if let Err(e) = send_res {
-                                    warn!("send_res: {:?}", e);
-                                }
-                                let send_res = tx_progress.send(size_diff).await;
-                                if let Err(e) = send_res {
-                                    warn!("progress send_res: {:?}", e);
-                                }
-                            }
-                            Err(e) => {
-                                warn!("webpify_image: {:?}", e);
-                            }
-                        },
+    let output = tile_jobs
+        .run(
+            mbt.tiles_stream(None)?,
+            Some(dst_mbtiles),
+            move |(tile, tile_data): (Tile, Vec<u8>)| {
+                // the original tile is written if webpifying fails
+                match webpify_tile(tile_data.clone(), options, keep_larger) {
+                    Ok((tile_bytes, sizes)) => {
+                        Ok(TileJob::new(vec![(tile, tile_bytes, None).into()], sizes))
                     }
+                    Err(error) => Err(TileJobFailed {
+                        error,
+                        fallback: vec![(tile, tile_data, None).into()],
+                    }),
                 }
-            })
-            .await;
-    });
-
-    let progress_future = tokio::spawn(async move {
-        let mut total_size_diff = 0;
-        let mut processed = 0;
-        let pb = indicatif::ProgressBar::new(total_count as u64);
-        let pb_style = ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
-        );
-        if args.quiet {
-            pb.set_draw_target(indicatif::ProgressDrawTarget::hidden());
-        }
-        pb.set_message("webpify");
-        match pb_style {
-            Err(e) => {
-                warn!("pb_style error: {:?}", e);
-            }
-            Ok(s) => {
-                pb.set_style(s);
-            }
-        }
-        pb.enable_steady_tick(Duration::from_millis(100));
-        while let Some(size_diff) = rx_progress.recv().await {
-            total_size_diff += size_diff;
-            processed += 1;
-            pb.inc(1);
-
-            let size_saved_msg = if total_size_diff > 0 {
-                format!(
-                    "-{}",
-                    size::Size::from_bytes(total_size_diff.unsigned_abs())
-                )
-            } else {
-                format!(
-                    "+{}",
-                    size::Size::from_bytes(total_size_diff.unsigned_abs())
-                )
-            };
-            pb.set_message(format!("webpify ~ size-diff: {size_saved_msg}"));
-        }
-        let total_size_str = if total_size_diff > 0 {
-            format!(
-                "-{}",
-                size::Size::from_bytes(total_size_diff.unsigned_abs())
-            )
-        } else {
-            format!(
-                "+{}",
-                size::Size::from_bytes(total_size_diff.unsigned_abs())
-            )
-        };
-
-        pb.finish_with_message(format!(
-            "Processed {processed} tiles, saved {total_size_str} ({total_size_diff}b)"
-        ));
-    });
-    let (result, writer_result, progress_res) =
-        join!(proc_future, writer.write(), progress_future);
+            },
+            WebpifySummary::default(),
+            |summary, sizes, pb| {
+                summary.add(&sizes);
+                pb.set_message(format!(
+                    "webpify ~ size-diff: {}",
+                    size_str(summary.size_diff())
+                ));
+            },
+        )
+        .await?;
     let elapsed = start_time.elapsed();
     info!("elapsed: {:?}", elapsed);
-    result?;
-    writer_result?;
-    progress_res?;
-
+    let mut summary = output.acc;
+    summary.nfailed = output.nfailed;
+    // the format is only webp if no tile was kept/copied in its original format
+    if summary.nkept == 0 && summary.nfailed == 0 {
+        if let Some(dst) = &output.dst {
+            dst.metadata_set("format", "webp")?;
+        }
+    } else {
+        warn!(
+            "{} tiles kept in their original format ({} webp was larger, {} failed); format metadata left as is",
+            summary.nkept + summary.nfailed,
+            summary.nkept,
+            summary.nfailed
+        );
+    }
+    summary.src = args.common.filepath;
+    summary.dst = args.dst;
+    summary.size_diff = summary.size_diff();
+    summary.total_time_ms = elapsed.as_millis();
+    let out_str = if args.common.min {
+        serde_json::to_string(&summary)
+    } else {
+        serde_json::to_string_pretty(&summary)
+    }?;
+    println!("{out_str}");
     Ok(())
 }
@@ -1,16 +1,18 @@
 use clap::{CommandFactory, FromArgMatches};
 use tracing::{debug, error, trace};
-use utiles_core::VERSION;
+use utiles_core::{Hilbert, Morton, VERSION};
 
 use crate::UtilesError;
 use crate::cli::args::{Cli, Commands};
 use crate::cli::commands::{
     about_main, addo_main, agg_hash_main, bounding_tile_main, burn_main, children_main,
-    commands_main, contains_main, copy_main, dev_main, edges_main, enumerate_main,
-    fmtstr_main, info_main, lint_main, merge_main, metadata_main, metadata_set_main,
-    neighbors_main, optimize_main, parent_main, pmtileid_main, quadkey_main,
-    rimraf_main, serve_main, shapes_main, tilejson_main, tiles_main, touch_main,
-    translate_main, update_main, vacuum_main, webpify_main, zxyify_main,
+    commands_main, composite_main, contains_main, convert_schema_main, copy_main,
+    dev_main, edges_main, enumerate_main, fmtstr_main, info_main, lint_main,
+    merge_main, metadata_main, metadata_set_main, neighbors_main, optimize_main,
+    parent_main, pixel_main, pmtileid_main, quadkey_main, raster_stats_main,
+    resize_main, resolution_main, rimraf_main, serve_main, shapes_main, terrain_main,
+    tileid_main, tilejson_main, tiles_main, touch_main, translate_main, update_main,
+    vacuum_main, webpify_main, zxyify_main,
 };
 use crate::errors::UtilesResult;
 use crate::internal::signal::shutdown_signal;
@@ -93,12 +95,27 @@ pub(crate) async fn cli_main_inner(cliopts: Option<CliOpts>) -> UtilesResult<u8>
     );
     let args = Cli::from_arg_matches(&matches).expect("from_arg_matches failed");
 
+    // the serve config (file) is parsed once; its log settings are used
+    // unless the cli log flags are given
+    let serve_cfg = match &args.command {
+        Commands::Serve(serve_args) => Some(serve_args.to_cfg()),
+        _ => None,
+    };
+    let serve_logcfg = serve_cfg
+        .as_ref()
+        .and_then(|cfg| cfg.as_ref().ok())
+        .and_then(|cfg| cfg.log);
+
     // if the command is "dev" init tracing w/ debug
     let logcfg = if let Commands::Dev(_) = args.command {
         LagerConfig {
             level: LagerLevel::Debug,
             json: args.log_json,
         }
+    } else if let Some(serve_logcfg) = serve_logcfg
+        && !(args.trace || args.debug || args.log_json)
+    {
+        serve_logcfg
     } else {
         let level = if args.trace {
             LagerLevel::Trace
@@ -135,6 +152,7 @@ pub(crate) async fn cli_main_inner(cliopts: Option<CliOpts>) -> UtilesResult<u8>
         Commands::Update(args) => update_main(&args).await,
         Commands::Tilejson(args) => tilejson_main(&args).await,
         Commands::Copy(args) => copy_main(args).await,
+        Commands::ConvertSchema(args) => convert_schema_main(&args).await,
         Commands::Info(args) => info_main(&args).await,
         Commands::AggHash(args) => agg_hash_main(&args).await,
         Commands::Dev(args) => dev_main(args).await,
@@ -146,12 +164,17 @@ pub(crate) async fn cli_main_inner(cliopts: Option<CliOpts>) -> UtilesResult<u8>
         Commands::Merge(args) => merge_main(args).await,
         Commands::Burn(args) => burn_main(args).await,
         Commands::Edges(args) => edges_main(args).await,
+        Commands::Tileset(tscmds) => tscmds.run(),
         Commands::Zxyify(args) => zxyify_main(args).await,
         // mercantile cli like
         Commands::Fmt(args) => fmtstr_main(args),
         Commands::Quadkey(args) => quadkey_main(args),
         Commands::Pmtileid(args) => pmtileid_main(args),
+        Commands::Hilbert(args) => tileid_main::<Hilbert>(args),
+        Commands::Morton(args) => tileid_main::<Morton>(args),
         Commands::BoundingTile(args) => bounding_tile_main(args),
+        Commands::Resolution(args) => resolution_main(&args),
+        Commands::Pixel(args) => pixel_main(&args),
         Commands::Tiles(args) => tiles_main(args, None).await,
         Commands::Neighbors(args) => neighbors_main(args),
         Commands::Children(args) => children_main(args),
@@ -159,8 +182,15 @@ pub(crate) async fn cli_main_inner(cliopts: Option<CliOpts>) -> UtilesResult<u8>
         Commands::Shapes(args) => shapes_main(args),
         Commands::Optimize(args) => optimize_main(args).await,
         Commands::Webpify(args) => webpify_main(args).await,
+        Commands::Resize(args) => resize_main(args).await,
+        Commands::Composite(args) => composite_main(args).await,
+        Commands::Terrain(cmd) => terrain_main(&cmd).await,
+        Commands::RasterStats(args) => raster_stats_main(&args).await,
         // server WIP
-        Commands::Serve(args) => serve_main(args).await,
+        Commands::Serve(args) => match serve_cfg.unwrap_or_else(|| args.to_cfg()) {
+            Ok(cfg) => serve_main(cfg).await,
+            Err(e) => Err(e),
+        },
         // unimplemented
         Commands::Addo => addo_main(None).await,
         Commands::Translate => translate_main(None).await,
@@ -12,5 +12,6 @@ mod entry;
 mod stdin2string;
 mod stdinterator;
 mod stdinterator_filter;
+mod tile_input;
 
 pub use crate::cli::entry::{CliOpts, cli_main, cli_main_sync};
@@ -0,0 +1,246 @@
+use serde_json::Value;
+use utiles_core::Tile;
+use utiles_core::tile_input::{TileColumns, parse_tile_path, tiles_from_geojson};
+use utiles_core::tms::TileMatrixSet;
+
+use crate::cli::args::{TileInputFormat, TileInputStreamArgs};
+use crate::cli::stdinterator_filter::stdin_filtered;
+use crate::errors::{UtilesError, UtilesResult};
+
+/// Parse a json tile (`[x, y, z]`, `{"x": x, "y": y, "z": z}` or
+/// `{"tile": [x, y, z]}`) checked against the tile matrix set instead of the
+/// web-mercator `x, y < 2^z` grid
+pub(crate) fn tms_json_tile(val: &Value, tms: &TileMatrixSet) -> UtilesResult<Tile> {
+    let xyz = match val.get("tile") {
+        Some(tile) if val.is_object() => tile,
+        _ => val,
+    };
+    let tile = if xyz.is_object() {
+        serde_json::from_value::<Tile>(xyz.clone())
+    } else {
+        serde_json::from_value::<(u32, u32, u8)>(xyz.clone())
+            .map(|(x, y, z)| Tile::new_unchecked(x, y, z))
+    }
+    .map_err(|_| UtilesError::ParsingError(format!("invalid tile: {val}")))?;
+    tms.xy_bounds(&tile)?;
+    Ok(tile)
+}
+
+/// Line-by-line tile parser (keeps the csv/tsv header columns)
+pub(crate) struct TileLineParser {
+    format: TileInputFormat,
+    columns: Option<TileColumns>,
+    tms: Option<TileMatrixSet>,
+    nlines: usize,
+}
+
+impl TileLineParser {
+    pub(crate) const fn new(format: TileInputFormat) -> Self {
+        Self {
+            format,
+            columns: None,
+            tms: None,
+            nlines: 0,
+        }
+    }
+
+    /// Tile input format
+    pub(crate) const fn format(&self) -> TileInputFormat {
+        self.format
+    }
+
+    /// Check json tiles against the tile matrix set's tile matrices instead
+    /// of the web-mercator `x, y < 2^z` grid
+    pub(crate) fn with_tms(mut self, tms: Option<TileMatrixSet>) -> Self {
+        self.tms = tms;
+        self
+    }
+
+    fn parse_json(&self, line: &str) -> UtilesResult<Vec<Tile>> {
+        if line.starts_with('{') {
+            let val = serde_json::from_str::<Value>(line)?;
+            if matches!(val["type"].as_str(), Some("Feature" | "FeatureCollection")) {
+                return Ok(tiles_from_geojson(&val)?);
+            }
+            if let Some(tms) = &self.tms {
+                return Ok(vec![tms_json_tile(&val, tms)?]);
+            }
+            Ok(vec![Tile::try_from(&val)?])
+        } else if let Some(tms) = &self.tms {
+            let val = serde_json::from_str::<Value>(line)?;
+            Ok(vec![tms_json_tile(&val, tms)?])
+        } else {
+            Ok(vec![Tile::from_json(line)?])
+        }
+    }
+
+    fn parse_path(line: &str) -> UtilesResult<Vec<Tile>> {
+        parse_tile_path(line).map(|tile| vec![tile]).ok_or_else(|| {
+            UtilesError::ParsingError(format!("invalid z/x/y tile path: {line}"))
+        })
+    }
+
+    fn parse_header(
+        &mut self,
+        line: &str,
+        delimiter: Option<char>,
+    ) -> UtilesResult<()> {
+        let columns = match delimiter {
+            Some(delimiter) => TileColumns::from_header(line, delimiter),
+            None => TileColumns::sniff(line),
+        };
+        self.columns = Some(columns.ok_or_else(|| {
+            UtilesError::ParsingError(format!(
+                "invalid tile header (no z/x/y columns): {line}"
+            ))
+        })?);
+        Ok(())
+    }
+
+    /// Parse the tiles of a line (header lines have no tiles)
+    ///
+    /// In auto mode the format is detected per line and a csv/tsv header is
+    /// only accepted on the first line.
+    pub(crate) fn parse_line(&mut self, line: &str) -> UtilesResult<Vec<Tile>> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(vec![]);
+        }
+        self.nlines += 1;
+        match self.format {
+            TileInputFormat::Json => self.parse_json(line),
+            TileInputFormat::Geojson => {
+                let val = serde_json::from_str::<Value>(line)?;
+                Ok(tiles_from_geojson(&val)?)
+            }
+            TileInputFormat::Path => Self::parse_path(line),
+            TileInputFormat::Csv | TileInputFormat::Tsv => {
+                if let Some(columns) = &self.columns {
+                    return Ok(vec![columns.parse_row(line)?]);
+                }
+                let delimiter = if self.format == TileInputFormat::Csv {
+                    ','
+                } else {
+                    '\t'
+                };
+                self.parse_header(line, Some(delimiter)).map(|()| vec![])
+            }
+            TileInputFormat::Auto => {
+                let line = line.trim_matches(|c| c == '"' || c == '\'');
+                if line.starts_with('[') || line.starts_with('{') {
+                    self.parse_json(line)
+                } else if let Some(columns) = self
+                    .columns
+                    .as_ref()
+                    .filter(|columns| line.contains(columns.delimiter))
+                {
+                    Ok(vec![columns.parse_row(line)?])
+                } else if line.contains(['/', '\\']) {
+                    Self::parse_path(line)
+                } else if self.nlines == 1 {
+                    self.parse_header(line, None).map(|()| vec![]).map_err(|_| {
+                        UtilesError::ParsingError(format!(
+                            "invalid tile (not json, z/x/y path or csv/tsv header): {line}"
+                        ))
+                    })
+                } else {
+                    Err(UtilesError::ParsingError(format!(
+                        "invalid tile (not json, z/x/y path or csv/tsv row w/ a header on the first line): {line}"
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// Iterate over the tiles of the input (stdin, file or arg)
+pub(crate) fn stdin_tiles(
+    inargs: TileInputStreamArgs,
+) -> impl Iterator<Item = UtilesResult<Tile>> {
+    stdin_tms_tiles(inargs, None)
+}
+
+/// Iterate over the tiles of the input w/ json tiles checked against the tile
+/// matrix set (if any) instead of the web-mercator grid
+pub(crate) fn stdin_tms_tiles(
+    inargs: TileInputStreamArgs,
+    tms: Option<TileMatrixSet>,
+) -> impl Iterator<Item = UtilesResult<Tile>> {
+    let mut parser = TileLineParser::new(inargs.input_format).with_tms(tms);
+    stdin_filtered(inargs.input).flat_map(move |line| {
+        let tiles = line
+            .map_err(UtilesError::from)
+            .and_then(|line| parser.parse_line(&line));
+        match tiles {
+            Ok(tiles) => tiles.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(e) => vec![Err(e)],
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn parse_lines(format: TileInputFormat, lines: &[&str]) -> UtilesResult<Vec<Tile>> {
+        let mut parser = TileLineParser::new(format);
+        let mut tiles = Vec::new();
+        for line in lines {
+            tiles.extend(parser.parse_line(line)?);
+        }
+        Ok(tiles)
+    }
+
+    #[test]
+    fn auto_detect() {
+        let tiles = parse_lines(
+            TileInputFormat::Auto,
+            &[
+                "[1, 2, 3]",
+                r#"{"x": 1, "y": 2, "z": 3}"#,
+                "https://tiles.io/3/1/2.png",
+                r#"{"type": "Feature", "id": "(1, 2, 3)", "properties": {}}"#,
+            ],
+        )
+        .unwrap();
+        assert_eq!(tiles, vec![Tile::new(1, 2, 3); 4]);
+        let tiles = parse_lines(
+            TileInputFormat::Auto,
+            &["zoom_level,tile_column,tile_row", "3,1,5", "0,0,0"],
+        )
+        .unwrap();
+        assert_eq!(tiles, vec![Tile::new(1, 2, 3), Tile::new(0, 0, 0)]);
+        assert!(parse_lines(TileInputFormat::Auto, &["a,b,c"]).is_err());
+        // rows after a header don't stop json/path detection
+        let tiles = parse_lines(
+            TileInputFormat::Auto,
+            &["z,x,y", "3,1,2", "[1, 2, 3]", "3/1/2"],
+        )
+        .unwrap();
+        assert_eq!(tiles, vec![Tile::new(1, 2, 3); 3]);
+        // headers are only detected on the first line
+        assert!(parse_lines(TileInputFormat::Auto, &["[1, 2, 3]", "z,x,y"]).is_err());
+    }
+
+    #[test]
+    fn forced_formats() {
+        let tiles = parse_lines(TileInputFormat::Tsv, &["x\ty\tz", "1\t2\t3"]).unwrap();
+        assert_eq!(tiles, vec![Tile::new(1, 2, 3)]);
+        assert!(parse_lines(TileInputFormat::Csv, &["1,2,3"]).is_err());
+        assert!(parse_lines(TileInputFormat::Path, &["[1, 2, 3]"]).is_err());
+    }
+
+    #[test]
+    fn tms_json_tiles() {
+        assert!(parse_lines(TileInputFormat::Json, &["[1, 0, 0]"]).is_err());
+        let tms = TileMatrixSet::world_crs84_quad();
+        let mut parser = TileLineParser::new(TileInputFormat::Auto).with_tms(Some(tms));
+        let tiles = parser.parse_line("[1, 0, 0]").unwrap();
+        assert_eq!(tiles, vec![Tile::new_unchecked(1, 0, 0)]);
+        let tiles = parser.parse_line(r#"{"x": 3, "y": 1, "z": 1}"#).unwrap();
+        assert_eq!(tiles, vec![Tile::new_unchecked(3, 1, 1)]);
+        assert!(parser.parse_line("[2, 0, 0]").is_err());
+    }
+}
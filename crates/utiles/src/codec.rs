@@ -0,0 +1,80 @@
+//! Tile data (de)compression
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use utiles_core::tile_type::{TileEncoding, tiletype};
+
+use crate::errors::{UtilesError, UtilesResult};
+
+/// Decompress data encoded w/ the given `TileEncoding`
+///
+/// Uncompressed and internally-compressed (image) data is returned as is.
+pub fn decompress(data: &[u8], encoding: TileEncoding) -> UtilesResult<Vec<u8>> {
+    match encoding {
+        TileEncoding::Uncompressed | TileEncoding::Internal => Ok(data.to_vec()),
+        TileEncoding::Gzip => {
+            let mut buf = Vec::with_capacity(data.len() * 2);
+            GzDecoder::new(data).read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+        TileEncoding::Zlib => {
+            let mut buf = Vec::with_capacity(data.len() * 2);
+            ZlibDecoder::new(data).read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+        TileEncoding::Brotli | TileEncoding::Zstd => Err(UtilesError::Unsupported(
+            format!("decompressing {encoding} tile data"),
+        )),
+    }
+}
+
+/// Compress data w/ the given `TileEncoding`
+pub fn compress(data: &[u8], encoding: TileEncoding) -> UtilesResult<Vec<u8>> {
+    compress_level(data, encoding, Compression::default().level())
+}
+
+/// Compress data w/ the given `TileEncoding` and compression level (0-9)
+pub fn compress_level(
+    data: &[u8],
+    encoding: TileEncoding,
+    level: u32,
+) -> UtilesResult<Vec<u8>> {
+    let level = Compression::new(level.min(9));
+    match encoding {
+        TileEncoding::Uncompressed | TileEncoding::Internal => Ok(data.to_vec()),
+        TileEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        TileEncoding::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        TileEncoding::Brotli | TileEncoding::Zstd => Err(UtilesError::Unsupported(
+            format!("compressing {encoding} tile data"),
+        )),
+    }
+}
+
+/// Recompress tile data w/ its detected `TileEncoding` at the best level
+///
+/// Returns `None` if the data is not (gzip/zlib) compressed or if the
+/// recompressed data is not smaller.
+pub fn recompress_tile_best(data: &[u8]) -> UtilesResult<Option<Vec<u8>>> {
+    let encoding = tiletype(data).encoding;
+    if !matches!(encoding, TileEncoding::Gzip | TileEncoding::Zlib) {
+        return Ok(None);
+    }
+    let raw = decompress(data, encoding)?;
+    let recompressed = compress_level(&raw, encoding, Compression::best().level())?;
+    Ok((recompressed.len() < data.len()).then_some(recompressed))
+}
+
+/// Decompress tile data based on its detected `TileEncoding`
+pub fn decompress_tile(data: &[u8]) -> UtilesResult<Vec<u8>> {
+    decompress(data, tiletype(data).encoding)
+}
@@ -13,28 +13,6 @@ pub(crate) struct LintConfig {
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct UtilesConfig {
     pub lint: LintConfig,
-    // TODO: server/log config
-    // pub log: LagerConfig,
-    // pub serve : ServeConfig,
+    // server (and its log) config lives in `server::cfg::UtilesServerConfig`
+    // and is loaded w/ `utiles serve --config <fspath>`
 }
-
-// #[derive(Debug, Deserialize, Serialize)]
-// pub struct ServeConfig {
-//     pub host: String,
-//     pub port: u16,
-// }
-//
-// #[derive(Debug, Deserialize, Serialize)]
-// pub struct LagerConfig {
-//     pub level: String,
-//     pub json: bool,
-// }
-
-// impl Default for LagerConfig {
-//     fn default() -> Self {
-//         Self {
-//             level: "info".to_string(),
-//             json: false,
-//         }
-//     }
-// }
@@ -6,7 +6,6 @@ use utiles_core::UtilesCoreError;
 use crate::UtilesError;
 use crate::copy::CopyConfig;
 use crate::errors::{UtilesCopyError, UtilesResult};
-use crate::hash::xxh64_be_hex_upper;
 use crate::mbt::{
     MbtStreamWriterSync, MbtType, MbtWriterStats, Mbtiles, MbtilesAsync,
     MbtilesClientAsync, MbtilesMetadataJson,
@@ -363,6 +362,10 @@ LIMIT 1;
         })?;
         let stream_o_tiles = src_db.tiles_stream(Some(&*sql_query))?;
 
+        let hash_type = self.cfg.hash.unwrap_or_default();
+        if dst_mbt_sync.query_mbt_type()? != MbtType::Flat {
+            dst_mbt_sync.metadata_set("tileid", &hash_type.to_string())?;
+        }
         let (tx2writer, rx) = tokio::sync::mpsc::channel(100);
 
         let process_tiles = tokio::spawn(async move {
@@ -371,7 +374,7 @@ LIMIT 1;
                     let tx_writer = tx2writer.clone();
                     async move {
                         let hash_res = tokio::task::spawn_blocking(move || {
-                            let hash = xxh64_be_hex_upper(&tile_data);
+                            let hash = hash_type.hash_hex_upper(&tile_data);
                             (tile_data, hash)
                         })
                         .await;
@@ -407,7 +410,14 @@ LIMIT 1;
         if let Err(e) = write_task_res {
             error!("write_task_res: {:?}", e);
         }
-        Ok(0)
+        info!(
+            "wrote {} tiles ~ deduped: {} ({} bytes; ratio: {:.3})",
+            writer.stats.count,
+            writer.stats.ndeduped,
+            writer.stats.nbytes_deduped,
+            writer.stats.dedup_ratio()
+        );
+        Ok(writer.stats.count)
     }
 
     // pub(crate) async fn copy_tiles_attach(
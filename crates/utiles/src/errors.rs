@@ -121,6 +121,10 @@ pub enum UtilesError {
     #[error("image error: {0}")]
     ImageError(#[from] image::ImageError),
 
+    /// Webp encoding error
+    #[error("webp encoding error: {0}")]
+    WebpEncodingError(String),
+
     /// Error from `json_patch`
     #[error("json_patch error: {0}")]
     JsonPatchError(#[from] json_patch::PatchError),
@@ -133,10 +137,10 @@ pub enum UtilesError {
     #[cfg(feature = "pmtiles")]
     #[error("pmtiles error: {0}")]
     PmtilesError(#[from] pmtiles::PmtError),
-    // /// Error from `oxipng`
-    // #[error("oxipng::PngError: {0}")]
-    // OxipngError(#[from] oxipng::PngError),
-
+    /// Error from `oxipng`
+    #[cfg(feature = "oxipng")]
+    #[error("oxipng::PngError: {0}")]
+    OxipngError(#[from] oxipng::PngError),
     // /// ndarray shape error
     // #[error("ndarray shape error: {0}")]
     // NdarrayShapeError(#[from] ndarray::ShapeError),
@@ -0,0 +1,309 @@
+//! Dissolve (merge) tiles into polygons
+//!
+//! Tiles are scaled to the max zoom of the input and split into row bands
+//! (between consecutive tile edges); the boundary of the merged x-spans is
+//! traced into rings (interior on the left) which are grouped into polygons
+//! w/ holes. Tiles touching only at a corner end up in separate polygons.
+use std::collections::HashMap;
+
+use geojson::{PolygonType, Position};
+use utiles_core::{Tile, ul};
+
+/// Grid point at the max zoom of the dissolved tiles
+type Pt = (u64, u64);
+
+/// Sorted, non-overlapping x-spans `[start, end)`
+type Spans = Vec<(u64, u64)>;
+
+fn merge_spans(mut spans: Spans) -> Spans {
+    spans.sort_unstable();
+    let mut merged: Spans = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Spans of `a` not covered by `b`
+fn span_difference(a: &[(u64, u64)], b: &[(u64, u64)]) -> Spans {
+    let mut out = Vec::new();
+    let mut bi = 0;
+    for &(start, end) in a {
+        let mut cur = start;
+        while bi < b.len() && b[bi].1 <= cur {
+            bi += 1;
+        }
+        let mut j = bi;
+        while cur < end {
+            match b.get(j) {
+                Some(&(bs, be)) if bs < end => {
+                    if bs > cur {
+                        out.push((cur, bs));
+                    }
+                    cur = cur.max(be);
+                    j += 1;
+                }
+                _ => {
+                    out.push((cur, end));
+                    cur = end;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Directed boundary edges (interior on the left, y-down grid)
+fn boundary_edges(ys: &[u64], bands: &[Spans]) -> Vec<(Pt, Pt)> {
+    let mut edges = Vec::new();
+    for (i, spans) in bands.iter().enumerate() {
+        let (top, bottom) = (ys[i], ys[i + 1]);
+        for &(start, end) in spans {
+            edges.push(((start, bottom), (start, top)));
+            edges.push(((end, top), (end, bottom)));
+        }
+    }
+    let empty = Vec::new();
+    for (j, &y) in ys.iter().enumerate() {
+        let above = if j > 0 { &bands[j - 1] } else { &empty };
+        let below = bands.get(j).unwrap_or(&empty);
+        for (start, end) in span_difference(below, above) {
+            edges.push(((start, y), (end, y)));
+        }
+        for (start, end) in span_difference(above, below) {
+            edges.push(((end, y), (start, y)));
+        }
+    }
+    edges
+}
+
+fn direction(edge: (Pt, Pt)) -> (i64, i64) {
+    let ((x0, y0), (x1, y1)) = edge;
+    ((x1.cmp(&x0) as i8).into(), (y1.cmp(&y0) as i8).into())
+}
+
+/// Turn rank ~ left turns first so corner-touching rings stay separate
+const fn turn_rank(din: (i64, i64), dout: (i64, i64)) -> i64 {
+    (din.0 * dout.1 - din.1 * dout.0).signum()
+}
+
+/// Trace the boundary edges into closed rings (w/o repeated first point)
+fn trace_rings(edges: &[(Pt, Pt)]) -> Vec<Vec<Pt>> {
+    let mut outgoing: HashMap<Pt, Vec<usize>> = HashMap::new();
+    for (i, (start, _)) in edges.iter().enumerate() {
+        outgoing.entry(*start).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = edges[first].0;
+        let mut ring = vec![start];
+        let mut cur = first;
+        loop {
+            let (_, end) = edges[cur];
+            let din = direction(edges[cur]);
+            let next = outgoing
+                .get(&end)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|&e| !used[e] || e == first)
+                .max_by_key(|&e| turn_rank(din, direction(edges[e])));
+            match next {
+                Some(e) if e != first => {
+                    used[e] = true;
+                    ring.push(end);
+                    cur = e;
+                }
+                _ => break,
+            }
+        }
+        rings.push(simplify_ring(&ring));
+    }
+    rings
+}
+
+/// Remove collinear points of an axis-aligned ring
+fn simplify_ring(ring: &[Pt]) -> Vec<Pt> {
+    let n = ring.len();
+    (0..n)
+        .filter(|&i| {
+            let prev = ring[(i + n - 1) % n];
+            let next = ring[(i + 1) % n];
+            !(prev.0 == next.0 || prev.1 == next.1)
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+/// Twice the signed area of a ring (positive for interior-left exteriors)
+fn ring_area2(ring: &[Pt]) -> i128 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (x0, y0) = ring[i];
+            let (x1, y1) = ring[(i + 1) % n];
+            i128::from(x0) * i128::from(y1) - i128::from(x1) * i128::from(y0)
+        })
+        .sum()
+}
+
+// grid coords are < 2^31 (exact as f64)
+#[expect(clippy::cast_precision_loss)]
+fn ring_contains(ring: &[Pt], (px, py): (f64, f64)) -> bool {
+    let n = ring.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (x0, y0) = (ring[i].0 as f64, ring[i].1 as f64);
+        let (x1, y1) = (ring[(i + 1) % n].0 as f64, ring[(i + 1) % n].1 as f64);
+        if (y0 > py) != (y1 > py) && px < x0 + (py - y0) * (x1 - x0) / (y1 - y0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Dissolve tiles into polygons of grid rings (exterior first) at `zoom`
+#[expect(clippy::cast_precision_loss)]
+fn dissolve_grid(tiles: &[Tile]) -> (u8, Vec<Vec<Vec<Pt>>>) {
+    let Some(zoom) = tiles.iter().map(|t| t.z).max() else {
+        return (0, Vec::new());
+    };
+    let rects = tiles
+        .iter()
+        .map(|t| {
+            let d = zoom - t.z;
+            let (x, y) = (u64::from(t.x), u64::from(t.y));
+            (x << d, y << d, (x + 1) << d, (y + 1) << d)
+        })
+        .collect::<Vec<_>>();
+    let mut ys = rects.iter().flat_map(|r| [r.1, r.3]).collect::<Vec<_>>();
+    ys.sort_unstable();
+    ys.dedup();
+    let mut bands: Vec<Spans> = vec![Vec::new(); ys.len() - 1];
+    for &(x0, y0, x1, y1) in &rects {
+        let i0 = ys.partition_point(|&y| y < y0);
+        let i1 = ys.partition_point(|&y| y < y1);
+        for band in &mut bands[i0..i1] {
+            band.push((x0, x1));
+        }
+    }
+    let bands = bands.into_iter().map(merge_spans).collect::<Vec<_>>();
+    let rings = trace_rings(&boundary_edges(&ys, &bands));
+
+    let (exteriors, holes): (Vec<_>, Vec<_>) =
+        rings.into_iter().partition(|r| ring_area2(r) > 0);
+    let mut polygons = exteriors.into_iter().map(|r| vec![r]).collect::<Vec<_>>();
+    for hole in holes {
+        // point just inside the hole (right of its first edge)
+        let (a, b) = (hole[0], hole[1]);
+        let (dx, dy) = direction((a, b));
+        let p = (
+            (a.0 + b.0) as f64 / 2.0 + dy as f64 / 2.0,
+            (a.1 + b.1) as f64 / 2.0 - dx as f64 / 2.0,
+        );
+        let owner = polygons
+            .iter_mut()
+            .filter(|poly| ring_contains(&poly[0], p))
+            .min_by_key(|poly| ring_area2(&poly[0]));
+        if let Some(poly) = owner {
+            poly.push(hole);
+        }
+    }
+    (zoom, polygons)
+}
+
+/// Dissolve tiles into lng/lat polygons (rfc-7946 ring orientation)
+#[must_use]
+pub fn dissolve_tiles(tiles: &[Tile]) -> Vec<PolygonType> {
+    let (zoom, polygons) = dissolve_grid(tiles);
+    polygons
+        .into_iter()
+        .map(|rings| {
+            rings
+                .into_iter()
+                .map(|ring| {
+                    // y-down grid -> y-up lng/lat flips the orientation back
+                    let mut coords = ring
+                        .iter()
+                        .rev()
+                        .map(|&(x, y)| {
+                            let lnglat = ul(x as u32, y as u32, zoom);
+                            Position::from([lnglat.lng(), lnglat.lat()])
+                        })
+                        .collect::<Vec<_>>();
+                    if let Some(first) = coords.first().cloned() {
+                        coords.push(first);
+                    }
+                    coords
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiles(xys: &[(u32, u32)], z: u8) -> Vec<Tile> {
+        xys.iter().map(|&(x, y)| Tile::new(x, y, z)).collect()
+    }
+
+    #[test]
+    fn dissolve_block() {
+        let (zoom, polys) = dissolve_grid(&tiles(&[(0, 0), (1, 0), (0, 1), (1, 1)], 3));
+        assert_eq!(zoom, 3);
+        assert_eq!(polys.len(), 1);
+        assert_eq!(polys[0].len(), 1);
+        assert_eq!(polys[0][0].len(), 4);
+        assert_eq!(ring_area2(&polys[0][0]), 8);
+    }
+
+    #[test]
+    fn dissolve_hole_and_corner() {
+        // 3x3 ring w/ a hole + a tile touching only at a corner
+        let mut xys = (0..3)
+            .flat_map(|x| (0..3).map(move |y| (x, y)))
+            .filter(|&xy| xy != (1, 1))
+            .collect::<Vec<_>>();
+        xys.push((3, 3));
+        let (_, polys) = dissolve_grid(&tiles(&xys, 4));
+        assert_eq!(polys.len(), 2);
+        let ring_poly = polys.iter().find(|p| p.len() == 2).expect("poly w/ hole");
+        assert_eq!(ring_area2(&ring_poly[0]), 18);
+        assert_eq!(ring_area2(&ring_poly[1]), -2);
+    }
+
+    #[test]
+    fn dissolve_mixed_zooms() {
+        // z1 tile + its z2 neighbor to the right
+        let (zoom, polys) = dissolve_grid(&[Tile::new(0, 0, 1), Tile::new(2, 1, 2)]);
+        assert_eq!(zoom, 2);
+        assert_eq!(polys.len(), 1);
+        assert_eq!(polys[0][0].len(), 6);
+        assert_eq!(ring_area2(&polys[0][0]), 10);
+    }
+
+    #[test]
+    fn dissolve_lnglat_ccw() {
+        let polys = dissolve_tiles(&tiles(&[(0, 0)], 0));
+        let ring = &polys[0][0];
+        assert_eq!(ring.len(), 5);
+        let area2: f64 = ring
+            .windows(2)
+            .map(|w| {
+                let (a, b) = (w[0].as_slice(), w[1].as_slice());
+                a[0] * b[1] - b[0] * a[1]
+            })
+            .sum();
+        assert!(area2 > 0.0);
+    }
+}
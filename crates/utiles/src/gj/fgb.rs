@@ -0,0 +1,706 @@
+//! Minimal `FlatGeobuf` writer (no spatial index) for geojson features
+//!
+//! Writes the magic bytes, a size-prefixed header flatbuffer and size-prefixed
+//! feature flatbuffers (<https://flatgeobuf.org>) w/ the `flatbuffers`
+//! builder; table slots follow the `FlatGeobuf` `header.fbs`/`feature.fbs`
+//! schemas.
+use std::io::Write;
+
+use flatbuffers::{FlatBufferBuilder, TableFinishedWIPOffset, VOffsetT, WIPOffset};
+use geojson::{Feature, GeometryValue, Position};
+use serde_json::Value;
+
+use crate::errors::UtilesResult;
+
+/// `FlatGeobuf` magic bytes (v3.0)
+const MAGIC: [u8; 8] = [0x66, 0x67, 0x62, 0x03, 0x66, 0x67, 0x62, 0x00];
+
+/// Finished table offset
+type TableOff = WIPOffset<TableFinishedWIPOffset>;
+
+/// Vtable offset of the schema field w/ id `id`
+const fn slot(id: VOffsetT) -> VOffsetT {
+    4 + 2 * id
+}
+
+/// Schema field ids
+mod field {
+    use flatbuffers::VOffsetT;
+
+    pub(super) const HEADER_NAME: VOffsetT = 0;
+    pub(super) const HEADER_ENVELOPE: VOffsetT = 1;
+    pub(super) const HEADER_GEOMETRY_TYPE: VOffsetT = 2;
+    pub(super) const HEADER_COLUMNS: VOffsetT = 7;
+    pub(super) const HEADER_FEATURES_COUNT: VOffsetT = 8;
+    pub(super) const HEADER_INDEX_NODE_SIZE: VOffsetT = 9;
+    pub(super) const HEADER_CRS: VOffsetT = 10;
+    pub(super) const COLUMN_NAME: VOffsetT = 0;
+    pub(super) const COLUMN_TYPE: VOffsetT = 1;
+    pub(super) const CRS_ORG: VOffsetT = 0;
+    pub(super) const CRS_CODE: VOffsetT = 1;
+    pub(super) const FEATURE_GEOMETRY: VOffsetT = 0;
+    pub(super) const FEATURE_PROPERTIES: VOffsetT = 1;
+    pub(super) const GEOMETRY_ENDS: VOffsetT = 0;
+    pub(super) const GEOMETRY_XY: VOffsetT = 1;
+    pub(super) const GEOMETRY_TYPE: VOffsetT = 6;
+    pub(super) const GEOMETRY_PARTS: VOffsetT = 7;
+}
+
+/// `FlatGeobuf` geometry types
+mod geometry_type {
+    pub(super) const UNKNOWN: u8 = 0;
+    pub(super) const POINT: u8 = 1;
+    pub(super) const LINESTRING: u8 = 2;
+    pub(super) const POLYGON: u8 = 3;
+    pub(super) const MULTIPOINT: u8 = 4;
+    pub(super) const MULTILINESTRING: u8 = 5;
+    pub(super) const MULTIPOLYGON: u8 = 6;
+    pub(super) const GEOMETRYCOLLECTION: u8 = 7;
+}
+
+/// `FlatGeobuf` column types (subset used for json values)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Bool = 2,
+    Long = 7,
+    Double = 10,
+    String = 11,
+    Json = 12,
+}
+
+impl ColumnType {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(Self::Bool),
+            Value::Number(n) if n.is_i64() => Some(Self::Long),
+            Value::Number(_) => Some(Self::Double),
+            Value::String(_) => Some(Self::String),
+            Value::Array(_) | Value::Object(_) => Some(Self::Json),
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Long | Self::Double, Self::Long | Self::Double) => Self::Double,
+            _ => Self::Json,
+        }
+    }
+}
+
+const fn geometry_type(g: &GeometryValue) -> u8 {
+    match g {
+        GeometryValue::Point { .. } => geometry_type::POINT,
+        GeometryValue::MultiPoint { .. } => geometry_type::MULTIPOINT,
+        GeometryValue::LineString { .. } => geometry_type::LINESTRING,
+        GeometryValue::MultiLineString { .. } => geometry_type::MULTILINESTRING,
+        GeometryValue::Polygon { .. } => geometry_type::POLYGON,
+        GeometryValue::MultiPolygon { .. } => geometry_type::MULTIPOLYGON,
+        GeometryValue::GeometryCollection { .. } => geometry_type::GEOMETRYCOLLECTION,
+    }
+}
+
+fn push_xy(xy: &mut Vec<f64>, positions: &[Position]) {
+    for p in positions {
+        xy.extend(p.as_slice().iter().take(2));
+    }
+}
+
+/// Flat xy coordinates and part ends (in number of points) of rings/lines
+fn xy_ends(parts: &[Vec<Position>]) -> (Vec<f64>, Vec<u32>) {
+    let mut xy = Vec::new();
+    let mut ends = Vec::with_capacity(parts.len());
+    for part in parts {
+        push_xy(&mut xy, part);
+        ends.push((xy.len() / 2) as u32);
+    }
+    (xy, ends)
+}
+
+/// Geometry table w/ xy coordinates and (multi-part) ends
+fn geometry_table(
+    fbb: &mut FlatBufferBuilder,
+    gtype: u8,
+    xy: &[f64],
+    ends: &[u32],
+) -> TableOff {
+    let xy = fbb.create_vector(xy);
+    let ends = (ends.len() > 1).then(|| fbb.create_vector(ends));
+    let table = fbb.start_table();
+    fbb.push_slot_always(slot(field::GEOMETRY_XY), xy);
+    if let Some(ends) = ends {
+        fbb.push_slot_always(slot(field::GEOMETRY_ENDS), ends);
+    }
+    fbb.push_slot(slot(field::GEOMETRY_TYPE), gtype, geometry_type::UNKNOWN);
+    fbb.end_table(table)
+}
+
+/// Geometry table w/ geometry `parts`
+fn parts_table(fbb: &mut FlatBufferBuilder, gtype: u8, parts: &[TableOff]) -> TableOff {
+    let parts = fbb.create_vector(parts);
+    let table = fbb.start_table();
+    fbb.push_slot_always(slot(field::GEOMETRY_PARTS), parts);
+    fbb.push_slot(slot(field::GEOMETRY_TYPE), gtype, geometry_type::UNKNOWN);
+    fbb.end_table(table)
+}
+
+fn build_geometry(fbb: &mut FlatBufferBuilder, g: &GeometryValue) -> TableOff {
+    let gtype = geometry_type(g);
+    match g {
+        GeometryValue::Point { coordinates } => {
+            let mut xy = Vec::with_capacity(2);
+            push_xy(&mut xy, std::slice::from_ref(coordinates));
+            geometry_table(fbb, gtype, &xy, &[])
+        }
+        GeometryValue::MultiPoint { coordinates }
+        | GeometryValue::LineString { coordinates } => {
+            let mut xy = Vec::with_capacity(2 * coordinates.len());
+            push_xy(&mut xy, coordinates);
+            geometry_table(fbb, gtype, &xy, &[])
+        }
+        GeometryValue::MultiLineString { coordinates }
+        | GeometryValue::Polygon { coordinates } => {
+            let (xy, ends) = xy_ends(coordinates);
+            geometry_table(fbb, gtype, &xy, &ends)
+        }
+        GeometryValue::MultiPolygon { coordinates } => {
+            let parts = coordinates
+                .iter()
+                .map(|polygon| {
+                    let (xy, ends) = xy_ends(polygon);
+                    geometry_table(fbb, geometry_type::POLYGON, &xy, &ends)
+                })
+                .collect::<Vec<_>>();
+            parts_table(fbb, gtype, &parts)
+        }
+        GeometryValue::GeometryCollection { geometries } => {
+            let parts = geometries
+                .iter()
+                .map(|g| build_geometry(fbb, &g.value))
+                .collect::<Vec<_>>();
+            parts_table(fbb, gtype, &parts)
+        }
+    }
+}
+
+/// Property bytes ~ `(u16 column index, value)` pairs
+fn properties_bytes(feature: &Feature, columns: &[(String, ColumnType)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let Some(properties) = &feature.properties else {
+        return bytes;
+    };
+    for (i, (name, column_type)) in columns.iter().enumerate() {
+        let Some(value) = properties.get(name).filter(|v| !v.is_null()) else {
+            continue;
+        };
+        bytes.extend((i as u16).to_le_bytes());
+        match (column_type, value) {
+            (ColumnType::Bool, Value::Bool(b)) => bytes.push(u8::from(*b)),
+            (ColumnType::Long, Value::Number(n)) => {
+                bytes.extend(n.as_i64().unwrap_or_default().to_le_bytes());
+            }
+            (ColumnType::Double, Value::Number(n)) => {
+                bytes.extend(n.as_f64().unwrap_or_default().to_le_bytes());
+            }
+            (ColumnType::String, Value::String(s)) => {
+                bytes.extend((s.len() as u32).to_le_bytes());
+                bytes.extend(s.as_bytes());
+            }
+            (_, value) => {
+                let s = value.to_string();
+                bytes.extend((s.len() as u32).to_le_bytes());
+                bytes.extend(s.as_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+/// Size-prefixed feature flatbuffer (the builder is reset and reused)
+fn feature_bytes<'a>(
+    fbb: &'a mut FlatBufferBuilder,
+    feature: &Feature,
+    columns: &[(String, ColumnType)],
+) -> &'a [u8] {
+    fbb.reset();
+    let geometry = feature
+        .geometry
+        .as_ref()
+        .map(|geometry| build_geometry(fbb, &geometry.value));
+    let properties = properties_bytes(feature, columns);
+    let properties = (!properties.is_empty()).then(|| fbb.create_vector(&properties));
+    let table = fbb.start_table();
+    if let Some(geometry) = geometry {
+        fbb.push_slot_always(slot(field::FEATURE_GEOMETRY), geometry);
+    }
+    if let Some(properties) = properties {
+        fbb.push_slot_always(slot(field::FEATURE_PROPERTIES), properties);
+    }
+    let root = fbb.end_table(table);
+    fbb.finish_size_prefixed(root, None);
+    fbb.finished_data()
+}
+
+/// Property columns (in first-seen order) w/ types merged across features
+fn feature_columns(features: &[Feature]) -> Vec<(String, ColumnType)> {
+    let mut columns: Vec<(String, ColumnType)> = Vec::new();
+    for properties in features.iter().filter_map(|f| f.properties.as_ref()) {
+        for (name, value) in properties {
+            let Some(column_type) = ColumnType::of(value) else {
+                continue;
+            };
+            match columns.iter_mut().find(|(n, _)| n == name) {
+                Some(column) => column.1 = column.1.merge(column_type),
+                None => columns.push((name.clone(), column_type)),
+            }
+        }
+    }
+    columns
+}
+
+fn envelope(features: &[Feature]) -> Option<[f64; 4]> {
+    let mut env: Option<[f64; 4]> = None;
+    let points = features
+        .iter()
+        .filter_map(|f| f.geometry.clone())
+        .flat_map(super::geojson_geometry_points);
+    for p in points {
+        let (x, y) = (p.as_slice()[0], p.as_slice()[1]);
+        env = Some(match env {
+            Some([x0, y0, x1, y1]) => [x0.min(x), y0.min(y), x1.max(x), y1.max(y)],
+            None => [x, y, x, y],
+        });
+    }
+    env
+}
+
+fn header_bytes(
+    name: &str,
+    features: &[Feature],
+    columns: &[(String, ColumnType)],
+    epsg: i32,
+) -> Vec<u8> {
+    let mut fbb = FlatBufferBuilder::new();
+    let gtype = match features
+        .iter()
+        .filter_map(|f| f.geometry.as_ref())
+        .map(|g| geometry_type(&g.value))
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [first, rest @ ..] if rest.iter().all(|t| t == first) => *first,
+        _ => geometry_type::UNKNOWN,
+    };
+    let name = fbb.create_string(name);
+    let envelope = envelope(features).map(|env| fbb.create_vector(&env));
+    let column_offs = columns
+        .iter()
+        .map(|(name, column_type)| {
+            let name = fbb.create_string(name);
+            let table = fbb.start_table();
+            fbb.push_slot_always(slot(field::COLUMN_NAME), name);
+            fbb.push_slot_always(slot(field::COLUMN_TYPE), *column_type as u8);
+            fbb.end_table(table)
+        })
+        .collect::<Vec<_>>();
+    let columns_off =
+        (!column_offs.is_empty()).then(|| fbb.create_vector(&column_offs));
+    let org = fbb.create_string("EPSG");
+    let crs_table = fbb.start_table();
+    fbb.push_slot_always(slot(field::CRS_ORG), org);
+    fbb.push_slot_always(slot(field::CRS_CODE), epsg);
+    let crs = fbb.end_table(crs_table);
+
+    let table = fbb.start_table();
+    fbb.push_slot_always(slot(field::HEADER_NAME), name);
+    if let Some(envelope) = envelope {
+        fbb.push_slot_always(slot(field::HEADER_ENVELOPE), envelope);
+    }
+    fbb.push_slot(
+        slot(field::HEADER_GEOMETRY_TYPE),
+        gtype,
+        geometry_type::UNKNOWN,
+    );
+    if let Some(columns_off) = columns_off {
+        fbb.push_slot_always(slot(field::HEADER_COLUMNS), columns_off);
+    }
+    fbb.push_slot_always(slot(field::HEADER_FEATURES_COUNT), features.len() as u64);
+    // no spatial index (the schema default is 16)
+    fbb.push_slot_always(slot(field::HEADER_INDEX_NODE_SIZE), 0_u16);
+    fbb.push_slot_always(slot(field::HEADER_CRS), crs);
+    let root = fbb.end_table(table);
+    fbb.finish_size_prefixed(root, None);
+    fbb.finished_data().to_vec()
+}
+
+/// Write features as a `FlatGeobuf` dataset (w/o spatial index)
+///
+/// Property columns/types are inferred from the feature properties; mixed
+/// typed properties are written as json columns.
+pub fn write_fgb<W: Write>(
+    w: &mut W,
+    name: &str,
+    features: &[Feature],
+    epsg: i32,
+) -> UtilesResult<()> {
+    let columns = feature_columns(features);
+    w.write_all(&MAGIC)?;
+    w.write_all(&header_bytes(name, features, &columns, epsg))?;
+    let mut fbb = FlatBufferBuilder::new();
+    for feature in features {
+        w.write_all(feature_bytes(&mut fbb, feature, &columns))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use flatbuffers::{
+        Follow, ForwardsUOffset, InvalidFlatbuffer, SkipSizePrefix, Table, Vector,
+        Verifiable, Verifier, VerifierOptions,
+    };
+    use geojson::Geometry;
+    use serde_json::{Map, json};
+
+    use super::*;
+
+    // `FlatGeobuf` schema tables (`header.fbs`/`feature.fbs`) for the
+    // flatbuffers verifier
+    struct HeaderFb;
+    struct ColumnFb;
+    struct CrsFb;
+    struct FeatureFb;
+    struct GeometryFb;
+
+    type Str<'a> = ForwardsUOffset<&'a str>;
+    type Vec64<'a> = ForwardsUOffset<Vector<'a, f64>>;
+    type Tables<'a, T> = ForwardsUOffset<Vector<'a, ForwardsUOffset<T>>>;
+
+    impl Verifiable for HeaderFb {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Str>("name", slot(0), false)?
+                .visit_field::<Vec64>("envelope", slot(1), false)?
+                .visit_field::<u8>("geometry_type", slot(2), false)?
+                .visit_field::<bool>("has_z", slot(3), false)?
+                .visit_field::<bool>("has_m", slot(4), false)?
+                .visit_field::<bool>("has_t", slot(5), false)?
+                .visit_field::<bool>("has_tm", slot(6), false)?
+                .visit_field::<Tables<ColumnFb>>("columns", slot(7), false)?
+                .visit_field::<u64>("features_count", slot(8), false)?
+                .visit_field::<u16>("index_node_size", slot(9), false)?
+                .visit_field::<ForwardsUOffset<CrsFb>>("crs", slot(10), false)?
+                .visit_field::<Str>("title", slot(11), false)?
+                .visit_field::<Str>("description", slot(12), false)?
+                .visit_field::<Str>("metadata", slot(13), false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    impl Verifiable for ColumnFb {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Str>("name", slot(0), true)?
+                .visit_field::<u8>("type", slot(1), false)?
+                .visit_field::<Str>("title", slot(2), false)?
+                .visit_field::<Str>("description", slot(3), false)?
+                .visit_field::<i32>("width", slot(4), false)?
+                .visit_field::<i32>("precision", slot(5), false)?
+                .visit_field::<i32>("scale", slot(6), false)?
+                .visit_field::<bool>("nullable", slot(7), false)?
+                .visit_field::<bool>("unique", slot(8), false)?
+                .visit_field::<bool>("primary_key", slot(9), false)?
+                .visit_field::<Str>("metadata", slot(10), false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    impl Verifiable for CrsFb {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Str>("org", slot(0), false)?
+                .visit_field::<i32>("code", slot(1), false)?
+                .visit_field::<Str>("name", slot(2), false)?
+                .visit_field::<Str>("description", slot(3), false)?
+                .visit_field::<Str>("wkt", slot(4), false)?
+                .visit_field::<Str>("code_string", slot(5), false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    impl Verifiable for FeatureFb {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<ForwardsUOffset<GeometryFb>>("geometry", slot(0), false)?
+                .visit_field::<ForwardsUOffset<Vector<u8>>>(
+                    "properties",
+                    slot(1),
+                    false,
+                )?
+                .visit_field::<Tables<ColumnFb>>("columns", slot(2), false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    impl Verifiable for GeometryFb {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<ForwardsUOffset<Vector<u32>>>("ends", slot(0), false)?
+                .visit_field::<Vec64>("xy", slot(1), false)?
+                .visit_field::<Vec64>("z", slot(2), false)?
+                .visit_field::<Vec64>("m", slot(3), false)?
+                .visit_field::<Vec64>("t", slot(4), false)?
+                .visit_field::<ForwardsUOffset<Vector<u64>>>("tm", slot(5), false)?
+                .visit_field::<u8>("type", slot(6), false)?
+                .visit_field::<Tables<Self>>("parts", slot(7), false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    /// Verify a size-prefixed flatbuffer and return its root table and size
+    #[expect(unsafe_code)]
+    fn verified_root<T: Verifiable>(buf: &[u8]) -> (Table<'_>, usize) {
+        let size = u32::from_le_bytes(buf[..4].try_into().unwrap()) as usize;
+        let buf = &buf[..4 + size];
+        let opts = VerifierOptions::default();
+        let mut verifier = Verifier::new(&opts, buf);
+        <SkipSizePrefix<ForwardsUOffset<T>>>::run_verifier(&mut verifier, 0).unwrap();
+        // Safety: verified above
+        let root = unsafe { flatbuffers::size_prefixed_root_unchecked::<Table>(buf) };
+        (root, 4 + size)
+    }
+
+    /// Field of a verified table
+    #[expect(unsafe_code)]
+    fn get<'a, T: Follow<'a> + 'a>(
+        table: &Table<'a>,
+        id: VOffsetT,
+    ) -> Option<T::Inner> {
+        // Safety: tables are only read after `verified_root`
+        unsafe { table.get::<T>(slot(id), None) }
+    }
+
+    fn positions(xy: &[f64]) -> Vec<Position> {
+        xy.chunks(2).map(|p| Position::from([p[0], p[1]])).collect()
+    }
+
+    fn lines(xy: &[f64], ends: Option<Vector<u32>>) -> Vec<Vec<Position>> {
+        let npoints = xy.len() / 2;
+        let ends = ends
+            .map_or_else(|| vec![npoints], |e| e.iter().map(|e| e as usize).collect());
+        let mut start = 0;
+        ends.into_iter()
+            .map(|end| {
+                let line = positions(&xy[2 * start..2 * end]);
+                start = end;
+                line
+            })
+            .collect()
+    }
+
+    /// Decode a geometry table back to geojson (`None` for unknown types)
+    fn decode_geometry(table: &Table) -> Option<GeometryValue> {
+        let xy = get::<Vec64>(table, field::GEOMETRY_XY)
+            .map(|xy| xy.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let ends = get::<ForwardsUOffset<Vector<u32>>>(table, field::GEOMETRY_ENDS);
+        let parts = || {
+            get::<Tables<Table>>(table, field::GEOMETRY_PARTS)
+                .unwrap()
+                .iter()
+                .map(|part| decode_geometry(&part))
+                .collect::<Option<Vec<_>>>()
+        };
+        let geometry = match get::<u8>(table, field::GEOMETRY_TYPE).unwrap_or_default()
+        {
+            geometry_type::POINT => GeometryValue::Point {
+                coordinates: positions(&xy).remove(0),
+            },
+            geometry_type::MULTIPOINT => GeometryValue::MultiPoint {
+                coordinates: positions(&xy),
+            },
+            geometry_type::LINESTRING => GeometryValue::LineString {
+                coordinates: positions(&xy),
+            },
+            geometry_type::MULTILINESTRING => GeometryValue::MultiLineString {
+                coordinates: lines(&xy, ends),
+            },
+            geometry_type::POLYGON => GeometryValue::Polygon {
+                coordinates: lines(&xy, ends),
+            },
+            geometry_type::MULTIPOLYGON => GeometryValue::MultiPolygon {
+                coordinates: parts()?
+                    .into_iter()
+                    .map(|part| match part {
+                        GeometryValue::Polygon { coordinates } => Some(coordinates),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            },
+            geometry_type::GEOMETRYCOLLECTION => GeometryValue::GeometryCollection {
+                geometries: parts()?.into_iter().map(Geometry::new).collect(),
+            },
+            _ => return None,
+        };
+        Some(geometry)
+    }
+
+    /// Decode property bytes back to json
+    fn decode_properties(bytes: &[u8], columns: &[(String, u8)]) -> Map<String, Value> {
+        let mut properties = Map::new();
+        let mut at = 0;
+        let u32_at =
+            |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        while at < bytes.len() {
+            let i =
+                usize::from(u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap()));
+            at += 2;
+            let (name, column_type) = &columns[i];
+            let value = match *column_type {
+                2 => {
+                    at += 1;
+                    json!(bytes[at - 1] != 0)
+                }
+                7 => {
+                    at += 8;
+                    json!(i64::from_le_bytes(bytes[at - 8..at].try_into().unwrap()))
+                }
+                10 => {
+                    at += 8;
+                    json!(f64::from_le_bytes(bytes[at - 8..at].try_into().unwrap()))
+                }
+                t => {
+                    let len = u32_at(at) as usize;
+                    at += 4 + len;
+                    let s = std::str::from_utf8(&bytes[at - len..at]).unwrap();
+                    if t == 12 {
+                        serde_json::from_str(s).unwrap()
+                    } else {
+                        json!(s)
+                    }
+                }
+            };
+            properties.insert(name.clone(), value);
+        }
+        properties
+    }
+
+    fn feature(value: GeometryValue, properties: &Value) -> Feature {
+        Feature {
+            geometry: Some(Geometry::new(value)),
+            properties: properties.as_object().cloned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fgb_roundtrip() {
+        let square = vec![
+            Position::from([0.0, 0.0]),
+            Position::from([2.0, 0.0]),
+            Position::from([2.0, 2.0]),
+            Position::from([0.0, 0.0]),
+        ];
+        let hole = vec![
+            Position::from([0.5, 0.5]),
+            Position::from([1.0, 0.5]),
+            Position::from([1.0, 1.0]),
+            Position::from([0.5, 0.5]),
+        ];
+        let features = vec![
+            feature(
+                GeometryValue::Polygon {
+                    coordinates: vec![square.clone(), hole.clone()],
+                },
+                &json!({"title": "tile", "count": 3.0, "ok": true, "n": 7}),
+            ),
+            feature(
+                GeometryValue::MultiPolygon {
+                    coordinates: vec![vec![square.clone()], vec![hole.clone()]],
+                },
+                &json!({"count": 1.5, "tags": ["a"]}),
+            ),
+            feature(
+                GeometryValue::GeometryCollection {
+                    geometries: vec![
+                        Geometry::new(GeometryValue::Point {
+                            coordinates: Position::from([-3.0, 4.0]),
+                        }),
+                        Geometry::new(GeometryValue::LineString { coordinates: hole }),
+                    ],
+                },
+                &json!({}),
+            ),
+        ];
+        let mut buf = Vec::new();
+        write_fgb(&mut buf, "tiles", &features, 4326).unwrap();
+        assert_eq!(buf[..8], MAGIC);
+
+        let (header, header_size) = verified_root::<HeaderFb>(&buf[8..]);
+        assert_eq!(get::<Str>(&header, field::HEADER_NAME), Some("tiles"));
+        let envelope = get::<Vec64>(&header, field::HEADER_ENVELOPE).unwrap();
+        assert_eq!(
+            envelope.iter().collect::<Vec<_>>(),
+            vec![-3.0, 0.0, 2.0, 4.0]
+        );
+        assert_eq!(
+            get::<u8>(&header, field::HEADER_GEOMETRY_TYPE).unwrap_or_default(),
+            geometry_type::UNKNOWN
+        );
+        assert_eq!(get::<u64>(&header, field::HEADER_FEATURES_COUNT), Some(3));
+        assert_eq!(get::<u16>(&header, field::HEADER_INDEX_NODE_SIZE), Some(0));
+        let crs = get::<ForwardsUOffset<Table>>(&header, field::HEADER_CRS).unwrap();
+        assert_eq!(get::<Str>(&crs, field::CRS_ORG), Some("EPSG"));
+        assert_eq!(get::<i32>(&crs, field::CRS_CODE), Some(4326));
+        let columns = get::<Tables<Table>>(&header, field::HEADER_COLUMNS)
+            .unwrap()
+            .iter()
+            .map(|c| {
+                (
+                    get::<Str>(&c, field::COLUMN_NAME).unwrap().to_string(),
+                    get::<u8>(&c, field::COLUMN_TYPE).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let column_types = columns
+            .iter()
+            .map(|(n, t)| (n.as_str(), *t))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            column_types,
+            vec![
+                ("title", 11),
+                ("count", 10),
+                ("ok", 2),
+                ("n", 7),
+                ("tags", 12)
+            ]
+        );
+
+        let mut at = 8 + header_size;
+        for expected in &features {
+            let (table, size) = verified_root::<FeatureFb>(&buf[at..]);
+            at += size;
+            let geometry =
+                get::<ForwardsUOffset<Table>>(&table, field::FEATURE_GEOMETRY).unwrap();
+            assert_eq!(
+                decode_geometry(&geometry).unwrap(),
+                expected.geometry.as_ref().unwrap().value
+            );
+            let properties =
+                get::<ForwardsUOffset<Vector<u8>>>(&table, field::FEATURE_PROPERTIES)
+                    .map(|p| decode_properties(p.bytes(), &columns))
+                    .unwrap_or_default();
+            assert_eq!(Some(properties), expected.properties);
+        }
+        assert_eq!(at, buf.len());
+    }
+}
@@ -3,7 +3,10 @@ use geojson::{Feature, GeoJson, Geometry, GeometryValue, Position};
 use crate::UtilesError;
 use crate::errors::UtilesResult;
 
+pub mod dissolve;
+pub mod fgb;
 pub mod parsing;
+pub mod wkt;
 
 pub fn geojson_geometry_points(g: Geometry) -> Box<dyn Iterator<Item = Position>> {
     match g.value {
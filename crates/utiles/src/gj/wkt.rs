@@ -0,0 +1,122 @@
+//! Well-known-text (WKT) for geojson geometries
+use geojson::{GeometryValue, Position};
+
+fn wkt_position(p: &Position) -> String {
+    p.as_slice()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn wkt_positions(positions: &[Position]) -> String {
+    let coords = positions.iter().map(wkt_position).collect::<Vec<_>>();
+    format!("({})", coords.join(", "))
+}
+
+fn wkt_rings(rings: &[Vec<Position>]) -> String {
+    let rings = rings.iter().map(|r| wkt_positions(r)).collect::<Vec<_>>();
+    format!("({})", rings.join(", "))
+}
+
+fn wkt_tagged(tag: &str, body: &str, is_empty: bool) -> String {
+    if is_empty {
+        format!("{tag} EMPTY")
+    } else {
+        format!("{tag} {body}")
+    }
+}
+
+/// Return the WKT string for a geojson geometry
+#[must_use]
+pub fn geometry_wkt(geometry: &GeometryValue) -> String {
+    match geometry {
+        GeometryValue::Point { coordinates } => wkt_tagged(
+            "POINT",
+            &format!("({})", wkt_position(coordinates)),
+            coordinates.is_empty(),
+        ),
+        GeometryValue::MultiPoint { coordinates } => {
+            let points = coordinates
+                .iter()
+                .map(|p| format!("({})", wkt_position(p)))
+                .collect::<Vec<_>>();
+            wkt_tagged(
+                "MULTIPOINT",
+                &format!("({})", points.join(", ")),
+                coordinates.is_empty(),
+            )
+        }
+        GeometryValue::LineString { coordinates } => wkt_tagged(
+            "LINESTRING",
+            &wkt_positions(coordinates),
+            coordinates.is_empty(),
+        ),
+        GeometryValue::MultiLineString { coordinates } => wkt_tagged(
+            "MULTILINESTRING",
+            &wkt_rings(coordinates),
+            coordinates.is_empty(),
+        ),
+        GeometryValue::Polygon { coordinates } => {
+            wkt_tagged("POLYGON", &wkt_rings(coordinates), coordinates.is_empty())
+        }
+        GeometryValue::MultiPolygon { coordinates } => {
+            let polygons = coordinates.iter().map(|p| wkt_rings(p)).collect::<Vec<_>>();
+            wkt_tagged(
+                "MULTIPOLYGON",
+                &format!("({})", polygons.join(", ")),
+                coordinates.is_empty(),
+            )
+        }
+        GeometryValue::GeometryCollection { geometries } => {
+            let geometries = geometries
+                .iter()
+                .map(|g| geometry_wkt(&g.value))
+                .collect::<Vec<_>>();
+            wkt_tagged(
+                "GEOMETRYCOLLECTION",
+                &format!("({})", geometries.join(", ")),
+                geometries.is_empty(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wkt_geometries() {
+        let ring = vec![
+            Position::from([0.0, 0.0]),
+            Position::from([1.0, 0.0]),
+            Position::from([1.0, 1.0]),
+            Position::from([0.0, 0.0]),
+        ];
+        assert_eq!(
+            geometry_wkt(&GeometryValue::Point {
+                coordinates: Position::from([1.5, -2.0])
+            }),
+            "POINT (1.5 -2)"
+        );
+        assert_eq!(
+            geometry_wkt(&GeometryValue::Polygon {
+                coordinates: vec![ring.clone()]
+            }),
+            "POLYGON ((0 0, 1 0, 1 1, 0 0))"
+        );
+        assert_eq!(
+            geometry_wkt(&GeometryValue::MultiPolygon {
+                coordinates: vec![vec![ring.clone()], vec![ring]]
+            }),
+            "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((0 0, 1 0, 1 1, 0 0)))"
+        );
+        assert_eq!(
+            geometry_wkt(&GeometryValue::MultiLineString {
+                coordinates: vec![]
+            }),
+            "MULTILINESTRING EMPTY"
+        );
+    }
+}
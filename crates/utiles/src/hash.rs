@@ -52,6 +52,7 @@ macro_rules! hex_fns {
         }
     };
 }
+hex_fns!(md5_be, md5_be_hex, md5_be_hex_upper, 16);
 hex_fns!(fnv1a_be, fnv1a_be_hex, fnv1a_be_hex_upper, 8);
 hex_fns!(xxh32_be, xxh32_be_hex, xxh32_be_hex_upper, 4);
 hex_fns!(xxh64_be, xxh64_be_hex, xxh64_be_hex_upper, 8);
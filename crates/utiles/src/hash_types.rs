@@ -3,6 +3,10 @@ use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 
 use crate::UtilesError;
+use crate::hash::{
+    fnv1a_be_hex_upper, md5_be_hex_upper, xxh3_128_be_hex_upper, xxh3_be_hex_upper,
+    xxh32_be_hex_upper, xxh64_be_hex_upper,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
@@ -82,4 +86,48 @@ impl HashType {
             Self::Fnv1a | Self::Xxh64 | Self::Xxh3_64 => 8,
         }
     }
+
+    /// Upper-case hex digest of `data` (same as the sqlite `*_hex` functions)
+    #[must_use]
+    pub fn hash_hex_upper(&self, data: impl AsRef<[u8]>) -> String {
+        match self {
+            Self::Md5 => md5_be_hex_upper(data),
+            Self::Fnv1a => fnv1a_be_hex_upper(data),
+            Self::Xxh32 => xxh32_be_hex_upper(data),
+            Self::Xxh64 => xxh64_be_hex_upper(data),
+            Self::Xxh3_64 => xxh3_be_hex_upper(data),
+            Self::Xxh3_128 => xxh3_128_be_hex_upper(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn hash_hex_upper_matches_sqlite() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::sqlite_utiles::register_utiles_sqlite(&conn).unwrap();
+        for hash_type in [
+            HashType::Md5,
+            HashType::Fnv1a,
+            HashType::Xxh32,
+            HashType::Xxh64,
+            HashType::Xxh3_64,
+            HashType::Xxh3_128,
+        ] {
+            let sql = format!("SELECT {}(?1)", hash_type.sqlite_hex_fn_name());
+            let expected: String = conn
+                .query_row(&sql, [b"tile-data".to_vec()], |row| row.get(0))
+                .unwrap();
+            assert_eq!(
+                hash_type.hash_hex_upper(b"tile-data"),
+                expected,
+                "{hash_type}"
+            );
+        }
+    }
 }
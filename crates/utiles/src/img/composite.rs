@@ -0,0 +1,157 @@
+//! Raster tile compositing (alpha blending)
+//!
+//! Layers are blended in stack order (first layer at the bottom) using the
+//! W3C compositing model: the blend mode mixes the layer color w/ the
+//! backdrop color and the result is composited "source-over" w/ the layer
+//! alpha scaled by the layer opacity.
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Blend mode for mixing a layer w/ the layers below it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Layer color replaces the backdrop color
+    #[default]
+    Normal,
+    /// Backdrop color * layer color (darkens)
+    Multiply,
+    /// Inverse of multiplying the inverses (lightens)
+    Screen,
+}
+
+impl BlendMode {
+    fn mix(self, backdrop: f32, source: f32) -> f32 {
+        match self {
+            Self::Normal => source,
+            Self::Multiply => backdrop * source,
+            Self::Screen => backdrop + source - backdrop * source,
+        }
+    }
+}
+
+/// Blend mode + opacity of a layer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompositeLayer {
+    pub blend: BlendMode,
+    /// Opacity 0.0-1.0 (multiplies the layer alpha)
+    pub opacity: f32,
+}
+
+impl Default for CompositeLayer {
+    fn default() -> Self {
+        Self {
+            blend: BlendMode::Normal,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Blend a source pixel onto a backdrop pixel
+#[must_use]
+pub fn blend_pixel(
+    backdrop: Rgba<u8>,
+    source: Rgba<u8>,
+    layer: CompositeLayer,
+) -> Rgba<u8> {
+    let a_b = f32::from(backdrop[3]) / 255.0;
+    let a_s = f32::from(source[3]) / 255.0 * layer.opacity.clamp(0.0, 1.0);
+    if a_s <= 0.0 {
+        return backdrop;
+    }
+    let a_o = a_s + a_b * (1.0 - a_s);
+    let mut out = [0_u8; 4];
+    for c in 0..3 {
+        let c_b = f32::from(backdrop[c]) / 255.0;
+        let c_s = f32::from(source[c]) / 255.0;
+        let mixed = (1.0 - a_b) * c_s + a_b * layer.blend.mix(c_b, c_s);
+        let c_o = (a_s * mixed + a_b * c_b * (1.0 - a_s)) / a_o;
+        out[c] = (c_o * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+    out[3] = (a_o * 255.0).round().clamp(0.0, 255.0) as u8;
+    Rgba(out)
+}
+
+/// Composite layers (bottom to top) into a single image
+///
+/// The output is the size of the first layer; other layers are resampled
+/// to that size (bilinear) if they differ. Returns `None` if there are no
+/// layers.
+#[must_use]
+pub fn composite_images(
+    layers: Vec<(DynamicImage, CompositeLayer)>,
+) -> Option<DynamicImage> {
+    let (width, height) = {
+        let (first, _) = layers.first()?;
+        (first.width(), first.height())
+    };
+    let mut out = RgbaImage::new(width, height);
+    for (img, layer) in layers {
+        let img = if img.width() == width && img.height() == height {
+            img
+        } else {
+            img.resize_exact(width, height, FilterType::Triangle)
+        };
+        let img = img.to_rgba8();
+        for (dst, src) in out.pixels_mut().zip(img.pixels()) {
+            *dst = blend_pixel(*dst, *src, layer);
+        }
+    }
+    Some(DynamicImage::ImageRgba8(out))
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use image::GenericImageView;
+
+    use super::*;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+    const GREY: Rgba<u8> = Rgba([128, 128, 128, 255]);
+    const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+    fn layer(blend: BlendMode, opacity: f32) -> CompositeLayer {
+        CompositeLayer { blend, opacity }
+    }
+
+    #[test]
+    fn blend_modes() {
+        let normal = layer(BlendMode::Normal, 1.0);
+        assert_eq!(blend_pixel(BLUE, RED, normal), RED);
+        assert_eq!(blend_pixel(CLEAR, RED, normal), RED);
+        assert_eq!(blend_pixel(RED, CLEAR, normal), RED);
+        assert_eq!(
+            blend_pixel(BLUE, RED, layer(BlendMode::Normal, 0.5)),
+            Rgba([128, 0, 128, 255])
+        );
+        assert_eq!(
+            blend_pixel(RED, GREY, layer(BlendMode::Multiply, 1.0)),
+            Rgba([128, 0, 0, 255])
+        );
+        assert_eq!(
+            blend_pixel(RED, GREY, layer(BlendMode::Screen, 1.0)),
+            Rgba([255, 128, 128, 255])
+        );
+        // blend mode is irrelevant over a transparent backdrop
+        assert_eq!(
+            blend_pixel(CLEAR, GREY, layer(BlendMode::Multiply, 0.5)),
+            Rgba([128, 128, 128, 128])
+        );
+    }
+
+    #[test]
+    fn composite_stack() {
+        let base = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, RED));
+        let overlay = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, GREY));
+        let out = composite_images(vec![
+            (base, CompositeLayer::default()),
+            (overlay, layer(BlendMode::Multiply, 1.0)),
+        ])
+        .unwrap();
+        assert_eq!(out.dimensions(), (4, 4));
+        assert_eq!(out.get_pixel(3, 3), Rgba([128, 0, 0, 255]));
+        assert!(composite_images(vec![]).is_none());
+    }
+}
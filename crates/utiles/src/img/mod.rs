@@ -1,30 +1,29 @@
-use std::io::Cursor;
-
+#[cfg(feature = "oxipng")]
 use tracing::warn;
+#[cfg(feature = "oxipng")]
 use utiles_core::tile_type::{TileFormat, tiletype};
 
 use crate::UtilesResult;
 
+pub mod composite;
 pub mod raster_tile_join;
+pub mod resize;
+pub mod stats;
+pub mod terrain;
+pub mod webp;
+
+pub use composite::{BlendMode, CompositeLayer, blend_pixel, composite_images};
+pub use resize::{encode_tile, mosaic_tiles, resize_tile, split_tile};
+pub use terrain::{ElevationTile, HillshadeOptions, convert_terrain_tile, hillshade};
+pub use webp::{WebpOptions, encode_webp, webpify_image_with};
 
 pub fn load_from_memory(data: &[u8]) -> UtilesResult<image::DynamicImage> {
     image::load_from_memory(data).map_err(|e| e.into())
 }
 
+/// Convert image data (png/jpg/gif) to (exact) webp
 pub fn webpify_image(data: &[u8]) -> UtilesResult<Vec<u8>> {
-    match tiletype(data).format {
-        TileFormat::Webp => Ok(data.to_vec()),
-        TileFormat::Jpg | TileFormat::Png | TileFormat::Gif => {
-            let img = image::load_from_memory(data)?;
-            let mut buf = Vec::new();
-            img.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::WebP)?;
-            Ok(buf)
-        }
-        _ => {
-            warn!("Unsupported image type");
-            Ok(data.to_vec())
-        }
-    }
+    webpify_image_with(data, &WebpOptions::default())
 }
 
 #[must_use]
@@ -38,6 +37,30 @@ pub fn image_is_transparent(img: &image::DynamicImage) -> bool {
     }
 }
 
+/// Return true if every pixel of the image is fully transparent (alpha 0)
+#[must_use]
+pub fn image_is_empty(img: &image::DynamicImage) -> bool {
+    image_is_transparent(img)
+        && match img {
+            image::DynamicImage::ImageRgba8(img) => img.pixels().all(|p| p[3] == 0),
+            image::DynamicImage::ImageRgba16(img) => img.pixels().all(|p| p[3] == 0),
+            image::DynamicImage::ImageLumaA8(img) => img.pixels().all(|p| p[1] == 0),
+            image::DynamicImage::ImageLumaA16(img) => img.pixels().all(|p| p[1] == 0),
+            _ => false,
+        }
+}
+
+/// Optimize png data w/ oxipng (non-png data is returned as is)
+#[cfg(feature = "oxipng")]
+pub fn oxipngify(data: &[u8], options: &oxipng::Options) -> UtilesResult<Vec<u8>> {
+    if tiletype(data).format == TileFormat::Png {
+        oxipng::optimize_from_memory(data, options).map_err(Into::into)
+    } else {
+        warn!("Unsupported image type");
+        Ok(data.to_vec())
+    }
+}
+
 // TODO: Implement pngify_image
 // fn pngify_image(data: &Vec<u8>) -> UtilesResult<Vec<u8>> {
 //     let img = image::load_from_memory(&data)?;
@@ -0,0 +1,173 @@
+//! Raster tile resizing
+//!
+//! Tiles are resized by mosaicking the 4 children of a tile into a single
+//! tile (e.g. 4x 256px tiles at z+1 -> 1x 512px tile at z) or by splitting a
+//! tile into its 4 children (e.g. 1x 512px tile at z -> 4x 256px tiles at
+//! z+1). Images that are not the expected size are resampled w/ the filter.
+use std::io::Cursor;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage, ImageFormat};
+use utiles_core::tile_type::TileFormat;
+
+use super::image_is_transparent;
+use super::webp::{WebpOptions, encode_webp};
+use crate::{UtilesError, UtilesResult};
+
+/// Resample an image to `tile_size` x `tile_size` (if not already)
+#[must_use]
+pub fn resize_tile(
+    img: DynamicImage,
+    tile_size: u32,
+    filter: FilterType,
+) -> DynamicImage {
+    if img.width() == tile_size && img.height() == tile_size {
+        img
+    } else {
+        img.resize_exact(tile_size, tile_size, filter)
+    }
+}
+
+/// Mosaic 4 children (top-left, top-right, bottom-left, bottom-right) into a
+/// single `tile_size` image; missing children are left transparent
+///
+/// Returns `None` if all the children are missing.
+///
+/// # Errors
+///
+/// Returns an error if `tile_size` is not even.
+pub fn mosaic_tiles(
+    children: [Option<DynamicImage>; 4],
+    tile_size: u32,
+    filter: FilterType,
+) -> UtilesResult<Option<DynamicImage>> {
+    if !tile_size.is_multiple_of(2) {
+        return Err(UtilesError::AdHoc(format!(
+            "tile size must be even: {tile_size}"
+        )));
+    }
+    if children.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+    let half = tile_size / 2;
+    let mut out = DynamicImage::new_rgba8(tile_size, tile_size);
+    for (i, child) in children.into_iter().enumerate() {
+        if let Some(child) = child {
+            let (x, y) = (i as u32 % 2 * half, i as u32 / 2 * half);
+            let child = resize_tile(child, half, filter).to_rgba8();
+            out.copy_from(&child, x, y)?;
+        }
+    }
+    Ok(Some(out))
+}
+
+/// Split an image into its 4 children (top-left, top-right, bottom-left,
+/// bottom-right) each `tile_size` x `tile_size`
+#[must_use]
+pub fn split_tile(
+    img: DynamicImage,
+    tile_size: u32,
+    filter: FilterType,
+) -> [DynamicImage; 4] {
+    let img = resize_tile(img, tile_size * 2, filter);
+    [(0, 0), (1, 0), (0, 1), (1, 1)]
+        .map(|(x, y)| img.crop_imm(x * tile_size, y * tile_size, tile_size, tile_size))
+}
+
+/// Encode a tile image as png, jpg or webp
+///
+/// Opaque images are encoded w/o an alpha channel; jpg never has one.
+///
+/// # Errors
+///
+/// Returns an error if the format is not a supported raster format or if
+/// encoding fails.
+pub fn encode_tile(img: &DynamicImage, format: TileFormat) -> UtilesResult<Vec<u8>> {
+    let opaque = !image_is_transparent(img);
+    let mut buf = Vec::new();
+    match format {
+        TileFormat::Png => {
+            if opaque {
+                DynamicImage::ImageRgb8(img.to_rgb8())
+                    .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)?;
+            } else {
+                img.write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)?;
+            }
+        }
+        TileFormat::Jpg => {
+            DynamicImage::ImageRgb8(img.to_rgb8())
+                .write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)?;
+        }
+        TileFormat::Webp => {
+            buf = encode_webp(img, &WebpOptions::default())?;
+        }
+        _ => {
+            return Err(UtilesError::Unsupported(format!(
+                "raster tile format: {format}"
+            )));
+        }
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use image::{GenericImageView, Rgba, RgbaImage};
+
+    use super::*;
+
+    fn quadrants(size: u32) -> DynamicImage {
+        let half = size / 2;
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(size, size, |x, y| {
+            let q = (x / half + 2 * (y / half)) as u8;
+            Rgba([q * 60, 0, 0, 255])
+        }))
+    }
+
+    #[test]
+    fn split_then_mosaic_roundtrip() {
+        let img = quadrants(512);
+        let children = split_tile(img.clone(), 256, FilterType::Nearest);
+        for (i, child) in children.iter().enumerate() {
+            assert_eq!(child.dimensions(), (256, 256));
+            assert_eq!(child.get_pixel(0, 0), Rgba([i as u8 * 60, 0, 0, 255]));
+        }
+        let joined = mosaic_tiles(children.map(Some), 512, FilterType::Nearest)
+            .unwrap()
+            .unwrap();
+        assert_eq!(joined.to_rgba8(), img.to_rgba8());
+    }
+
+    #[test]
+    fn mosaic_missing_children() {
+        let child = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            128,
+            128,
+            Rgba([255, 0, 0, 255]),
+        ));
+        assert!(
+            mosaic_tiles([None, None, None, None], 512, FilterType::Nearest)
+                .unwrap()
+                .is_none()
+        );
+        let joined =
+            mosaic_tiles([None, None, None, Some(child)], 512, FilterType::Triangle)
+                .unwrap()
+                .unwrap();
+        assert_eq!(joined.dimensions(), (512, 512));
+        assert_eq!(joined.get_pixel(0, 0)[3], 0);
+        assert_eq!(joined.get_pixel(511, 511), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn encode_formats() {
+        let img = quadrants(64);
+        for format in [TileFormat::Png, TileFormat::Jpg, TileFormat::Webp] {
+            let data = encode_tile(&img, format).unwrap();
+            assert_eq!(utiles_core::tile_type::tiletype(&data).format, format);
+        }
+        assert!(encode_tile(&img, TileFormat::Pbf).is_err());
+    }
+}
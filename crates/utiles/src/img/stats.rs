@@ -0,0 +1,302 @@
+//! Raster tile statistics (band histograms, blank/single-color tiles and
+//! dominant colors)
+//!
+//! Fully transparent pixels are counted as `#00000000` regardless of their
+//! rgb values. Dominant colors are approximate: only the top colors of each
+//! tile are accumulated.
+use std::collections::HashMap;
+
+use image::DynamicImage;
+use serde::Serialize;
+
+use crate::{UtilesError, UtilesResult};
+
+/// Band names (rgba)
+pub const BANDS: [&str; 4] = ["r", "g", "b", "a"];
+
+/// Min/max/sum + histogram of a band
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BandStats {
+    pub min: u8,
+    pub max: u8,
+    pub sum: u64,
+    pub histogram: Vec<u64>,
+}
+
+impl BandStats {
+    #[must_use]
+    pub fn new(bins: usize) -> Self {
+        Self {
+            min: u8::MAX,
+            max: u8::MIN,
+            sum: 0,
+            histogram: vec![0; bins.clamp(1, 256)],
+        }
+    }
+
+    fn add_value(&mut self, value: u8) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += u64::from(value);
+        let bin = usize::from(value) * self.histogram.len() / 256;
+        self.histogram[bin] += 1;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        for (a, b) in self.histogram.iter_mut().zip(&other.histogram) {
+            *a += b;
+        }
+    }
+}
+
+/// Statistics of a single raster tile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileRasterStats {
+    pub npixels: u64,
+    pub bands: [BandStats; 4],
+    /// The color of every pixel if the tile is a single color
+    pub single_color: Option<[u8; 4]>,
+    /// Most common colors w/ their pixel counts (descending)
+    pub top_colors: Vec<([u8; 4], u64)>,
+}
+
+impl TileRasterStats {
+    /// Every pixel is fully transparent
+    #[must_use]
+    pub fn is_transparent(&self) -> bool {
+        self.single_color.is_some_and(|c| c[3] == 0)
+    }
+}
+
+const fn normalize(pixel: [u8; 4]) -> [u8; 4] {
+    if pixel[3] == 0 { [0, 0, 0, 0] } else { pixel }
+}
+
+/// Sort color counts (descending count, then color) and keep the top `n`
+fn top_n(counts: HashMap<[u8; 4], u64>, n: usize) -> Vec<([u8; 4], u64)> {
+    let mut colors: Vec<([u8; 4], u64)> = counts.into_iter().collect();
+    colors.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    colors.truncate(n);
+    colors
+}
+
+/// Compute the statistics of a tile image w/ `bins` histogram bins per band
+/// and the top `ncolors` colors
+#[must_use]
+pub fn tile_raster_stats(
+    img: &DynamicImage,
+    bins: usize,
+    ncolors: usize,
+) -> TileRasterStats {
+    let rgba = img.to_rgba8();
+    let mut bands: [BandStats; 4] = std::array::from_fn(|_| BandStats::new(bins));
+    let mut counts: HashMap<[u8; 4], u64> = HashMap::new();
+    for pixel in rgba.pixels() {
+        for (band, value) in bands.iter_mut().zip(pixel.0) {
+            band.add_value(value);
+        }
+        *counts.entry(normalize(pixel.0)).or_default() += 1;
+    }
+    let single_color = if counts.len() == 1 {
+        counts.keys().next().copied()
+    } else {
+        None
+    };
+    TileRasterStats {
+        npixels: u64::from(rgba.width()) * u64::from(rgba.height()),
+        bands,
+        single_color,
+        top_colors: top_n(counts, ncolors),
+    }
+}
+
+/// Format a color as `#rrggbbaa`
+#[must_use]
+pub fn color_hex(color: [u8; 4]) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        color[0], color[1], color[2], color[3]
+    )
+}
+
+/// Parse a `#rrggbb` or `#rrggbbaa` color (`#` optional)
+///
+/// # Errors
+///
+/// Returns an error if the string is not a hex color.
+pub fn parse_color_hex(s: &str) -> UtilesResult<[u8; 4]> {
+    let hex = s.trim().trim_start_matches('#');
+    let invalid = || UtilesError::ParsingError(format!("invalid hex color: {s}"));
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut color = [0, 0, 0, 255];
+    for (i, c) in color.iter_mut().enumerate().take(hex.len() / 2) {
+        *c = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(normalize(color))
+}
+
+/// Band summary (json)
+#[derive(Debug, Clone, Serialize)]
+pub struct BandSummary {
+    pub band: &'static str,
+    pub min: u8,
+    pub max: u8,
+    pub mean: f64,
+    pub histogram: Vec<u64>,
+}
+
+/// Color w/ its pixel count (json)
+#[derive(Debug, Clone, Serialize)]
+pub struct ColorCount {
+    pub color: String,
+    pub npixels: u64,
+    /// Fraction of all pixels
+    pub ratio: f64,
+}
+
+/// Accumulated statistics of many raster tiles
+#[derive(Debug, Clone)]
+pub struct RasterStats {
+    pub ntiles: u64,
+    pub npixels: u64,
+    pub ntransparent: u64,
+    pub nsingle_color: u64,
+    pub bands: [BandStats; 4],
+    pub colors: HashMap<[u8; 4], u64>,
+}
+
+impl RasterStats {
+    #[must_use]
+    pub fn new(bins: usize) -> Self {
+        Self {
+            ntiles: 0,
+            npixels: 0,
+            ntransparent: 0,
+            nsingle_color: 0,
+            bands: std::array::from_fn(|_| BandStats::new(bins)),
+            colors: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, stats: &TileRasterStats) {
+        self.ntiles += 1;
+        self.npixels += stats.npixels;
+        if stats.is_transparent() {
+            self.ntransparent += 1;
+        }
+        if stats.single_color.is_some() {
+            self.nsingle_color += 1;
+        }
+        for (band, other) in self.bands.iter_mut().zip(&stats.bands) {
+            band.merge(other);
+        }
+        for (color, n) in &stats.top_colors {
+            *self.colors.entry(*color).or_default() += n;
+        }
+    }
+
+    #[must_use]
+    #[expect(clippy::cast_precision_loss)]
+    pub fn band_summaries(&self) -> Vec<BandSummary> {
+        BANDS
+            .iter()
+            .zip(&self.bands)
+            .map(|(name, band)| BandSummary {
+                band: name,
+                min: band.min,
+                max: band.max,
+                mean: if self.npixels == 0 {
+                    0.0
+                } else {
+                    band.sum as f64 / self.npixels as f64
+                },
+                histogram: band.histogram.clone(),
+            })
+            .collect()
+    }
+
+    #[must_use]
+    #[expect(clippy::cast_precision_loss)]
+    pub fn dominant_colors(&self, n: usize) -> Vec<ColorCount> {
+        top_n(self.colors.clone(), n)
+            .into_iter()
+            .map(|(color, npixels)| ColorCount {
+                color: color_hex(color),
+                npixels,
+                ratio: if self.npixels == 0 {
+                    0.0
+                } else {
+                    npixels as f64 / self.npixels as f64
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    #[test]
+    fn single_color_and_transparent() {
+        let ocean = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            4,
+            4,
+            Rgba([170, 211, 223, 255]),
+        ));
+        let stats = tile_raster_stats(&ocean, 16, 4);
+        assert_eq!(stats.single_color, Some([170, 211, 223, 255]));
+        assert!(!stats.is_transparent());
+        assert_eq!(stats.bands[0].min, 170);
+        assert_eq!(stats.bands[0].histogram[170 * 16 / 256], 16);
+
+        // transparent pixels w/ different rgb values are all `#00000000`
+        let clear = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, _| {
+            Rgba([x as u8, 0, 0, 0])
+        }));
+        let stats = tile_raster_stats(&clear, 16, 4);
+        assert!(stats.is_transparent());
+        assert_eq!(color_hex(stats.single_color.unwrap()), "#00000000");
+    }
+
+    #[test]
+    fn accumulate() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, _| {
+            if x == 0 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        }));
+        let tile_stats = tile_raster_stats(&img, 4, 8);
+        assert!(tile_stats.single_color.is_none());
+        let mut stats = RasterStats::new(4);
+        stats.add(&tile_stats);
+        stats.add(&tile_stats);
+        assert_eq!(stats.ntiles, 2);
+        let dominant = stats.dominant_colors(1);
+        assert_eq!(dominant[0].color, "#0000ffff");
+        assert!((dominant[0].ratio - 0.75).abs() < 1e-9);
+        let bands = stats.band_summaries();
+        assert!((bands[0].mean - 63.75).abs() < 1e-9);
+        assert_eq!(bands[2].histogram, vec![8, 0, 0, 24]);
+    }
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color_hex("#aad3df").unwrap(), [170, 211, 223, 255]);
+        assert_eq!(parse_color_hex("aad3df80").unwrap(), [170, 211, 223, 128]);
+        assert_eq!(parse_color_hex("#ffffff00").unwrap(), [0, 0, 0, 0]);
+        assert!(parse_color_hex("#abc").is_err());
+        assert!(parse_color_hex("#gggggg").is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Elevation (dem) raster tiles
+//!
+//! Decode Terrain-RGB/Terrarium tiles into elevations (and back), convert
+//! between the encodings and render hillshade tiles (Horn's method).
+//!
+//! Hillshade slopes at the tile edges are computed w/ the edge pixels
+//! repeated (no neighboring tiles are read).
+use image::{DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+use utiles_core::terrain::TerrainEncoding;
+use utiles_core::tile_type::TileFormat;
+
+use super::load_from_memory;
+use super::resize::encode_tile;
+use crate::{UtilesError, UtilesResult};
+
+/// Elevations (meters) of a tile's pixels (row major)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElevationTile {
+    pub width: u32,
+    pub height: u32,
+    pub elevations: Vec<f64>,
+}
+
+impl ElevationTile {
+    /// Decode the elevations of an image w/ the given encoding
+    #[must_use]
+    pub fn from_image(img: &DynamicImage, encoding: TerrainEncoding) -> Self {
+        let rgb = img.to_rgb8();
+        Self {
+            width: rgb.width(),
+            height: rgb.height(),
+            elevations: rgb.pixels().map(|p| encoding.decode(p.0)).collect(),
+        }
+    }
+
+    /// Decode the elevations of tile data (png/webp) w/ the given encoding
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data cannot be decoded as an image.
+    pub fn decode(data: &[u8], encoding: TerrainEncoding) -> UtilesResult<Self> {
+        Ok(Self::from_image(&load_from_memory(data)?, encoding))
+    }
+
+    /// Elevation at a pixel (clamped to the tile)
+    #[must_use]
+    pub fn get(&self, x: i64, y: i64) -> f64 {
+        let x = x.clamp(0, i64::from(self.width) - 1) as usize;
+        let y = y.clamp(0, i64::from(self.height) - 1) as usize;
+        self.elevations[y * self.width as usize + x]
+    }
+
+    /// Encode the elevations as an rgb image w/ the given encoding
+    #[must_use]
+    pub fn to_image(&self, encoding: TerrainEncoding) -> RgbImage {
+        RgbImage::from_fn(self.width, self.height, |x, y| {
+            Rgb(encoding.encode(self.get(i64::from(x), i64::from(y))))
+        })
+    }
+}
+
+/// Re-encode elevation tile data (e.g. Terrain-RGB -> Terrarium)
+///
+/// # Errors
+///
+/// Returns an error if the data cannot be decoded, if the format is lossy
+/// (jpg; which would corrupt the elevations) or if encoding fails.
+pub fn convert_terrain_tile(
+    data: &[u8],
+    from: TerrainEncoding,
+    to: TerrainEncoding,
+    format: TileFormat,
+) -> UtilesResult<Vec<u8>> {
+    if format == TileFormat::Jpg {
+        return Err(UtilesError::Unsupported(
+            "jpg elevation tiles (lossy)".to_string(),
+        ));
+    }
+    let elevation = ElevationTile::decode(data, from)?;
+    encode_tile(&DynamicImage::ImageRgb8(elevation.to_image(to)), format)
+}
+
+/// Hillshade options (GDAL `gdaldem hillshade` defaults)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HillshadeOptions {
+    /// Direction of the light source (degrees clockwise from north)
+    pub azimuth: f64,
+    /// Altitude of the light source (degrees above the horizon)
+    pub altitude: f64,
+    /// Vertical exaggeration
+    pub z_factor: f64,
+}
+
+impl Default for HillshadeOptions {
+    fn default() -> Self {
+        Self {
+            azimuth: 315.0,
+            altitude: 45.0,
+            z_factor: 1.0,
+        }
+    }
+}
+
+/// Render a (grayscale) hillshade of elevations w/ pixels `cell_size`
+/// meters wide
+#[must_use]
+#[expect(clippy::many_single_char_names)]
+pub fn hillshade(
+    elevation: &ElevationTile,
+    cell_size: f64,
+    options: &HillshadeOptions,
+) -> GrayImage {
+    let zenith = (90.0 - options.altitude).to_radians();
+    let azimuth = (360.0 - options.azimuth + 90.0)
+        .rem_euclid(360.0)
+        .to_radians();
+    let (cos_zenith, sin_zenith) = (zenith.cos(), zenith.sin());
+    GrayImage::from_fn(elevation.width, elevation.height, |x, y| {
+        let (x, y) = (i64::from(x), i64::from(y));
+        let z = |dx: i64, dy: i64| elevation.get(x + dx, y + dy);
+        // 3x3 window: a b c / d e f / g h i
+        let (a, b, c) = (z(-1, -1), z(0, -1), z(1, -1));
+        let (d, f) = (z(-1, 0), z(1, 0));
+        let (g, h, i) = (z(-1, 1), z(0, 1), z(1, 1));
+        let dz_east = ((c + 2.0 * f + i) - (a + 2.0 * d + g)) / (8.0 * cell_size);
+        let dz_south = ((g + 2.0 * h + i) - (a + 2.0 * b + c)) / (8.0 * cell_size);
+        let slope = (options.z_factor * dz_east.hypot(dz_south)).atan();
+        let aspect = dz_south.atan2(-dz_east);
+        let shade = cos_zenith.mul_add(
+            slope.cos(),
+            sin_zenith * slope.sin() * (azimuth - aspect).cos(),
+        );
+        Luma([(255.0 * shade).round().clamp(0.0, 255.0) as u8])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn plane(dx: f64, dy: f64) -> ElevationTile {
+        let elevations = (0..16 * 16)
+            .map(|i| f64::from(i % 16) * dx + f64::from(i / 16) * dy)
+            .collect();
+        ElevationTile {
+            width: 16,
+            height: 16,
+            elevations,
+        }
+    }
+
+    #[test]
+    fn terrain_convert_roundtrip() {
+        let elevation = plane(12.3, -4.5);
+        let img =
+            DynamicImage::ImageRgb8(elevation.to_image(TerrainEncoding::TerrainRgb));
+        let data = encode_tile(&img, TileFormat::Png).unwrap();
+        let terrarium = convert_terrain_tile(
+            &data,
+            TerrainEncoding::TerrainRgb,
+            TerrainEncoding::Terrarium,
+            TileFormat::Webp,
+        )
+        .unwrap();
+        let decoded =
+            ElevationTile::decode(&terrarium, TerrainEncoding::Terrarium).unwrap();
+        for (a, b) in decoded.elevations.iter().zip(&elevation.elevations) {
+            assert!((a - b).abs() < 0.06, "{a} {b}");
+        }
+        assert!(
+            convert_terrain_tile(
+                &data,
+                TerrainEncoding::TerrainRgb,
+                TerrainEncoding::Terrarium,
+                TileFormat::Jpg,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn hillshade_light_direction() {
+        let options = HillshadeOptions::default();
+        let flat = hillshade(&plane(0.0, 0.0), 10.0, &options);
+        // flat ground is lit at cos(zenith)
+        assert_eq!(flat.get_pixel(8, 8)[0], 180);
+        // rising to the south-east faces the (north-west) light
+        let lit = hillshade(&plane(5.0, 5.0), 10.0, &options);
+        let shaded = hillshade(&plane(-5.0, -5.0), 10.0, &options);
+        assert!(lit.get_pixel(8, 8)[0] > 180);
+        assert!(shaded.get_pixel(8, 8)[0] < 180);
+    }
+}
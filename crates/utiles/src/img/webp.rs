@@ -0,0 +1,154 @@
+//! Webp encoding w/ options
+//!
+//! Encoded w/ libwebp (`webp` crate); lossless (VP8L) unless a lossy
+//! `quality` is given, in which case the image is encoded as lossy VP8 w/
+//! the given alpha quality.
+use tracing::warn;
+use utiles_core::tile_type::{TileFormat, tiletype};
+use webp::{Encoder, WebPConfig};
+
+use crate::{UtilesError, UtilesResult};
+
+/// Webp encoding options (defaults encode the exact pixels)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WebpOptions {
+    /// Lossy quality 0-100; `None` encodes lossless
+    pub quality: Option<u8>,
+    /// Compression effort 0-6 (0 = fastest, 6 = smallest)
+    pub method: u8,
+    /// Lossy alpha quality 0-100
+    pub alpha_quality: u8,
+    /// Near-lossless preprocessing level 0-100 (100 = off; lossless only)
+    pub near_lossless: u8,
+}
+
+impl Default for WebpOptions {
+    fn default() -> Self {
+        Self {
+            quality: None,
+            method: 4,
+            alpha_quality: 100,
+            near_lossless: 100,
+        }
+    }
+}
+
+impl WebpOptions {
+    /// libwebp encoder config for the options
+    fn config(self) -> UtilesResult<WebPConfig> {
+        let mut config = WebPConfig::new().map_err(|()| {
+            UtilesError::WebpEncodingError("invalid libwebp config".to_string())
+        })?;
+        if let Some(quality) = self.quality {
+            config.lossless = 0;
+            config.quality = f32::from(quality.min(100));
+            config.alpha_quality = i32::from(self.alpha_quality.min(100));
+        } else {
+            config.lossless = 1;
+            // keep the rgb of transparent pixels
+            config.exact = 1;
+            config.near_lossless = i32::from(self.near_lossless.min(100));
+        }
+        config.method = i32::from(self.method.min(6));
+        Ok(config)
+    }
+}
+
+/// Encode an image as webp w/ the given options
+pub fn encode_webp(
+    img: &image::DynamicImage,
+    options: &WebpOptions,
+) -> UtilesResult<Vec<u8>> {
+    let config = options.config()?;
+    let (width, height) = (img.width(), img.height());
+    let encoded = if img.color().has_alpha() {
+        let rgba = img.to_rgba8();
+        Encoder::from_rgba(&rgba, width, height).encode_advanced(&config)
+    } else {
+        let rgb = img.to_rgb8();
+        Encoder::from_rgb(&rgb, width, height).encode_advanced(&config)
+    }
+    .map_err(|e| UtilesError::WebpEncodingError(format!("{e:?}")))?;
+    Ok(encoded.to_vec())
+}
+
+/// Convert image data (png/jpg/gif) to webp w/ the given options
+///
+/// Webp data is returned as is.
+pub fn webpify_image_with(data: &[u8], options: &WebpOptions) -> UtilesResult<Vec<u8>> {
+    match tiletype(data).format {
+        TileFormat::Webp => Ok(data.to_vec()),
+        TileFormat::Jpg | TileFormat::Png | TileFormat::Gif => {
+            let img = image::load_from_memory(data)?;
+            encode_webp(&img, options)
+        }
+        _ => {
+            warn!("Unsupported image type");
+            Ok(data.to_vec())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use std::io::Cursor;
+
+    use image::{ImageFormat, Rgba, RgbaImage};
+
+    use super::*;
+
+    fn png_data() -> Vec<u8> {
+        // noisy pixels (lossy helps compression)
+        let mut state = 0x2545_f491_u32;
+        let img = RgbaImage::from_fn(64, 64, |_, _| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            Rgba([state as u8, (state >> 8) as u8, (state >> 16) as u8, 200])
+        });
+        let mut buf = Vec::new();
+        img.write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+            .unwrap();
+        buf
+    }
+
+    /// Webp chunk fourcc after the RIFF header (`VP8L` lossless, `VP8X`
+    /// extended ~ lossy w/ alpha)
+    fn webp_chunk(data: &[u8]) -> &[u8] {
+        &data[12..16]
+    }
+
+    #[test]
+    fn webp_lossless_vs_quality() {
+        let png = png_data();
+        let exact = webpify_image_with(
+            &png,
+            &WebpOptions {
+                method: 6,
+                ..WebpOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(tiletype(&exact).format, TileFormat::Webp);
+        assert_eq!(webp_chunk(&exact), b"VP8L");
+        let decoded = image::load_from_memory(&exact).unwrap().to_rgba8();
+        assert_eq!(decoded, image::load_from_memory(&png).unwrap().to_rgba8());
+
+        let lossy = webpify_image_with(
+            &png,
+            &WebpOptions {
+                quality: Some(25),
+                alpha_quality: 50,
+                ..WebpOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(tiletype(&lossy).format, TileFormat::Webp);
+        assert_eq!(webp_chunk(&lossy), b"VP8X");
+        assert!(lossy.len() < exact.len());
+        let decoded = image::load_from_memory(&lossy).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (64, 64));
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use globset::{Glob, GlobSetBuilder};
 use tracing::{debug, warn};
@@ -33,6 +33,37 @@ pub(crate) fn find_datasets(fspath: &str) -> UtilesResult<Vec<PathBuf>> {
     Ok(filepaths)
 }
 
+/// Return the directories in `fspaths` (and their immediate subdirectories)
+/// that satisfy `is_dataset_dir` ~ used to find tile pyramid directories
+pub(crate) fn find_dirpaths(
+    fspaths: &[String],
+    is_dataset_dir: impl Fn(&Path) -> bool,
+) -> Vec<PathBuf> {
+    let mut dirpaths: Vec<PathBuf> = vec![];
+    for fspath in fspaths {
+        let path = Path::new(fspath);
+        if !path.is_dir() {
+            continue;
+        }
+        if is_dataset_dir(path) {
+            dirpaths.push(path.into());
+            continue;
+        }
+        for entry in WalkDir::new(path)
+            .min_depth(1)
+            .max_depth(1)
+            .into_iter()
+            .flatten()
+        {
+            if entry.file_type().is_dir() && is_dataset_dir(entry.path()) {
+                dirpaths.push(entry.path().into());
+            }
+        }
+    }
+    debug!("dirpaths: {:?}", dirpaths);
+    dirpaths
+}
+
 pub(crate) fn find_filepaths(fspaths: &[String]) -> UtilesResult<Vec<PathBuf>> {
     // split the paths up into files and dirs/patterns
     let mut files: Vec<String> = vec![];
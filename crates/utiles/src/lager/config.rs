@@ -1,8 +1,10 @@
+use serde::{Deserialize, Serialize};
 use tracing_subscriber::EnvFilter;
 
 use crate::lager::LagerLevel;
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct LagerConfig {
     pub json: bool,
     pub level: LagerLevel,
@@ -1,9 +1,12 @@
 use std::fmt::Formatter;
 use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
 use crate::UtilesError;
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LagerLevel {
     Trace = 0,
     Debug = 1,
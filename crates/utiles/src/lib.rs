@@ -22,6 +22,7 @@ pub mod print;
 
 #[cfg(feature = "cli")]
 pub mod cli;
+pub mod codec;
 mod config;
 pub mod copy;
 pub mod core;
@@ -2,6 +2,7 @@ use futures::{Stream, StreamExt};
 use hex::ToHex;
 use md5::Digest;
 use noncrypto_digests::Fnv;
+use rusqlite::Connection;
 use serde::Serialize;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::debug;
@@ -10,6 +11,7 @@ use utiles_core::TileLike;
 use crate::errors::UtilesResult;
 use crate::hash_types::HashType;
 use crate::mbt::{MbtilesClientAsync, TilesFilter};
+use crate::sqlite::RusqliteResult;
 use crate::tile_stream::TileReceiverStream;
 
 #[derive(Debug, Serialize)]
@@ -147,3 +149,67 @@ pub async fn mbt_agg_tiles_hash_stream(
         dt,
     })
 }
+
+fn hash_tiles_rows<T: Digest>(
+    conn: &Connection,
+    query: &str,
+) -> RusqliteResult<(String, usize)> {
+    let mut stmt = conn.prepare(query)?;
+    let mut rows = stmt.query([])?;
+    let mut hasher = T::new();
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let z: i64 = row.get(0)?;
+        let x: i64 = row.get(1)?;
+        let y: i64 = row.get(2)?;
+        let tile_data: Vec<u8> = row.get(3)?;
+        hasher.update(
+            [
+                z.to_string().as_bytes(),
+                x.to_string().as_bytes(),
+                y.to_string().as_bytes(),
+                &tile_data,
+            ]
+            .concat(),
+        );
+        count += 1;
+    }
+    Ok((hasher.finalize().to_vec().encode_hex_upper(), count))
+}
+
+/// Aggregate tiles hash (same as `mbt_agg_tiles_hash_stream`) computed on
+/// a connection, so it sees uncommitted changes of an open transaction
+pub fn mbt_agg_tiles_hash(
+    conn: &Connection,
+    hash_type: HashType,
+    prefix: Option<&str>,
+    filter: &Option<TilesFilter>,
+) -> UtilesResult<AggHashResult> {
+    let where_clause = if let Some(filter) = filter {
+        filter.mbtiles_sql_where(prefix)?
+    } else {
+        String::new()
+    };
+    let query = format!(
+        "SELECT zoom_level, tile_column, tile_row, tile_data FROM tiles {where_clause} ORDER BY zoom_level, tile_column, tile_row;"
+    );
+    let ti = std::time::Instant::now();
+    let (hash, ntiles) = match hash_type {
+        HashType::Md5 => hash_tiles_rows::<md5::Md5>(conn, &query),
+        HashType::Fnv1a => hash_tiles_rows::<Fnv>(conn, &query),
+        HashType::Xxh32 => hash_tiles_rows::<noncrypto_digests::Xxh32>(conn, &query),
+        HashType::Xxh64 => hash_tiles_rows::<noncrypto_digests::Xxh64>(conn, &query),
+        HashType::Xxh3_64 => {
+            hash_tiles_rows::<noncrypto_digests::Xxh3_64>(conn, &query)
+        }
+        HashType::Xxh3_128 => {
+            hash_tiles_rows::<noncrypto_digests::Xxh3_128>(conn, &query)
+        }
+    }?;
+    Ok(AggHashResult {
+        hash_type,
+        hash,
+        ntiles,
+        dt: ti.elapsed(),
+    })
+}
@@ -0,0 +1,506 @@
+//! In-place mbtiles schema conversion (flat <-> hash <-> norm)
+//!
+//! Tiles are moved in batches from the src tables into `_utiles_convert_*`
+//! tables; each batch is committed along w/ a checkpoint row (in the
+//! `_utiles_convert_schema` table) and the moved rows are deleted from the
+//! src tables so that freed pages are re-used and the db does not double in
+//! size (norm src `images` are kept until the swap). An interrupted
+//! conversion is resumed by running it again.
+//!
+//! The final swap (drop src tables, rename the new tables, rebuild the
+//! indexes/views and update the `mbtype`/`dbtype` metadata) happens in a
+//! single transaction that is only committed if the agg-tiles-hash of the
+//! converted db matches the hash of the db before conversion.
+//!
+//! Tile ids of hash/norm dbs are carried over; converting a flat db hashes
+//! the tiles w/ the conversion hash type, which is written as the `tileid`
+//! metadata.
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::errors::{UtilesError, UtilesResult};
+use crate::hash_types::HashType;
+use crate::mbt::query::{
+    create_mbtiles_indexes_norm, create_mbtiles_tiles_view_norm,
+    create_tiles_index_flat, create_tiles_index_hash, create_tiles_view_hash,
+    query_mbtiles_type,
+};
+use crate::mbt::{MbtType, mbt_agg_tiles_hash};
+
+const CHECKPOINT_TABLE: &str = "_utiles_convert_schema";
+
+#[derive(Debug, Serialize)]
+pub struct ConvertSchemaResult {
+    pub src: MbtType,
+    pub dst: MbtType,
+    pub ntiles: usize,
+    pub resumed: bool,
+    pub hash_type: HashType,
+    pub hash: String,
+}
+
+#[derive(Debug)]
+struct Checkpoint {
+    src: MbtType,
+    dst: MbtType,
+    hash_type: HashType,
+    hash: String,
+    ntiles: usize,
+    nconverted: usize,
+}
+
+fn checkpoint_read(conn: &Connection) -> UtilesResult<Option<Checkpoint>> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) = 1 FROM sqlite_schema WHERE type = 'table' AND name = ?1",
+        [CHECKPOINT_TABLE],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(None);
+    }
+    let row = conn
+        .query_row(
+            &format!(
+                "SELECT src, dst, hash_type, hash, ntiles, nconverted FROM {CHECKPOINT_TABLE}"
+            ),
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)? as usize,
+                    row.get::<_, i64>(5)? as usize,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((src, dst, hash_type, hash, ntiles, nconverted)) = row else {
+        return Ok(None);
+    };
+    let parse_mbtype = |s: &str| {
+        s.parse::<MbtType>().map_err(|()| {
+            UtilesError::Error(format!("invalid convert-schema checkpoint: {s}"))
+        })
+    };
+    Ok(Some(Checkpoint {
+        src: parse_mbtype(&src)?,
+        dst: parse_mbtype(&dst)?,
+        hash_type: hash_type.parse()?,
+        hash,
+        ntiles,
+        nconverted,
+    }))
+}
+
+/// Create the checkpoint + `_utiles_convert_*` tables (in one transaction)
+fn convert_init(conn: &mut Connection, checkpoint: &Checkpoint) -> UtilesResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(&format!(
+        "CREATE TABLE {CHECKPOINT_TABLE} (
+            src TEXT NOT NULL,
+            dst TEXT NOT NULL,
+            hash_type TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            ntiles INTEGER NOT NULL,
+            nconverted INTEGER NOT NULL
+        );"
+    ))?;
+    tx.execute(
+        &format!(
+            "INSERT INTO {CHECKPOINT_TABLE} (src, dst, hash_type, hash, ntiles, nconverted) VALUES (?1, ?2, ?3, ?4, ?5, 0)"
+        ),
+        params![
+            checkpoint.src.as_str(),
+            checkpoint.dst.as_str(),
+            checkpoint.hash_type.to_string(),
+            checkpoint.hash,
+            checkpoint.ntiles as i64,
+        ],
+    )?;
+    let sql = match checkpoint.dst {
+        MbtType::Flat => {
+            "CREATE TABLE _utiles_convert_tiles (
+                zoom_level  INTEGER NOT NULL,
+                tile_column INTEGER NOT NULL,
+                tile_row    INTEGER NOT NULL,
+                tile_data   BLOB
+            );"
+        }
+        MbtType::Hash => {
+            "CREATE TABLE _utiles_convert_tiles_with_hash (
+                zoom_level  INTEGER NOT NULL,
+                tile_column INTEGER NOT NULL,
+                tile_row    INTEGER NOT NULL,
+                tile_data   BLOB,
+                tile_hash   TEXT
+            );"
+        }
+        _ => {
+            "CREATE TABLE _utiles_convert_map (
+                zoom_level  INTEGER NOT NULL,
+                tile_column INTEGER NOT NULL,
+                tile_row    INTEGER NOT NULL,
+                tile_id     TEXT
+            );
+            CREATE TABLE _utiles_convert_images (
+                tile_id   TEXT NOT NULL,
+                tile_data BLOB NOT NULL
+            );
+            CREATE UNIQUE INDEX _utiles_convert_images_id ON _utiles_convert_images (tile_id);"
+        }
+    };
+    tx.execute_batch(sql)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// (select-batch, delete-through-key) queries for the src tables
+const fn src_queries(src: MbtType) -> (&'static str, &'static str) {
+    match src {
+        MbtType::Flat => (
+            "SELECT zoom_level, tile_column, tile_row, tile_data, NULL FROM tiles ORDER BY zoom_level, tile_column, tile_row LIMIT ?1",
+            "DELETE FROM tiles WHERE (zoom_level, tile_column, tile_row) <= (?1, ?2, ?3)",
+        ),
+        MbtType::Hash => (
+            "SELECT zoom_level, tile_column, tile_row, tile_data, tile_hash FROM tiles_with_hash ORDER BY zoom_level, tile_column, tile_row LIMIT ?1",
+            "DELETE FROM tiles_with_hash WHERE (zoom_level, tile_column, tile_row) <= (?1, ?2, ?3)",
+        ),
+        _ => (
+            "SELECT map.zoom_level, map.tile_column, map.tile_row, images.tile_data, CAST(map.tile_id AS TEXT) FROM map JOIN images ON images.tile_id = map.tile_id ORDER BY map.zoom_level, map.tile_column, map.tile_row LIMIT ?1",
+            "DELETE FROM map WHERE (zoom_level, tile_column, tile_row) <= (?1, ?2, ?3)",
+        ),
+    }
+}
+
+type SrcRow = (i64, i64, i64, Option<Vec<u8>>, Option<String>);
+
+/// Move (up to) `batch_size` tiles into the new tables; returns n-moved
+fn convert_batch(
+    conn: &mut Connection,
+    checkpoint: &Checkpoint,
+    batch_size: usize,
+) -> UtilesResult<usize> {
+    let (src, dst) = (checkpoint.src, checkpoint.dst);
+    let (select_sql, delete_sql) = src_queries(src);
+    let tx = conn.transaction()?;
+    let rows = {
+        let mut stmt = tx.prepare_cached(select_sql)?;
+        stmt.query_map([batch_size as i64], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?
+        .collect::<Result<Vec<SrcRow>, _>>()?
+    };
+    let Some(&(z, x, y, _, _)) = rows.last() else {
+        return Ok(0);
+    };
+    {
+        let mut tiles_stmt = tx.prepare_cached(match dst {
+            MbtType::Flat => {
+                "INSERT INTO _utiles_convert_tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)"
+            }
+            MbtType::Hash => {
+                "INSERT INTO _utiles_convert_tiles_with_hash (zoom_level, tile_column, tile_row, tile_data, tile_hash) VALUES (?1, ?2, ?3, ?4, ?5)"
+            }
+            _ => {
+                "INSERT INTO _utiles_convert_map (zoom_level, tile_column, tile_row, tile_id) VALUES (?1, ?2, ?3, ?4)"
+            }
+        })?;
+        let mut images_stmt = if dst == MbtType::Norm {
+            Some(tx.prepare_cached(
+                "INSERT OR IGNORE INTO _utiles_convert_images (tile_id, tile_data) VALUES (?1, ?2)",
+            )?)
+        } else {
+            None
+        };
+        for (z, x, y, tile_data, tile_id) in &rows {
+            if dst == MbtType::Flat {
+                tiles_stmt.execute(params![z, x, y, tile_data])?;
+                continue;
+            }
+            let tile_id = tile_id.clone().unwrap_or_else(|| {
+                checkpoint
+                    .hash_type
+                    .hash_hex_upper(tile_data.as_deref().unwrap_or_default())
+            });
+            if let Some(images_stmt) = images_stmt.as_mut() {
+                tiles_stmt.execute(params![z, x, y, tile_id])?;
+                images_stmt.execute(params![tile_id, tile_data])?;
+            } else {
+                tiles_stmt.execute(params![z, x, y, tile_data, tile_id])?;
+            }
+        }
+    }
+    tx.execute(delete_sql, params![z, x, y])?;
+    tx.execute(
+        &format!("UPDATE {CHECKPOINT_TABLE} SET nconverted = nconverted + ?1"),
+        [rows.len() as i64],
+    )?;
+    tx.commit()?;
+    Ok(rows.len())
+}
+
+/// Swap the new tables in, verify the agg-tiles-hash and commit
+fn convert_finish(conn: &mut Connection, checkpoint: &Checkpoint) -> UtilesResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(match checkpoint.src {
+        MbtType::Flat => "DROP TABLE tiles;",
+        MbtType::Hash => "DROP VIEW IF EXISTS tiles; DROP TABLE tiles_with_hash;",
+        _ => "DROP VIEW IF EXISTS tiles; DROP TABLE map; DROP TABLE images;",
+    })?;
+    match checkpoint.dst {
+        MbtType::Flat => {
+            tx.execute_batch("ALTER TABLE _utiles_convert_tiles RENAME TO tiles;")?;
+            create_tiles_index_flat(&tx)?;
+        }
+        MbtType::Hash => {
+            tx.execute_batch(
+                "ALTER TABLE _utiles_convert_tiles_with_hash RENAME TO tiles_with_hash;",
+            )?;
+            create_tiles_index_hash(&tx)?;
+            create_tiles_view_hash(&tx)?;
+        }
+        _ => {
+            tx.execute_batch(
+                "DROP INDEX _utiles_convert_images_id;
+                ALTER TABLE _utiles_convert_map RENAME TO map;
+                ALTER TABLE _utiles_convert_images RENAME TO images;",
+            )?;
+            create_mbtiles_indexes_norm(&tx)?;
+            create_mbtiles_tiles_view_norm(&tx)?;
+        }
+    }
+    tx.execute(
+        "UPDATE metadata SET value = ?1 WHERE name IN ('mbtype', 'dbtype')",
+        [checkpoint.dst.as_str()],
+    )?;
+    if checkpoint.src == MbtType::Flat {
+        tx.execute("DELETE FROM metadata WHERE name = 'tileid'", [])?;
+        tx.execute(
+            "INSERT INTO metadata (name, value) VALUES ('tileid', ?1)",
+            [checkpoint.hash_type.to_string()],
+        )?;
+    }
+    let agg_hash = mbt_agg_tiles_hash(&tx, checkpoint.hash_type, None, &None)?;
+    if agg_hash.hash != checkpoint.hash || agg_hash.ntiles != checkpoint.ntiles {
+        return Err(UtilesError::Error(format!(
+            "convert-schema verification failed (rolled back swap): {} hash {} ({} tiles) != {} ({} tiles)",
+            checkpoint.hash_type,
+            agg_hash.hash,
+            agg_hash.ntiles,
+            checkpoint.hash,
+            checkpoint.ntiles
+        )));
+    }
+    tx.execute_batch(&format!("DROP TABLE {CHECKPOINT_TABLE};"))?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Convert an mbtiles db to another (flat/hash/norm) schema in place
+///
+/// `on_progress` is called w/ the number of tiles converted after each
+/// batch. An interrupted conversion (checkpoint table exists) is resumed.
+///
+/// # Errors
+///
+/// Returns error if the db-type is not flat/hash/norm, a different
+/// conversion is in progress, the db has tiles w/ NULL `tile_data` or the
+/// agg-tiles-hash verification fails
+pub fn mbt_convert_schema<F: FnMut(usize, usize)>(
+    conn: &mut Connection,
+    dst: MbtType,
+    batch_size: usize,
+    hash_type: HashType,
+    mut on_progress: F,
+) -> UtilesResult<ConvertSchemaResult> {
+    if !matches!(dst, MbtType::Flat | MbtType::Hash | MbtType::Norm) {
+        return Err(UtilesError::Unsupported(format!("convert-schema to {dst}")));
+    }
+    let (checkpoint, resumed) = if let Some(checkpoint) = checkpoint_read(conn)? {
+        if checkpoint.dst != dst {
+            return Err(UtilesError::Error(format!(
+                "convert-schema {} -> {} in progress; resume w/ --to {}",
+                checkpoint.src, checkpoint.dst, checkpoint.dst
+            )));
+        }
+        info!(
+            "resuming convert-schema {} -> {} ({}/{} tiles)",
+            checkpoint.src, checkpoint.dst, checkpoint.nconverted, checkpoint.ntiles
+        );
+        (checkpoint, true)
+    } else {
+        let src = query_mbtiles_type(conn)?;
+        if !matches!(src, MbtType::Flat | MbtType::Hash | MbtType::Norm) {
+            return Err(UtilesError::Unsupported(format!(
+                "convert-schema from {src}"
+            )));
+        }
+        if src == dst {
+            return Err(UtilesError::Error(format!("db-type is already {dst}")));
+        }
+        let nnull: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM tiles WHERE tile_data IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        if nnull > 0 {
+            return Err(UtilesError::Error(format!(
+                "convert-schema: {nnull} tile(s) w/ NULL tile_data; delete them first"
+            )));
+        }
+        let agg_hash = mbt_agg_tiles_hash(conn, hash_type, None, &None)?;
+        debug!("convert-schema src agg-hash: {agg_hash:?}");
+        let checkpoint = Checkpoint {
+            src,
+            dst,
+            hash_type,
+            hash: agg_hash.hash,
+            ntiles: agg_hash.ntiles,
+            nconverted: 0,
+        };
+        convert_init(conn, &checkpoint)?;
+        (checkpoint, false)
+    };
+    let mut nconverted = checkpoint.nconverted;
+    on_progress(nconverted, checkpoint.ntiles);
+    loop {
+        let n = convert_batch(conn, &checkpoint, batch_size.max(1))?;
+        if n == 0 {
+            break;
+        }
+        nconverted += n;
+        on_progress(nconverted, checkpoint.ntiles);
+    }
+    convert_finish(conn, &checkpoint)?;
+    Ok(ConvertSchemaResult {
+        src: checkpoint.src,
+        dst: checkpoint.dst,
+        ntiles: checkpoint.ntiles,
+        resumed,
+        hash_type: checkpoint.hash_type,
+        hash: checkpoint.hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::mbt::mbtiles::init_mbtiles;
+
+    fn flat_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_mbtiles(&mut conn, &MbtType::Flat).unwrap();
+        conn.execute_batch(
+            "INSERT INTO metadata (name, value) VALUES ('mbtype', 'flat'), ('name', 'test');",
+        )
+        .unwrap();
+        for (z, x, y) in [(0, 0, 0), (1, 0, 0), (1, 0, 1), (1, 1, 0), (1, 1, 1)] {
+            let data = if z == 0 {
+                vec![0_u8, 1, 2]
+            } else {
+                vec![3_u8; 8]
+            };
+            conn.execute(
+                "INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)",
+                params![z, x, y, data],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn convert_roundtrip() {
+        let mut conn = flat_db();
+        let hash = mbt_agg_tiles_hash(&conn, HashType::Md5, None, &None).unwrap();
+        for dst in [MbtType::Norm, MbtType::Hash, MbtType::Norm, MbtType::Flat] {
+            let res = mbt_convert_schema(&mut conn, dst, 2, HashType::Md5, |_, _| {})
+                .unwrap();
+            assert_eq!(res.hash, hash.hash);
+            assert_eq!(res.ntiles, 5);
+            assert_eq!(query_mbtiles_type(&conn).unwrap(), dst);
+            let mbtype: String = conn
+                .query_row(
+                    "SELECT value FROM metadata WHERE name = 'mbtype'",
+                    [],
+                    |r| r.get(0),
+                )
+                .unwrap();
+            assert_eq!(mbtype, dst.as_str());
+            if dst == MbtType::Norm {
+                // flat src tiles are hashed w/ the conversion hash type
+                let (tile_id, tileid_metadata): (String, String) = conn
+                    .query_row(
+                        "SELECT tile_id, (SELECT value FROM metadata WHERE name = 'tileid') FROM map WHERE zoom_level = 0",
+                        [],
+                        |r| Ok((r.get(0)?, r.get(1)?)),
+                    )
+                    .unwrap();
+                assert_eq!(tile_id, HashType::Md5.hash_hex_upper([0_u8, 1, 2]));
+                assert_eq!(tileid_metadata, HashType::Md5.to_string());
+            }
+        }
+        assert!(checkpoint_read(&conn).unwrap().is_none());
+        assert!(
+            mbt_convert_schema(&mut conn, MbtType::Flat, 2, HashType::Md5, |_, _| {})
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn convert_null_tile_data_errors() {
+        let mut conn = flat_db();
+        conn.execute(
+            "INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (2, 0, 0, NULL)",
+            [],
+        )
+        .unwrap();
+        let res =
+            mbt_convert_schema(&mut conn, MbtType::Norm, 2, HashType::Md5, |_, _| {});
+        assert!(res.is_err());
+        assert!(checkpoint_read(&conn).unwrap().is_none());
+        assert_eq!(query_mbtiles_type(&conn).unwrap(), MbtType::Flat);
+    }
+
+    #[test]
+    fn convert_resume() {
+        let mut conn = flat_db();
+        let hash = mbt_agg_tiles_hash(&conn, HashType::Xxh64, None, &None).unwrap();
+        let checkpoint = Checkpoint {
+            src: MbtType::Flat,
+            dst: MbtType::Norm,
+            hash_type: HashType::Xxh64,
+            hash: hash.hash.clone(),
+            ntiles: hash.ntiles,
+            nconverted: 0,
+        };
+        convert_init(&mut conn, &checkpoint).unwrap();
+        assert_eq!(convert_batch(&mut conn, &checkpoint, 3).unwrap(), 3);
+        // other conversion in progress
+        assert!(
+            mbt_convert_schema(&mut conn, MbtType::Hash, 3, HashType::Md5, |_, _| {})
+                .is_err()
+        );
+        let res =
+            mbt_convert_schema(&mut conn, MbtType::Norm, 3, HashType::Md5, |_, _| {})
+                .unwrap();
+        assert!(res.resumed);
+        assert_eq!(res.hash_type, HashType::Xxh64);
+        assert_eq!(res.hash, hash.hash);
+        let nimages: i64 = conn
+            .query_row("SELECT COUNT(*) FROM images", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(nimages, 2);
+    }
+}
@@ -34,6 +34,61 @@ pub struct MbtilesStats {
     pub minzoom: Option<u32>,
     pub maxzoom: Option<u32>,
     pub zooms: Vec<MbtilesZoomStats>,
+    /// Blob deduplication of norm dbs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup: Option<MbtilesDedupStats>,
+}
+
+/// Space saved by storing identical tiles once (norm dbs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MbtilesDedupStats {
+    /// Number of tiles (map rows)
+    pub ntiles: u64,
+    /// Number of unique blobs (images rows)
+    pub nblobs: u64,
+    /// Bytes of all tiles if every tile stored its own blob
+    pub nbytes: u64,
+    /// Bytes of the unique blobs
+    pub nbytes_stored: u64,
+    pub nbytes_saved: u64,
+    /// Fraction of tiles that reference a shared blob
+    pub dedup_ratio: f64,
+}
+
+#[expect(clippy::cast_precision_loss)]
+pub fn query_norm_dedup_stats(conn: &Connection) -> UtilesResult<MbtilesDedupStats> {
+    let (ntiles, nblobs, nbytes, nbytes_stored) = conn.query_row(
+        r"
+        SELECT
+            (SELECT COUNT(*) FROM map),
+            (SELECT COUNT(*) FROM images),
+            (SELECT COALESCE(SUM(LENGTH(images.tile_data)), 0)
+                FROM map JOIN images ON map.tile_id = images.tile_id),
+            (SELECT COALESCE(SUM(LENGTH(tile_data)), 0) FROM images)
+        ",
+        [],
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)? as u64,
+                row.get::<_, i64>(1)? as u64,
+                row.get::<_, i64>(2)? as u64,
+                row.get::<_, i64>(3)? as u64,
+            ))
+        },
+    )?;
+    let dedup_ratio = if ntiles == 0 {
+        0.0
+    } else {
+        1.0 - (nblobs.min(ntiles) as f64 / ntiles as f64)
+    };
+    Ok(MbtilesDedupStats {
+        ntiles,
+        nblobs,
+        nbytes,
+        nbytes_stored,
+        nbytes_saved: nbytes.saturating_sub(nbytes_stored),
+        dedup_ratio,
+    })
 }
 
 pub fn query_mbt_stats(
@@ -56,16 +111,22 @@ pub fn query_mbt_stats(
     // if the file is over 10gb and full is None or false just don't do the
     // zoom_stats query that counts size... bc it is slow af
     // let zoom_stats = self.zoom_stats(zoom_stats_full)?;
-    let zoom_stats =
-        if full.unwrap_or(false) || (filesize < 10_000_000_000 && filesize > 0) {
-            zoom_stats_full(conn)?
-        } else {
-            zoom_stats(conn)?
-        };
+    let full = full.unwrap_or(false) || (filesize < 10_000_000_000 && filesize > 0);
+    let zoom_stats = if full {
+        zoom_stats_full(conn)?
+    } else {
+        zoom_stats(conn)?
+    };
     debug!("zoom_stats: {:?}", zoom_stats);
     let query_dt = query_ti.elapsed();
     debug!("Finished zoom_stats query in {:?}", query_dt);
     let mbt_type = query_mbtiles_type(conn)?;
+    // summing the blob sizes is as slow as the full zoom stats
+    let dedup = if full && mbt_type == MbtType::Norm {
+        Some(query_norm_dedup_stats(conn)?)
+    } else {
+        None
+    };
     if zoom_stats.is_empty() {
         return Ok(MbtilesStats {
             filesize,
@@ -78,6 +139,7 @@ pub fn query_mbt_stats(
             maxzoom: None,
             nzooms: 0,
             zooms: vec![],
+            dedup,
         });
     }
 
@@ -94,5 +156,6 @@ pub fn query_mbt_stats(
         maxzoom,
         nzooms: zoom_stats.len() as u32,
         zooms: zoom_stats,
+        dedup,
     })
 }
@@ -305,6 +305,11 @@ impl Mbtiles {
         insert_tile_flat_mbtiles::<T>(&mut self.conn, tile.tile(), datal)
     }
 
+    pub fn delete_tiles(&mut self, tiles: &[Tile]) -> UtilesResult<usize> {
+        let mbt_type = self.query_mbt_type()?;
+        delete_tiles_mbtiles(&mut self.conn, &mbt_type, tiles)
+    }
+
     pub fn magic_number(&self) -> RusqliteResult<u32> {
         self.application_id()
     }
@@ -801,6 +806,45 @@ pub fn insert_tiles_flat_mbtiles(
     Ok(naff)
 }
 
+/// Delete tiles from a flat, hash or norm mbtiles (orphaned norm images
+/// are deleted too); returns the number of tiles deleted
+pub fn delete_tiles_mbtiles(
+    conn: &mut Connection,
+    mbt_type: &MbtType,
+    tiles: &[Tile],
+) -> UtilesResult<usize> {
+    let table = match mbt_type {
+        MbtType::Flat => "tiles",
+        MbtType::Hash => "tiles_with_hash",
+        MbtType::Norm => "map",
+        _ => {
+            return Err(UtilesError::Unsupported(format!(
+                "deleting tiles from {} mbtiles",
+                mbt_type.as_str()
+            )));
+        }
+    };
+    let tx = conn.transaction()?;
+    let mut naff: usize = 0;
+    {
+        let statement = format!(
+            "DELETE FROM {table} WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3"
+        );
+        let mut stmt = tx.prepare_cached(&statement)?;
+        for tile in tiles {
+            naff += stmt.execute(params![tile.z, tile.x, tile.flipy()])?;
+        }
+    }
+    if *mbt_type == MbtType::Norm {
+        tx.execute(
+            "DELETE FROM images WHERE tile_id NOT IN (SELECT tile_id FROM map)",
+            [],
+        )?;
+    }
+    tx.commit()?;
+    Ok(naff)
+}
+
 pub fn metadata_get(
     conn: &Connection,
     name: &str,
@@ -1,14 +1,23 @@
-pub use agg_tiles_hash::mbt_agg_tiles_hash_stream;
+pub use agg_tiles_hash::{
+    AggHashResult, mbt_agg_tiles_hash, mbt_agg_tiles_hash_stream,
+};
+pub use convert_schema::{ConvertSchemaResult, mbt_convert_schema};
 pub use info::mbinfo;
 pub use mbtiles::Mbtiles;
 pub use mbtiles_async::MbtilesAsync;
 pub use mbtiles_async_sqlite::{MbtilesClientAsync, MbtilesPoolAsync};
 pub use mbtype::MbtType;
 pub use metadata::*;
-pub use stream_writer::{MbtStreamWriterSync, MbtWriterStats};
+pub use salvage::{
+    SalvageTableStats, open_salvage_src, salvage_metadata, salvage_tiles,
+};
+pub use stream_writer::{MbtStreamWriterSync, MbtWriterStats, MbtWriterStreamData};
 pub use tiles_row::MbtTileRow;
 
-pub use crate::mbt::mbt_stats::{MbtilesStats, MbtilesZoomStats, query_mbt_stats};
+pub use crate::mbt::mbt_stats::{
+    MbtilesDedupStats, MbtilesStats, MbtilesZoomStats, query_mbt_stats,
+    query_norm_dedup_stats,
+};
 pub use crate::mbt::metadata_row::{
     MbtMetadataRow, MbtilesMetadataJson, MbtilesMetadataJsonRaw,
     MbtilesMetadataRowParsed, MbtilesMetadataRows,
@@ -17,6 +26,7 @@ pub use crate::mbt::minzoom_maxzoom::MinZoomMaxZoom;
 pub use crate::mbt::tiles_filter::TilesFilter;
 
 mod agg_tiles_hash;
+mod convert_schema;
 mod info;
 mod mbt_stats;
 mod mbtiles_async;
@@ -28,6 +38,7 @@ mod minzoom_maxzoom;
 pub mod mbtiles;
 pub mod mbtiles_async_sqlite;
 pub mod query;
+mod salvage;
 mod stream_writer;
 mod tiles_filter;
 mod tiles_row;
@@ -0,0 +1,327 @@
+//! Salvage the readable tiles and metadata of a damaged mbtiles db
+//!
+//! Rows are read by rowid in batches; a batch that fails to read (corrupt
+//! or missing pages) is re-read row by row and the unreadable rows are
+//! skipped and reported as lost rowid ranges.
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Row};
+use serde::Serialize;
+use tracing::{debug, warn};
+use utiles_core::{Tile, flipy, utile_yup};
+
+use crate::errors::{UtilesError, UtilesResult};
+use crate::mbt::{MbtMetadataRow, MbtType};
+
+/// Salvaged/lost rows of a table
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SalvageTableStats {
+    pub table: String,
+    pub nsalvaged: u64,
+    pub nlost: u64,
+    /// Unreadable rowids as inclusive `[start, end]` ranges
+    pub lost_rowids: Vec<[i64; 2]>,
+    /// The end of the table is unreadable (truncated db); rows after the
+    /// last salvaged row may be lost
+    pub tail_lost: bool,
+    /// Tiles whose row was read but whose data is missing (norm dbs)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lost_tiles: Vec<Tile>,
+}
+
+impl SalvageTableStats {
+    fn new(table: &str) -> Self {
+        Self {
+            table: table.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn lose_rowid(&mut self, rowid: i64) {
+        self.nlost += 1;
+        match self.lost_rowids.last_mut() {
+            Some(range) if range[1] + 1 == rowid => range[1] = rowid,
+            _ => self.lost_rowids.push([rowid, rowid]),
+        }
+    }
+
+    /// Forget the lost rowids after `rowid`
+    fn trim_lost_after(&mut self, rowid: i64) {
+        let mut ntrimmed = 0;
+        self.lost_rowids.retain_mut(|range| {
+            if range[0] > rowid {
+                ntrimmed += range[1] - range[0] + 1;
+                false
+            } else {
+                ntrimmed += (range[1] - rowid).max(0);
+                range[1] = range[1].min(rowid);
+                true
+            }
+        });
+        self.nlost -= ntrimmed as u64;
+    }
+}
+
+/// Open a damaged db (read-only) for salvaging
+///
+/// The db is opened read-only so a left over wal/journal is not applied to
+/// it and w/ `writable_schema` on so that sqlite reads truncated dbs (the
+/// in-header size larger than the file is otherwise reported as corrupt).
+///
+/// # Errors
+///
+/// Returns an error if the db cannot be opened.
+pub fn open_salvage_src<P: AsRef<Path>>(path: P) -> UtilesResult<Connection> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    conn.pragma_update(None, "writable_schema", true)?;
+    Ok(conn)
+}
+
+/// Consecutive empty batches after which a scan w/o a known max rowid stops
+const EMPTY_BATCHES_STOP: usize = 10;
+
+/// `MIN(rowid)`/`MAX(rowid)` of a table; `None` if unreadable
+fn rowid_bound(conn: &Connection, table: &str, agg: &str) -> Option<i64> {
+    conn.query_row(&format!("SELECT {agg}(rowid) FROM {table}"), [], |row| {
+        row.get::<_, Option<i64>>(0)
+    })
+    .inspect_err(|e| debug!("{table} {agg}(rowid): {e}"))
+    .ok()
+    .flatten()
+}
+
+/// Read the rows of `select` (selecting from `table`) in rowid order w/
+/// `map_row`, skipping the rows that cannot be read
+///
+/// If the max rowid cannot be read (truncated db) rows are read until
+/// `EMPTY_BATCHES_STOP` batches in a row are empty/unreadable.
+fn salvage_rows<T, F>(
+    conn: &Connection,
+    table: &str,
+    select: &str,
+    batch_size: i64,
+    map_row: F,
+    mut on_row: impl FnMut(T, &mut SalvageTableStats),
+) -> UtilesResult<SalvageTableStats>
+where
+    F: Fn(&Row) -> rusqlite::Result<T>,
+{
+    let mut stats = SalvageTableStats::new(table);
+    let mut stmt = conn.prepare(&format!(
+        "{select} WHERE {table}.rowid BETWEEN ?1 AND ?2 ORDER BY {table}.rowid"
+    ))?;
+    let max_rowid = rowid_bound(conn, table, "MAX");
+    if max_rowid.is_none() {
+        // empty table or unreadable end of the table
+        if matches!(stmt.exists([i64::MIN, i64::MAX]), Ok(false)) {
+            return Ok(stats);
+        }
+        stats.tail_lost = true;
+        warn!("{table}: max rowid unreadable (truncated?)");
+    }
+    let batch_size = batch_size.max(1);
+    let mut start = rowid_bound(conn, table, "MIN").unwrap_or(1);
+    let mut empty_batches = 0;
+    let mut last_salvaged = start - 1;
+    while max_rowid.is_none_or(|max_rowid| start <= max_rowid) {
+        let end = start
+            .saturating_add(batch_size - 1)
+            .min(max_rowid.unwrap_or(i64::MAX));
+        let nsalvaged = stats.nsalvaged;
+        let batch = stmt
+            .query_map([start, end], &map_row)
+            .and_then(Iterator::collect::<rusqlite::Result<Vec<T>>>);
+        match batch {
+            Ok(rows) => {
+                if !rows.is_empty() {
+                    last_salvaged = end;
+                }
+                for row in rows {
+                    stats.nsalvaged += 1;
+                    on_row(row, &mut stats);
+                }
+            }
+            Err(e) => {
+                debug!("{table} rowids {start}..={end} unreadable ({e}); reading rows");
+                for rowid in start..=end {
+                    match stmt.query_row([rowid, rowid], &map_row).optional() {
+                        Ok(Some(row)) => {
+                            last_salvaged = rowid;
+                            stats.nsalvaged += 1;
+                            on_row(row, &mut stats);
+                        }
+                        // no row w/ the rowid (deleted)
+                        Ok(None) => {}
+                        Err(e) => {
+                            debug!("{table} rowid {rowid}: {e}");
+                            stats.lose_rowid(rowid);
+                        }
+                    }
+                }
+            }
+        }
+        if max_rowid.is_none() {
+            if stats.nsalvaged == nsalvaged {
+                empty_batches += 1;
+                if empty_batches >= EMPTY_BATCHES_STOP {
+                    // rows past the end of the readable table are unknown
+                    // (not counted as lost)
+                    stats.trim_lost_after(last_salvaged);
+                    break;
+                }
+            } else {
+                empty_batches = 0;
+            }
+        }
+        if end == i64::MAX {
+            break;
+        }
+        start = end + 1;
+    }
+    if stats.nlost > 0 {
+        warn!("{table}: lost {} unreadable rows", stats.nlost);
+    }
+    Ok(stats)
+}
+
+/// Salvage the readable metadata rows
+///
+/// # Errors
+///
+/// Returns an error if the metadata table cannot be found/read at all.
+pub fn salvage_metadata(
+    conn: &Connection,
+) -> UtilesResult<(Vec<MbtMetadataRow>, SalvageTableStats)> {
+    let mut rows = vec![];
+    let stats = salvage_rows(
+        conn,
+        "metadata",
+        "SELECT name, value FROM metadata",
+        1000,
+        |row| {
+            Ok(MbtMetadataRow {
+                name: row.get(0)?,
+                value: row.get(1)?,
+            })
+        },
+        |row, _| rows.push(row),
+    )?;
+    Ok((rows, stats))
+}
+
+/// Salvage the readable tiles of a flat/hash/norm db calling `on_tile`
+/// w/ every tile read
+///
+/// # Errors
+///
+/// Returns an error if the db type is not flat/hash/norm or if the tiles
+/// table cannot be found/read at all.
+pub fn salvage_tiles(
+    conn: &Connection,
+    mbt_type: &MbtType,
+    batch_size: i64,
+    mut on_tile: impl FnMut(Tile, Vec<u8>),
+) -> UtilesResult<SalvageTableStats> {
+    let (table, select) = match mbt_type {
+        MbtType::Flat => (
+            "tiles",
+            "SELECT zoom_level, tile_column, tile_row, tile_data FROM tiles",
+        ),
+        MbtType::Hash => (
+            "tiles_with_hash",
+            "SELECT zoom_level, tile_column, tile_row, tile_data FROM tiles_with_hash",
+        ),
+        MbtType::Norm => (
+            "map",
+            "SELECT map.zoom_level, map.tile_column, map.tile_row, images.tile_data FROM map LEFT JOIN images ON map.tile_id = images.tile_id",
+        ),
+        _ => {
+            return Err(UtilesError::Unsupported(format!(
+                "salvage of {mbt_type} mbtiles"
+            )));
+        }
+    };
+    salvage_rows(
+        conn,
+        table,
+        select,
+        batch_size,
+        |row| {
+            let z: u8 = row.get(0)?;
+            let x: u32 = row.get(1)?;
+            let yup: u32 = row.get(2)?;
+            let tile_data: Option<Vec<u8>> = row.get(3)?;
+            Ok((utile_yup!(x, yup, z), tile_data))
+        },
+        |(tile, tile_data), stats| {
+            if let Some(tile_data) = tile_data {
+                on_tile(tile, tile_data);
+            } else {
+                stats.nsalvaged -= 1;
+                stats.nlost += 1;
+                stats.lost_tiles.push(tile);
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::mbt::mbtiles::init_mbtiles;
+    use crate::sqlite::pragma_page_size_get;
+
+    #[test]
+    fn salvage_skips_unreadable_rows() {
+        let path = std::env::temp_dir().join(format!(
+            "utiles-test-salvage-{}.mbtiles",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let mut conn = Connection::open(&path).unwrap();
+        init_mbtiles(&mut conn, &MbtType::Flat).unwrap();
+        conn.execute_batch(
+            "INSERT INTO metadata (name, value) VALUES ('name', 'salvage');
+             WITH RECURSIVE n(i) AS (SELECT 0 UNION ALL SELECT i + 1 FROM n WHERE i < 255)
+             INSERT INTO tiles SELECT 8, i, 0, randomblob(600) FROM n;
+             UPDATE tiles SET tile_data = X'DEADBEEF' || randomblob(596) WHERE tile_column = 128;",
+        )
+        .unwrap();
+        let page_size = pragma_page_size_get(&conn).unwrap() as usize;
+        drop(conn);
+
+        // zero the page of tile 8/128/0
+        let mut bytes = std::fs::read(&path).unwrap();
+        let offset = bytes
+            .windows(4)
+            .position(|w| w == [0xDE, 0xAD, 0xBE, 0xEF])
+            .unwrap();
+        let page = offset / page_size;
+        bytes[page * page_size..(page + 1) * page_size].fill(0);
+        // + truncate the last page
+        std::fs::write(&path, &bytes[..bytes.len() - page_size]).unwrap();
+
+        let conn = open_salvage_src(&path).unwrap();
+        let (metadata, _) = salvage_metadata(&conn).unwrap();
+        assert_eq!(metadata[0].value, "salvage");
+        let mut tiles = vec![];
+        let stats =
+            salvage_tiles(&conn, &MbtType::Flat, 64, |tile, _| tiles.push(tile))
+                .unwrap();
+        assert!(stats.nlost > 0, "{stats:?}");
+        assert!(!tiles.contains(&Tile::new(128, 255, 8)));
+        if stats.tail_lost {
+            assert!(stats.nsalvaged + stats.nlost < 256);
+        } else {
+            assert_eq!(stats.nsalvaged + stats.nlost, 256);
+        }
+        assert_eq!(tiles.len() as u64, stats.nsalvaged);
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
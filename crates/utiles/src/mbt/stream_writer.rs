@@ -5,7 +5,7 @@ use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, warn};
 use utiles_core::{Tile, TileLike};
 
-use crate::hash::xxh64_be_hex_upper;
+use crate::hash_types::HashType;
 use crate::mbt::{MbtType, Mbtiles};
 use crate::sqlite::InsertStrategy;
 use crate::{UtilesError, UtilesResult};
@@ -21,12 +21,46 @@ impl From<(Tile, Vec<u8>, Option<String>)> for MbtWriterStreamData {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct MbtWriterStats {
     pub count: usize,
     pub nbytes: usize,
+    /// Tiles whose blob was already written (same hash; norm dbs only as
+    /// hash dbs store every blob)
+    pub ndeduped: usize,
+    /// Bytes of the deduplicated blobs (norm dbs only)
+    pub nbytes_deduped: usize,
 }
 
+impl MbtWriterStats {
+    /// Fraction of tiles whose blob was deduplicated
+    #[must_use]
+    #[expect(clippy::cast_precision_loss)]
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.ndeduped as f64 / self.count as f64
+        }
+    }
+
+    fn add(&mut self, nbytes: usize, deduped: bool) {
+        self.count += 1;
+        self.nbytes += nbytes;
+        if deduped {
+            self.ndeduped += 1;
+            self.nbytes_deduped += nbytes;
+        }
+        debug!(
+            "count: {}, nbytes: {}, ndeduped: {}",
+            self.count, self.nbytes, self.ndeduped
+        );
+    }
+}
+
+/// Hash-db tiles to insert in one statement
+type HashBatch = Vec<(Tile, Vec<u8>, String)>;
+
 pub struct MbtStreamWriterSync {
     pub stream: ReceiverStream<MbtWriterStreamData>,
     pub mbt: Mbtiles,
@@ -65,6 +99,17 @@ impl MbtStreamWriterSync {
             .map_err(Into::into)
     }
 
+    /// Hash type of the db's `tileid` metadata (default xxh64) used to
+    /// hash tiles sent w/o a hash
+    pub fn hash_type(&self) -> UtilesResult<HashType> {
+        let hash_type = self
+            .mbt
+            .metadata_get("tileid")?
+            .and_then(|tileid| tileid.parse::<HashType>().ok())
+            .unwrap_or_default();
+        Ok(hash_type)
+    }
+
     pub async fn write_flat(&mut self) -> UtilesResult<()> {
         let stmt_str = match self.on_conflict {
             InsertStrategy::Ignore => {
@@ -97,12 +142,7 @@ impl MbtStreamWriterSync {
                     if let Err(e) = insert_res {
                         warn!("insert_res: {:?}", e);
                     } else {
-                        self.stats.count += 1;
-                        self.stats.nbytes += tile_data.len();
-                        debug!(
-                            "count: {}, nbytes: {}",
-                            self.stats.count, self.stats.nbytes
-                        );
+                        self.stats.add(tile_data.len(), false);
                     }
                 }
             }
@@ -128,24 +168,20 @@ impl MbtStreamWriterSync {
                 "INSERT INTO tiles_with_hash (zoom_level, tile_column, tile_row, tile_data, tile_hash) VALUES (?1, ?2, ?3, ?4, ?5);"
             }
         };
+        let hash_type = self.hash_type()?;
         let mut stmt = self.mbt.conn.prepare(stmt_str)?;
         let stream = &mut self.stream;
         while let Some(value) = stream.next().await {
             if let MbtWriterStreamData::Tile(tile, tile_data, hash_hex) = value {
                 let hash_hex =
-                    hash_hex.unwrap_or_else(|| xxh64_be_hex_upper(&tile_data));
+                    hash_hex.unwrap_or_else(|| hash_type.hash_hex_upper(&tile_data));
                 let tile_params =
                     rusqlite::params![tile.z, tile.x, tile.yup(), tile_data, hash_hex];
                 let insert_res = stmt.execute(tile_params);
                 if let Err(e) = insert_res {
                     warn!("insert_res: {:?}", e);
                 } else {
-                    self.stats.count += 1;
-                    self.stats.nbytes += tile_data.len();
-                    debug!(
-                        "count: {}, nbytes: {}",
-                        self.stats.count, self.stats.nbytes
-                    );
+                    self.stats.add(tile_data.len(), false);
                 }
             }
         }
@@ -170,39 +206,37 @@ impl MbtStreamWriterSync {
                 "INSERT INTO map (zoom_level, tile_column, tile_row, tile_id) VALUES (?1, ?2, ?3, ?4);"
             }
         };
+        let hash_type = self.hash_type()?;
         let mut map_stmt = self.mbt.conn.prepare(map_stmt_str)?;
+        // identical blobs are only written once; a blob insert w/ no
+        // affected rows is a duplicate
         let mut blob_stmt = self.mbt.conn.prepare(
             "INSERT OR IGNORE INTO images (tile_id, tile_data) VALUES (?1, ?2);",
         )?;
         while let Some(value) = self.stream.next().await {
             match value {
                 MbtWriterStreamData::Tile(tile, tile_data, hash_hex) => {
-                    let hash_hex =
-                        hash_hex.unwrap_or_else(|| xxh64_be_hex_upper(&tile_data));
+                    let hash_hex = hash_hex
+                        .unwrap_or_else(|| hash_type.hash_hex_upper(&tile_data));
                     let map_insert_res =
                         rusqlite::params![tile.z, tile.x, tile.yup(), hash_hex];
-                    let map_insert_res = map_stmt.execute(map_insert_res);
-                    if let Err(e) = map_insert_res {
-                        warn!("insert_res: {:?}", e);
-                    } else {
-                        self.stats.count += 1;
-                        self.stats.nbytes += tile_data.len();
-                        debug!(
-                            "count: {}, nbytes: {}",
-                            self.stats.count, self.stats.nbytes
-                        );
+                    match map_stmt.execute(map_insert_res) {
+                        // ignored on conflict ~ no blob to write
+                        Ok(0) => continue,
+                        Ok(_) => {}
+                        Err(e) => {
+                            warn!("insert_res: {:?}", e);
+                            continue;
+                        }
                     }
                     let blob_params = rusqlite::params![hash_hex, tile_data];
-                    let insert_res = blob_stmt.execute(blob_params);
-                    if let Err(e) = insert_res {
-                        warn!("blob insert res: {:?}", e);
-                    } else {
-                        self.stats.count += 1;
-                        self.stats.nbytes += tile_data.len();
-                        debug!(
-                            "count: {}, nbytes: {}",
-                            self.stats.count, self.stats.nbytes
-                        );
+                    match blob_stmt.execute(blob_params) {
+                        Ok(nchanged) => {
+                            self.stats.add(tile_data.len(), nchanged == 0);
+                        }
+                        Err(e) => {
+                            warn!("blob insert res: {:?}", e);
+                        }
                     }
                 }
                 MbtWriterStreamData::Metadata(_key, _value) => {
@@ -228,83 +262,198 @@ impl MbtStreamWriterSync {
         Ok(())
     }
 
+    /// Insert a batch of tiles into a hash db (one statement)
+    fn insert_hash_batch(&mut self, batch: &mut HashBatch) -> UtilesResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let placeholders = batch
+            .iter()
+            .map(|_| "(?, ?, ?, ?, ?)")
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = self.mbt.conn.prepare_cached(&format!(
+            "INSERT INTO tiles_with_hash (zoom_level, tile_column, tile_row, tile_data, tile_hash) VALUES {placeholders};"
+        ))?;
+        let param_values: Vec<Value> = batch
+            .iter()
+            .flat_map(|(tile, tile_data, hash_hex)| {
+                vec![
+                    Value::Integer(i64::from(tile.z())),
+                    Value::Integer(i64::from(tile.x())),
+                    Value::Integer(i64::from(tile.yup())),
+                    Value::Blob(tile_data.clone()),
+                    Value::Text(hash_hex.clone()),
+                ]
+            })
+            .collect();
+        let insert_res = stmt.execute(params_from_iter(param_values.iter()));
+        if let Err(e) = insert_res {
+            warn!("insert_res: {:?}", e);
+        } else {
+            for (_, tile_data, _) in batch.iter() {
+                self.stats.add(tile_data.len(), false);
+            }
+        }
+        batch.clear();
+        Ok(())
+    }
+
+    /// Write tiles to a hash db in batches of 100
     pub async fn write_batched(&mut self) -> UtilesResult<()> {
         self.preflight()?;
-        let mut batch = vec![];
+        let hash_type = self.hash_type()?;
+        let mut batch: HashBatch = vec![];
         while let Some(value) = self.stream.next().await {
             match value {
                 MbtWriterStreamData::Metadata(_key, _value) => {
                     warn!("Writing metadata not yet supported");
                 }
                 MbtWriterStreamData::Tile(tile, tile_data, hash_hex) => {
-                    let hash_hex =
-                        hash_hex.unwrap_or_else(|| xxh64_be_hex_upper(&tile_data));
+                    let hash_hex = hash_hex
+                        .unwrap_or_else(|| hash_type.hash_hex_upper(&tile_data));
                     batch.push((tile, tile_data, hash_hex));
                     if batch.len() >= 100 {
-                        let placeholders = batch
-                            .iter()
-                            .map(|_| "(?, ?, ?, ?, ?)")
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        let mut stmt = self.mbt.conn.prepare_cached(
-                            &format!("INSERT INTO tiles_with_hash (zoom_level, tile_column, tile_row, tile_data, tile_hash) VALUES {placeholders};"),
-                        )?;
-                        let param_values: Vec<Value> = batch
-                            .iter()
-                            .flat_map(|(tile, tile_data, hash_hex)| {
-                                vec![
-                                    Value::Integer(i64::from(tile.z())),
-                                    Value::Integer(i64::from(tile.x())),
-                                    Value::Integer(i64::from(tile.yup())),
-                                    Value::Blob(tile_data.clone()),
-                                    Value::Text(hash_hex.clone()),
-                                ]
-                            })
-                            .collect();
-                        let insert_res =
-                            stmt.execute(params_from_iter(param_values.iter()));
-                        batch.clear();
-                        if let Err(e) = insert_res {
-                            warn!("insert_res: {:?}", e);
-                        } else {
-                            debug!(
-                                "count: {}, nbytes: {}",
-                                self.stats.count, self.stats.nbytes
-                            );
-                        }
+                        self.insert_hash_batch(&mut batch)?;
                     }
                 }
             }
         }
-
-        if !batch.is_empty() {
-            let placeholders = batch
-                .iter()
-                .map(|_| "(?, ?, ?, ?)")
-                .collect::<Vec<_>>()
-                .join(", ");
-            let mut stmt = self.mbt.conn.prepare_cached(
-                &format!("INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES {placeholders};"),
-            )?;
-            let param_values: Vec<Value> = batch
-                .iter()
-                .flat_map(|(tile, tile_data, _)| {
-                    vec![
-                        Value::Integer(i64::from(tile.z())),
-                        Value::Integer(i64::from(tile.x())),
-                        Value::Integer(i64::from(tile.yup())),
-                        Value::Blob(tile_data.clone()),
-                    ]
-                })
-                .collect();
-            let insert_res = stmt.execute(params_from_iter(param_values.iter()));
-            if let Err(e) = insert_res {
-                warn!("insert_res: {:?}", e);
-            } else {
-                debug!("count: {}, nbytes: {}", self.stats.count, self.stats.nbytes);
-            }
-        }
+        self.insert_hash_batch(&mut batch)?;
         self.postflight()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::mbt::mbtiles::init_mbtiles;
+    use crate::mbt::query_norm_dedup_stats;
+
+    async fn write_tiles(mbtype: MbtType) -> MbtStreamWriterSync {
+        let mut mbt = Mbtiles::open_in_memory().unwrap();
+        init_mbtiles(&mut mbt.conn, &mbtype).unwrap();
+        mbt.metadata_set("tileid", "md5").unwrap();
+        let (tx, rx) = tokio::sync::mpsc::channel(10);
+        let mut writer = MbtStreamWriterSync {
+            stream: ReceiverStream::new(rx),
+            mbt,
+            on_conflict: InsertStrategy::None,
+            stats: MbtWriterStats::default(),
+        };
+        tokio::spawn(async move {
+            // 3 identical "ocean" tiles + 1 other
+            for (x, data) in [(0, "ocean"), (1, "ocean"), (2, "land"), (3, "ocean")] {
+                let tile = Tile::new(x, 0, 2);
+                tx.send((tile, data.as_bytes().to_vec(), None).into())
+                    .await
+                    .unwrap();
+            }
+        });
+        writer.write().await.unwrap();
+        writer
+    }
+
+    #[tokio::test]
+    async fn write_norm_dedupes_blobs() {
+        let writer = write_tiles(MbtType::Norm).await;
+        assert_eq!(writer.stats.count, 4);
+        assert_eq!(writer.stats.ndeduped, 2);
+        assert_eq!(writer.stats.nbytes_deduped, 10);
+        assert!((writer.stats.dedup_ratio() - 0.5).abs() < 1e-9);
+        let dedup = query_norm_dedup_stats(&writer.mbt.conn).unwrap();
+        assert_eq!((dedup.ntiles, dedup.nblobs), (4, 2));
+        assert_eq!((dedup.nbytes, dedup.nbytes_stored), (19, 9));
+        assert_eq!(dedup.nbytes_saved, 10);
+        // blobs are keyed by the configured (tileid) hash type
+        let tile_id: String = writer
+            .mbt
+            .conn
+            .query_row("SELECT tile_id FROM map WHERE tile_column = 2", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(tile_id, HashType::Md5.hash_hex_upper("land"));
+    }
+
+    #[tokio::test]
+    async fn write_norm_ignored_tile_writes_no_blob() {
+        let mut mbt = Mbtiles::open_in_memory().unwrap();
+        init_mbtiles(&mut mbt.conn, &MbtType::Norm).unwrap();
+        let (tx, rx) = tokio::sync::mpsc::channel(10);
+        let mut writer = MbtStreamWriterSync {
+            stream: ReceiverStream::new(rx),
+            mbt,
+            on_conflict: InsertStrategy::Ignore,
+            stats: MbtWriterStats::default(),
+        };
+        tokio::spawn(async move {
+            for data in ["ocean", "land"] {
+                tx.send((Tile::new(0, 0, 2), data.as_bytes().to_vec(), None).into())
+                    .await
+                    .unwrap();
+            }
+        });
+        writer.write().await.unwrap();
+        assert_eq!(writer.stats.count, 1);
+        let nblobs: i64 = writer
+            .mbt
+            .conn
+            .query_row("SELECT COUNT(*) FROM images", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(nblobs, 1);
+    }
+
+    #[tokio::test]
+    async fn write_hash_reports_no_dedup() {
+        // hash dbs store the blob of every tile
+        let writer = write_tiles(MbtType::Hash).await;
+        assert_eq!(writer.stats.count, 4);
+        assert_eq!((writer.stats.ndeduped, writer.stats.nbytes_deduped), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn write_batched_hashes_w_tileid_type() {
+        let mut mbt = Mbtiles::open_in_memory().unwrap();
+        init_mbtiles(&mut mbt.conn, &MbtType::Hash).unwrap();
+        mbt.metadata_set("tileid", "md5").unwrap();
+        let (tx, rx) = tokio::sync::mpsc::channel(10);
+        let mut writer = MbtStreamWriterSync {
+            stream: ReceiverStream::new(rx),
+            mbt,
+            on_conflict: InsertStrategy::None,
+            stats: MbtWriterStats::default(),
+        };
+        tokio::spawn(async move {
+            for x in 0..150 {
+                let data = if x % 2 == 0 { "ocean" } else { "land" };
+                tx.send((Tile::new(x, 0, 8), data.as_bytes().to_vec(), None).into())
+                    .await
+                    .unwrap();
+            }
+        });
+        writer.write_batched().await.unwrap();
+        assert_eq!(writer.stats.count, 150);
+        assert_eq!(writer.stats.ndeduped, 0);
+        let (ntiles, tile_hash): (i64, String) = writer
+            .mbt
+            .conn
+            .query_row(
+                "SELECT COUNT(*), MIN(tile_hash) FROM tiles_with_hash WHERE tile_column = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(ntiles, 1);
+        assert_eq!(tile_hash, HashType::Md5.hash_hex_upper("land"));
+        let ntiles: i64 = writer
+            .mbt
+            .conn
+            .query_row("SELECT COUNT(*) FROM tiles_with_hash", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(ntiles, 150);
+    }
+}
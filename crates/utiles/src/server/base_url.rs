@@ -0,0 +1,90 @@
+//! Public base url resolution for absolute `tiles` urls
+use axum::http::HeaderMap;
+use axum::http::header::HOST;
+
+use crate::server::cfg::UtilesServerConfig;
+
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_PREFIX: &str = "x-forwarded-prefix";
+
+/// First value of a (possibly comma separated) header
+fn first_header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Return the base url (no trailing slash) that clients should use to reach
+/// the server
+///
+/// Precedence:
+///   1. configured `public_url`
+///   2. `X-Forwarded-Proto` / `X-Forwarded-Host` / `X-Forwarded-Prefix`
+///   3. `Host` header
+///   4. the bind address
+pub(super) fn base_url(cfg: &UtilesServerConfig, headers: &HeaderMap) -> String {
+    if let Some(public_url) = &cfg.public_url {
+        return public_url.trim_end_matches('/').to_string();
+    }
+    let proto = first_header_value(headers, X_FORWARDED_PROTO).unwrap_or("http");
+    let host = first_header_value(headers, X_FORWARDED_HOST)
+        .or_else(|| first_header_value(headers, HOST.as_str()))
+        .map_or_else(|| cfg.addr(), str::to_string);
+    let prefix = first_header_value(headers, X_FORWARDED_PREFIX)
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("/{p}"))
+        .unwrap_or_default();
+    format!("{proto}://{host}{prefix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn cfg() -> UtilesServerConfig {
+        UtilesServerConfig::new("0.0.0.0".to_string(), 3333, vec![])
+    }
+
+    #[test]
+    fn host_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("localhost:3333"));
+        assert_eq!(base_url(&cfg(), &headers), "http://localhost:3333");
+    }
+
+    #[test]
+    fn no_headers() {
+        assert_eq!(base_url(&cfg(), &HeaderMap::new()), "http://0.0.0.0:3333");
+    }
+
+    #[test]
+    fn forwarded_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("127.0.0.1:3333"));
+        headers.insert(
+            X_FORWARDED_HOST,
+            HeaderValue::from_static("maps.example.com, proxy.internal"),
+        );
+        headers.insert(X_FORWARDED_PROTO, HeaderValue::from_static("https"));
+        headers.insert(X_FORWARDED_PREFIX, HeaderValue::from_static("/utiles/"));
+        assert_eq!(
+            base_url(&cfg(), &headers),
+            "https://maps.example.com/utiles"
+        );
+    }
+
+    #[test]
+    fn public_url_wins() {
+        let mut headers = HeaderMap::new();
+        headers.insert(X_FORWARDED_HOST, HeaderValue::from_static("proxy"));
+        let cfg = cfg().with_public_url(Some("https://tiles.example.com/".into()));
+        assert_eq!(base_url(&cfg, &headers), "https://tiles.example.com");
+    }
+}
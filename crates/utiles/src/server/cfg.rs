@@ -1,25 +1,332 @@
 //! Utiles server configuration
+//!
+//! Loaded from a toml (or json) file w/ `utiles serve --config serve.toml`:
+//!
+//! ```toml
+//! host = "0.0.0.0"
+//! port = 3333
+//! fspaths = ["./tiles"]
+//! public_url = "https://maps.example.com/utiles"
+//! request_timeout_ms = 5000
+//! cors_origins = ["https://example.com"]
+//! cache_control = "public, max-age=3600"
+//!
+//! [compression]
+//! gzip = true
+//! zstd = true
+//! br = false
+//! deflate = false
+//!
+//! [log]
+//! level = "info"
+//! json = false
+//!
+//! # dataset w/ an alias id
+//! [datasets.osm]
+//! path = "./tiles/osm-standard.z0z4.mbtiles"
+//! maxzoom = 3
+//! bbox = [-10.0, 35.0, 30.0, 60.0]
+//!
+//! # options for a dataset found via `fspaths` (no `path`)
+//! [datasets.landcover]
+//! cors_origins = ["*"]
+//! cache_control = "public, max-age=86400"
+//! ```
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
+use utiles_core::{BBox, TileLike};
+
+use crate::errors::{UtilesError, UtilesResult};
+use crate::lager::LagerConfig;
+
+const fn default_port() -> u16 {
+    3333
+}
+
+fn default_host() -> String {
+    "0.0.0.0".to_string()
+}
+
+const fn default_request_timeout_ms() -> u64 {
+    1000
+}
+
+/// Response compression toggles
+#[expect(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    pub gzip: bool,
+    pub zstd: bool,
+    pub br: bool,
+    pub deflate: bool,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            zstd: true,
+            br: false,
+            deflate: false,
+        }
+    }
+}
+
+/// Per-dataset options
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatasetConfig {
+    /// Filesystem path (mbtiles file or tile pyramid dir); if not set the
+    /// options apply to the dataset found via `fspaths` w/ the same id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Minimum zoom served (clamps the dataset's minzoom)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minzoom: Option<u8>,
+
+    /// Maximum zoom served (clamps the dataset's maxzoom)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maxzoom: Option<u8>,
+
+    /// Only serve tiles intersecting `[west, south, east, north]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<[f64; 4]>,
+
+    /// Allowed CORS origins (`"*"` for any); overrides the server setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors_origins: Option<Vec<String>>,
+
+    /// `Cache-Control` header for tile responses; overrides the server setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+}
+
+impl DatasetConfig {
+    #[must_use]
+    pub fn bbox(&self) -> Option<BBox> {
+        self.bbox.map(|[w, s, e, n]| BBox::new(w, s, e, n))
+    }
+
+    /// Return true if the tile is within the zoom range and bbox (which may
+    /// cross the antimeridian)
+    #[must_use]
+    pub fn allows_tile<T: TileLike>(&self, tile: &T) -> bool {
+        let z = tile.z();
+        if self.minzoom.is_some_and(|minzoom| z < minzoom)
+            || self.maxzoom.is_some_and(|maxzoom| z > maxzoom)
+        {
+            return false;
+        }
+        self.bbox().is_none_or(|bbox| {
+            let tbox = tile.geobbox();
+            bbox.bboxes().iter().any(|bbox| {
+                tbox.west < bbox.east
+                    && tbox.east > bbox.west
+                    && tbox.south < bbox.north
+                    && tbox.north > bbox.south
+            })
+        })
+    }
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct UtilesServerConfig {
+    #[serde(default = "default_host")]
     pub host: String,
+
+    #[serde(default = "default_port")]
     pub port: u16,
+
+    #[serde(default)]
     pub fspaths: Vec<String>,
+
+    /// Public base url (e.g. `https://maps.example.com/utiles`) used for the
+    /// `tiles` urls in tilejson responses instead of the request host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_url: Option<String>,
+
+    /// Request timeout in milliseconds
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+
+    /// Allowed CORS origins (`"*"` for any); defaults to any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors_origins: Option<Vec<String>>,
+
+    /// Default `Cache-Control` header for tile responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// Log settings (cli `--debug`/`--trace`/`--log-json` take precedence)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log: Option<LagerConfig>,
+
+    /// Dataset options/aliases keyed by dataset id
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub datasets: BTreeMap<String, DatasetConfig>,
 }
 
 impl UtilesServerConfig {
     #[must_use]
-    pub const fn new(host: String, port: u16, fspaths: Vec<String>) -> Self {
+    pub fn new(host: String, port: u16, fspaths: Vec<String>) -> Self {
         Self {
             host,
             port,
             fspaths,
+            public_url: None,
+            request_timeout_ms: default_request_timeout_ms(),
+            cors_origins: None,
+            cache_control: None,
+            compression: CompressionConfig::default(),
+            log: None,
+            datasets: BTreeMap::new(),
         }
     }
 
+    /// Load a server config from a toml (or `.json`) file
+    pub fn from_file(fspath: &Path) -> UtilesResult<Self> {
+        let s = std::fs::read_to_string(fspath).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                UtilesError::FileDoesNotExist(fspath.display().to_string())
+            } else {
+                UtilesError::IoError(e)
+            }
+        })?;
+        let is_json = fspath
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let cfg = if is_json {
+            serde_json::from_str(&s)?
+        } else {
+            toml::from_str(&s).map_err(|e| {
+                UtilesError::ParsingError(format!("{}: {e}", fspath.display()))
+            })?
+        };
+        Ok(cfg)
+    }
+
+    #[must_use]
+    pub fn with_public_url(mut self, public_url: Option<String>) -> Self {
+        self.public_url = public_url;
+        self
+    }
+
     #[must_use]
     pub fn addr(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    /// Dataset options for the dataset id (default if not configured)
+    #[must_use]
+    pub fn dataset_config(&self, id: &str) -> DatasetConfig {
+        self.datasets.get(id).cloned().unwrap_or_default()
+    }
+
+    /// Return true if the origin is allowed for the dataset id
+    #[must_use]
+    pub fn cors_allows(&self, dataset: Option<&str>, origin: &str) -> bool {
+        let origins = dataset
+            .and_then(|id| self.datasets.get(id))
+            .and_then(|ds| ds.cors_origins.as_ref())
+            .or(self.cors_origins.as_ref());
+        origins.is_none_or(|origins| {
+            origins
+                .iter()
+                .any(|allowed| allowed == "*" || allowed == origin)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utiles_core::Tile;
+
+    use super::*;
+    use crate::lager::LagerLevel;
+
+    const CFG_TOML: &str = r#"
+port = 4444
+fspaths = ["./tiles"]
+request_timeout_ms = 2500
+cors_origins = ["https://a.example.com"]
+
+[compression]
+br = true
+
+[log]
+level = "debug"
+json = true
+
+[datasets.osm]
+path = "./osm.mbtiles"
+minzoom = 1
+maxzoom = 3
+bbox = [0.0, 0.0, 10.0, 10.0]
+
+[datasets.open]
+cors_origins = ["*"]
+"#;
+
+    #[test]
+    fn parse_toml() {
+        let cfg: UtilesServerConfig = toml::from_str(CFG_TOML).expect("parse");
+        assert_eq!(cfg.host, "0.0.0.0");
+        assert_eq!(cfg.port, 4444);
+        assert_eq!(cfg.request_timeout_ms, 2500);
+        assert!(cfg.compression.gzip && cfg.compression.br);
+        let log = cfg.log.expect("log");
+        assert_eq!(log.level, LagerLevel::Debug);
+        assert!(log.json);
+        assert_eq!(cfg.datasets["osm"].path.as_deref(), Some("./osm.mbtiles"));
+    }
+
+    #[test]
+    fn unknown_fields_rejected() {
+        let res = toml::from_str::<UtilesServerConfig>("prot = 1234");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn dataset_allows_tile() {
+        let cfg: UtilesServerConfig = toml::from_str(CFG_TOML).expect("parse");
+        let osm = cfg.dataset_config("osm");
+        // zoom range
+        assert!(!osm.allows_tile(&Tile::new(0, 0, 0)));
+        assert!(!osm.allows_tile(&Tile::new(0, 0, 4)));
+        // 3/4/3 is [0, 0, 45, 40.98]
+        assert!(osm.allows_tile(&Tile::new(4, 3, 3)));
+        // 3/3/3 is west of the bbox (touching)
+        assert!(!osm.allows_tile(&Tile::new(3, 3, 3)));
+        assert!(
+            cfg.dataset_config("unconfigured")
+                .allows_tile(&Tile::new(0, 0, 0))
+        );
+        // antimeridian crossing bbox
+        let fiji = DatasetConfig {
+            bbox: Some([175.0, -20.0, -178.0, -15.0]),
+            ..DatasetConfig::default()
+        };
+        assert!(fiji.allows_tile(&Tile::new(7, 4, 3)));
+        assert!(fiji.allows_tile(&Tile::new(0, 4, 3)));
+        assert!(!fiji.allows_tile(&Tile::new(3, 4, 3)));
+    }
+
+    #[test]
+    fn cors() {
+        let cfg: UtilesServerConfig = toml::from_str(CFG_TOML).expect("parse");
+        assert!(cfg.cors_allows(None, "https://a.example.com"));
+        assert!(!cfg.cors_allows(None, "https://b.example.com"));
+        assert!(!cfg.cors_allows(Some("osm"), "https://b.example.com"));
+        assert!(cfg.cors_allows(Some("open"), "https://b.example.com"));
+        let any = UtilesServerConfig::new("0.0.0.0".into(), 3333, vec![]);
+        assert!(any.cors_allows(Some("osm"), "https://b.example.com"));
+    }
 }
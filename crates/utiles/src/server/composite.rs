@@ -0,0 +1,267 @@
+//! Composite (multi-dataset) vector tile sources ~ `/tiles/{a},{b}/...`
+//!
+//! Mapbox-vector-tiles are protobuf messages with a repeated `layers`
+//! field, so the (decompressed) tiles of several datasets can be
+//! concatenated into a single valid tile; layers w/ a name already merged
+//! from an earlier dataset are dropped (as in the tilejson `vector_layers`).
+use std::collections::HashSet;
+
+use tilejson::TileJSON;
+use utiles_core::Tile;
+use utiles_core::tile_type::TileKind;
+
+use crate::codec::decompress_tile;
+use crate::errors::{UtilesError, UtilesResult};
+use crate::server::state::{Dataset, Datasets};
+
+/// Separator for dataset ids in a composite source id
+pub(super) const COMPOSITE_SEPARATOR: char = ',';
+
+/// Return true if the dataset id refers to a composite source
+pub(super) fn is_composite_id(id: &str) -> bool {
+    id.contains(COMPOSITE_SEPARATOR)
+}
+
+/// Resolve all datasets of a composite id; all must exist and be vector and
+/// may only be listed once
+pub(super) fn resolve<'a>(
+    datasets: &'a Datasets,
+    id: &str,
+) -> UtilesResult<Vec<(&'a str, &'a Dataset)>> {
+    let mut names = HashSet::new();
+    id.split(COMPOSITE_SEPARATOR)
+        .map(|name| {
+            if !names.insert(name) {
+                return Err(UtilesError::Unsupported(format!(
+                    "composite w/ repeated dataset: {name}"
+                )));
+            }
+            let (name, ds) =
+                datasets.datasets.get_key_value(name).ok_or_else(|| {
+                    UtilesError::Error(format!("dataset not found: {name}"))
+                })?;
+            if ds.tilekind == TileKind::Vector {
+                Ok((name.as_str(), ds))
+            } else {
+                Err(UtilesError::Unsupported(format!(
+                    "composite of non-vector dataset: {name}"
+                )))
+            }
+        })
+        .collect()
+}
+
+/// Metrics id of a resolved composite ~ the sorted dataset names so that
+/// the orderings of a composite share a label
+pub(super) fn metrics_id(datasets: &[(&str, &Dataset)]) -> String {
+    let mut names = datasets.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.join(&COMPOSITE_SEPARATOR.to_string())
+}
+
+/// Merge the tilejson(s) of the composite datasets
+///
+/// `vector_layers` are concatenated (first dataset wins on duplicate ids),
+/// bounds are unioned and the zoom range covers all datasets.
+pub(super) fn composite_tilejson(datasets: &[(&str, &Dataset)]) -> TileJSON {
+    let mut layer_ids = HashSet::new();
+    let mut attributions: Vec<String> = vec![];
+    let mut tj = datasets.first().map_or_else(
+        || tilejson::tilejson! { tiles: vec![] },
+        |(_, ds)| ds.tilejson.clone(),
+    );
+    tj.vector_layers = None;
+    tj.attribution = None;
+    tj.name = Some(
+        datasets
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(&COMPOSITE_SEPARATOR.to_string()),
+    );
+    for (_, ds) in datasets {
+        let other = &ds.tilejson;
+        for layer in other.vector_layers.iter().flatten() {
+            if layer_ids.insert(layer.id.clone()) {
+                tj.vector_layers
+                    .get_or_insert_with(Vec::new)
+                    .push(layer.clone());
+            }
+        }
+        if let Some(attribution) = &other.attribution
+            && !attributions.contains(attribution)
+        {
+            attributions.push(attribution.clone());
+        }
+        tj.minzoom = match (tj.minzoom, other.minzoom) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        tj.maxzoom = match (tj.maxzoom, other.maxzoom) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        tj.bounds = match (tj.bounds, other.bounds) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+    if !attributions.is_empty() {
+        tj.attribution = Some(attributions.join(" "));
+    }
+    tj
+}
+
+/// Read a protobuf varint at `pos`, advancing `pos` past it
+fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Protobuf field as `(field number, length-delimited payload, raw bytes)`
+type PbfField<'a> = (u64, Option<&'a [u8]>, &'a [u8]);
+
+/// Split a protobuf message into its fields; `None` if malformed
+fn pbf_fields(buf: &[u8]) -> Option<Vec<PbfField<'_>>> {
+    let mut fields = vec![];
+    let mut pos = 0;
+    while pos < buf.len() {
+        let start = pos;
+        let key = read_varint(buf, &mut pos)?;
+        let payload = match key & 0x7 {
+            0 => {
+                read_varint(buf, &mut pos)?;
+                None
+            }
+            1 => {
+                pos = pos.checked_add(8)?;
+                None
+            }
+            2 => {
+                let len = usize::try_from(read_varint(buf, &mut pos)?).ok()?;
+                let end = pos.checked_add(len)?;
+                let payload = buf.get(pos..end)?;
+                pos = end;
+                Some(payload)
+            }
+            5 => {
+                pos = pos.checked_add(4)?;
+                None
+            }
+            _ => return None,
+        };
+        fields.push((key >> 3, payload, buf.get(start..pos)?));
+    }
+    Some(fields)
+}
+
+/// `Tile.layers` field number
+const TILE_LAYERS_FIELD: u64 = 3;
+/// `Layer.name` field number
+const LAYER_NAME_FIELD: u64 = 1;
+
+/// Append the fields of a (decompressed) vector tile to `merged`, skipping
+/// layers w/ a name in `layer_names` and adding the new layer names
+///
+/// Malformed tiles are appended as is.
+fn merge_tile_layers<'a>(
+    merged: &mut Vec<u8>,
+    layer_names: &mut HashSet<&'a [u8]>,
+    data: &'a [u8],
+) {
+    let Some(fields) = pbf_fields(data) else {
+        merged.extend_from_slice(data);
+        return;
+    };
+    for (field, payload, raw) in fields {
+        let name = payload
+            .filter(|_| field == TILE_LAYERS_FIELD)
+            .and_then(pbf_fields)
+            .and_then(|layer| {
+                layer
+                    .into_iter()
+                    .find(|(field, _, _)| *field == LAYER_NAME_FIELD)
+                    .and_then(|(_, name, _)| name)
+            });
+        if name.is_none_or(|name| layer_names.insert(name)) {
+            merged.extend_from_slice(raw);
+        }
+    }
+}
+
+/// Query and merge the (decompressed) tiles of the composite datasets
+///
+/// Returns `None` if none of the datasets have the tile.
+pub(super) async fn composite_tile(
+    datasets: &[(&str, &Dataset)],
+    tile: &Tile,
+) -> UtilesResult<Option<Vec<u8>>> {
+    let mut tiles = vec![];
+    for (_, ds) in datasets {
+        if !ds.config.allows_tile(tile) {
+            continue;
+        }
+        if let Some(data) = ds.source.query_tile(tile).await? {
+            tiles.push(decompress_tile(&data)?);
+        }
+    }
+    if tiles.is_empty() {
+        return Ok(None);
+    }
+    let mut merged = vec![];
+    let mut layer_names = HashSet::new();
+    for data in &tiles {
+        merge_tile_layers(&mut merged, &mut layer_names, data);
+    }
+    Ok(Some(merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Length-delimited protobuf field
+    fn pbf_bytes(field: u8, payload: &[u8]) -> Vec<u8> {
+        let mut buf = vec![(field << 3) | 2, u8::try_from(payload.len()).expect("len")];
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    /// Tile w/ a single layer w/ the given name and version
+    fn layer_tile(name: &str, version: u8) -> Vec<u8> {
+        let mut layer = pbf_bytes(1, name.as_bytes());
+        layer.extend_from_slice(&[(15 << 3), version]);
+        pbf_bytes(3, &layer)
+    }
+
+    #[test]
+    fn merge_drops_duplicate_layers() {
+        let a = [layer_tile("roads", 2), layer_tile("water", 2)].concat();
+        let b = [layer_tile("water", 1), layer_tile("poi", 1)].concat();
+        let mut merged = vec![];
+        let mut layer_names = HashSet::new();
+        merge_tile_layers(&mut merged, &mut layer_names, &a);
+        merge_tile_layers(&mut merged, &mut layer_names, &b);
+        assert_eq!(
+            merged,
+            [
+                layer_tile("roads", 2),
+                layer_tile("water", 2),
+                layer_tile("poi", 1)
+            ]
+            .concat()
+        );
+        // malformed tiles are appended as is
+        let mut merged = vec![];
+        merge_tile_layers(&mut merged, &mut HashSet::new(), &[0x1a, 0x7f]);
+        assert_eq!(merged, [0x1a, 0x7f]);
+    }
+}
@@ -0,0 +1,302 @@
+//! Tile pyramid directory (`z/x/y.ext`) dataset source
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tilejson::TileJSON;
+use tokio::fs;
+use tracing::{debug, warn};
+use utiles_core::tile_type::{TileKind, tiletype};
+use utiles_core::{Tile, TileLike};
+
+use crate::errors::UtilesResult;
+use crate::mbt::{MbtMetadataRow, parse_metadata_json};
+use crate::utilejson::metadata2tilejson;
+
+const MAX_ZOOM: u8 = 30;
+
+/// Read-only tile pyramid as written by `copy_mbtiles2fs` (xyz / y-down)
+#[derive(Debug)]
+pub(super) struct TilePyramidFs {
+    root: PathBuf,
+    /// known tile file extensions (`""` for none); tried before falling back
+    /// to a `{y}.*` match in the x-dir, which adds any new extension found
+    exts: Mutex<Vec<String>>,
+}
+
+fn numeric_entries(dirpath: &Path) -> Vec<(u32, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dirpath) else {
+        return vec![];
+    };
+    let mut numeric = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            let stem = path.file_name()?.to_str()?.split('.').next()?;
+            let n = stem.parse::<u32>().ok()?;
+            Some((n, path))
+        })
+        .collect::<Vec<_>>();
+    numeric.sort_by_key(|(n, _)| *n);
+    numeric
+}
+
+fn zoom_dirs(root: &Path) -> Vec<(u8, PathBuf)> {
+    numeric_entries(root)
+        .into_iter()
+        .filter(|(_, path)| path.is_dir())
+        .filter_map(|(z, path)| {
+            u8::try_from(z)
+                .ok()
+                .filter(|z| *z <= MAX_ZOOM)
+                .map(|z| (z, path))
+        })
+        .collect()
+}
+
+/// Return the first `z/x/y.ext` tile filepath found under `root`
+fn first_tile_filepath(root: &Path) -> Option<PathBuf> {
+    for (_, zdir) in zoom_dirs(root) {
+        for (_, xdir) in numeric_entries(&zdir) {
+            if !xdir.is_dir() {
+                continue;
+            }
+            if let Some((_, ypath)) = numeric_entries(&xdir)
+                .into_iter()
+                .find(|(_, path)| path.is_file())
+            {
+                return Some(ypath);
+            }
+        }
+    }
+    None
+}
+
+/// Tile file extension (`""` for none)
+fn tile_extension(path: &Path) -> Option<String> {
+    let fname = path.file_name()?.to_str()?;
+    Some(fname.split_once('.').map_or("", |(_, ext)| ext).to_string())
+}
+
+/// Return true if the directory looks like a `z/x/y.ext` tile pyramid
+pub(super) fn is_tile_pyramid_dir(dirpath: &Path) -> bool {
+    dirpath.is_dir() && first_tile_filepath(dirpath).is_some()
+}
+
+impl TilePyramidFs {
+    pub(super) fn open(root: &Path) -> Self {
+        let exts = first_tile_filepath(root)
+            .and_then(|path| tile_extension(&path))
+            .into_iter()
+            .collect::<Vec<_>>();
+        debug!("pyramid: {} (exts: {:?})", root.display(), exts);
+        Self {
+            root: root.to_path_buf(),
+            exts: Mutex::new(exts),
+        }
+    }
+
+    pub(super) fn fspath(&self) -> String {
+        self.root.to_string_lossy().to_string()
+    }
+
+    /// Name of the pyramid ~ the directory name
+    pub(super) fn name(&self) -> String {
+        self.root
+            .file_name()
+            .map_or_else(|| self.fspath(), |name| name.to_string_lossy().to_string())
+    }
+
+    fn xdir(&self, tile: &Tile) -> PathBuf {
+        self.root
+            .join(tile.z().to_string())
+            .join(tile.x().to_string())
+    }
+
+    pub(super) async fn query_tile(
+        &self,
+        tile: &Tile,
+    ) -> UtilesResult<Option<Vec<u8>>> {
+        let xdir = self.xdir(tile);
+        let y = tile.y();
+        let exts = self.known_exts();
+        for ext in &exts {
+            let fname = if ext.is_empty() {
+                y.to_string()
+            } else {
+                format!("{y}.{ext}")
+            };
+            match fs::read(xdir.join(fname)).await {
+                Ok(data) => return Ok(Some(data)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        // not a known extension (mixed formats) ~ match the `{y}.*` stem
+        let Ok(mut entries) = fs::read_dir(&xdir).await else {
+            return Ok(None);
+        };
+        let y = y.to_string();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(ext) = tile_extension(&path) else {
+                continue;
+            };
+            let fname = entry.file_name();
+            let stem = fname.to_str().and_then(|f| f.split('.').next());
+            if stem == Some(y.as_str()) && !exts.contains(&ext) {
+                let data = fs::read(&path).await?;
+                if let Ok(mut known) = self.exts.lock()
+                    && !known.contains(&ext)
+                {
+                    known.push(ext);
+                }
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+
+    fn known_exts(&self) -> Vec<String> {
+        self.exts
+            .lock()
+            .map(|exts| exts.clone())
+            .unwrap_or_default()
+    }
+
+    /// Tile kind of the pyramid based on the first tile found
+    pub(super) async fn query_tilekind(&self) -> UtilesResult<TileKind> {
+        match first_tile_filepath(&self.root) {
+            Some(path) => {
+                let data = fs::read(path).await?;
+                Ok(tiletype(&data).format.kind())
+            }
+            None => Ok(TileKind::Unknown),
+        }
+    }
+
+    async fn metadata_rows(&self) -> Vec<MbtMetadataRow> {
+        let metadata_path = self.root.join("metadata.json");
+        match fs::read_to_string(&metadata_path).await {
+            Ok(metadata_str) => {
+                parse_metadata_json(&metadata_str).unwrap_or_else(|e| {
+                    warn!("{}: {e}", metadata_path.display());
+                    vec![]
+                })
+            }
+            Err(_) => vec![],
+        }
+    }
+
+    /// `TileJSON` from the optional `metadata.json` with minzoom, maxzoom,
+    /// name and format filled in from the directory when missing
+    pub(super) async fn tilejson(&self) -> UtilesResult<TileJSON> {
+        let mut metadata = self.metadata_rows().await;
+        let has = |metadata: &[MbtMetadataRow], name: &str| {
+            metadata.iter().any(|row| row.name == name)
+        };
+        let zooms = zoom_dirs(&self.root);
+        if let (Some((minzoom, _)), Some((maxzoom, _))) = (zooms.first(), zooms.last())
+        {
+            if !has(&metadata, "minzoom") {
+                metadata
+                    .push(MbtMetadataRow::new("minzoom".into(), minzoom.to_string()));
+            }
+            if !has(&metadata, "maxzoom") {
+                metadata
+                    .push(MbtMetadataRow::new("maxzoom".into(), maxzoom.to_string()));
+            }
+        }
+        if !has(&metadata, "name") {
+            metadata.push(MbtMetadataRow::new("name".into(), self.name()));
+        }
+        if !has(&metadata, "format")
+            && let Some(path) = first_tile_filepath(&self.root)
+        {
+            let data = fs::read(path).await?;
+            metadata.push(MbtMetadataRow::new(
+                "format".into(),
+                tiletype(&data).format.to_string(),
+            ));
+        }
+        metadata2tilejson(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    fn write_pyramid(root: &Path) {
+        std::fs::create_dir_all(root.join("1").join("0")).expect("mkdir");
+        std::fs::create_dir_all(root.join("2").join("3")).expect("mkdir");
+        std::fs::write(root.join("1").join("0").join("1.png"), PNG_MAGIC)
+            .expect("write");
+        std::fs::write(root.join("2").join("3").join("2.webp"), b"RIFF\0\0\0\0WEBP")
+            .expect("write");
+        // mixed formats across the x-dirs of a zoom
+        std::fs::create_dir_all(root.join("2").join("0")).expect("mkdir");
+        std::fs::create_dir_all(root.join("2").join("1")).expect("mkdir");
+        std::fs::write(root.join("2").join("0").join("0.png"), PNG_MAGIC)
+            .expect("write");
+        std::fs::write(root.join("2").join("1").join("3.jpg"), b"\xff\xd8\xff")
+            .expect("write");
+    }
+
+    #[tokio::test]
+    async fn pyramid_query_tile_and_tilejson() {
+        let root = std::env::temp_dir()
+            .join(format!("utiles-test-pyramid-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        write_pyramid(&root);
+        assert!(is_tile_pyramid_dir(&root));
+        assert!(!is_tile_pyramid_dir(&root.join("1").join("0")));
+
+        let pyramid = TilePyramidFs::open(&root);
+        let png = pyramid
+            .query_tile(&Tile::new(0, 1, 1))
+            .await
+            .expect("query");
+        assert_eq!(png.as_deref(), Some(PNG_MAGIC));
+        // different extension than the first tile
+        let webp = pyramid
+            .query_tile(&Tile::new(3, 2, 2))
+            .await
+            .expect("query");
+        assert!(webp.is_some());
+        let png = pyramid
+            .query_tile(&Tile::new(0, 0, 2))
+            .await
+            .expect("query");
+        assert_eq!(png.as_deref(), Some(PNG_MAGIC));
+        for _ in 0..2 {
+            // found by stem, then by the learned extension
+            let jpg = pyramid
+                .query_tile(&Tile::new(1, 3, 2))
+                .await
+                .expect("query");
+            assert_eq!(jpg.as_deref(), Some(&b"\xff\xd8\xff"[..]));
+        }
+        assert!(pyramid.known_exts().contains(&"jpg".to_string()));
+        let missing = pyramid
+            .query_tile(&Tile::new(1, 0, 2))
+            .await
+            .expect("query");
+        assert!(missing.is_none());
+        let missing = pyramid
+            .query_tile(&Tile::new(0, 0, 1))
+            .await
+            .expect("query");
+        assert!(missing.is_none());
+
+        let tj = pyramid.tilejson().await.expect("tilejson");
+        assert_eq!(tj.minzoom, Some(1));
+        assert_eq!(tj.maxzoom, Some(2));
+        assert_eq!(
+            pyramid.query_tilekind().await.expect("kind"),
+            TileKind::Raster
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,298 @@
+//! Prometheus (text exposition format) metrics for the tile server
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::server::state::ServerState;
+
+/// Latency histogram buckets (seconds)
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.000_5, 0.001, 0.002_5, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    counts: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; LATENCY_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bucket, count) in LATENCY_BUCKETS.iter().zip(self.counts.iter_mut()) {
+            if secs <= *bucket {
+                *count += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (bucket, count) in LATENCY_BUCKETS.iter().zip(self.counts.iter()) {
+            let _ =
+                writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{bucket}\"}} {count}");
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let _ = writeln!(out, "{name}_sum{labels} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{labels} {}", self.count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct HttpMetrics {
+    requests: BTreeMap<u16, u64>,
+    latency: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct DatasetMetrics {
+    requests: BTreeMap<u16, u64>,
+    bytes: u64,
+    latency: Histogram,
+    query: Histogram,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Metrics {
+    http: Mutex<HttpMetrics>,
+    datasets: Mutex<BTreeMap<String, DatasetMetrics>>,
+}
+
+/// Escape a prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_help_type(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+impl Metrics {
+    pub(super) fn observe_http(&self, status: StatusCode, latency: Duration) {
+        if let Ok(mut http) = self.http.lock() {
+            *http.requests.entry(status.as_u16()).or_default() += 1;
+            http.latency.observe(latency);
+        }
+    }
+
+    /// Record a tile request for a dataset (or composite metrics) id
+    pub(super) fn observe_tile(
+        &self,
+        dataset: &str,
+        status: StatusCode,
+        nbytes: usize,
+        latency: Duration,
+    ) {
+        if let Ok(mut datasets) = self.datasets.lock() {
+            let ds = datasets.entry(dataset.to_string()).or_default();
+            *ds.requests.entry(status.as_u16()).or_default() += 1;
+            ds.bytes += nbytes as u64;
+            ds.latency.observe(latency);
+        }
+    }
+
+    /// Record the time spent querying a tile from a dataset's db/fs
+    pub(super) fn observe_query(&self, dataset: &str, duration: Duration) {
+        if let Ok(mut datasets) = self.datasets.lock() {
+            datasets
+                .entry(dataset.to_string())
+                .or_default()
+                .query
+                .observe(duration);
+        }
+    }
+
+    /// Render all metrics in the prometheus text exposition format
+    pub(super) fn render(&self, uptime: Duration) -> String {
+        let mut out = String::new();
+        write_help_type(&mut out, "utiles_uptime_seconds", "Server uptime", "gauge");
+        let _ = writeln!(out, "utiles_uptime_seconds {}", uptime.as_secs_f64());
+
+        if let Ok(http) = self.http.lock() {
+            write_help_type(
+                &mut out,
+                "utiles_http_requests_total",
+                "HTTP requests by status code",
+                "counter",
+            );
+            for (status, n) in &http.requests {
+                let _ = writeln!(
+                    out,
+                    "utiles_http_requests_total{{status=\"{status}\"}} {n}"
+                );
+            }
+            write_help_type(
+                &mut out,
+                "utiles_http_request_duration_seconds",
+                "HTTP request latency",
+                "histogram",
+            );
+            http.latency
+                .render(&mut out, "utiles_http_request_duration_seconds", "");
+        }
+
+        if let Ok(datasets) = self.datasets.lock() {
+            write_help_type(
+                &mut out,
+                "utiles_tile_requests_total",
+                "Tile requests by dataset and status code",
+                "counter",
+            );
+            for (name, ds) in datasets.iter() {
+                let name = escape_label(name);
+                for (status, n) in &ds.requests {
+                    let _ = writeln!(
+                        out,
+                        "utiles_tile_requests_total{{dataset=\"{name}\",status=\"{status}\"}} {n}"
+                    );
+                }
+            }
+            write_help_type(
+                &mut out,
+                "utiles_tile_bytes_total",
+                "Tile bytes served (before response compression)",
+                "counter",
+            );
+            for (name, ds) in datasets.iter() {
+                let _ = writeln!(
+                    out,
+                    "utiles_tile_bytes_total{{dataset=\"{}\"}} {}",
+                    escape_label(name),
+                    ds.bytes
+                );
+            }
+            write_help_type(
+                &mut out,
+                "utiles_tile_request_duration_seconds",
+                "Tile request latency by dataset",
+                "histogram",
+            );
+            for (name, ds) in datasets.iter() {
+                let labels = format!("dataset=\"{}\"", escape_label(name));
+                ds.latency.render(
+                    &mut out,
+                    "utiles_tile_request_duration_seconds",
+                    &labels,
+                );
+            }
+            write_help_type(
+                &mut out,
+                "utiles_tile_query_duration_seconds",
+                "Tile query (sqlite/fs) time by dataset",
+                "histogram",
+            );
+            for (name, ds) in datasets.iter() {
+                let labels = format!("dataset=\"{}\"", escape_label(name));
+                ds.query.render(
+                    &mut out,
+                    "utiles_tile_query_duration_seconds",
+                    &labels,
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Middleware recording status code and latency of every request
+pub(super) async fn track_http(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let response = next.run(request).await;
+    state
+        .metrics
+        .observe_http(response.status(), start.elapsed());
+    response
+}
+
+/// `/metrics` handler
+pub(super) async fn metrics(
+    State(state): State<Arc<ServerState>>,
+) -> impl IntoResponse {
+    let body = state.metrics.render(state.start_ts.elapsed());
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        )],
+        body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_metrics() {
+        let metrics = Metrics::default();
+        metrics.observe_http(StatusCode::OK, Duration::from_millis(3));
+        metrics.observe_http(StatusCode::NOT_FOUND, Duration::from_millis(1));
+        metrics.observe_tile("osm", StatusCode::OK, 100, Duration::from_millis(3));
+        metrics.observe_tile(
+            "osm",
+            StatusCode::NO_CONTENT,
+            0,
+            Duration::from_millis(1),
+        );
+        metrics.observe_query("osm", Duration::from_micros(700));
+        let txt = metrics.render(Duration::from_secs(1));
+        assert!(txt.contains("utiles_http_requests_total{status=\"200\"} 1\n"));
+        assert!(txt.contains("utiles_http_requests_total{status=\"404\"} 1\n"));
+        assert!(txt.contains(
+            "utiles_tile_requests_total{dataset=\"osm\",status=\"204\"} 1\n"
+        ));
+        assert!(txt.contains("utiles_tile_bytes_total{dataset=\"osm\"} 100\n"));
+        assert!(txt.contains(
+            "utiles_tile_query_duration_seconds_bucket{dataset=\"osm\",le=\"0.001\"} 1\n"
+        ));
+        assert!(txt.contains(
+            "utiles_tile_query_duration_seconds_bucket{dataset=\"osm\",le=\"0.0005\"} 0\n"
+        ));
+        assert!(
+            txt.contains(
+                "utiles_http_request_duration_seconds_bucket{le=\"+Inf\"} 2\n"
+            )
+        );
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
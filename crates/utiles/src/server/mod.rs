@@ -3,20 +3,18 @@ use std::time::Duration;
 
 use axum::body::Body;
 use axum::extract::{Path, State};
-use axum::http::header::{HeaderMap, HeaderValue};
+use axum::http::header::{self, HeaderMap, HeaderValue};
 use axum::http::{Method, StatusCode};
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::{Json, Router};
-use axum_extra::TypedHeader;
-use headers::Host;
 use request_id::Radix36MakeRequestId;
 use serde::Deserialize;
 use serde_json::json;
 use tilejson::TileJSON;
 use tower::ServiceBuilder;
 use tower_http::compression::CompressionLayer;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::request_id::{PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::{
@@ -26,18 +24,22 @@ use tracing::info;
 use utiles_core::tile_type::{TileKind, blob2headers};
 use utiles_core::{Tile, quadkey2tile, utile};
 
-use crate::errors::UtilesResult;
+use crate::errors::{UtilesError, UtilesResult};
 use crate::internal::signal::shutdown_signal;
-use crate::mbt::MbtilesAsync;
-pub use crate::server::cfg::UtilesServerConfig;
+pub use crate::server::cfg::{CompressionConfig, DatasetConfig, UtilesServerConfig};
 use crate::server::health::Health;
+use crate::server::metrics::Metrics;
 use crate::server::preflight::preflight;
-use crate::server::state::{MbtilesDataset, ServerState};
+use crate::server::state::{Dataset, ServerState};
 use crate::server::ui::uitiles;
 
+mod base_url;
 mod cfg;
+mod composite;
 mod favicon;
+mod fs_pyramid;
 mod health;
+mod metrics;
 mod preflight;
 pub mod radix36;
 mod request_id;
@@ -55,13 +57,18 @@ pub async fn utiles_serve(cfg: UtilesServerConfig) -> UtilesResult<()> {
     let state = ServerState {
         config: cfg,
         datasets,
+        metrics: Metrics::default(),
         start_ts: start,
     };
     // Wrap state in an Arc so that it can be shared with the app...
     // ...seems to be the idiomatic way to do this...
     let shared_state = Arc::new(state);
-    let compression_layer: CompressionLayer =
-        CompressionLayer::new().gzip(true).zstd(true);
+    let compression = shared_state.config.compression;
+    let compression_layer: CompressionLayer = CompressionLayer::new()
+        .gzip(compression.gzip)
+        .zstd(compression.zstd)
+        .br(compression.br)
+        .deflate(compression.deflate);
 
     let trace_layer = TraceLayer::new_for_http()
         .make_span_with(DefaultMakeSpan::new().include_headers(true))
@@ -92,8 +99,7 @@ pub async fn utiles_serve(cfg: UtilesServerConfig) -> UtilesResult<()> {
     let cors_layer = CorsLayer::new()
         // allow `GET` and `POST` when accessing the resource
         .allow_methods([Method::GET, Method::POST])
-        // allow requests from any origin
-        .allow_origin(Any);
+        .allow_origin(cors_allow_origin(&shared_state.config));
     // Build our middleware stack
     let middleware = ServiceBuilder::new()
         .layer(SetRequestIdLayer::x_request_id(
@@ -103,7 +109,7 @@ pub async fn utiles_serve(cfg: UtilesServerConfig) -> UtilesResult<()> {
         .layer(PropagateRequestIdLayer::x_request_id())
         .layer(TimeoutLayer::with_status_code(
             StatusCode::REQUEST_TIMEOUT,
-            Duration::from_secs(1),
+            Duration::from_millis(shared_state.config.request_timeout_ms),
         ))
         .layer(compression_layer);
 
@@ -112,6 +118,7 @@ pub async fn utiles_serve(cfg: UtilesServerConfig) -> UtilesResult<()> {
         .route("/", get(root))
         .route("/favicon.ico", get(favicon::favicon))
         .route("/health", get(health))
+        .route("/metrics", get(metrics::metrics))
         .route("/cfg", get(get_cfg))
         .route("/uitiles", get(uitiles))
         .route("/datasets", get(get_datasets))
@@ -121,11 +128,17 @@ pub async fn utiles_serve(cfg: UtilesServerConfig) -> UtilesResult<()> {
             get(get_dataset_tile_quadkey),
         )
         .route("/tiles/{dataset}/{z}/{x}/{y}", get(get_dataset_tile_zxy))
+        // fallback before the layers so 404s go through them (+ metrics)
+        .fallback(four_o_four) // 404
         .layer(trace_layer)
         .layer(middleware)
+        // outside the timeout layer so timeouts (408) are tracked
+        .layer(axum::middleware::from_fn_with_state(
+            shared_state.clone(),
+            metrics::track_http,
+        ))
         .layer(cors_layer)
-        .with_state(shared_state) // shared app/server state
-        .fallback(four_o_four); // 404
+        .with_state(shared_state); // shared app/server state
 
     // let addr = cfg.addr();
     info!("Listening on: {}", addr);
@@ -135,6 +148,33 @@ pub async fn utiles_serve(cfg: UtilesServerConfig) -> UtilesResult<()> {
         .await?;
     Ok(())
 }
+/// Allowed CORS origin(s) ~ any origin unless configured otherwise
+fn cors_allow_origin(cfg: &UtilesServerConfig) -> AllowOrigin {
+    let configured = cfg.cors_origins.is_some()
+        || cfg.datasets.values().any(|ds| ds.cors_origins.is_some());
+    if !configured {
+        return AllowOrigin::from(Any);
+    }
+    let cfg = cfg.clone();
+    AllowOrigin::predicate(move |origin, parts| {
+        let Ok(origin) = origin.to_str() else {
+            return false;
+        };
+        // `/tiles/{dataset}/...` ~ composite ids must be allowed by all
+        let dataset = parts
+            .uri
+            .path()
+            .strip_prefix("/tiles/")
+            .and_then(|rest| rest.split('/').next());
+        match dataset {
+            Some(id) => id
+                .split(composite::COMPOSITE_SEPARATOR)
+                .all(|id| cfg.cors_allows(Some(id), origin)),
+            None => cfg.cors_allows(None, origin),
+        }
+    })
+}
+
 // =====================================================================
 // ROUTES ~ ROUTES ~ ROUTES ~ ROUTES ~ ROUTES ~ ROUTES ~ ROUTES ~ ROUTES
 // =====================================================================
@@ -153,10 +193,10 @@ enum GetTileResponse {
 }
 
 async fn dataset_query_tile(
-    dataset: &MbtilesDataset,
+    dataset: &Dataset,
     tile: &Tile,
 ) -> anyhow::Result<GetTileResponse> {
-    let tile_data = dataset.mbtiles.query_tile(tile).await?;
+    let tile_data = dataset.source.query_tile(tile).await?;
     match tile_data {
         Some(data) => Ok(GetTileResponse::Data(data)),
         None => {
@@ -169,63 +209,161 @@ async fn dataset_query_tile(
     }
 }
 
-async fn get_dataset_tile_zxy(
-    State(state): State<Arc<ServerState>>,
-    Path(path): Path<TileZxyPath>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
-    let mbtiles = state.datasets.mbtiles.get(&path.dataset);
-    let t = utile!(path.x, path.y, path.z);
-    if let Some(mbt_ds) = mbtiles {
-        let tile_data = dataset_query_tile(mbt_ds, &t).await;
+/// Status and json error for a composite id that doesn't resolve; 400 for
+/// unsupported (non-vector or repeated dataset) composites, 404 for missing
+/// datasets
+fn composite_error(dataset: &str, e: &UtilesError) -> (StatusCode, serde_json::Value) {
+    let status = if matches!(e, UtilesError::Unsupported(_)) {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::NOT_FOUND
+    };
+    (
+        status,
+        json!({
+            "error": e.to_string(),
+            "dataset": dataset,
+            "status": status.as_u16(),
+        }),
+    )
+}
+
+fn dataset_not_found(dataset: &str) -> (StatusCode, String) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": "Dataset not found",
+            "dataset": dataset,
+            "status": 404,
+        }))
+        .to_string(),
+    )
+}
 
-        match tile_data {
-            Ok(GetTileResponse::Data(data)) => {
-                let headers = blob2headers(&data);
-                let mut headers_map = HeaderMap::new();
-                for (k, v) in headers {
-                    if let Ok(hvalue) = HeaderValue::from_str(v) {
-                        headers_map.insert(k, hvalue);
-                    }
+/// Query a tile from a dataset (or composite of datasets) by id and record
+/// the request in the server metrics
+async fn tile_response(
+    state: &ServerState,
+    dataset: &str,
+    tile: &Tile,
+) -> Result<(StatusCode, HeaderMap, Body), (StatusCode, String)> {
+    let start = std::time::Instant::now();
+    let composite = composite::is_composite_id(dataset)
+        .then(|| composite::resolve(&state.datasets, dataset));
+    // metrics label; `None` for unknown datasets
+    let metrics_id = match &composite {
+        Some(resolved) => resolved.as_deref().ok().map(composite::metrics_id),
+        None => state.datasets.get(dataset).map(|_| dataset.to_string()),
+    };
+    let res = query_tile_response(state, dataset, composite, tile).await;
+    if let Some(metrics_id) = metrics_id {
+        let (status, nbytes) = match &res {
+            Ok((status, _, data)) => (*status, data.len()),
+            Err((status, _)) => (*status, 0),
+        };
+        state
+            .metrics
+            .observe_tile(&metrics_id, status, nbytes, start.elapsed());
+    }
+    res.map(|(status, headers, data)| (status, headers, Body::from(data)))
+}
+
+/// Query a tile response; `composite` is the resolved composite datasets if
+/// the id is a composite id
+async fn query_tile_response(
+    state: &ServerState,
+    dataset: &str,
+    composite: Option<UtilesResult<Vec<(&str, &Dataset)>>>,
+    tile: &Tile,
+) -> Result<(StatusCode, HeaderMap, Vec<u8>), (StatusCode, String)> {
+    let tile_data = if let Some(resolved) = composite {
+        let datasets = resolved.map_err(|e| {
+            let (status, error) = composite_error(dataset, &e);
+            (status, error.to_string())
+        })?;
+        let query_start = std::time::Instant::now();
+        let res = composite::composite_tile(&datasets, tile)
+            .await
+            .map(|data| data.map_or(GetTileResponse::NoContent, GetTileResponse::Data))
+            .map_err(anyhow::Error::from);
+        state
+            .metrics
+            .observe_query(&composite::metrics_id(&datasets), query_start.elapsed());
+        res
+    } else {
+        let ds = state
+            .datasets
+            .get(dataset)
+            .ok_or_else(|| dataset_not_found(dataset))?;
+        if ds.config.allows_tile(tile) {
+            let query_start = std::time::Instant::now();
+            let res = dataset_query_tile(ds, tile).await;
+            state.metrics.observe_query(dataset, query_start.elapsed());
+            res
+        } else if ds.tilekind == TileKind::Vector {
+            Ok(GetTileResponse::NoContent)
+        } else {
+            Ok(GetTileResponse::NotFound)
+        }
+    };
+    let cache_control = state
+        .datasets
+        .get(dataset)
+        .and_then(|ds| ds.config.cache_control.as_ref())
+        .or(state.config.cache_control.as_ref())
+        .and_then(|cc| HeaderValue::from_str(cc).ok());
+    match tile_data {
+        Ok(GetTileResponse::Data(data)) => {
+            let headers = blob2headers(&data);
+            let mut headers_map = HeaderMap::new();
+            for (k, v) in headers {
+                if let Ok(hvalue) = HeaderValue::from_str(v) {
+                    headers_map.insert(k, hvalue);
                 }
-                Ok((StatusCode::OK, headers_map, Body::from(data)))
             }
-            Ok(GetTileResponse::NoContent) => {
-                Ok((StatusCode::NO_CONTENT, HeaderMap::new(), Body::empty()))
+            if let Some(cc) = cache_control {
+                headers_map.insert(header::CACHE_CONTROL, cc);
             }
-            Ok(GetTileResponse::NotFound) => Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({
-                    "error": "Tile not found",
-                    "dataset": path.dataset,
-                    "tile": t,
-                    "status": 404,
-                }))
-                .to_string(),
-            )),
-            Err(e) => Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({
-                    "error": e.to_string(),
-                    "dataset": path.dataset,
-                    "tile": t,
-                    "status": 404,
-                }))
-                .to_string(),
-            )),
+            Ok((StatusCode::OK, headers_map, data))
         }
-    } else {
-        Err((
+        Ok(GetTileResponse::NoContent) => {
+            let mut headers_map = HeaderMap::new();
+            if let Some(cc) = cache_control {
+                headers_map.insert(header::CACHE_CONTROL, cc);
+            }
+            Ok((StatusCode::NO_CONTENT, headers_map, vec![]))
+        }
+        Ok(GetTileResponse::NotFound) => Err((
             StatusCode::NOT_FOUND,
             Json(json!({
-                "error": "Dataset not found",
-                "dataset": path.dataset,
+                "error": "Tile not found",
+                "dataset": dataset,
+                "tile": tile,
                 "status": 404,
             }))
             .to_string(),
-        ))
+        )),
+        Err(e) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": e.to_string(),
+                "dataset": dataset,
+                "tile": tile,
+                "status": 404,
+            }))
+            .to_string(),
+        )),
     }
 }
 
+async fn get_dataset_tile_zxy(
+    State(state): State<Arc<ServerState>>,
+    Path(path): Path<TileZxyPath>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let t = utile!(path.x, path.y, path.z);
+    tile_response(&state, &path.dataset, &t).await
+}
+
 #[derive(Deserialize)]
 struct TileQuadkeyPath {
     dataset: String,
@@ -236,67 +374,19 @@ async fn get_dataset_tile_quadkey(
     State(state): State<Arc<ServerState>>,
     Path(path): Path<TileQuadkeyPath>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let mbt_ds = state.datasets.mbtiles.get(&path.dataset);
-    if let Some(mbt_ds) = mbt_ds {
-        let parsed_tile = quadkey2tile(&path.quadkey).map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Error parsing quadkey: {e}"),
-            )
-        })?;
-        let tile_data = dataset_query_tile(mbt_ds, &parsed_tile).await;
-        match tile_data {
-            Ok(GetTileResponse::Data(data)) => {
-                let headers = blob2headers(&data);
-                let mut headers_map = HeaderMap::new();
-                for (k, v) in headers {
-                    if let Ok(hvalue) = HeaderValue::from_str(v) {
-                        headers_map.insert(k, hvalue);
-                    }
-                }
-                Ok((StatusCode::OK, headers_map, Body::from(data)))
-            }
-            Ok(GetTileResponse::NoContent) => {
-                Ok((StatusCode::NO_CONTENT, HeaderMap::new(), Body::empty()))
-            }
-            Ok(GetTileResponse::NotFound) => Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({
-                    "error": "Tile not found",
-                    "dataset": path.dataset,
-                    "tile": parsed_tile,
-                    "status": 404,
-                }))
-                .to_string(),
-            )),
-            Err(e) => Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({
-                    "error": e.to_string(),
-                    "dataset": path.dataset,
-                    "tile": parsed_tile,
-                    "status": 404,
-                }))
-                .to_string(),
-            )),
-        }
-    } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "error": "Dataset not found",
-                "dataset": path.dataset,
-                "status": 404,
-            }))
-            .to_string(),
-        ))
-    }
+    let parsed_tile = quadkey2tile(&path.quadkey).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Error parsing quadkey: {e}"),
+        )
+    })?;
+    tile_response(&state, &path.dataset, &parsed_tile).await
 }
 
 async fn get_datasets(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
     let r = state
         .datasets
-        .mbtiles
+        .datasets
         .keys()
         .cloned()
         .collect::<Vec<String>>();
@@ -304,34 +394,47 @@ async fn get_datasets(State(state): State<Arc<ServerState>>) -> impl IntoRespons
 }
 
 async fn get_dataset_tilejson(
-    TypedHeader(host): TypedHeader<Host>,
+    headers: HeaderMap,
     State(state): State<Arc<ServerState>>,
     Path(path): Path<String>,
 ) -> impl IntoResponse {
     let dataset = path;
-
-    let ds = state.datasets.mbtiles.get(&dataset);
-    if let Some(ds) = ds {
-        let tilejson = ds.tilejson.clone();
-        let tiles_url = format!("http://{host}/tiles/{dataset}/{{z}}/{{x}}/{{y}}");
-        let tilejson_with_tiles = TileJSON {
-            tiles: vec![tiles_url],
-            ..tilejson
-        };
-        if let Ok(tjval) = serde_json::to_value(&tilejson_with_tiles) {
-            Json(tjval)
-        } else {
+    let tilejson = if composite::is_composite_id(&dataset) {
+        match composite::resolve(&state.datasets, &dataset) {
+            Ok(datasets) => composite::composite_tilejson(&datasets),
+            Err(e) => {
+                let (status, error) = composite_error(&dataset, &e);
+                return (status, Json(error));
+            }
+        }
+    } else if let Some(ds) = state.datasets.get(&dataset) {
+        ds.tilejson.clone()
+    } else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Dataset not found",
+                "dataset": dataset,
+                "status": 404,
+            })),
+        );
+    };
+    let base_url = base_url::base_url(&state.config, &headers);
+    let tiles_url = format!("{base_url}/tiles/{dataset}/{{z}}/{{x}}/{{y}}");
+    let tilejson_with_tiles = TileJSON {
+        tiles: vec![tiles_url],
+        ..tilejson
+    };
+    if let Ok(tjval) = serde_json::to_value(&tilejson_with_tiles) {
+        (StatusCode::OK, Json(tjval))
+    } else {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
                 "error": "Error serializing tilejson",
                 "status": 500,
-            }))
-        }
-    } else {
-        Json(json!({
-            "error": "Dataset not found",
-            "dataset": dataset,
-            "status": 404,
-        }))
+            })),
+        )
     }
 }
 
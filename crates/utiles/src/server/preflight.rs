@@ -1,16 +1,20 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::collections::btree_map::Entry;
+use std::path::{Path, PathBuf};
 
 use futures::{StreamExt, stream};
+use tilejson::{Bounds, TileJSON};
 use tracing::{debug, info, warn};
 
-use crate::internal::globster::find_filepaths;
+use crate::internal::globster::{find_dirpaths, find_filepaths};
 use crate::mbt::{MbtilesAsync, MbtilesClientAsync};
 use crate::server::UtilesServerConfig;
-use crate::server::state::{Datasets, MbtilesDataset};
+use crate::server::cfg::DatasetConfig;
+use crate::server::fs_pyramid::{TilePyramidFs, is_tile_pyramid_dir};
+use crate::server::state::{Dataset, Datasets, TileSource};
 use crate::{UtilesError, UtilesResult};
 
-async fn check_mbtiles(fspath: &PathBuf) -> UtilesResult<MbtilesDataset> {
+async fn check_mbtiles(fspath: &PathBuf) -> UtilesResult<(String, Dataset)> {
     let mbt = MbtilesClientAsync::open_readonly(fspath).await?;
     debug!("sanity check: {:?}", mbt.filepath());
     let is_valid = mbt.is_mbtiles().await;
@@ -25,11 +29,16 @@ async fn check_mbtiles(fspath: &PathBuf) -> UtilesResult<MbtilesDataset> {
             info!("{}: is valid mbtiles", mbt.filepath());
             let tilejson = mbt.tilejson_ext().await?;
             let tilekind = mbt.query_tilekind().await?;
-            Ok(MbtilesDataset {
-                mbtiles: mbt,
-                tilejson,
-                tilekind,
-            })
+            let filename = mbt.filename().to_string().replace(".mbtiles", "");
+            Ok((
+                filename,
+                Dataset {
+                    source: TileSource::Mbtiles(mbt),
+                    tilejson,
+                    tilekind,
+                    config: DatasetConfig::default(),
+                },
+            ))
         }
         Err(e) => {
             warn!("{}: is not valid mbtiles: {:?}", mbt.filepath(), e);
@@ -40,6 +49,86 @@ async fn check_mbtiles(fspath: &PathBuf) -> UtilesResult<MbtilesDataset> {
     }
 }
 
+async fn check_pyramid(dirpath: &Path) -> UtilesResult<(String, Dataset)> {
+    let pyramid = TilePyramidFs::open(dirpath);
+    let tilejson = pyramid.tilejson().await?;
+    let tilekind = pyramid.query_tilekind().await?;
+    info!("{}: is tile pyramid ({:?})", pyramid.fspath(), tilekind);
+    Ok((
+        pyramid.name(),
+        Dataset {
+            source: TileSource::Pyramid(pyramid),
+            tilejson,
+            tilekind,
+            config: DatasetConfig::default(),
+        },
+    ))
+}
+
+/// Check a configured dataset path (mbtiles file or tile pyramid dir)
+async fn check_fspath(fspath: &str) -> UtilesResult<Dataset> {
+    let path = Path::new(fspath);
+    if path.is_file() {
+        check_mbtiles(&path.to_path_buf()).await.map(|(_, ds)| ds)
+    } else if is_tile_pyramid_dir(path) {
+        check_pyramid(path).await.map(|(_, ds)| ds)
+    } else {
+        Err(UtilesError::NotMbtilesLike(fspath.to_string()))
+    }
+}
+
+/// Clamp the tilejson zoom range and bounds to the dataset options
+///
+/// Options that would invert the zoom range or the bounds (no overlap w/ the
+/// dataset) are dropped from the dataset options (w/ a warning) so the
+/// served tiles match the tilejson; an antimeridian crossing bbox only
+/// filters the served tiles.
+fn apply_dataset_config(id: &str, tilejson: &mut TileJSON, dscfg: &mut DatasetConfig) {
+    let minzoom = dscfg.minzoom.map_or(tilejson.minzoom, |minzoom| {
+        Some(tilejson.minzoom.map_or(minzoom, |z| z.max(minzoom)))
+    });
+    let maxzoom = dscfg.maxzoom.map_or(tilejson.maxzoom, |maxzoom| {
+        Some(tilejson.maxzoom.map_or(maxzoom, |z| z.min(maxzoom)))
+    });
+    if let (Some(min), Some(max)) = (minzoom, maxzoom)
+        && min > max
+    {
+        warn!(
+            "{id}: configured zoom range does not overlap the dataset ({min} > {max}); not applied"
+        );
+        dscfg.minzoom = None;
+        dscfg.maxzoom = None;
+    } else {
+        tilejson.minzoom = minzoom;
+        tilejson.maxzoom = maxzoom;
+    }
+    if let Some(bbox) = dscfg.bbox() {
+        if bbox.crosses_antimeridian() {
+            debug!("{id}: antimeridian crossing bbox; bounds not clamped");
+            return;
+        }
+        let bounds = tilejson.bounds.map_or(
+            Bounds::new(bbox.west, bbox.south, bbox.east, bbox.north),
+            |b| {
+                Bounds::new(
+                    b.left.max(bbox.west),
+                    b.bottom.max(bbox.south),
+                    b.right.min(bbox.east),
+                    b.top.min(bbox.north),
+                )
+            },
+        );
+        if bounds.left > bounds.right || bounds.bottom > bounds.top {
+            warn!(
+                "{id}: configured bbox does not overlap the dataset bounds; not applied"
+            );
+            dscfg.bbox = None;
+        } else {
+            tilejson.bounds = Some(bounds);
+        }
+    }
+}
+
 pub(crate) async fn preflight(config: &UtilesServerConfig) -> UtilesResult<Datasets> {
     let now = std::time::Instant::now();
     info!("__PREFLIGHT__ ~ starting");
@@ -47,29 +136,62 @@ pub(crate) async fn preflight(config: &UtilesServerConfig) -> UtilesResult<Datas
 
     let filepaths = find_filepaths(&config.fspaths)?;
     debug!("filepaths: {:?}", filepaths);
+    let pyramid_dirpaths = find_dirpaths(&config.fspaths, is_tile_pyramid_dir);
+    debug!("pyramid dirpaths: {:?}", pyramid_dirpaths);
 
     let mut datasets = BTreeMap::new();
     let mbtiles_stream = stream::iter(filepaths)
         .map(|path| async move {
             let r = check_mbtiles(&path).await;
-            match r {
-                Ok(ds) => {
-                    let filename =
-                        ds.mbtiles.filename().to_string().replace(".mbtiles", "");
-                    Ok((filename, ds))
-                }
-                Err(e) => {
-                    warn!("{}: is not valid mbtiles: {:?}", path.to_string_lossy(), e);
-                    Err(e)
-                }
+            if let Err(e) = &r {
+                warn!("{}: is not valid mbtiles: {:?}", path.to_string_lossy(), e);
             }
+            r
         })
         .buffer_unordered(4);
     let mbtiles = mbtiles_stream.collect::<Vec<_>>().await;
     datasets.extend(mbtiles.into_iter().filter_map(Result::ok));
+    for dirpath in pyramid_dirpaths {
+        match check_pyramid(&dirpath).await {
+            Ok((name, ds)) => {
+                if let Entry::Vacant(entry) = datasets.entry(name) {
+                    entry.insert(ds);
+                } else {
+                    warn!("{}: duplicate dataset name", dirpath.display());
+                }
+            }
+            Err(e) => {
+                warn!("{}: is not valid tile pyramid: {:?}", dirpath.display(), e);
+            }
+        }
+    }
+    // configured datasets (aliases) ~ take precedence over found datasets
+    for (id, dscfg) in &config.datasets {
+        match &dscfg.path {
+            Some(fspath) => match check_fspath(fspath).await {
+                Ok(ds) => {
+                    if datasets.insert(id.clone(), ds).is_some() {
+                        warn!("{id}: configured dataset replaces found dataset");
+                    }
+                }
+                Err(e) => {
+                    warn!("{id}: invalid dataset path {fspath}: {e:?}");
+                }
+            },
+            None => {
+                if !datasets.contains_key(id) {
+                    warn!("{id}: configured dataset not found in fspaths");
+                }
+            }
+        }
+    }
+    for (id, ds) in &mut datasets {
+        ds.config = config.dataset_config(id);
+        apply_dataset_config(id, &mut ds.tilejson, &mut ds.config);
+    }
     // print the datasets
     for (k, ds) in &datasets {
-        info!("{}: {}", k, ds.mbtiles.filepath());
+        info!("{}: {}", k, ds.source.fspath());
     }
     let elapsed_duration = now.elapsed();
     match jiff::Span::try_from(elapsed_duration) {
@@ -80,7 +202,7 @@ pub(crate) async fn preflight(config: &UtilesServerConfig) -> UtilesResult<Datas
             info!("__PREFLIGHT__ ~ done ({:?})", elapsed_duration);
         }
     }
-    Ok(Datasets { mbtiles: datasets })
+    Ok(Datasets { datasets })
 }
 //
 // async fn preflight_og(config: &UtilesServerConfig) -> UtilesResult<Datasets> {
@@ -126,3 +248,57 @@ pub(crate) async fn preflight(config: &UtilesServerConfig) -> UtilesResult<Datas
 //
 //     Ok(Datasets { mbtiles: datasets })
 // }
+
+#[cfg(test)]
+mod tests {
+    use tilejson::tilejson;
+    use utiles_core::Tile;
+
+    use super::*;
+
+    fn tilejson() -> TileJSON {
+        let mut tj = tilejson! { tiles: vec![] };
+        tj.minzoom = Some(2);
+        tj.maxzoom = Some(8);
+        tj.bounds = Some(Bounds::new(-10.0, 30.0, 20.0, 60.0));
+        tj
+    }
+
+    #[test]
+    fn apply_dataset_config_clamps() {
+        let mut tj = tilejson();
+        let mut dscfg = DatasetConfig {
+            maxzoom: Some(5),
+            bbox: Some([0.0, 0.0, 40.0, 50.0]),
+            ..DatasetConfig::default()
+        };
+        apply_dataset_config("ds", &mut tj, &mut dscfg);
+        assert_eq!((tj.minzoom, tj.maxzoom), (Some(2), Some(5)));
+        assert_eq!(dscfg.maxzoom, Some(5));
+        assert_eq!(tj.bounds, Some(Bounds::new(0.0, 30.0, 20.0, 50.0)));
+    }
+
+    #[test]
+    fn apply_dataset_config_no_inverted_bounds() {
+        let mut tj = tilejson();
+        let mut dscfg = DatasetConfig {
+            minzoom: Some(10),
+            bbox: Some([100.0, 0.0, 120.0, 10.0]),
+            ..DatasetConfig::default()
+        };
+        apply_dataset_config("ds", &mut tj, &mut dscfg);
+        assert_eq!((tj.minzoom, tj.maxzoom), (Some(2), Some(8)));
+        assert_eq!(tj.bounds, Some(Bounds::new(-10.0, 30.0, 20.0, 60.0)));
+        // not applied options don't filter the served tiles either
+        assert_eq!((dscfg.minzoom, dscfg.bbox), (None, None));
+        assert!(dscfg.allows_tile(&Tile::new(0, 0, 2)));
+
+        // antimeridian crossing bbox
+        let mut dscfg = DatasetConfig {
+            bbox: Some([170.0, 30.0, -170.0, 60.0]),
+            ..DatasetConfig::default()
+        };
+        apply_dataset_config("ds", &mut tj, &mut dscfg);
+        assert_eq!(tj.bounds, Some(Bounds::new(-10.0, 30.0, 20.0, 60.0)));
+    }
+}
@@ -1,26 +1,64 @@
 use std::collections::BTreeMap;
 
 use tilejson::TileJSON;
+use utiles_core::Tile;
 use utiles_core::tile_type::TileKind;
 
-use crate::mbt::MbtilesClientAsync;
-use crate::server::cfg::UtilesServerConfig;
+use crate::errors::UtilesResult;
+use crate::mbt::{MbtilesAsync, MbtilesClientAsync};
+use crate::server::cfg::{DatasetConfig, UtilesServerConfig};
+use crate::server::fs_pyramid::TilePyramidFs;
+use crate::server::metrics::Metrics;
 
+/// Where a dataset's tiles live
 #[derive(Debug)]
-pub(super) struct MbtilesDataset {
-    pub mbtiles: MbtilesClientAsync,
+pub(super) enum TileSource {
+    Mbtiles(MbtilesClientAsync),
+    Pyramid(TilePyramidFs),
+}
+
+impl TileSource {
+    pub(super) async fn query_tile(
+        &self,
+        tile: &Tile,
+    ) -> UtilesResult<Option<Vec<u8>>> {
+        match self {
+            Self::Mbtiles(mbt) => mbt.query_tile(tile).await,
+            Self::Pyramid(pyramid) => pyramid.query_tile(tile).await,
+        }
+    }
+
+    pub(super) fn fspath(&self) -> String {
+        match self {
+            Self::Mbtiles(mbt) => mbt.filepath().to_string(),
+            Self::Pyramid(pyramid) => pyramid.fspath(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Dataset {
+    pub source: TileSource,
     pub tilejson: TileJSON,
     pub tilekind: TileKind,
+    pub config: DatasetConfig,
 }
 
 #[derive(Debug)]
 pub(super) struct Datasets {
-    pub mbtiles: BTreeMap<String, MbtilesDataset>,
+    pub datasets: BTreeMap<String, Dataset>,
+}
+
+impl Datasets {
+    pub(super) fn get(&self, name: &str) -> Option<&Dataset> {
+        self.datasets.get(name)
+    }
 }
 
 #[derive(Debug)]
 pub(super) struct ServerState {
     pub config: UtilesServerConfig,
     pub datasets: Datasets,
+    pub metrics: Metrics,
     pub start_ts: std::time::Instant,
 }
@@ -1,7 +1,15 @@
-use axum::Json;
-use serde_json::json;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::IntoResponse;
 
-/// UI-tiles (ui) wip
-pub(crate) async fn uitiles() -> Json<serde_json::Value> {
-    Json(json!({"status": "TODO/WIP/NOT-IMPLEMENTED-YET"}))
+/// Self-contained (no cdn) tile viewer html/js
+const UITILES_HTML: &str = include_str!("uitiles.html");
+
+/// UI-tiles (ui) ~ embedded tile viewer for browsing served datasets
+pub(crate) async fn uitiles() -> impl IntoResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    (StatusCode::OK, headers, UITILES_HTML)
 }
@@ -0,0 +1,194 @@
+//! Sqlite db checks for damaged (truncated/partially written) dbs
+//!
+//! Checks the header fields, the file size against the in-header database
+//! size, left over wal/journal files and runs `PRAGMA integrity_check` (or
+//! `quick_check`). The db is opened read-only so a left over wal is not
+//! checkpointed into the db by the check.
+use std::io::Read;
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+
+use crate::sqlite::{SqliteHeader, SqliteResult, pragma_integrity_check};
+
+/// File next to a db written by sqlite (`-wal`, `-shm` or `-journal`)
+#[derive(Debug, Clone, Serialize)]
+pub struct SqliteSidecarFile {
+    pub path: String,
+    pub nbytes: u64,
+}
+
+/// Existing `-wal`/`-shm`/`-journal` files of a db
+#[must_use]
+pub fn sqlite_sidecar_files(path: &Path) -> Vec<SqliteSidecarFile> {
+    ["-wal", "-shm", "-journal"]
+        .iter()
+        .filter_map(|suffix| {
+            let mut sidecar = path.as_os_str().to_owned();
+            sidecar.push(suffix);
+            let md = std::fs::metadata(&sidecar).ok()?;
+            Some(SqliteSidecarFile {
+                path: sidecar.to_string_lossy().to_string(),
+                nbytes: md.len(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct SqliteCheck {
+    pub fspath: String,
+    pub filesize: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<SqliteHeader>,
+    pub header_errors: Vec<String>,
+    /// File size from the in-header database size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_filesize: Option<u64>,
+    pub truncated: bool,
+    /// Header read/write versions are 2 (journal mode wal)
+    pub wal_mode: bool,
+    pub sidecar_files: Vec<SqliteSidecarFile>,
+    /// `integrity_check` or `quick_check`
+    pub check: &'static str,
+    pub check_errors: Vec<String>,
+    /// Non-fatal problems (eg a left over wal w/ un-checkpointed pages)
+    pub warnings: Vec<String>,
+    pub ok: bool,
+}
+
+fn read_header(path: &Path) -> std::io::Result<Option<[u8; 100]>> {
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = [0u8; 100];
+    match file.read_exact(&mut buf) {
+        Ok(()) => Ok(Some(buf)),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Check a sqlite db file w/ `integrity_check` (or `quick_check` if
+/// `quick`) reporting at most `max_errors` problems
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read; problems w/ the db itself
+/// are reported in the returned `SqliteCheck`.
+pub fn sqlite_check(
+    path: &Path,
+    quick: bool,
+    max_errors: u32,
+) -> SqliteResult<SqliteCheck> {
+    let filesize = std::fs::metadata(path)?.len();
+    let mut header_errors = vec![];
+    let header = if let Some(buf) = read_header(path)? {
+        match SqliteHeader::parse(&buf) {
+            Ok(header) => Some(header),
+            Err(e) => {
+                header_errors.push(e.to_string());
+                None
+            }
+        }
+    } else {
+        header_errors.push(format!("file too small for header: {filesize} bytes"));
+        None
+    };
+    if let Some(header) = &header {
+        header_errors.extend(header.errors().iter().map(ToString::to_string));
+    }
+    let expected_filesize = header.as_ref().and_then(SqliteHeader::expected_filesize);
+    let truncated = expected_filesize.is_some_and(|expected| filesize < expected);
+
+    let sidecar_files = sqlite_sidecar_files(path);
+    let mut warnings = vec![];
+    for sidecar in &sidecar_files {
+        if sidecar.path.ends_with("-wal") && sidecar.nbytes > 0 {
+            warnings.push(format!(
+                "wal file w/ {} bytes (un-checkpointed transactions or an interrupted writer): {}",
+                sidecar.nbytes, sidecar.path
+            ));
+        } else if sidecar.path.ends_with("-journal") && sidecar.nbytes > 0 {
+            warnings.push(format!(
+                "hot journal (interrupted transaction; rolled back on the next write): {}",
+                sidecar.path
+            ));
+        }
+    }
+    if truncated {
+        warnings.push(format!(
+            "file is truncated: {filesize} bytes < {} bytes (in-header size)",
+            expected_filesize.unwrap_or_default()
+        ));
+    }
+
+    let check_errors = if header.is_some() {
+        Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .and_then(|conn| pragma_integrity_check(&conn, quick, max_errors))
+        .unwrap_or_else(|e| vec![e.to_string()])
+    } else {
+        vec!["not a sqlite db".to_string()]
+    };
+    let ok = header_errors.is_empty() && !truncated && check_errors.is_empty();
+    Ok(SqliteCheck {
+        fspath: path.to_string_lossy().to_string(),
+        filesize,
+        header,
+        header_errors,
+        expected_filesize,
+        truncated,
+        wal_mode: header.as_ref().is_some_and(SqliteHeader::is_wal),
+        sidecar_files,
+        check: if quick {
+            "quick_check"
+        } else {
+            "integrity_check"
+        },
+        check_errors,
+        warnings,
+        ok,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn test_db(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("utiles-test-{name}-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, data BLOB);
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 200)
+             INSERT INTO t SELECT i, zeroblob(1000) FROM n;",
+        )
+        .unwrap();
+        drop(conn);
+        path
+    }
+
+    #[test]
+    fn check_ok_and_truncated() {
+        let path = test_db("check");
+        let check = sqlite_check(&path, false, 10).unwrap();
+        assert!(check.ok, "{check:?}");
+        assert_eq!(check.expected_filesize, Some(check.filesize));
+
+        // chop off the last pages
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(check.filesize / 2).unwrap();
+        drop(file);
+        let check = sqlite_check(&path, true, 10).unwrap();
+        assert!(!check.ok);
+        assert!(check.truncated);
+        assert!(!check.check_errors.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -269,6 +269,52 @@ impl SqliteHeader {
         }
     }
 
+    /// Page size in bytes (the header stores 65536 as 1)
+    #[must_use]
+    pub fn page_size_bytes(&self) -> u32 {
+        if self.page_size == 1 {
+            65536
+        } else {
+            u32::from(self.page_size)
+        }
+    }
+
+    /// True if the db is in WAL mode (read/write versions 2)
+    #[must_use]
+    pub const fn is_wal(&self) -> bool {
+        self.write_version == 2 || self.read_version == 2
+    }
+
+    /// Expected file size (bytes) from the in-header database size; `None`
+    /// if the in-header size is not valid (written by a pre-3.7.0 sqlite)
+    #[must_use]
+    pub fn expected_filesize(&self) -> Option<u64> {
+        if self.database_size > 0 && self.file_change_counter == self.version_valid_for
+        {
+            Some(u64::from(self.database_size) * u64::from(self.page_size_bytes()))
+        } else {
+            None
+        }
+    }
+
+    /// All failed header field checks
+    #[must_use]
+    pub fn errors(&self) -> Vec<SqliteError> {
+        [
+            self.page_size_ok(),
+            self.file_format_write_version_ok(),
+            self.file_format_read_version_ok(),
+            self.reserved_space_ok(),
+            self.payload_fractions_ok(),
+            self.text_encoding_ok(),
+            self.schema_format_number_ok(),
+            self.reserved_expansion_space_ok(),
+        ]
+        .into_iter()
+        .filter_map(Result::err)
+        .collect()
+    }
+
     pub fn is_ok(&self) -> SqliteResult<()> {
         self.page_size_ok()?;
         self.file_format_write_version_ok()?;
@@ -4,6 +4,7 @@
 pub use affected::{AffectedType, RowsAffected};
 pub use async_sqlite3::{AsyncSqliteConn, AsyncSqliteConnMut, SqliteDbAsyncClient};
 pub use attach::{attach_db, detach_db};
+pub use check::{SqliteCheck, SqliteSidecarFile, sqlite_check, sqlite_sidecar_files};
 pub use db::*;
 pub use dbpath::*;
 pub use errors::{SqliteError, SqliteResult};
@@ -16,6 +17,7 @@ pub use sqlike3::{Sqlike3, Sqlike3Async};
 mod affected;
 mod async_sqlite3;
 mod attach;
+mod check;
 mod db;
 mod dbpath;
 mod errors;
@@ -36,6 +36,25 @@ pub fn pragma_page_count(conn: &Connection) -> RusqliteResult<i64> {
     Ok(count)
 }
 
+/// Run `PRAGMA integrity_check(N)` (or `quick_check(N)` if `quick`) and
+/// return the reported problems (empty if the db is ok)
+pub fn pragma_integrity_check(
+    conn: &Connection,
+    quick: bool,
+    max_errors: u32,
+) -> RusqliteResult<Vec<String>> {
+    let pragma = if quick {
+        "quick_check"
+    } else {
+        "integrity_check"
+    };
+    let mut stmt = conn.prepare(&format!("PRAGMA {pragma}({max_errors})"))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<RusqliteResult<Vec<String>>>()?;
+    Ok(rows.into_iter().filter(|row| row != "ok").collect())
+}
+
 pub fn analysis_limit(conn: &Connection) -> RusqliteResult<i64> {
     let limit_i64: i64 =
         conn.pragma_query_value(None, "analysis_limit", |row| row.get(0))?;
@@ -1,36 +1,15 @@
 //! utiles-sqlite ~ sqlite extension function(s) for utiles
 //!
-//! Adds the following functions:
-//!   - `ut_tiletype(blob)`   - returns the tile type of the blob
-//!   - `ut_tilesize(blob)`   - returns the size of raster tile or None
-//!   - `xxh3_int(blob|str)`  - returns xxh3 hash as `i64` big-endian view
-//!   - `xxh64_int(blob|str)` - returns xxh64 hash as `i64` big-endian view
+//! Registers the `sqlite-hashes` hash functions and the `utiles-sqlite`
+//! functions (`ut_tiletype`, `ut_tilesize`, tile math, ...; see the
+//! `utiles_sqlite` crate docs).
 use rusqlite::{Connection, Result};
 use tracing::debug;
 
-use crate::sqlite_utiles::base64::add_function_base64_encode;
-use crate::sqlite_utiles::hash_int::{
-    add_function_fnv_i64, add_function_xxh3_i64, add_function_xxh64_i64,
-};
-use crate::sqlite_utiles::tilesize::add_function_ut_tilesize;
-use crate::sqlite_utiles::tiletype::add_function_ut_tiletype;
-
-mod base64;
-mod hash_int;
-mod tilesize;
-mod tiletype;
-
 pub fn register_utiles_sqlite(conn: &Connection) -> Result<()> {
     debug!("registering sqlite-utiles functions...");
     sqlite_hashes::register_hash_functions(conn)?;
-
-    add_function_ut_tiletype(conn)?;
-    add_function_ut_tilesize(conn)?;
-    add_function_base64_encode(conn)?;
-
-    add_function_xxh3_i64(conn)?;
-    add_function_xxh64_i64(conn)?;
-    add_function_fnv_i64(conn)?;
+    utiles_sqlite::register_utiles_functions(conn)?;
     debug!("registered sqlite-utiles functions!");
     Ok(())
 }
@@ -275,7 +275,11 @@ def geotransform2optzoom(
     geotransform: tuple[float, float, float, float, float, float],
 ) -> int: ...
 def geojson2tiles(
-    geojson_str: str, maxzoom: int, minzoom: int | None = None
+    geojson_str: str,
+    maxzoom: int,
+    minzoom: int | None = None,
+    mode: t.Literal["intersects", "within", "centroid"] = "intersects",
+    buffer: str | None = None,
 ) -> list[Tile]: ...
 def parse_textiles(string: str) -> list[Tile]: ...
 
@@ -1,23 +1,36 @@
 use geojson::GeoJson;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use utiles::cover::{GeojsonCoverOptions, geojson2tiles as ut_geojson2tiles};
+use utiles::cover::{
+    CoverBuffer, CoverMode, GeojsonCoverOptions, geojson2tiles as ut_geojson2tiles,
+};
 
 use crate::pyutiles::PyTile;
 
 #[pyfunction]
-#[pyo3(signature = (geojson_str, maxzoom, minzoom=None))]
+#[pyo3(signature = (geojson_str, maxzoom, minzoom=None, mode="intersects", buffer=None))]
 pub(crate) fn geojson2tiles(
     geojson_str: &str,
     maxzoom: u8,
     minzoom: Option<u8>,
+    mode: &str,
+    buffer: Option<&str>,
 ) -> PyResult<Vec<PyTile>> {
+    let mode = mode
+        .parse::<CoverMode>()
+        .map_err(|e| PyErr::new::<PyValueError, _>(format!("Error: {e}")))?;
+    let buffer = buffer
+        .map(str::parse::<CoverBuffer>)
+        .transpose()
+        .map_err(|e| PyErr::new::<PyValueError, _>(format!("Error: {e}")))?;
     let geojson_res = geojson_str.parse::<GeoJson>();
     match geojson_res {
         Ok(gj) => {
             let options = GeojsonCoverOptions {
                 zoom: maxzoom,
                 minzoom,
+                mode,
+                buffer,
             };
             let tiles = ut_geojson2tiles(&gj, options)
                 .map_err(|e| Err(PyErr::new::<PyValueError, _>(format!("Error: {e}"))));